@@ -0,0 +1,1423 @@
+/// Module holding the `gdfm` command line
+///
+/// [`command`] describes every subcommand and argument, and [`run`] parses a
+/// command line and runs what it asks for. The binary only calls [`run`].
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use miette::{miette, IntoDiagnostic, Result};
+
+
+use crate::cli::backup::{backup, restore};
+use crate::cli::clean::clean;
+use crate::cli::db::{import, shell};
+use crate::cli::doctor::doctor;
+use crate::cli::deliveries::{list_deliveries, missed_deliveries, redeliver, show_delivery};
+use crate::cli::export::{export_after_collection, export_csv, export_excel, export_parquet, export_postgres, export_provenance};
+use crate::cli::features::list_features;
+use crate::cli::init::init;
+use crate::cli::listen::listen;
+use crate::cli::query::query;
+use crate::cli::release_notes::release_notes;
+use crate::cli::report::report;
+use crate::cli::runs::{list_runs, record_run};
+use crate::cli::schema::schema;
+use crate::cli::serve::serve;
+use crate::cli::sync::{collect_all, collect_owner, sync, wildcard_owner};
+use crate::cli::simulate::simulate_rotation;
+use crate::cli::watch::watch_pulls;
+use crate::cli::collect::{run_collector, CollectOptions, NumberRange, Shard};
+use crate::client::ScheduleWindow;
+use crate::config::Config;
+use crate::context::Context;
+use crate::database::TABLES;
+use crate::forge::FORGES;
+use crate::constants::{
+    CLI_ARGS_REPO,
+    CLI_ARGS_ARCHIVE,
+    CLI_ARGS_COLLECT,
+    CLI_ARGS_MISSING_ONLY,
+    CLI_ARGS_PATH,
+    CLI_ARGS_YES,
+    CLI_ARGS_DRY_RUN,
+    CLI_ARGS_NUMBER,
+    CLI_ARGS_OUTPUT,
+    CLI_ARGS_LOCALE,
+    CLI_ARGS_INTERVAL,
+    CLI_ARGS_EVERY,
+    CLI_ARGS_ALL_EVENTS,
+    CLI_ARGS_EMAIL,
+    CLI_ARGS_APPENDIX,
+    CLI_ARGS_NOTES,
+    CLI_ARGS_STATE,
+    CLI_ARGS_SINCE,
+    CLI_ARGS_FULL,
+    CLI_ARGS_RESUME,
+    CLI_ARGS_FORMAT,
+    CLI_ARGS_REVIEWERS,
+    CLI_ARGS_DAYS,
+    CLI_ARGS_PORT,
+    CLI_ARGS_BIND,
+    CLI_ARGS_PUBLIC,
+    CLI_ARGS_HOOK,
+    CLI_ARGS_DELIVERY,
+    CLI_ARGS_LIMIT,
+    CLI_ARGS_JOBS,
+    CLI_ARGS_CONCURRENCY,
+    CLI_ARGS_THROTTLE_MS,
+    CLI_ARGS_SCHEDULE_WINDOW,
+    CLI_ARGS_RANGE,
+    CLI_ARGS_SHARD,
+    CLI_ARGS_STRICT,
+    CLI_ARGS_KEEP_RAW,
+    CLI_ARGS_TABLE,
+    CLI_ARGS_ALL_TABLES,
+    CLI_ARGS_TOPIC,
+    CLI_ARGS_VISIBILITY,
+    CLI_ARGS_GITHUB_URL,
+    CLI_ARGS_PROJECT,
+    CLI_ARGS_NO_COLOR,
+    CLI_ARGS_BUILTIN,
+    CLI_ARGS_SECRET,
+    CLI_ARGS_FORGE,
+    CLI_ARGS_REFRESH,
+    CLI_ARGS_SQL,
+    CLI_ARGS_PUBLISH,
+    CLI_ARGS_BUCKET,
+    CLI_ARGS_FIX,
+};
+use crate::report::locale::LOCALES;
+use crate::terminal::disable_colors;
+use crate::report::periods::Bucket;
+
+/// The `gdfm` command line, with every subcommand and argument
+pub fn command() -> Command {
+    let collect = Command::new("collect")
+        .about("Various commands for collecting data about a repository")
+        .after_help("Pass owner/* (quoted) as the repository to collect every repository of an organization or user")
+        .arg(
+            Arg::new(CLI_ARGS_TOPIC)
+                .long(CLI_ARGS_TOPIC)
+                .help("With owner/*, only collect repositories with this topic")
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_VISIBILITY)
+                .long(CLI_ARGS_VISIBILITY)
+                .help("With owner/*, which repositories to collect")
+                .value_parser(["all", "public", "private"])
+                .default_value("all")
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_SCHEDULE_WINDOW)
+                .long(CLI_ARGS_SCHEDULE_WINDOW)
+                .help("Only make API requests between these local times, e.g. 22:00-06:00, waiting outside them")
+                .value_parser(ScheduleWindow::parse)
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_RANGE)
+                .long(CLI_ARGS_RANGE)
+                .help("Only collect pull requests numbered in this range, e.g. 1000..2000 (the end is excluded)")
+                .value_parser(NumberRange::parse)
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_SHARD)
+                .long(CLI_ARGS_SHARD)
+                .help("Only collect this worker's share of pull requests, e.g. 2/5 for the second of five workers")
+                .value_parser(Shard::parse)
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_STRICT)
+                .long(CLI_ARGS_STRICT)
+                .help("Fail on items missing fields such as created_at or the author instead of storing them empty")
+                .action(ArgAction::SetTrue)
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_KEEP_RAW)
+                .long(CLI_ARGS_KEEP_RAW)
+                .help("Also store the JSON GitHub sent for every pull request, event and review, to reparse later")
+                .action(ArgAction::SetTrue)
+                .global(true)
+        )
+        .subcommand(
+            Command::new("all")
+                .about("Run every collector for a given repository, in dependency order")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_CONCURRENCY)
+                        .short('c')
+                        .long(CLI_ARGS_CONCURRENCY)
+                        .help("Number of pull requests the collectors fetch at the same time")
+                        .value_parser(is_valid_number)
+                        .default_value("1")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_THROTTLE_MS)
+                        .long(CLI_ARGS_THROTTLE_MS)
+                        .help("Pause this many milliseconds between pull requests instead of adapting to the rate limit")
+                        .value_parser(is_valid_number)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("pulls")
+                .about("Collect pull requests for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_STATE)
+                        .long(CLI_ARGS_STATE)
+                        .help("Which pull requests to collect")
+                        .value_parser(["open", "closed", "all"])
+                        .default_value("closed")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_SINCE)
+                        .long(CLI_ARGS_SINCE)
+                        .help("Only fetch what changed after this date or RFC 3339 time, instead of since the last run")
+                        .value_parser(is_valid_since)
+                        .conflicts_with(CLI_ARGS_FULL)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_FULL)
+                        .long(CLI_ARGS_FULL)
+                        .help("Fetch everything again instead of only what changed since the last run")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_RESUME)
+                        .long(CLI_ARGS_RESUME)
+                        .help("Continue where the previous run stopped if it didn't finish")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("issues")
+                .about("Collect issues for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("events")
+                .about("Collect pull request events for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_ALL_EVENTS)
+                        .long(CLI_ARGS_ALL_EVENTS)
+                        .action(ArgAction::SetTrue)
+                        .help("Store every event type, ignoring collect.event_types in the config")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_NUMBER)
+                        .short('n')
+                        .long(CLI_ARGS_NUMBER)
+                        .help("The pull request number")
+                        .action(ArgAction::Set)
+                        .value_parser(is_valid_number)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_MISSING_ONLY)
+                        .long(CLI_ARGS_MISSING_ONLY)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(CLI_ARGS_NUMBER)
+                        .help("Only fetch pull requests and issues whose events were never collected")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_SINCE)
+                        .long(CLI_ARGS_SINCE)
+                        .help("Only fetch what changed after this date or RFC 3339 time, instead of since the last run")
+                        .value_parser(is_valid_since)
+                        .conflicts_with(CLI_ARGS_FULL)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_FULL)
+                        .long(CLI_ARGS_FULL)
+                        .help("Fetch everything again instead of only what changed since the last run")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_RESUME)
+                        .long(CLI_ARGS_RESUME)
+                        .help("Continue where the previous run stopped if it didn't finish")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_CONCURRENCY)
+                        .short('c')
+                        .long(CLI_ARGS_CONCURRENCY)
+                        .help("Number of pull requests to fetch at the same time")
+                        .value_parser(is_valid_number)
+                        .default_value("1")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_THROTTLE_MS)
+                        .long(CLI_ARGS_THROTTLE_MS)
+                        .help("Pause this many milliseconds between pull requests instead of adapting to the rate limit")
+                        .value_parser(is_valid_number)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("reviews")
+                .about("Collect pull request reviews for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_NUMBER)
+                        .short('n')
+                        .long(CLI_ARGS_NUMBER)
+                        .help("The pull request number")
+                        .action(ArgAction::Set)
+                        .value_parser(is_valid_number)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_MISSING_ONLY)
+                        .long(CLI_ARGS_MISSING_ONLY)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(CLI_ARGS_NUMBER)
+                        .help("Only fetch pull requests that have no reviews stored yet")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_SINCE)
+                        .long(CLI_ARGS_SINCE)
+                        .help("Only fetch what changed after this date or RFC 3339 time, instead of since the last run")
+                        .value_parser(is_valid_since)
+                        .conflicts_with(CLI_ARGS_FULL)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_FULL)
+                        .long(CLI_ARGS_FULL)
+                        .help("Fetch everything again instead of only what changed since the last run")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_RESUME)
+                        .long(CLI_ARGS_RESUME)
+                        .help("Continue where the previous run stopped if it didn't finish")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_CONCURRENCY)
+                        .short('c')
+                        .long(CLI_ARGS_CONCURRENCY)
+                        .help("Number of pull requests to fetch at the same time")
+                        .value_parser(is_valid_number)
+                        .default_value("1")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_THROTTLE_MS)
+                        .long(CLI_ARGS_THROTTLE_MS)
+                        .help("Pause this many milliseconds between pull requests instead of adapting to the rate limit")
+                        .value_parser(is_valid_number)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("files")
+                .about("Collect the files changed by merged pull requests for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_NUMBER)
+                        .short('n')
+                        .long(CLI_ARGS_NUMBER)
+                        .help("The pull request number")
+                        .action(ArgAction::Set)
+                        .value_parser(is_valid_number)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_THROTTLE_MS)
+                        .long(CLI_ARGS_THROTTLE_MS)
+                        .help("Pause this many milliseconds between pull requests instead of adapting to the rate limit")
+                        .value_parser(is_valid_number)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("pull-details")
+                .about("Collect lines added and deleted, files changed and commits of every pull request")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_NUMBER)
+                        .short('n')
+                        .long(CLI_ARGS_NUMBER)
+                        .help("The pull request number")
+                        .action(ArgAction::Set)
+                        .value_parser(is_valid_number)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_MISSING_ONLY)
+                        .long(CLI_ARGS_MISSING_ONLY)
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(CLI_ARGS_NUMBER)
+                        .help("Only fetch pull requests whose size isn't stored yet")
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_SINCE)
+                        .long(CLI_ARGS_SINCE)
+                        .help("Only fetch what changed after this date or RFC 3339 time, instead of since the last run")
+                        .value_parser(is_valid_since)
+                        .conflicts_with(CLI_ARGS_FULL)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_FULL)
+                        .long(CLI_ARGS_FULL)
+                        .help("Fetch everything again instead of only what changed since the last run")
+                        .action(ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_THROTTLE_MS)
+                        .long(CLI_ARGS_THROTTLE_MS)
+                        .help("Pause this many milliseconds between pull requests instead of adapting to the rate limit")
+                        .value_parser(is_valid_number)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("wiki")
+                .about("Collect recent wiki edits for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("comments")
+                .about("Collect issue and pull request comments for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("review-comments")
+                .about("Collect inline pull request review comments for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("commits")
+                .about("Collect commits for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("governance")
+                .about("Collect the history of license and governance files such as LICENSE and MAINTAINERS")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("releases")
+                .about("Collect releases for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("deployments")
+                .about("Collect deployments and their statuses for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("labels")
+                .about("Collect labels and apply them to stored pull requests and issues")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("discussions")
+                .about("Collect discussions, their answers and comments for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("milestones")
+                .about("Collect milestones and assign stored pull requests and issues to them")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("stars")
+                .about("Collect stargazers and when they starred a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("forks")
+                .about("Collect forks of a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("contributors")
+                .about("Collect contributors with their commit counts and weekly activity")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("maintainers")
+                .about("Collect who has push access to a repository, directly or through a team")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("traffic")
+                .about("Collect daily views and clones for the last 14 days")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("demand")
+                .about("Collect comment and reaction counts for open issues in a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("dependencies")
+                .about("Collect the dependency graph of a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("repo-events")
+                .about("Collect issue transfers and other repository-level events for a given repository")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository to collect data from")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        );
+
+    let init = Command::new("init")
+        .about("Initialize a new project")
+        .arg(
+            Arg::new(CLI_ARGS_REPO)
+                .help("The repository to track")
+                .required(true)
+                .index(1)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_ARCHIVE)
+                .long(CLI_ARGS_ARCHIVE)
+                .help("Stop tracking the repository and hide it from reports, keeping its collected data")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_COLLECT)
+                .long(CLI_ARGS_COLLECT)
+                .help("Collect pull requests, events and reviews right away")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(CLI_ARGS_ARCHIVE)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_FORGE)
+                .long(CLI_ARGS_FORGE)
+                .help("Where the repository is hosted; GitLab projects in subgroups are written group/subgroup/name")
+                .value_parser(FORGES.to_vec())
+                .conflicts_with(CLI_ARGS_ARCHIVE)
+        )
+        .arg_required_else_help(true);
+
+    let report = Command::new("report")
+        .about("Generate a report about the repository")
+        .arg(
+            Arg::new(CLI_ARGS_PATH)
+                .help("The path to the repository")
+                .required(true)
+                .index(1)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_LOCALE)
+                .long(CLI_ARGS_LOCALE)
+                .help("Locale used for dates, numbers and the first day of the week")
+                .value_parser(LOCALES.iter().map(|locale| locale.tag).collect::<Vec<_>>())
+                .default_value("en-US")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_EMAIL)
+                .long(CLI_ARGS_EMAIL)
+                .help("Also email the report using the [email] settings in the config file")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_PUBLISH)
+                .long(CLI_ARGS_PUBLISH)
+                .value_name("BRANCH")
+                .help("Also commit the report to this branch, e.g. gh-pages, using the [publish] settings")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_APPENDIX)
+                .long(CLI_ARGS_APPENDIX)
+                .help("Append sortable tables of every merged pull request and review")
+                .action(ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_NOTES)
+                .long(CLI_ARGS_NOTES)
+                .help("Markdown file whose ## sections are shown in the report sections of the same title")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_BUCKET)
+                .long(CLI_ARGS_BUCKET)
+                .help("Reporting period activity is counted in: week, month, quarter or fiscal-year[:month]")
+                .value_parser(Bucket::parse)
+                .default_value("month")
+        )
+        .arg_required_else_help(true);
+
+    let release_notes = Command::new("release-notes")
+        .about("Draft Markdown release notes from the pull requests merged since a release")
+        .arg(
+            Arg::new(CLI_ARGS_PATH)
+                .help("The path to the repository")
+                .required(true)
+                .index(1)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_SINCE)
+                .long(CLI_ARGS_SINCE)
+                .help("Tag of the release to start from, instead of the latest one")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_OUTPUT)
+                .short('o')
+                .long(CLI_ARGS_OUTPUT)
+                .help("The path of the Markdown file to write, instead of printing the notes")
+        )
+        .arg_required_else_help(true);
+
+    let clean = Command::new("clean")
+        .about("Remove the database file, or only some repositories and their data")
+        .arg(
+            Arg::new(CLI_ARGS_YES)
+                .short('y')
+                .long("yes")
+                .action(ArgAction::SetTrue)
+                .help("Force the removal of the database file")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_DRY_RUN)
+                .long("dry-run")
+                .action(ArgAction::SetTrue)
+                .help("Show which repositories, tables and rows would be removed without removing them")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_REPO)
+                .long("repo")
+                .help("Only remove this repository and its data, keeping the database; may be given more than once")
+                .action(ArgAction::Append)
+        );
+
+    let backup = Command::new("backup")
+        .about("Save the database, or some of its repositories, to a newline-delimited JSON file")
+        .arg(
+            Arg::new(CLI_ARGS_PATH)
+                .help("The file to write the backup to")
+                .required(true)
+                .index(1)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_REPO)
+                .long("repo")
+                .help("Only back up this repository; may be given more than once")
+                .action(ArgAction::Append)
+        );
+
+    let restore = Command::new("restore")
+        .about("Restore a backup, replacing the stored rows it holds and keeping everything else")
+        .arg(
+            Arg::new(CLI_ARGS_PATH)
+                .help("The backup file to restore")
+                .required(true)
+                .index(1)
+        );
+
+    let export = Command::new("export")
+        .about("Export collected data to other formats")
+        .subcommand(
+            Command::new("excel")
+                .about("Export every table to an Excel workbook with a summary sheet")
+                .arg(
+                    Arg::new(CLI_ARGS_OUTPUT)
+                        .short('o')
+                        .long(CLI_ARGS_OUTPUT)
+                        .help("The path of the workbook to write")
+                        .default_value("gdfm.xlsx")
+                )
+        )
+        .subcommand(
+            Command::new("csv")
+                .about("Export a table, or every table, to CSV")
+                .arg(
+                    Arg::new(CLI_ARGS_TABLE)
+                        .long(CLI_ARGS_TABLE)
+                        .help("The table to export")
+                        .value_parser(TABLES.to_vec())
+                        .required_unless_present(CLI_ARGS_ALL_TABLES)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_ALL_TABLES)
+                        .long(CLI_ARGS_ALL_TABLES)
+                        .help("Export every table into a directory, one file per table")
+                        .action(ArgAction::SetTrue)
+                        .conflicts_with(CLI_ARGS_TABLE)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_OUTPUT)
+                        .short('o')
+                        .long(CLI_ARGS_OUTPUT)
+                        .help("The file to write, - for stdout, or with --all the directory [default: <table>.csv, gdfm-csv]")
+                )
+        )
+        .subcommand(
+            Command::new("parquet")
+                .about("Export every table to a Parquet file, keeping column types and timestamps")
+                .arg(
+                    Arg::new(CLI_ARGS_PATH)
+                        .help("The directory to write the Parquet files to")
+                        .required(true)
+                        .index(1)
+                )
+        )
+        .subcommand(
+            Command::new("postgres")
+                .about("Copy every table to the Postgres database DATABASE_URL points at, updating rows in place")
+        )
+        .subcommand(
+            Command::new("provenance")
+                .about("Write a JSON manifest of the repositories, date ranges and collection runs, for citing the dataset")
+                .arg(
+                    Arg::new(CLI_ARGS_OUTPUT)
+                        .short('o')
+                        .long(CLI_ARGS_OUTPUT)
+                        .help("The path of the manifest to write")
+                        .default_value("gdfm-provenance.json")
+                )
+        );
+
+    let watch = Command::new("watch")
+        .about("Follow activity on GitHub as it happens")
+        .subcommand(
+            Command::new("pr")
+                .about("Print new events and reviews for a set of pull requests")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository the pull requests belong to")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_NUMBER)
+                        .short('n')
+                        .long(CLI_ARGS_NUMBER)
+                        .help("The pull request number")
+                        .required(true)
+                        .action(ArgAction::Set)
+                        .value_parser(is_valid_number)
+                        .num_args(1..),
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_INTERVAL)
+                        .long(CLI_ARGS_INTERVAL)
+                        .help("Seconds to wait between polls")
+                        .value_parser(is_valid_number)
+                        .default_value("60")
+                )
+                .arg_required_else_help(true)
+        );
+
+    let schema = Command::new("schema")
+        .about("Print the database schema with a description of each column")
+        .arg(
+            Arg::new(CLI_ARGS_FORMAT)
+                .long(CLI_ARGS_FORMAT)
+                .help("Output format")
+                .value_parser(["text", "markdown", "json"])
+                .default_value("text")
+        );
+
+    let doctor = Command::new("doctor")
+        .about("Check the database for orphaned, duplicate, missing and malformed rows")
+        .arg(
+            Arg::new(CLI_ARGS_FIX)
+                .long(CLI_ARGS_FIX)
+                .help("Repair the problems that don't need collecting again")
+                .action(ArgAction::SetTrue)
+        );
+
+    let query = Command::new("query")
+        .about("Run read-only SQL against the database and print the rows")
+        .arg(
+            Arg::new(CLI_ARGS_SQL)
+                .help("The statement to run, e.g. \"SELECT author, COUNT(*) FROM pulls GROUP BY author\"")
+                .required(true)
+                .index(1)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_FORMAT)
+                .long(CLI_ARGS_FORMAT)
+                .help("Output format")
+                .value_parser(["table", "json", "csv"])
+                .default_value("table")
+        );
+
+    let db = Command::new("db")
+        .about("Work with the gdfm database directly")
+        .subcommand(
+            Command::new("shell")
+                .about("Open an SQLite shell on the database with foreign keys and convenience views enabled")
+                .arg(
+                    Arg::new(CLI_ARGS_BUILTIN)
+                        .long(CLI_ARGS_BUILTIN)
+                        .help("Use the built-in shell even when sqlite3 is installed")
+                        .action(ArgAction::SetTrue)
+                )
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Merge gdfm databases collected elsewhere, such as by --shard workers, into this one")
+                .arg(
+                    Arg::new(CLI_ARGS_PATH)
+                        .help("The database files to merge")
+                        .required(true)
+                        .num_args(1..)
+                        .index(1)
+                )
+        )
+        .arg_required_else_help(true);
+
+    let simulate = Command::new("simulate")
+        .about("Estimate how a different process would have played out on collected data")
+        .subcommand(
+            Command::new("rotation")
+                .about("Estimate how a review rotation would have distributed recent review load")
+                .arg(
+                    Arg::new(CLI_ARGS_PATH)
+                        .help("The path to the repository")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_REVIEWERS)
+                        .long(CLI_ARGS_REVIEWERS)
+                        .help("Comma separated GitHub logins in rotation order")
+                        .required(true)
+                        .value_delimiter(',')
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_DAYS)
+                        .long(CLI_ARGS_DAYS)
+                        .help("Number of days of history to replay")
+                        .value_parser(is_valid_number)
+                        .default_value("90")
+                )
+                .arg_required_else_help(true)
+        );
+
+    let listen = Command::new("listen")
+        .about("Receive, verify and ingest GitHub webhook deliveries")
+        .arg(
+            Arg::new(CLI_ARGS_PORT)
+                .long(CLI_ARGS_PORT)
+                .help("Port to listen on")
+                .value_parser(clap::value_parser!(u16))
+                .default_value("8080")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_SECRET)
+                .long(CLI_ARGS_SECRET)
+                .help("Webhook secret for repositories without their own; overrides webhook.secret in the config")
+        );
+
+    let serve = Command::new("serve")
+        .about("Generate reports on request over HTTP")
+        .arg(
+            Arg::new(CLI_ARGS_PORT)
+                .long(CLI_ARGS_PORT)
+                .help("Port to listen on")
+                .value_parser(clap::value_parser!(u16))
+                .default_value("8000")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_BIND)
+                .long(CLI_ARGS_BIND)
+                .help("Address to listen on; the server has no authentication of its own")
+                .value_parser(clap::value_parser!(std::net::IpAddr))
+                .default_value("127.0.0.1")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_PUBLIC)
+                .long(CLI_ARGS_PUBLIC)
+                .help("Replace usernames with pseudonyms and refuse raw data, for a dashboard anyone can see")
+                .action(ArgAction::SetTrue)
+        );
+
+    let hook_arg = Arg::new(CLI_ARGS_HOOK)
+        .long(CLI_ARGS_HOOK)
+        .help("Webhook id; defaults to the hook of the last delivery received for the repository")
+        .value_parser(clap::value_parser!(i64));
+    let sync = Command::new("sync")
+        .about("Run the core collectors for several repositories concurrently")
+        .arg(
+            Arg::new(CLI_ARGS_REPO)
+                .help("Repositories to sync; defaults to every tracked repository")
+                .num_args(1..)
+                .index(1)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_JOBS)
+                .short('j')
+                .long(CLI_ARGS_JOBS)
+                .help("Number of repositories to sync at the same time")
+                .value_parser(is_valid_number)
+                .default_value("4")
+        )
+        .arg(
+            Arg::new(CLI_ARGS_SCHEDULE_WINDOW)
+                .long(CLI_ARGS_SCHEDULE_WINDOW)
+                .help("Only make API requests between these local times, e.g. 22:00-06:00, waiting outside them")
+                .value_parser(ScheduleWindow::parse)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_EVERY)
+                .long(CLI_ARGS_EVERY)
+                .help("Keep running, syncing again this many hours after each sync started")
+                .value_parser(clap::value_parser!(u32).range(1..))
+        )
+        .arg(
+            Arg::new(CLI_ARGS_EMAIL)
+                .long(CLI_ARGS_EMAIL)
+                .help("Email a report of every synced repository after each sync, using the [email] settings")
+                .action(ArgAction::SetTrue)
+        );
+
+    let runs = Command::new("runs")
+        .about("Inspect past collect runs and the API budget they used")
+        .subcommand(
+            Command::new("list")
+                .about("List the most recent collect runs")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("Only list runs for this repository")
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_LIMIT)
+                        .short('n')
+                        .long(CLI_ARGS_LIMIT)
+                        .help("Number of runs to list")
+                        .value_parser(is_valid_number)
+                        .default_value("20")
+                )
+        );
+
+    let features = Command::new("features")
+        .about("Show which optional APIs the GitHub host offers")
+        .arg(
+            Arg::new(CLI_ARGS_REFRESH)
+                .long(CLI_ARGS_REFRESH)
+                .help("Ask the host again instead of using what was found in the last week")
+                .action(ArgAction::SetTrue)
+        );
+
+    let deliveries = Command::new("deliveries")
+        .about("Inspect webhook deliveries received by the listener")
+        .subcommand(
+            Command::new("list")
+                .about("List the most recent deliveries received")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("Only list deliveries for this repository")
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_LIMIT)
+                        .short('n')
+                        .long(CLI_ARGS_LIMIT)
+                        .help("Number of deliveries to list")
+                        .value_parser(is_valid_number)
+                        .default_value("20")
+                )
+        )
+        .subcommand(
+            Command::new("show")
+                .about("Print the payload of a received delivery")
+                .arg(
+                    Arg::new(CLI_ARGS_DELIVERY)
+                        .help("The delivery id (X-GitHub-Delivery)")
+                        .required(true)
+                        .index(1)
+                )
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("missed")
+                .about("List recent deliveries GitHub sent that were not received or not verified")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository the webhook belongs to")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(hook_arg.clone())
+                .arg_required_else_help(true)
+        )
+        .subcommand(
+            Command::new("redeliver")
+                .about("Ask GitHub to send a delivery again")
+                .arg(
+                    Arg::new(CLI_ARGS_REPO)
+                        .help("The repository the webhook belongs to")
+                        .required(true)
+                        .index(1)
+                )
+                .arg(
+                    Arg::new(CLI_ARGS_DELIVERY)
+                        .help("The delivery id (X-GitHub-Delivery)")
+                        .required(true)
+                        .index(2)
+                )
+                .arg(hook_arg)
+                .arg_required_else_help(true)
+        );
+
+    Command::new("gdfm")
+        .about("A CLI for collecting and presenting data about GitHub repositories")
+        .subcommand_required(true)
+        .arg_required_else_help(true)
+        .arg(
+            Arg::new(CLI_ARGS_GITHUB_URL)
+                .long(CLI_ARGS_GITHUB_URL)
+                .help("Address of a GitHub Enterprise Server to use instead of github.com, e.g. https://github.example.com")
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_PROJECT)
+                .long(CLI_ARGS_PROJECT)
+                .help("Keep the data in the database of this project instead of the default one")
+                .value_parser(is_valid_project)
+                .global(true)
+        )
+        .arg(
+            Arg::new(CLI_ARGS_NO_COLOR)
+                .long(CLI_ARGS_NO_COLOR)
+                .help("Print without colors; setting NO_COLOR does the same")
+                .action(ArgAction::SetTrue)
+                .global(true)
+        )
+        .subcommand(init)
+        .subcommand(report)
+        .subcommand(release_notes)
+        .subcommand(clean)
+        .subcommand(backup)
+        .subcommand(restore)
+        .subcommand(collect)
+        .subcommand(export)
+        .subcommand(watch)
+        .subcommand(schema)
+        .subcommand(doctor)
+        .subcommand(db)
+        .subcommand(query)
+        .subcommand(simulate)
+        .subcommand(listen)
+        .subcommand(serve)
+        .subcommand(deliveries)
+        .subcommand(runs)
+        .subcommand(features)
+        .subcommand(sync)
+}
+
+pub fn is_valid_number(s: &str) -> Result<u32, String> {
+    if s.parse::<u32>().is_ok() {
+        Ok(s.parse::<u32>().unwrap())
+    } else {
+        Err("Must be a positive integer".to_string())
+    }
+}
+
+/// Project names become file names, so they are kept to letters, digits, `-` and `_`
+pub fn is_valid_project(s: &str) -> Result<String, String> {
+    if !s.is_empty() && s.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        Ok(s.to_string())
+    } else {
+        Err("Must only contain letters, digits, - and _".to_string())
+    }
+}
+
+/// Parses a `YYYY-MM-DD` date (taken as midnight UTC) or an RFC 3339 timestamp
+pub fn is_valid_since(s: &str) -> Result<DateTime<Utc>, String> {
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return Ok(date.and_time(NaiveTime::MIN).and_utc());
+    }
+    DateTime::parse_from_rfc3339(s)
+        .map(|at| at.with_timezone(&Utc))
+        .map_err(|_| "Must be a date such as 2025-01-31 or an RFC 3339 timestamp".to_string())
+}
+
+/// The context a command runs with, from its arguments and the config
+///
+/// Only collecting is limited to a schedule window, by `--schedule-window` or
+/// else `collect.schedule_window`.
+fn command_context(matches: &ArgMatches, config: &Config) -> Result<Context> {
+    let github_url = matches.get_one::<String>(CLI_ARGS_GITHUB_URL).map(String::as_str);
+    let mut context = Context::from_config(config, github_url)?;
+    context.project = matches.get_one::<String>(CLI_ARGS_PROJECT).cloned();
+
+    let collecting = match matches.subcommand() {
+        Some(("collect", sub_matches)) => sub_matches.subcommand().map(|(_, sub_matches)| sub_matches),
+        Some(("sync", sub_matches)) => Some(sub_matches),
+        _ => None,
+    };
+    match collecting {
+        Some(sub_matches) => {
+            if let Some(window) = sub_matches.get_one::<ScheduleWindow>(CLI_ARGS_SCHEDULE_WINDOW) {
+                context.schedule_window = Some(*window);
+            }
+            if matches.subcommand_name() == Some("collect") {
+                context.shard = sub_matches.get_one::<Shard>(CLI_ARGS_SHARD).copied();
+            }
+        }
+        None => context.schedule_window = None,
+    }
+
+    Ok(context)
+}
+
+/// Replaces an alias from the config at the start of a command line with what it stands for
+pub fn expand_aliases(args: Vec<String>) -> Result<Vec<String>> {
+    let config = Config::load().into_diagnostic()?;
+    let command = command();
+    let builtins: Vec<&str> = command.get_subcommands().map(|cmd| cmd.get_name()).collect();
+    // Global options that take a value, which comes before the subcommand when they do
+    let value_options: Vec<String> = command
+        .get_arguments()
+        .filter(|arg| arg.is_global_set() && arg.get_action().takes_values())
+        .filter_map(|arg| arg.get_long().map(|long| format!("--{}", long)))
+        .collect();
+    let value_options: Vec<&str> = value_options.iter().map(String::as_str).collect();
+
+    config.expand_aliases(args, &builtins, &value_options).into_diagnostic()
+}
+
+/// Runs a `gdfm` command line, such as `["gdfm", "collect", "pulls", "owner/repo"]`
+///
+/// Unlike the binary, which leaves bad arguments and `--help` to clap, this
+/// returns them as an error instead of exiting. Each command runs with a
+/// [`Context`] of its own, so commands run side by side may use different
+/// projects and API hosts.
+pub async fn run(args: Vec<String>) -> Result<()> {
+    let args = expand_aliases(args)?;
+    let matches = command().try_get_matches_from(&args).map_err(|err| miette!("{}", err))?;
+
+    execute(&matches, &args).await
+}
+
+/// Runs the command `matches` holds; `args` is the command line it was parsed from
+pub async fn execute(matches: &ArgMatches, args: &[String]) -> Result<()> {
+    let config = Config::load().into_diagnostic()?;
+
+    if matches.get_flag(CLI_ARGS_NO_COLOR) || std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+        disable_colors();
+    }
+
+    command_context(matches, &config)?.scope(dispatch(matches, args)).await
+}
+
+/// Runs the subcommand `matches` holds, within the context of the command
+async fn dispatch(matches: &ArgMatches, args: &[String]) -> Result<()> {
+    match matches.subcommand() {
+        Some(("init", sub_matches)) => {
+            init(sub_matches).await?;
+        }
+        Some(("report", sub_matches)) => {
+            report(sub_matches).await?;
+        }
+        Some(("release-notes", sub_matches)) => {
+            release_notes(sub_matches).await?;
+        }
+        Some(("query", sub_matches)) => {
+            query(sub_matches).await?;
+        }
+        Some(("schema", sub_matches)) => {
+            schema(sub_matches).await?;
+        }
+        Some(("doctor", sub_matches)) => {
+            doctor(sub_matches).await?;
+        }
+        Some(("serve", sub_matches)) => {
+            serve(sub_matches).await?;
+        }
+        Some(("clean", sub_matches)) => {
+            clean(sub_matches).await?;
+        }
+        Some(("backup", sub_matches)) => {
+            backup(sub_matches).await?;
+        }
+        Some(("restore", sub_matches)) => {
+            restore(sub_matches).await?;
+        }
+        Some(("db", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("shell", sub_matches)) => {
+                    shell(sub_matches).await?;
+                }
+                Some(("import", sub_matches)) => {
+                    import(sub_matches).await?;
+                }
+                _ => {
+                    if let Some(sub_cmd) = command().find_subcommand_mut("db") {
+                        sub_cmd.print_help().unwrap();
+                    }
+                }
+            }
+        }
+        Some(("collect", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some((collector, sub_matches)) if wildcard_owner(sub_matches).is_some() => {
+                    collect_owner(collector, sub_matches, args).await?;
+                }
+                Some(("all", sub_matches)) => {
+                    collect_all(&CollectOptions::from_matches(sub_matches)).await?;
+                }
+                Some((collector, sub_matches)) => {
+                    let options = CollectOptions::from_matches(sub_matches);
+                    record_run(collector, &options.repository, run_collector(collector, &options)).await?;
+                }
+                None => {
+                    if let Some(sub_cmd) = command().find_subcommand_mut("collect") {
+                        sub_cmd.print_help().unwrap();
+                    }
+                }
+            }
+        }
+        Some(("export", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("excel", sub_matches)) => {
+                    export_excel(sub_matches).await?;
+                }
+                Some(("csv", sub_matches)) => {
+                    export_csv(sub_matches).await?;
+                }
+                Some(("parquet", sub_matches)) => {
+                    export_parquet(sub_matches).await?;
+                }
+                Some(("postgres", sub_matches)) => {
+                    export_postgres(sub_matches).await?;
+                }
+                Some(("provenance", sub_matches)) => {
+                    export_provenance(sub_matches).await?;
+                }
+                _ => {
+                    if let Some(sub_cmd) = command().find_subcommand_mut("export") {
+                        sub_cmd.print_help().unwrap();
+                    }
+                }
+            }
+        }
+        Some(("watch", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("pr", sub_matches)) => {
+                    watch_pulls(sub_matches).await?;
+                }
+                _ => {
+                    if let Some(sub_cmd) = command().find_subcommand_mut("watch") {
+                        sub_cmd.print_help().unwrap();
+                    }
+                }
+            }
+        }
+        Some(("listen", sub_matches)) => {
+            listen(sub_matches).await?;
+        }
+        Some(("deliveries", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("list", sub_matches)) => {
+                    list_deliveries(sub_matches).await?;
+                }
+                Some(("show", sub_matches)) => {
+                    show_delivery(sub_matches).await?;
+                }
+                Some(("missed", sub_matches)) => {
+                    missed_deliveries(sub_matches).await?;
+                }
+                Some(("redeliver", sub_matches)) => {
+                    redeliver(sub_matches).await?;
+                }
+                _ => {
+                    if let Some(sub_cmd) = command().find_subcommand_mut("deliveries") {
+                        sub_cmd.print_help().unwrap();
+                    }
+                }
+            }
+        }
+        Some(("sync", sub_matches)) => {
+            sync(sub_matches).await?;
+        }
+        Some(("runs", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("list", sub_matches)) => {
+                    list_runs(sub_matches).await?;
+                }
+                _ => {
+                    if let Some(sub_cmd) = command().find_subcommand_mut("runs") {
+                        sub_cmd.print_help().unwrap();
+                    }
+                }
+            }
+        }
+        Some(("features", sub_matches)) => {
+            list_features(sub_matches).await?;
+        }
+        Some(("simulate", sub_matches)) => {
+            match sub_matches.subcommand() {
+                Some(("rotation", sub_matches)) => {
+                    simulate_rotation(sub_matches).await?;
+                }
+                _ => {
+                    if let Some(sub_cmd) = command().find_subcommand_mut("simulate") {
+                        sub_cmd.print_help().unwrap();
+                    }
+                }
+            }
+        }
+        _ => {
+            command().print_help().unwrap();
+        }
+    }
+
+    let collected = match matches.subcommand() {
+        Some(("collect", sub_matches)) => sub_matches.subcommand().is_some(),
+        Some(("sync", _)) => true,
+        _ => false,
+    };
+    if collected {
+        export_after_collection().await?;
+    }
+
+    Ok(())
+}
\ No newline at end of file
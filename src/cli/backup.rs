@@ -0,0 +1,321 @@
+/// Module holding the `backup` and `restore` commands
+///
+/// A backup is newline-delimited JSON: a header, then for every table a line
+/// with its `CREATE TABLE` statement followed by a line per row, so backups of
+/// any size are written and read a row at a time. Restoring loads the rows into
+/// a temporary database and merges it into this one, replacing the rows it
+/// matches, so a backup taken on another machine can be restored too. The
+/// temporary tables are made from this version's schema rather than the
+/// statements in the backup, which are only there for people reading it.
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use clap::ArgMatches;
+use console::style;
+use futures_util::TryStreamExt;
+use miette::{miette, IntoDiagnostic, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions};
+use sqlx::{Connection, Pool, SqliteConnection, Transaction};
+
+use crate::constants::{APP_NAME, CLI_ARGS_PATH, CLI_ARGS_REPO};
+use crate::database::{
+    merge_database, repository_filter, row_values, setup_db, table_columns, table_info, CellValue, ColumnInfo,
+    Repository, TABLES,
+};
+
+/// Version of the backup format, raised whenever it changes incompatibly
+const BACKUP_FORMAT: u32 = 1;
+
+/// One line of a backup
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum Line {
+    Header {
+        format: u32,
+        tool: String,
+        version: String,
+        created_at: String,
+        /// Repositories the backup is limited to; empty when it holds the whole database
+        repositories: Vec<String>,
+    },
+    Table {
+        table: String,
+        sql: String,
+    },
+    Row {
+        table: String,
+        row: Map<String, Value>,
+    },
+}
+
+pub async fn backup(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<String>(CLI_ARGS_PATH).expect("file is required");
+    let pool = setup_db().await.into_diagnostic()?;
+
+    let repositories: Vec<String> = matches.get_many::<String>(CLI_ARGS_REPO).into_iter().flatten().cloned().collect();
+    let mut ids = Vec::new();
+    for repository in &repositories {
+        let repo = Repository::from(&pool, repository)
+            .await
+            .map_err(|_| miette!("{} is not tracked", repository))?;
+        ids.push(repo.id.to_string());
+    }
+    let ids = ids.join(", ");
+
+    let mut out = BufWriter::new(File::create(path).into_diagnostic()?);
+    write_line(&mut out, &Line::Header {
+        format: BACKUP_FORMAT,
+        tool: APP_NAME.to_string(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: chrono::Utc::now().to_string(),
+        repositories: repositories.clone(),
+    })?;
+
+    let mut written = 0;
+    for table in TABLES {
+        let columns = table_columns(&pool, table).await.into_diagnostic()?;
+        let filter = match repository_filter(table, &columns, &ids) {
+            Some(filter) if !repositories.is_empty() => format!("WHERE {}", filter),
+            // Tables that aren't tied to a repository only go into backups of everything
+            None if !repositories.is_empty() => continue,
+            _ => String::new(),
+        };
+        let sql: String = sqlx::query_scalar("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = $1")
+            .bind(table)
+            .fetch_one(&pool)
+            .await
+            .into_diagnostic()?;
+        write_line(&mut out, &Line::Table { table: table.to_string(), sql })?;
+
+        let query = format!("SELECT * FROM {} {} ORDER BY rowid", table, filter);
+        let mut rows = sqlx::query(&query).fetch(&pool);
+        while let Some(row) = rows.try_next().await.into_diagnostic()? {
+            let row: Map<String, Value> = columns
+                .iter()
+                .cloned()
+                .zip(row_values(&row).into_diagnostic()?.into_iter().map(CellValue::into_json))
+                .collect();
+            write_line(&mut out, &Line::Row { table: table.to_string(), row })?;
+            written += 1;
+        }
+    }
+    out.flush().into_diagnostic()?;
+
+    println!("Backed up {} rows to {}", style(written).bold().cyan(), style(path).bold().cyan());
+
+    Ok(())
+}
+
+pub async fn restore(matches: &ArgMatches) -> Result<()> {
+    let path = matches.get_one::<String>(CLI_ARGS_PATH).expect("file is required");
+    let pool = setup_db().await.into_diagnostic()?;
+
+    let staging = staging_file()?;
+    let restored = match stage(&pool, path, &staging).await {
+        Ok(rows) => merge_database(&pool, &staging.to_string_lossy(), true)
+            .await
+            .map(|merged| (rows, merged))
+            .map_err(|err| miette!("Could not restore {}: {}", path, err)),
+        Err(err) => Err(err),
+    };
+    let _ = std::fs::remove_file(&staging);
+    let (rows, merged) = restored?;
+
+    println!(
+        "Restored {} rows from {}, {} of them new or changed",
+        style(rows).bold().cyan(),
+        style(path).bold().cyan(),
+        merged
+    );
+
+    Ok(())
+}
+
+/// Creates an empty file for the temporary database of a restore, under a
+/// name no other file had
+fn staging_file() -> Result<PathBuf> {
+    loop {
+        let mut bytes = [0u8; 8];
+        getrandom::getrandom(&mut bytes).map_err(|err| miette!("Could not name the restore's database: {}", err))?;
+        let path = std::env::temp_dir().join(format!("{}-restore-{}.db", APP_NAME, hex::encode(bytes)));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(_) => return Ok(path),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(miette!("Could not create {}: {}", path.display(), err)),
+        }
+    }
+}
+
+/// Loads a backup into the empty database at `staging` and returns the number of rows it holds
+///
+/// Each table is created from the schema of `pool`, with the columns of it the
+/// backup's rows have, so merging leaves the others alone.
+async fn stage(pool: &Pool<Sqlite>, path: &str, staging: &Path) -> Result<u64> {
+    let file = File::open(path).map_err(|err| miette!("Could not open {}: {}", path, err))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header = lines.next().ok_or_else(|| miette!("{} is empty", path))?.into_diagnostic()?;
+    match serde_json::from_str(&header) {
+        Ok(Line::Header { format, .. }) if format <= BACKUP_FORMAT => {}
+        Ok(Line::Header { format, .. }) => {
+            return Err(miette!("{} is a newer backup (format {}); upgrade gdfm to restore it", path, format));
+        }
+        _ => return Err(miette!("{} is not a gdfm backup", path)),
+    }
+
+    let options = SqliteConnectOptions::new().filename(staging).foreign_keys(false);
+    let mut connection = SqliteConnection::connect_with(&options).await.into_diagnostic()?;
+    let mut transaction = connection.begin().await.into_diagnostic()?;
+    // Tables of the backup, with the columns they were created with once a row came
+    let mut tables: HashMap<String, Option<Vec<String>>> = HashMap::new();
+    let mut rows = 0;
+
+    for (number, line) in lines.enumerate() {
+        let line = line.into_diagnostic()?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        // The header is line 1
+        let invalid = |err: String| miette!("Line {} of {} is invalid: {}", number + 2, path, err);
+        match serde_json::from_str(&line).map_err(|err| invalid(err.to_string()))? {
+            // Tables this version doesn't know are skipped, as they couldn't be merged
+            Line::Table { table, .. } if TABLES.contains(&table.as_str()) => {
+                tables.insert(table, None);
+            }
+            Line::Table { .. } => {}
+            Line::Row { table, row } if tables.contains_key(&table) => {
+                let columns = match tables.get_mut(&table).expect("the table was declared") {
+                    Some(columns) => columns,
+                    created => {
+                        let known: Vec<ColumnInfo> = table_info(pool, &table)
+                            .await
+                            .into_diagnostic()?
+                            .into_iter()
+                            .filter(|column| row.contains_key(&column.name))
+                            .collect();
+                        if known.is_empty() {
+                            return Err(invalid(format!("a row of {} has none of its columns", table)));
+                        }
+                        create_staged_table(&mut transaction, &table, &known).await?;
+                        created.insert(known.into_iter().map(|column| column.name).collect())
+                    }
+                };
+                // Columns this version doesn't know are left out
+                let values: Vec<(&String, &Value)> =
+                    columns.iter().filter_map(|column| Some((column, row.get(column)?))).collect();
+                let insert = format!(
+                    "INSERT INTO {} (\"{}\") VALUES ({})",
+                    table,
+                    values.iter().map(|(column, _)| column.as_str()).collect::<Vec<_>>().join("\", \""),
+                    vec!["?"; values.len()].join(", ")
+                );
+                let mut query = sqlx::query(&insert);
+                for (_, value) in values {
+                    query = match value {
+                        Value::Null => query.bind(None::<i64>),
+                        Value::Bool(value) => query.bind(i64::from(*value)),
+                        Value::Number(number) => match number.as_i64() {
+                            Some(value) => query.bind(value),
+                            None => query.bind(number.as_f64()),
+                        },
+                        Value::String(value) => query.bind(value.clone()),
+                        value => query.bind(value.to_string()),
+                    };
+                }
+                query.execute(&mut *transaction).await.map_err(|err| invalid(err.to_string()))?;
+                rows += 1;
+            }
+            Line::Row { table, .. } if TABLES.contains(&table.as_str()) => {
+                return Err(invalid(format!("a row of {} comes before the table", table)));
+            }
+            Line::Row { .. } => {}
+            Line::Header { .. } => return Err(invalid("a second header".to_string())),
+        }
+    }
+    // Tables without rows are merged as empty ones
+    for (table, columns) in &tables {
+        if columns.is_none() {
+            let columns = table_info(pool, table).await.into_diagnostic()?;
+            create_staged_table(&mut transaction, table, &columns).await?;
+        }
+    }
+    transaction.commit().await.into_diagnostic()?;
+    connection.close().await.into_diagnostic()?;
+
+    Ok(rows)
+}
+
+/// Creates a table of the staging database with `columns` of the table of the same name here
+///
+/// Staged rows are only read to be merged, so constraints and indexes are left out.
+async fn create_staged_table(
+    transaction: &mut Transaction<'_, Sqlite>,
+    table: &str,
+    columns: &[ColumnInfo],
+) -> Result<()> {
+    let columns: Vec<String> =
+        columns.iter().map(|column| format!("\"{}\" {}", column.name, column.data_type)).collect();
+    sqlx::query(&format!("CREATE TABLE \"{}\" ({})", table, columns.join(", ")))
+        .execute(&mut **transaction)
+        .await
+        .into_diagnostic()?;
+
+    Ok(())
+}
+
+fn write_line(out: &mut impl Write, line: &Line) -> Result<()> {
+    serde_json::to_writer(&mut *out, line).into_diagnostic()?;
+    writeln!(out).into_diagnostic()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::open_db;
+
+    #[tokio::test]
+    async fn restores_ignore_the_statements_in_the_backup() {
+        let local = staging_file().unwrap();
+        let pool = open_db(&local).await.unwrap();
+        let backup = local.with_extension("jsonl");
+        let staging = staging_file().unwrap();
+        let lines = [
+            r#"{"kind":"header","format":1,"tool":"gdfm","version":"0.1.0","created_at":"","repositories":[]}"#,
+            r#"{"kind":"table","table":"repositories","sql":"CREATE TABLE repositories (id); CREATE TABLE evil (id)"}"#,
+            r#"{"kind":"row","table":"repositories","row":{"id":7,"owner":"octo","name":"repo","stars":3}}"#,
+            r#"{"kind":"table","table":"pulls","sql":"DROP TABLE repositories"}"#,
+        ];
+        std::fs::write(&backup, lines.join("\n")).unwrap();
+
+        let staged = stage(&pool, backup.to_str().unwrap(), &staging).await;
+        let mut connection = SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(&staging))
+            .await
+            .unwrap();
+        let tables: Vec<String> =
+            sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' ORDER BY name")
+                .fetch_all(&mut connection)
+                .await
+                .unwrap();
+        connection.close().await.unwrap();
+        let merged = merge_database(&pool, &staging.to_string_lossy(), true).await;
+        let repositories: Vec<(String, String)> =
+            sqlx::query_as("SELECT owner, name FROM repositories").fetch_all(&pool).await.unwrap();
+        pool.close().await;
+        for path in [&local, &backup, &staging] {
+            for suffix in ["", "-wal", "-shm"] {
+                let mut path = path.clone().into_os_string();
+                path.push(suffix);
+                let _ = std::fs::remove_file(path);
+            }
+        }
+
+        assert_eq!(staged.unwrap(), 1);
+        assert_eq!(merged.unwrap(), 1);
+        assert_eq!(tables, ["pulls", "repositories"]);
+        assert_eq!(repositories, [("octo".to_string(), "repo".to_string())]);
+    }
+}
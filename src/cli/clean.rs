@@ -1,13 +1,30 @@
 /// Module for commands that removes the database file
+///
+/// With `--repo`, only the given repositories and everything collected for
+/// them are removed, and the data of every other repository is kept.
 use clap::ArgMatches;
-use miette::{Result, IntoDiagnostic};
+use console::style;
+use miette::{miette, Result, IntoDiagnostic};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
 
-use crate::constants::CLI_ARGS_YES;
-use crate::database::destroy_db;
+use crate::constants::{CLI_ARGS_DRY_RUN, CLI_ARGS_REPO, CLI_ARGS_YES};
+use crate::database::{
+    count_repository_rows, delete_repository, destroy_db, get_db_path, setup_db, table_columns, Repository, TABLES,
+};
+use crate::terminal::Table;
 
 pub async fn clean(matches: &ArgMatches) -> Result<()> {
     let force = matches.get_flag(CLI_ARGS_YES);
 
+    if let Some(repositories) = matches.get_many::<String>(CLI_ARGS_REPO) {
+        return clean_repositories(repositories.map(String::as_str).collect(), force, matches.get_flag(CLI_ARGS_DRY_RUN))
+            .await;
+    }
+    if matches.get_flag(CLI_ARGS_DRY_RUN) {
+        return dry_run().await;
+    }
+
     if force {
         destroy_db().await.into_diagnostic()?;
     } else {
@@ -22,4 +39,113 @@ pub async fn clean(matches: &ArgMatches) -> Result<()> {
     }
 
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Removes some repositories and everything collected for them, after asking unless `force` is set
+async fn clean_repositories(paths: Vec<&str>, force: bool, dry_run: bool) -> Result<()> {
+    let pool = setup_db().await.into_diagnostic()?;
+    let mut repositories = Vec::new();
+    for path in paths {
+        let repo = Repository::from(&pool, path)
+            .await
+            .map_err(|_| miette!("{} is not tracked", path))?;
+        repositories.push((path, repo));
+    }
+
+    if dry_run {
+        for (path, repo) in &repositories {
+            let counts = count_repository_rows(&pool, repo.id).await.into_diagnostic()?;
+            println!("Would remove {}:", style(path).bold().cyan());
+            row_counts(&counts, "Table").print();
+            println!("{} rows in total\n", counts.iter().map(|(_, rows)| rows).sum::<i64>());
+        }
+        println!("Nothing was removed (dry run).");
+        return Ok(());
+    }
+
+    for (path, repo) in repositories {
+        if !force {
+            let confirm = dialoguer::Confirm::new()
+                .with_prompt(format!("Are you sure you want to remove {} and all data collected for it?", path))
+                .interact()
+                .into_diagnostic()?;
+            if !confirm {
+                continue;
+            }
+        }
+        let deleted = delete_repository(&pool, repo.id).await.into_diagnostic()?;
+        println!("Removed {} ({} rows)", style(path).bold().cyan(), deleted);
+    }
+
+    Ok(())
+}
+
+/// Prints what `clean` would remove without removing anything
+async fn dry_run() -> Result<()> {
+    let path = get_db_path();
+    let Ok(metadata) = std::fs::metadata(&path) else {
+        println!("No database at {}; nothing would be removed.", path.display());
+        return Ok(());
+    };
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let repositories = rows_per_repository(&pool).await.into_diagnostic()?;
+
+    println!(
+        "Would remove {} ({:.2} MB)\n",
+        path.display(),
+        metadata.len() as f64 / (1024.0 * 1024.0)
+    );
+
+    if repositories.is_empty() {
+        println!("No repositories");
+    } else {
+        row_counts(&repositories, "Repository").print();
+    }
+
+    let mut tables = Vec::new();
+    for table in TABLES {
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+            .fetch_one(&pool)
+            .await
+            .into_diagnostic()?;
+        tables.push((table.to_string(), rows));
+    }
+    let total: i64 = tables.iter().map(|(_, rows)| rows).sum();
+    println!();
+    row_counts(&tables, "Table").print();
+
+    println!("\n{} rows in total. Nothing was removed (dry run).", total);
+
+    Ok(())
+}
+
+/// Rows of each repository or table, as `clean` lists what it would remove
+fn row_counts<T: AsRef<str>>(counts: &[(T, i64)], what: &str) -> Table {
+    let mut table = Table::new(&[what, "Rows"]).align_right(1);
+    for (name, rows) in counts {
+        table.push(vec![name.as_ref().to_string(), rows.to_string()]);
+    }
+    table
+}
+
+/// Counts the rows belonging to each repository across every table with a `repo_id`
+async fn rows_per_repository(pool: &Pool<Sqlite>) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    let mut counts = Vec::new();
+    for table in TABLES {
+        if table_columns(pool, table).await?.iter().any(|column| column == "repo_id") {
+            counts.push(format!("SELECT repo_id, COUNT(*) AS rows FROM {} GROUP BY repo_id", table));
+        }
+    }
+
+    let query = format!(
+        "SELECT r.owner || '/' || r.name, COALESCE(SUM(c.rows), 0)
+        FROM repositories r
+        LEFT JOIN ({}) c ON c.repo_id = r.id
+        GROUP BY r.id
+        ORDER BY r.owner, r.name",
+        counts.join(" UNION ALL ")
+    );
+
+    sqlx::query_as(&query).fetch_all(pool).await
+}
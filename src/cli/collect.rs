@@ -2,199 +2,2399 @@
 /// 
 /// This module collects the data from the repositories and stores it in the database.
 /// We do this using the GitHub API.
+use std::collections::HashMap;
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
 use clap::ArgMatches;
+use http::header::{HeaderMap, HeaderValue, ACCEPT};
 use miette::{miette, Result, IntoDiagnostic};
 use octocrab::params::State;
-use octocrab::Octocrab;
-use indicatif::{ProgressBar, ProgressStyle};
+use futures_util::future::try_join_all;
+use octocrab::{Octocrab, Page};
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use tokio::time::{sleep, Duration};
 
+use crate::client::{
+    allowed_concurrency, github_client, graphql_client, retry, RateLimiter, Raw,
+};
+use crate::cli::runs::CURRENT_RUN;
+use crate::config::Config;
+use crate::context::Context;
 use crate::constants::{
     CLI_ARGS_REPO,
-    CLI_ARGS_NUMBER
+    CLI_ARGS_NUMBER,
+    CLI_ARGS_ALL_EVENTS,
+    CLI_ARGS_MISSING_ONLY,
+    CLI_ARGS_STATE,
+    CLI_ARGS_SINCE,
+    CLI_ARGS_FULL,
+    CLI_ARGS_RESUME,
+    CLI_ARGS_RANGE,
+    CLI_ARGS_STRICT,
+    CLI_ARGS_KEEP_RAW,
+    CLI_ARGS_CONCURRENCY,
+    CLI_ARGS_THROTTLE_MS
 };
-use crate::database::{
-    setup_db,
-    Repository,
-    PullRequest as DbPullRequest,
-    PullRequestReview,
-    PullRequestEvent
+use crate::database::{PullRequest, Repository};
+use crate::features::unavailable_collectors;
+use crate::forge::{open_forge, Etags, Forge, ForgeError, Listing, FORGE_COLLECTORS, GITHUB};
+use crate::storage::{open_storage, Storage};
+use crate::github::{
+    ActivityEvent,
+    assignee_logins,
+    checklist_counts,
+    closing_issue_numbers,
+    event_type_name,
+    requested_reviewers,
+    Branch,
+    Collaborator,
+    Comparison,
+    Contributor,
+    ContributorStats,
+    Deployment,
+    DeploymentStatus,
+    DiscussionsData,
+    GraphQlResponse,
+    IssueComment,
+    ReviewComment,
+    IssueWithReactions,
+    RepositoryCommit,
+    RepositoryIssueEvent,
+    RepositoryTeam,
+    SbomResponse,
+    Stargazer,
+    STAR_MEDIA_TYPE,
+    Traffic,
+    DISCUSSIONS_QUERY,
+    WIKI_EVENT_TYPE,
+    DISAPPEARING_EVENT_TYPES
 };
 
-pub async fn collect_pull_requests(matches: &ArgMatches) -> Result<()> {
-    let github_api_token = std::env::var("GITHUB_TOKEN")
-        .map_err(|_| miette!("GitHub token not found. Please set GITHUB_TOKEN environment variable"))?;
+/// Options of the collectors, as `gdfm collect` takes them on the command line
+///
+/// Each collector reads the options it has arguments for and ignores the rest.
+#[derive(Debug, Clone)]
+pub struct CollectOptions {
+    /// Repository to collect from, as `owner/name`
+    pub repository: String,
+    /// Pull request numbers to collect, from `--number`; every one when empty
+    pub numbers: Vec<u32>,
+    /// Pull requests `collect pulls` fetches by state
+    pub state: State,
+    /// Only pull requests with nothing stored yet, from `--missing-only`
+    pub missing_only: bool,
+    /// Every event type rather than those in `collect.event_types`, from `--all-events`
+    pub all_events: bool,
+    /// Only what changed after this time instead of since the last run, from `--since`
+    pub since: Option<DateTime<Utc>>,
+    /// Everything again instead of only what changed since the last run, from `--full`
+    pub full: bool,
+    /// Continue where an unfinished run stopped, from `--resume`
+    pub resume: bool,
+    /// Only pull requests numbered in this range, from `--range`
+    pub range: Option<NumberRange>,
+    /// Fail on incomplete items, also when `collect.strict` is off
+    pub strict: bool,
+    /// Store the JSON of every item, also when `collect.keep_raw` is off
+    pub keep_raw: bool,
+    /// Pull requests fetched at the same time, from `--concurrency`
+    pub concurrency: u32,
+    /// Fixed pause between pull requests, from `--throttle-ms`
+    pub throttle: Option<Duration>,
+}
+
+impl CollectOptions {
+    /// Default options for a repository, as given by `gdfm collect <collector> owner/name`
+    pub fn new(repository: &str) -> Self {
+        Self {
+            repository: repository.to_string(),
+            numbers: Vec::new(),
+            state: State::Closed,
+            missing_only: false,
+            all_events: false,
+            since: None,
+            full: false,
+            resume: false,
+            range: None,
+            strict: false,
+            keep_raw: false,
+            concurrency: 1,
+            throttle: None,
+        }
+    }
+
+    /// Options from the arguments of a `gdfm collect` subcommand; options the
+    /// collector has no argument for keep their defaults
+    pub fn from_matches(matches: &ArgMatches) -> Self {
+        let flag = |id: &str| matches.try_get_one::<bool>(id).ok().flatten().copied().unwrap_or(false);
+        let repository = matches.try_get_one::<String>(CLI_ARGS_REPO).ok().flatten().cloned().unwrap_or_default();
+        let defaults = Self::new(&repository);
+
+        Self {
+            numbers: matches
+                .try_get_many::<u32>(CLI_ARGS_NUMBER)
+                .ok()
+                .flatten()
+                .map(|numbers| numbers.copied().collect())
+                .unwrap_or_default(),
+            state: match matches.try_get_one::<String>(CLI_ARGS_STATE).ok().flatten().map(String::as_str) {
+                Some("open") => State::Open,
+                Some("all") => State::All,
+                _ => State::Closed,
+            },
+            missing_only: flag(CLI_ARGS_MISSING_ONLY),
+            all_events: flag(CLI_ARGS_ALL_EVENTS),
+            since: matches.try_get_one::<DateTime<Utc>>(CLI_ARGS_SINCE).ok().flatten().copied(),
+            full: flag(CLI_ARGS_FULL),
+            resume: flag(CLI_ARGS_RESUME),
+            range: matches.try_get_one::<NumberRange>(CLI_ARGS_RANGE).ok().flatten().copied(),
+            strict: flag(CLI_ARGS_STRICT),
+            keep_raw: flag(CLI_ARGS_KEEP_RAW),
+            concurrency: matches
+                .try_get_one::<u32>(CLI_ARGS_CONCURRENCY)
+                .ok()
+                .flatten()
+                .copied()
+                .unwrap_or(defaults.concurrency),
+            throttle: matches
+                .try_get_one::<u32>(CLI_ARGS_THROTTLE_MS)
+                .ok()
+                .flatten()
+                .map(|millis| Duration::from_millis(u64::from(*millis))),
+            ..defaults
+        }
+    }
+}
+
+/// Runs the `collect` subcommand with the given name
+pub async fn run_collector(collector: &str, options: &CollectOptions) -> Result<()> {
+    let repo = tracked_repository(options).await?;
+    if let Some(repo) = repo.as_ref().filter(|repo| repo.forge != GITHUB) {
+        return collect_from_forge(collector, repo, options).await;
+    }
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let unavailable = unavailable_collectors(storage.as_ref()).await.into_diagnostic()?;
+    if let Some((_, reason)) = unavailable.iter().find(|(name, _)| *name == collector) {
+        return Err(miette!("`gdfm collect {}` is not available: {}", collector, reason));
+    }
+
+    match collector {
+        "pulls" => collect_pull_requests(options).await,
+        "issues" => collect_issues(options).await,
+        "events" | "reviews" => match repo {
+            Some(repo) => collect_from_forge(collector, &repo, options).await,
+            None => Err(miette!("{} is not tracked", options.repository)),
+        },
+        "files" => collect_files(options).await,
+        "pull-details" => collect_pull_details(options).await,
+        "wiki" => collect_wiki(options).await,
+        "comments" => collect_comments(options).await,
+        "review-comments" => collect_review_comments(options).await,
+        "commits" => collect_commits(options).await,
+        "governance" => collect_governance(options).await,
+        "releases" => collect_releases(options).await,
+        "deployments" => collect_deployments(options).await,
+        "labels" => collect_labels(options).await,
+        "discussions" => collect_discussions(options).await,
+        "milestones" => collect_milestones(options).await,
+        "stars" => collect_stars(options).await,
+        "forks" => collect_forks(options).await,
+        "contributors" => collect_contributors(options).await,
+        "maintainers" => collect_maintainers(options).await,
+        "traffic" => collect_traffic(options).await,
+        "demand" => collect_issue_demand(options).await,
+        "dependencies" => collect_dependencies(options).await,
+        "repo-events" => collect_repository_events(options).await,
+        _ => Err(miette!("Unknown collector {}", collector)),
+    }
+}
+
+/// The repository a collector runs against, if it is tracked
+///
+/// Repositories that aren't tracked are left to the collector to complain about.
+async fn tracked_repository(options: &CollectOptions) -> Result<Option<Repository>> {
+    let storage = open_storage().await.into_diagnostic()?;
+
+    Ok(storage.get_repository(&options.repository).await.ok())
+}
+
+/// Runs one of the `FORGE_COLLECTORS` through the forge hosting a repository
+///
+/// Events and reviews are collected this way on every forge, pull requests and
+/// comments only on forges other than GitHub, whose own collectors store more.
+async fn collect_from_forge(collector: &str, repo: &Repository, options: &CollectOptions) -> Result<()> {
+    if !FORGE_COLLECTORS.contains(&collector) {
+        return Err(miette!(
+            "`gdfm collect {}` is not available for {} projects; only {} are",
+            collector,
+            repo.forge,
+            FORGE_COLLECTORS.join(", ")
+        ));
+    }
+    let forge = open_forge(repo).await.into_diagnostic()?;
+
+    match collector {
+        "events" => collect_pull_events(options, repo, forge.as_ref()).await,
+        "reviews" => collect_pull_reviews(options, repo, forge.as_ref()).await,
+        "pulls" => collect_forge_pulls(options, repo, forge.as_ref()).await,
+        _ => collect_forge_comments(options, repo, forge.as_ref()).await,
+    }
+}
+
+/// Collects the pull requests of a project on a forge other than GitHub
+async fn collect_forge_pulls(options: &CollectOptions, repo: &Repository, forge: &dyn Forge) -> Result<()> {
+    let storage = open_storage().await.into_diagnostic()?;
+    let strict = strict(options)?;
+    let range = options.range;
+
+    // Every state is fetched at once, so there is only the one cursor, which a range doesn't use
+    let cursor = "pulls:all";
+    let since = match range {
+        Some(_) => None,
+        None => collection_start(options, storage.as_ref(), repo.id, cursor).await?,
+    };
+    let spinner = get_spinner("Fetching pull requests");
+    let mut pulls = forge.list_pulls(since).await.into_diagnostic()?;
+    pulls.retain(|pull| range.is_none_or(|range| range.contains(pull.number)) && in_shard(pull.number));
+    spinner.finish_and_clear();
+
+    for pull in &pulls {
+        require_fields(strict, &format!("Pull request #{}", pull.number), &[("author", pull.author.is_empty())])?;
+    }
+    storage.create_forge_pulls(repo.id, &pulls).await.into_diagnostic()?;
+    println!("Finished fetching pull requests ({} stored)", pulls.len());
+    if range.is_none() {
+        advance_cursor(storage.as_ref(), repo.id, cursor, pulls.iter().map(|pull| pull.updated_at).max()).await?;
+    }
+    storage.snapshot_associations(repo.id).await.into_diagnostic()?;
+
+    Ok(())
+}
+
+/// Collects the comments on every pull request of a project on a forge other than GitHub
+async fn collect_forge_comments(options: &CollectOptions, repo: &Repository, forge: &dyn Forge) -> Result<()> {
+    let storage = open_storage().await.into_diagnostic()?;
+    let strict = strict(options)?;
+    let range = options.range;
+
+    let pulls: Vec<PullRequest> = storage
+        .get_pull_requests(repo.id, &[])
+        .await
+        .into_diagnostic()?
+        .into_iter()
+        .filter(|pull| !pull.is_issue && range.is_none_or(|range| range.contains(pull.number)) && in_shard(pull.number))
+        .collect();
+
+    let progress_bar = get_progress_bar(pulls.len() as u64, "Fetching comments");
+    for pull in &pulls {
+        for comment in forge.list_comments(pull.number).await.into_diagnostic()? {
+            let item = format!("A comment on pull request #{}", pull.number);
+            require_fields(strict, &item, &[("author", comment.author.is_empty())])?;
+            storage.create_forge_comment(pull.id, &comment).await.into_diagnostic()?;
+        }
+        progress_bar.inc(1);
+    }
+    progress_bar.finish_with_message("Finished fetching comments");
+    storage.snapshot_associations(repo.id).await.into_diagnostic()?;
+
+    Ok(())
+}
 
-    let project_name= matches
-        .get_one::<String>(CLI_ARGS_REPO)
-        .expect("repository is required");
+pub async fn collect_pull_requests(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
     
-    let pool = setup_db().await.into_diagnostic()?;
-    let repo_db = Repository::from(&pool, project_name).await.into_diagnostic()?;
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo_db = storage.get_repository(project_name).await.into_diagnostic()?;
 
-    let octocrab = Octocrab::builder()
-        .personal_token(github_api_token)
-        .build()
-        .into_diagnostic()?;
+    let state = options.state;
+    let state_name = match state {
+        State::Open => "open",
+        State::All => "all",
+        _ => "closed",
+    };
+    // Each state has its own cursor, as they cover different pull requests. A
+    // range is a slice of its own, so it neither uses nor moves the cursor.
+    let cursor = format!("pulls:{}", state_name);
+    let range = options.range;
+    let since = match range {
+        Some(_) => None,
+        None => collection_start(options, storage.as_ref(), repo_db.id, &cursor).await?,
+    };
+    let scope = checkpoint_scope(&cursor, range);
+    let resume_after = resume_from(options, storage.as_ref(), repo_db.id, "pulls", &scope).await?;
+    let store_checklists = Config::load().into_diagnostic()?.collect.checklists.unwrap_or(true);
+    let strict = strict(options)?;
+    let keep_raw = keep_raw(options)?;
+
+    let octocrab = github_client().await?;
 
     let per_page = 100;
 
     let total_prs = get_total_pull_requests(
-        &octocrab, &repo_db.owner, &repo_db.name
+        &octocrab, &repo_db.owner, &repo_db.name, state, since
     ).await.into_diagnostic()?;   
 
     if total_prs > 0 {
         let total_pages = (total_prs as f64 / per_page as f64).ceil() as u32;
         let progress_bar = get_progress_bar(total_prs as u64, "Fetching pull requests");
+        let mut limiter = RateLimiter::new(&progress_bar);
+        let mut latest = None;
 
-        for page in 1..=total_pages {
-            let pulls = octocrab
-                .pulls(&repo_db.owner, &repo_db.name)
-                .list()
-                .state(State::Closed)
-                .per_page(per_page)
-                .page(page)
-                .send()
-                .await.into_diagnostic()?;
+        // Most recently updated first, so paging can stop at the first unchanged pull
+        // request, or oldest first for a range, so that numbers go up page by page
+        let (sort, direction) = match range {
+            Some(_) => ("created", "asc"),
+            None => ("updated", "desc"),
+        };
+        // Fetched as JSON first so `--keep-raw` can store what GitHub sent
+        let list_page = |page: u32| {
+            let route = format!(
+                "/repos/{}/{}/pulls?state={}&sort={}&direction={}&per_page={}&page={}",
+                repo_db.owner, repo_db.name, state_name, sort, direction, per_page, page
+            );
+            let octocrab = &octocrab;
+            async move {
+                retry(|| octocrab.get::<Page<Raw<octocrab::models::pulls::PullRequest>>, _, ()>(&route, None))
+                    .await
+                    .into_diagnostic()
+            }
+        };
+
+        // Pull requests only move to the front of the listing when updated, so
+        // whatever the checkpoint hadn't reached is still on its page or later
+        let mut first_page = resume_after.and_then(|checkpoint| checkpoint.page).unwrap_or(1);
+        if let Some(range) = range {
+            // Binary search for the first page reaching the start of the range
+            let (mut low, mut high) = (1, total_pages);
+            while low < high {
+                let middle = (low + high) / 2;
+                limiter.wait().await;
+                let pulls = list_page(middle).await?;
+                if pulls.items.last().is_some_and(|pull| (pull.number as u32) < range.start) {
+                    low = middle + 1;
+                } else {
+                    high = middle;
+                }
+            }
+            first_page = first_page.max(low);
+        }
+        progress_bar.set_position(((first_page - 1) * per_page as u32).min(total_prs) as u64);
 
-            for pull in pulls {
-                let _pull_db = DbPullRequest::create(&pool, &pull, repo_db.id).await.into_diagnostic()?;
+        for page in first_page..=total_pages {
+            limiter.wait().await;
+            let pulls = list_page(page).await?;
+
+            // Pull requests of the page to store, which happens at once when the page has been gone through
+            let mut stored = Vec::new();
+            let mut raw = Vec::new();
+            let mut finished = false;
+            let mut last = None;
+            for pull in &pulls.items {
+                if let Some(range) = range {
+                    if (pull.number as u32) < range.start {
+                        continue;
+                    }
+                    if !range.contains(pull.number as u32) {
+                        finished = true;
+                        break;
+                    }
+                }
+                if since.is_some_and(|since| pull.updated_at.is_some_and(|updated_at| updated_at <= since)) {
+                    finished = true;
+                    break;
+                }
+                // Oldest first, so the updates don't tell where the checkpoint was
+                let updated_at = pull.updated_at.filter(|_| range.is_none());
+                if resume_after.is_some_and(|checkpoint| checkpoint.covers(pull.number as u32, updated_at)) {
+                    progress_bar.inc(1);
+                    continue;
+                }
+                latest = latest.max(pull.updated_at);
+                last = Some((pull.number as u32, updated_at));
+                // Other shards store this one, but it still counts towards the cursor
+                if !in_shard(pull.number as u32) {
+                    progress_bar.inc(1);
+                    continue;
+                }
+
+                require_fields(strict, &format!("Pull request #{}", pull.number), &pull_missing_fields(pull))?;
+                stored.push(&pull.item);
+                if keep_raw {
+                    let pull_id = pull.id.into_inner() as i64;
+                    raw.push((pull_id, pull_id, &pull.json));
+                }
+            }
+            storage.create_pull_requests(&stored, repo_db.id).await.into_diagnostic()?;
+            storage.create_raw_payloads(repo_db.id, "pull", &raw).await.into_diagnostic()?;
+
+            for pull in stored {
+                let pull_id = pull.id.into_inner() as i64;
+                for label in pull.labels.iter().flatten() {
+                    storage.create_pull_request_label(repo_db.id, pull_id, label).await.into_diagnostic()?;
+                }
+                storage.set_pull_request_milestone(repo_db.id, pull_id, pull.milestone.as_deref())
+                    .await.into_diagnostic()?;
+                store_assignments(storage.as_ref(), pull_id, pull).await?;
+
+                if let Some(body) = &pull.body {
+                    for issue_number in closing_issue_numbers(body) {
+                        storage.create_linked_issue(pull_id, issue_number).await.into_diagnostic()?;
+                    }
+                }
+                if store_checklists {
+                    let counts = pull.body.as_deref().and_then(checklist_counts);
+                    storage.set_pull_request_checklist(pull_id, counts).await.into_diagnostic()?;
+                }
                 progress_bar.inc(1);
             }
+            if let Some((number, updated_at)) = last {
+                let checkpoint = Checkpoint {
+                    number,
+                    updated_at,
+                    page: Some(page),
+                    latest: resume_after.map_or(latest, |checkpoint| checkpoint.latest),
+                };
+                save_checkpoint(storage.as_ref(), &scope, &checkpoint).await?;
+            }
+            if finished {
+                break;
+            }
+        }
+        progress_bar.finish_with_message("Finished fetching pull requests");
+        if range.is_none() {
+            // Pull requests updated since the resumed run started were passed over
+            let latest = resume_after.map_or(latest, |checkpoint| checkpoint.latest);
+            advance_cursor(storage.as_ref(), repo_db.id, &cursor, latest).await?;
         }
+    } else if since.is_some() {
+        println!("No pull requests changed since the last run");
     } else {
         println!("No pull requests found");
     }
+    storage.snapshot_associations(repo_db.id).await.into_diagnostic()?;
+
     Ok(())
 }
 
-/// Parses the command line arguments and figures out what operations to perform
-pub async fn collect_pull_events(matches: &ArgMatches) -> Result<()> {
-    let github_api_token = std::env::var("GITHUB_TOKEN")
-        .map_err(|_| miette!("GitHub token not found. Please set GITHUB_TOKEN environment variable"))?;
+/// Collects issues for a repository, storing them alongside pull requests
+pub async fn collect_issues(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_spinner("Fetching issues");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let handler = octocrab.issues(&repo.owner, &repo.name);
+        let issues = retry(|| {
+            handler
+                .list()
+                .state(State::All)
+                .per_page(100)
+                .page(page)
+                .send()
+        })
+        .await
+        .into_diagnostic()?;
+
+        if issues.items.is_empty() {
+            break;
+        }
+
+        // The issues endpoint also returns pull requests, which `collect pulls` handles
+        let page_issues: Vec<_> = issues.items.iter().filter(|issue| issue.pull_request.is_none()).collect();
+        let issue_ids = storage.create_issues(&page_issues, repo.id).await.map_err(|err| {
+            miette!("Error creating issue db records: {}", err)
+        })?;
+        for (issue, issue_id) in page_issues.into_iter().zip(issue_ids) {
+            for label in &issue.labels {
+                storage.create_pull_request_label(repo.id, issue_id, label).await.into_diagnostic()?;
+            }
+            storage.set_pull_request_milestone(repo.id, issue_id, issue.milestone.as_ref())
+                .await.into_diagnostic()?;
+            let assignees: Vec<String> = issue.assignees.iter().map(|user| user.login.clone()).collect();
+            storage.set_pull_request_assignees(issue_id, &assignees).await.into_diagnostic()?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching issues");
+    storage.snapshot_associations(repo.id).await.into_diagnostic()?;
+
+    Ok(())
+}
 
-    let project_name= matches
-        .get_one::<String>(CLI_ARGS_REPO)
-        .expect("repository is required");
+/// Collects the timeline events of pull requests through the forge hosting the repository
+pub async fn collect_pull_events(options: &CollectOptions, repo: &Repository, forge: &dyn Forge) -> Result<()> {
+    let pr_numbers = &options.numbers;
 
-    let pr_numbers = match matches.get_many(CLI_ARGS_NUMBER) {
-        Some(numbers) => numbers.copied().collect(),
-        None => vec![],
+    let storage = open_storage().await.into_diagnostic()?;
+    // Explicit selections neither use nor move the cursor
+    let range = options.range;
+    let incremental = pr_numbers.is_empty() && range.is_none() && !options.missing_only;
+    let pulls = if options.missing_only {
+        storage.get_pull_requests_without_events(repo.id).await.into_diagnostic()?
+    } else if incremental {
+        pulls_to_refresh(options, storage.as_ref(), repo.id, "events").await?
+    } else {
+        storage.get_pull_requests(repo.id, pr_numbers).await.into_diagnostic()?
+    };
+    let scope = checkpoint_scope("events", range);
+    let resume_after = resume_from(options, storage.as_ref(), repo.id, "events", &scope).await?;
+    // Pull requests updated since the resumed run started are left to the next run
+    let latest = match resume_after {
+        Some(checkpoint) => checkpoint.latest,
+        None => pulls.iter().map(|pull| pull.updated_at).max(),
     };
 
-    let pool = setup_db().await.into_diagnostic()?;
-    let repo = Repository::from(&pool, project_name).await.into_diagnostic()?;
-    let pulls = DbPullRequest::fetch_many(&pool, repo.id, &pr_numbers).await.into_diagnostic()?;
+    // `None` means every event type is stored
+    let event_types = if options.all_events {
+        None
+    } else {
+        Some(Config::load().into_diagnostic()?.collect.event_types())
+    };
 
-    println!("Length {}: {}", pulls.len(), pr_numbers.len());
     // Number of numbers provided should match records fetched from the database
     if !pr_numbers.is_empty() && pulls.len() != pr_numbers.len()  {
         return Err(miette!("Number of pull requests provided does not match the number of records in the database"));
     }
 
-    let octocrab = Octocrab::builder()
-        .personal_token(github_api_token)
-        .build()
-        .into_diagnostic()?;
+    let strict = strict(options)?;
+    let keep_raw = keep_raw(options)?;
+    let etags = stored_etags(storage.as_ref(), repo.id, options).await?;
+
+    // Numbers give checkpoints an order to resume in
+    let mut pulls = pulls;
+    pulls.sort_by_key(|pull| pull.number);
+    pulls.retain(|pull| {
+        resume_after.is_none_or(|checkpoint| !checkpoint.covers(pull.number, None))
+            && range.is_none_or(|range| range.contains(pull.number))
+            && in_shard(pull.number)
+    });
 
     let progress_bar = get_progress_bar(pulls.len() as u64, "Fetching pull events");
+    let mut limiter = rate_limiter(repo, &progress_bar);
 
-    for pull in pulls {
-        let events = octocrab.issues(&repo.owner, &repo.name)
-            .list_timeline_events(pull.number as u64)
-            .page(1u32)
-            .per_page(100)
-            .send()
-            .await.into_diagnostic()?;
+    let mut unchanged = 0;
+    for batch in pulls.chunks(concurrency(options)) {
+        let listings = fetch_listings(&mut limiter, batch, |number| forge.list_events(number, &etags)).await?;
+
+        for (pull, listing) in batch.iter().zip(listings) {
+            if let Listing::Changed { items: events, etags } = listing {
+                let item = format!("An event on pull request #{}", pull.number);
+                let mut stored = Vec::new();
+                for event in &events {
+                    let wanted = match &event_types {
+                        Some(event_types) => event_types.contains(&event_type_name(&event.event)),
+                        None => true,
+                    };
+                    if wanted {
+                        let missing = [("actor", event.actor.is_empty()), ("created_at", event.created_at.is_none())];
+                        require_fields(strict, &item, &missing)?;
+                        stored.push(event);
+                    }
+                }
+                // Ids of the whole timeline, including event types that aren't stored
+                let timeline: Vec<i64> = events.iter().filter_map(|event| event.id).collect();
+                storage.replace_pull_request_events(pull.id, &stored, &timeline).await.map_err(|err| {
+                    miette!("Error creating pull request event db records: {}", err)
+                })?;
+                if keep_raw {
+                    // Kept even for event types that aren't stored, so they can be reparsed later
+                    let raw: Vec<_> = events
+                        .iter()
+                        .filter_map(|event| Some((event.id?, pull.id, event.raw.as_ref()?)))
+                        .collect();
+                    storage.create_raw_payloads(repo.id, "event", &raw).await.into_diagnostic()?;
+                }
+                remember_etags(storage.as_ref(), repo.id, etags).await?;
+            } else {
+                unchanged += 1;
+            }
+            storage.set_events_collected(pull.id).await.into_diagnostic()?;
+            progress_bar.inc(1);
+            let checkpoint = Checkpoint { number: pull.number, updated_at: None, page: None, latest };
+            save_checkpoint(storage.as_ref(), &scope, &checkpoint).await?;
+        }
+        if let Some(limiter) = &mut limiter {
+            limiter.throttle(options.throttle).await;
+        }
+    }
+    progress_bar.finish_with_message(format!("Finished fetching pull request events ({} unchanged)", unchanged));
+
+    if incremental {
+        advance_cursor(storage.as_ref(), repo.id, "events", latest).await?;
+    }
+    storage.snapshot_associations(repo.id).await.into_diagnostic()?;
+
+    Ok(())
+}
+
+/// Collects the reviews of pull requests through the forge hosting the repository
+pub async fn collect_pull_reviews(options: &CollectOptions, repo: &Repository, forge: &dyn Forge) -> Result<()> {
+    let pr_numbers = &options.numbers;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    // Explicit selections neither use nor move the cursor
+    let range = options.range;
+    let incremental = pr_numbers.is_empty() && range.is_none() && !options.missing_only;
+    let pulls = if options.missing_only {
+        storage.get_pull_requests_without_reviews(repo.id).await.into_diagnostic()?
+    } else if incremental {
+        pulls_to_refresh(options, storage.as_ref(), repo.id, "reviews").await?
+    } else {
+        storage.get_pull_requests(repo.id, pr_numbers).await.into_diagnostic()?
+    };
+    let scope = checkpoint_scope("reviews", range);
+    let resume_after = resume_from(options, storage.as_ref(), repo.id, "reviews", &scope).await?;
+    // Pull requests updated since the resumed run started are left to the next run
+    let latest = match resume_after {
+        Some(checkpoint) => checkpoint.latest,
+        None => pulls.iter().map(|pull| pull.updated_at).max(),
+    };
+
+    // Number of numbers provided should match records fetched from the database
+    if !pr_numbers.is_empty() && pulls.len() != pr_numbers.len()  {
+        return Err(miette!("Number of pull requests provided does not match the number of records in the database"));
+    }
+
+    // Issues share the pulls table but can't have reviews; numbers give checkpoints an order to resume in
+    let mut pulls: Vec<_> = pulls
+        .into_iter()
+        .filter(|pull| {
+            !pull.is_issue
+                && resume_after.is_none_or(|checkpoint| !checkpoint.covers(pull.number, None))
+                && range.is_none_or(|range| range.contains(pull.number))
+                && in_shard(pull.number)
+        })
+        .collect();
+    pulls.sort_by_key(|pull| pull.number);
+
+    let strict = strict(options)?;
+    let keep_raw = keep_raw(options)?;
+    let etags = stored_etags(storage.as_ref(), repo.id, options).await?;
+
+    let progress_bar = get_progress_bar(pulls.len() as u64, "Fetching pull request reviews");
+    let mut limiter = rate_limiter(repo, &progress_bar);
+
+    let mut unchanged = 0;
+    for batch in pulls.chunks(concurrency(options)) {
+        let listings = fetch_listings(&mut limiter, batch, |number| forge.list_reviews(number, &etags)).await?;
 
-        for event in events {
-            if event.id.is_some() {
-                PullRequestEvent::create(&pool, pull.id, &event).await.map_err(|err| {
-                    miette!("Error creating pull request event db record: {}", err)
+        for (pull, listing) in batch.iter().zip(listings) {
+            if let Listing::Changed { items: reviews, etags } = listing {
+                for review in &reviews {
+                    let item = format!("A review on pull request #{}", pull.number);
+                    require_fields(strict, &item, &[("reviewer", review.reviewer.is_empty())])?;
+                }
+                storage.replace_pull_request_reviews(pull.id, &reviews).await.map_err(|err| {
+                    miette!("Error creating pull request review db records: {}", err)
                 })?;
+                if keep_raw {
+                    let raw: Vec<_> = reviews
+                        .iter()
+                        .filter_map(|review| Some((review.id, pull.id, review.raw.as_ref()?)))
+                        .collect();
+                    storage.create_raw_payloads(repo.id, "review", &raw).await.into_diagnostic()?;
+                }
+                remember_etags(storage.as_ref(), repo.id, etags).await?;
+            } else {
+                unchanged += 1;
+            }
+            progress_bar.inc(1);
+            let checkpoint = Checkpoint { number: pull.number, updated_at: None, page: None, latest };
+            save_checkpoint(storage.as_ref(), &scope, &checkpoint).await?;
+        }
+        if let Some(limiter) = &mut limiter {
+            limiter.throttle(options.throttle).await;
+        }
+    }
+    progress_bar.finish_with_message(format!("Finished fetching pull request reviews ({} unchanged)", unchanged));
+
+    if incremental {
+        advance_cursor(storage.as_ref(), repo.id, "reviews", latest).await?;
+    }
+    storage.snapshot_associations(repo.id).await.into_diagnostic()?;
+
+    Ok(())
+}
+
+/// Collects the files changed by every merged or still open pull request in the database
+///
+/// Open pull requests are included so their size is known while they wait for
+/// review. GitHub lists at most 3000 files per pull request.
+pub async fn collect_files(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let pr_numbers = &options.numbers;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+    let range = options.range;
+    let pulls: Vec<_> = storage.get_pull_requests(repo.id, pr_numbers).await.into_diagnostic()?
+        .into_iter()
+        .filter(|pull| {
+            !pull.is_issue
+                && (pull.merged_at.is_some() || pull.closed_at.is_none())
+                && range.is_none_or(|range| range.contains(pull.number))
+                && in_shard(pull.number)
+        })
+        .collect();
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_progress_bar(pulls.len() as u64, "Fetching pull request files");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    for pull in pulls {
+        let route = format!("/repos/{}/{}/pulls/{}/files", repo.owner, repo.name, pull.number);
+        // Every page is fetched before the stored files are replaced, so a failed
+        // request leaves them as they were
+        let mut files = Vec::new();
+        let mut page = 1u32;
+        loop {
+            limiter.wait().await;
+            let page_str = page.to_string();
+            let params = [("per_page", "100"), ("page", page_str.as_str())];
+            let page_files: Vec<octocrab::models::repos::DiffEntry> = retry(|| octocrab.get(&route, Some(&params)))
+                .await.into_diagnostic()?;
+
+            if page_files.is_empty() {
+                break;
             }
+            files.extend(page_files);
+            page += 1;
         }
+        storage.replace_pull_request_files(pull.id, &files).await.map_err(|err| {
+            miette!("Error creating pull request file db records: {}", err)
+        })?;
         progress_bar.inc(1);
-        sleep(Duration::from_millis(1000)).await;
+        limiter.throttle(options.throttle).await;
     }
-    progress_bar.finish_with_message("Finished fetching pull request events");
+    progress_bar.finish_with_message("Finished fetching pull request files");
 
     Ok(())
 }
 
-pub async fn collect_pull_reviews(matches: &ArgMatches) -> Result<()> {
-    let github_api_token = std::env::var("GITHUB_TOKEN")
-        .map_err(|_| miette!("GitHub token not found. Please set GITHUB_TOKEN environment variable"))?;
+/// Collects the size of every pull request in the database: lines added and
+/// deleted, files changed and commits
+///
+/// Only the endpoint for a single pull request has these, so this costs one
+/// request per pull request. Like events and reviews, later runs only fetch
+/// pull requests updated since the last one.
+pub async fn collect_pull_details(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
 
-    let project_name= matches
-        .get_one::<String>(CLI_ARGS_REPO)
-        .expect("repository is required");
+    let pr_numbers = &options.numbers;
 
-    let pr_numbers = match matches.get_many(CLI_ARGS_NUMBER) {
-        Some(numbers) => numbers.copied().collect(),
-        None => vec![],
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+    // Explicit selections neither use nor move the cursor
+    let range = options.range;
+    let incremental = pr_numbers.is_empty() && range.is_none() && !options.missing_only;
+    let pulls = if options.missing_only {
+        storage.get_pull_requests(repo.id, &[]).await.into_diagnostic()?
+            .into_iter()
+            .filter(|pull| pull.additions.is_none())
+            .collect()
+    } else if incremental {
+        pulls_to_refresh(options, storage.as_ref(), repo.id, "pull-details").await?
+    } else {
+        storage.get_pull_requests(repo.id, pr_numbers).await.into_diagnostic()?
     };
-
-    let pool = setup_db().await.into_diagnostic()?;
-    let repo = Repository::from(&pool, project_name).await.into_diagnostic()?;
-    let pulls = DbPullRequest::fetch_many(&pool, repo.id, &pr_numbers).await.into_diagnostic()?;
+    let latest = pulls.iter().map(|pull| pull.updated_at).max();
 
     // Number of numbers provided should match records fetched from the database
     if !pr_numbers.is_empty() && pulls.len() != pr_numbers.len()  {
         return Err(miette!("Number of pull requests provided does not match the number of records in the database"));
     }
 
-    let octocrab = Octocrab::builder()
-        .personal_token(github_api_token)
-        .build()
-        .into_diagnostic()?;
+    let pulls: Vec<_> = pulls
+        .into_iter()
+        .filter(|pull| {
+            !pull.is_issue && range.is_none_or(|range| range.contains(pull.number)) && in_shard(pull.number)
+        })
+        .collect();
 
-    let progress_bar = get_progress_bar(pulls.len() as u64, "Fetching pull request reviews");
+    let strict = strict(options)?;
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_progress_bar(pulls.len() as u64, "Fetching pull request details");
+    let mut limiter = RateLimiter::new(&progress_bar);
 
     for pull in pulls {
-        let reviews = octocrab.pulls(&repo.owner, &repo.name)
-            .list_reviews(pull.number as u64)
-            .page(1u32)
-            .per_page(100)
-            .send()
+        limiter.wait().await;
+        let handler = octocrab.pulls(&repo.owner, &repo.name);
+        let details = retry(|| handler.get(u64::from(pull.number))).await.into_diagnostic()?;
+
+        require_fields(strict, &format!("Pull request #{}", pull.number), &pull_missing_fields(&details))?;
+        storage.create_pull_requests(&[&details], repo.id).await.map_err(|err| {
+            miette!("Error creating pull request db record: {}", err)
+        })?;
+        store_assignments(storage.as_ref(), pull.id, &details).await?;
+        progress_bar.inc(1);
+        limiter.throttle(options.throttle).await;
+    }
+    progress_bar.finish_with_message("Finished fetching pull request details");
+
+    if incremental {
+        advance_cursor(storage.as_ref(), repo.id, "pull-details", latest).await?;
+    }
+
+    Ok(())
+}
+
+/// Collects the conversation comments on every issue and pull request in the database
+///
+/// Comments are listed for the whole repository at once, which is far cheaper
+/// than one request per issue. Comments on items we haven't collected are skipped.
+pub async fn collect_comments(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+    let pull_ids: HashMap<u32, i64> = storage.get_pull_requests(repo.id, &[]).await.into_diagnostic()?
+        .into_iter()
+        .map(|pull| (pull.number, pull.id))
+        .collect();
+
+    let strict = strict(options)?;
+    let octocrab = github_client().await?;
+
+    let route = format!("/repos/{}/{}/issues/comments", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching comments");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [
+            ("sort", "created"), ("direction", "asc"), ("per_page", "100"), ("page", page_str.as_str()),
+        ];
+        let comments: Vec<IssueComment> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if comments.is_empty() {
+            break;
+        }
+
+        for comment in &comments {
+            let Some(pull_id) = comment.issue_number().and_then(|number| pull_ids.get(&number)) else {
+                continue;
+            };
+            require_fields(strict, &format!("Comment {}", comment.id), &[("author", comment.user.is_none())])?;
+            storage.create_pull_request_comment(*pull_id, comment).await.map_err(|err| {
+                miette!("Error creating comment db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching comments");
+    storage.snapshot_associations(repo.id).await.into_diagnostic()?;
+
+    Ok(())
+}
+
+/// Collects the line-level review comments on every pull request in the database
+pub async fn collect_review_comments(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+    let pull_ids: HashMap<u32, i64> = storage.get_pull_requests(repo.id, &[]).await.into_diagnostic()?
+        .into_iter()
+        .map(|pull| (pull.number, pull.id))
+        .collect();
+
+    let strict = strict(options)?;
+    let octocrab = github_client().await?;
+
+    let route = format!("/repos/{}/{}/pulls/comments", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching review comments");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [
+            ("sort", "created"), ("direction", "asc"), ("per_page", "100"), ("page", page_str.as_str()),
+        ];
+        let comments: Vec<ReviewComment> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if comments.is_empty() {
+            break;
+        }
+
+        for comment in &comments {
+            let Some(pull_id) = comment.pull_number().and_then(|number| pull_ids.get(&number)) else {
+                continue;
+            };
+            require_fields(strict, &format!("Review comment {}", comment.id), &[("author", comment.user.is_none())])?;
+            storage.create_review_comment(*pull_id, comment).await.map_err(|err| {
+                miette!("Error creating review comment db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching review comments");
+
+    Ok(())
+}
+
+/// Collects a snapshot of comment and reaction counts for every open issue
+///
+/// This replaces the previous snapshot for the repository, so the report always
+/// ranks issues that are still open.
+pub async fn collect_issue_demand(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    storage.clear_issue_demand(repo.id).await.into_diagnostic()?;
+
+    let route = format!("/repos/{}/{}/issues", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching open issues");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("state", "open"), ("per_page", "100"), ("page", page_str.as_str())];
+        let issues: Vec<IssueWithReactions> = retry(|| octocrab.get(&route, Some(&params)))
             .await.into_diagnostic()?;
 
-        for review in reviews {
-            PullRequestReview::create(&pool, pull.id, &review).await.map_err(|err| {
-                miette!("Error creating pull request event db record: {}", err)
+        if issues.is_empty() {
+            break;
+        }
+
+        // The issues endpoint also returns pull requests, which we don't rank
+        for issue in issues.iter().filter(|issue| issue.pull_request.is_none()) {
+            storage.create_issue_demand(repo.id, issue).await.map_err(|err| {
+                miette!("Error creating issue demand db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching open issues");
+
+    Ok(())
+}
+
+/// Collects the packages a repository depends on from GitHub's dependency graph
+///
+/// The dependency graph must be enabled for the repository. Like the demand
+/// snapshot, each run replaces what was previously stored.
+pub async fn collect_dependencies(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let route = format!("/repos/{}/{}/dependency-graph/sbom", repo.owner, repo.name);
+    let response: SbomResponse = retry(|| octocrab.get(&route, None::<&()>))
+        .await
+        .map_err(|err| miette!("Unable to fetch the dependency graph (is it enabled for this repository?): {}", err))?;
+
+    storage.clear_dependencies(repo.id).await.into_diagnostic()?;
+
+    let progress_bar = get_progress_bar(response.sbom.packages.len() as u64, "Storing dependencies");
+
+    for package in &response.sbom.packages {
+        if let Some((ecosystem, name)) = package.ecosystem_and_name() {
+            let version = package.version_info.clone().unwrap_or_default();
+            storage.create_dependency(repo.id, &ecosystem, &name, &version).await.map_err(|err| {
+                miette!("Error creating dependency db record: {}", err)
             })?;
         }
         progress_bar.inc(1);
-        sleep(Duration::from_millis(1000)).await;
     }
-    progress_bar.finish_with_message("Finished fetching pull request reviews");
+    progress_bar.finish_with_message("Finished storing dependencies");
 
     Ok(())
 }
 
-/// Creates a standard progress bar with a custom message
-pub fn get_progress_bar(total: u64, message: &str) -> ProgressBar {
-    let pb = ProgressBar::new(total);
-    pb.set_style(ProgressStyle::default_bar()
-        .template("[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",)
-        .unwrap()
-        .progress_chars("##-"));
-    pb.set_message(message.to_string());
-    pb
+/// Collects repository-wide issue events that move issues and pull requests out of the repository
+///
+/// Without these, an issue transferred to another repository (or converted to a
+/// discussion) simply looks like it disappeared.
+pub async fn collect_repository_events(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let route = format!("/repos/{}/{}/issues/events", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching repository events");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("per_page", "100"), ("page", page_str.as_str())];
+        let events: Vec<RepositoryIssueEvent> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if events.is_empty() {
+            break;
+        }
+
+        for event in events.iter().filter(|event| DISAPPEARING_EVENT_TYPES.contains(&event.event.as_str())) {
+            storage.create_repository_event(repo.id, event).await.map_err(|err| {
+                miette!("Error creating repository event db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching repository events");
+
+    Ok(())
 }
 
-pub async fn get_total_pull_requests(octocrab: &Octocrab, owner: &str, repo: &str) -> Result<u32, octocrab::Error> {
-    let search = format!("repo:{}/{} is:pr", owner, repo);
-    let results = octocrab.search()
-        .issues_and_pull_requests(&search)
-        .per_page(1)
-        .send()
-        .await?;
+/// Collects wiki page edits from the repository activity feed
+///
+/// GitHub only keeps the last 90 days (and at most 300 events) in the feed, so
+/// this needs to run regularly to build up a history.
+pub async fn collect_wiki(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
 
-    if let Some(count) = results.total_count {
-        Ok(count as u32)
-    } else {
-        Ok(0u32)
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let route = format!("/repos/{}/{}/events", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching wiki edits");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("per_page", "100"), ("page", page_str.as_str())];
+        let events: Vec<ActivityEvent> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if events.is_empty() {
+            break;
+        }
+
+        for event in events.iter().filter(|event| event.event_type == WIKI_EVENT_TYPE) {
+            for page in &event.payload.pages {
+                storage.create_wiki_edit(repo.id, event, page).await.map_err(|err| {
+                    miette!("Error creating wiki edit db record: {}", err)
+                })?;
+                progress_bar.inc(1);
+            }
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching wiki edits");
+
+    Ok(())
+}
+
+/// Collects the full history of the default branch, plus the commits other
+/// branches have on top of it
+pub async fn collect_commits(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let handler = octocrab.repos(&repo.owner, &repo.name);
+    let default_branch = retry(|| handler.get()).await.into_diagnostic()?
+        .default_branch
+        .ok_or_else(|| miette!("Repository {} has no default branch", project_name))?;
+
+    let progress_bar = get_spinner(&format!("Fetching commits on {}", default_branch));
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let route = format!("/repos/{}/{}/commits", repo.owner, repo.name);
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [
+            ("sha", default_branch.as_str()), ("per_page", "100"), ("page", page_str.as_str()),
+        ];
+        let commits: Vec<RepositoryCommit> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if commits.is_empty() {
+            break;
+        }
+
+        for commit in &commits {
+            storage.create_commit(repo.id, commit, true).await.map_err(|err| {
+                miette!("Error creating commit db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
     }
 
+    let route = format!("/repos/{}/{}/branches", repo.owner, repo.name);
+    let mut branches: Vec<Branch> = Vec::new();
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("per_page", "100"), ("page", page_str.as_str())];
+        let batch: Vec<Branch> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if batch.is_empty() {
+            break;
+        }
+        branches.extend(batch);
+        page += 1;
+    }
+
+    for branch in branches.iter().filter(|branch| branch.name != default_branch) {
+        limiter.wait().await;
+        progress_bar.set_message(format!("Fetching commits on {}", branch.name));
+
+        // Only lists commits not on the default branch, capped at 250 by GitHub
+        let route = format!(
+            "/repos/{}/{}/compare/{}...{}", repo.owner, repo.name, default_branch, branch.name
+        );
+        let comparison: Comparison = retry(|| octocrab.get(&route, None::<&()>))
+            .await.into_diagnostic()?;
+
+        for commit in &comparison.commits {
+            storage.create_commit(repo.id, commit, false).await.map_err(|err| {
+                miette!("Error creating commit db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+    }
+    progress_bar.finish_with_message("Finished fetching commits");
+
+    Ok(())
+}
+
+/// Collects the commits that changed a license or governance file
+///
+/// Paths come from `collect.governance_files`; paths the repository never had
+/// simply have no commits.
+pub async fn collect_governance(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+    let paths = Config::load().into_diagnostic()?.collect.governance_files();
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_progress_bar(paths.len() as u64, "Fetching governance file history");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let route = format!("/repos/{}/{}/commits", repo.owner, repo.name);
+    let mut changes = 0;
+    for path in &paths {
+        let mut page = 1u32;
+        loop {
+            limiter.wait().await;
+            let page_str = page.to_string();
+            let params = [("path", path.as_str()), ("per_page", "100"), ("page", page_str.as_str())];
+            let commits: Vec<RepositoryCommit> = retry(|| octocrab.get(&route, Some(&params)))
+                .await.into_diagnostic()?;
+
+            if commits.is_empty() {
+                break;
+            }
+
+            for commit in &commits {
+                storage.create_governance_change(repo.id, path, commit).await.map_err(|err| {
+                    miette!("Error creating governance change db record: {}", err)
+                })?;
+                changes += 1;
+            }
+            page += 1;
+        }
+        progress_bar.inc(1);
+    }
+    progress_bar.finish_with_message(format!("Finished fetching governance file history ({} changes)", changes));
+
+    Ok(())
+}
+
+/// Collects every release of a repository
+pub async fn collect_releases(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_spinner("Fetching releases");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let handler = octocrab.repos(&repo.owner, &repo.name);
+        let handler = handler.releases();
+        let releases = retry(|| {
+            handler
+                .list()
+                .per_page(100)
+                .page(page)
+                .send()
+        })
+        .await
+        .into_diagnostic()?;
+
+        if releases.items.is_empty() {
+            break;
+        }
+
+        for release in &releases.items {
+            storage.create_release(repo.id, release).await.map_err(|err| {
+                miette!("Error creating release db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching releases");
+
+    Ok(())
+}
+
+/// Collects the deployments of a repository and every status they went through
+pub async fn collect_deployments(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let route = format!("/repos/{}/{}/deployments", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching deployments");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("per_page", "100"), ("page", page_str.as_str())];
+        let deployments: Vec<Deployment> = retry(|| octocrab.get(&route, Some(&params))).await.into_diagnostic()?;
+
+        if deployments.is_empty() {
+            break;
+        }
+
+        for deployment in &deployments {
+            storage.create_deployment(repo.id, deployment).await.map_err(|err| {
+                miette!("Error creating deployment db record: {}", err)
+            })?;
+
+            let status_route = format!("{}/{}/statuses", route, deployment.id);
+            let mut status_page = 1u32;
+            loop {
+                limiter.wait().await;
+                let page_str = status_page.to_string();
+                let params = [("per_page", "100"), ("page", page_str.as_str())];
+                let statuses: Vec<DeploymentStatus> = retry(|| octocrab.get(&status_route, Some(&params)))
+                    .await.into_diagnostic()?;
+
+                if statuses.is_empty() {
+                    break;
+                }
+                for status in &statuses {
+                    storage.create_deployment_status(deployment.id, status).await.map_err(|err| {
+                        miette!("Error creating deployment status db record: {}", err)
+                    })?;
+                }
+                status_page += 1;
+            }
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching deployments");
+
+    Ok(())
+}
+
+/// Collects the labels of a repository and applies them to stored pull requests and issues
+///
+/// Pull requests and issues are matched by number, so they need to be collected first.
+pub async fn collect_labels(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_spinner("Fetching labels");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let handler = octocrab.issues(&repo.owner, &repo.name);
+        let labels = retry(|| {
+            handler
+                .list_labels_for_repo()
+                .per_page(100)
+                .page(page)
+                .send()
+        })
+        .await
+        .into_diagnostic()?;
+
+        if labels.items.is_empty() {
+            break;
+        }
+
+        for label in &labels.items {
+            storage.create_label(repo.id, label).await.map_err(|err| {
+                miette!("Error creating label db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+
+    let stored: HashMap<u32, i64> = storage
+        .get_pull_requests(repo.id, &[])
+        .await.into_diagnostic()?
+        .into_iter()
+        .map(|pull| (pull.number, pull.id))
+        .collect();
+
+    progress_bar.set_message("Fetching labelled pull requests and issues");
+
+    // The issues endpoint lists pull requests too, with the same labels
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let handler = octocrab.issues(&repo.owner, &repo.name);
+        let issues = retry(|| {
+            handler
+                .list()
+                .state(State::All)
+                .per_page(100)
+                .page(page)
+                .send()
+        })
+        .await
+        .into_diagnostic()?;
+
+        if issues.items.is_empty() {
+            break;
+        }
+
+        for issue in &issues.items {
+            let Some(issue_pull_id) = stored.get(&(issue.number as u32)) else {
+                continue;
+            };
+            for label in &issue.labels {
+                storage.create_pull_request_label(repo.id, *issue_pull_id, label).await.into_diagnostic()?;
+            }
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching labels");
+
+    Ok(())
+}
+
+/// Collects the milestones of a repository and assigns stored pull requests and issues to them
+///
+/// Pull requests and issues are matched by number, so they need to be collected first.
+pub async fn collect_milestones(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let route = format!("/repos/{}/{}/milestones", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching milestones");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("state", "all"), ("per_page", "100"), ("page", page_str.as_str())];
+        let milestones: Vec<octocrab::models::Milestone> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if milestones.is_empty() {
+            break;
+        }
+
+        for milestone in &milestones {
+            storage.create_milestone(repo.id, milestone).await.map_err(|err| {
+                miette!("Error creating milestone db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+
+    let stored: HashMap<u32, i64> = storage
+        .get_pull_requests(repo.id, &[])
+        .await.into_diagnostic()?
+        .into_iter()
+        .map(|pull| (pull.number, pull.id))
+        .collect();
+
+    progress_bar.set_message("Fetching pull request and issue milestones");
+
+    // The issues endpoint lists pull requests too, with the same milestone
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let handler = octocrab.issues(&repo.owner, &repo.name);
+        let issues = retry(|| {
+            handler
+                .list()
+                .state(State::All)
+                .per_page(100)
+                .page(page)
+                .send()
+        })
+        .await
+        .into_diagnostic()?;
+
+        if issues.items.is_empty() {
+            break;
+        }
+
+        for issue in &issues.items {
+            if let Some(issue_pull_id) = stored.get(&(issue.number as u32)) {
+                storage.set_pull_request_milestone(repo.id, *issue_pull_id, issue.milestone.as_ref())
+                    .await.into_diagnostic()?;
+            }
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching milestones");
+
+    Ok(())
+}
+
+/// Collects discussions with their answers and top-level comments through GraphQL
+///
+/// GraphQL always needs a token, even for public repositories.
+pub async fn collect_discussions(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = graphql_client()?;
+
+    let progress_bar = get_spinner("Fetching discussions");
+
+    let mut cursor: Option<String> = None;
+    loop {
+        let payload = serde_json::json!({
+            "query": DISCUSSIONS_QUERY,
+            "variables": { "owner": repo.owner, "name": repo.name, "cursor": cursor },
+        });
+        let response: GraphQlResponse<DiscussionsData> = retry(|| octocrab.graphql(&payload)).await.into_diagnostic()?;
+
+        if let Some(error) = response.errors.first() {
+            return Err(miette!("Error fetching discussions: {}", error.message));
+        }
+        let Some(repository) = response.data.and_then(|data| data.repository) else {
+            return Err(miette!("Repository {} not found", project_name));
+        };
+
+        let discussions = repository.discussions;
+        for discussion in &discussions.nodes {
+            storage.create_discussion(repo.id, discussion).await.map_err(|err| {
+                miette!("Error creating discussion db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+
+        if !discussions.page_info.has_next_page {
+            break;
+        }
+        cursor = discussions.page_info.end_cursor;
+    }
+    progress_bar.finish_with_message("Finished fetching discussions");
+
+    Ok(())
+}
+
+/// Collects everyone currently starring a repository and when they starred it
+pub async fn collect_stars(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let mut headers = HeaderMap::new();
+    headers.insert(ACCEPT, HeaderValue::from_static(STAR_MEDIA_TYPE));
+
+    let route = format!("/repos/{}/{}/stargazers", repo.owner, repo.name);
+    let progress_bar = get_spinner("Fetching stargazers");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut stargazers: Vec<Stargazer> = Vec::new();
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("per_page", "100"), ("page", page_str.as_str())];
+        let batch: Vec<Stargazer> = retry(|| octocrab.get_with_headers(&route, Some(&params), Some(headers.clone())))
+            .await.into_diagnostic()?;
+
+        if batch.is_empty() {
+            break;
+        }
+        progress_bar.inc(batch.len() as u64);
+        stargazers.extend(batch);
+        page += 1;
+    }
+
+    // Only replace the previous snapshot once the new one is complete
+    storage.clear_stargazers(repo.id).await.into_diagnostic()?;
+    for stargazer in &stargazers {
+        storage.create_stargazer(repo.id, stargazer).await.map_err(|err| {
+            miette!("Error creating stargazer db record: {}", err)
+        })?;
+    }
+    progress_bar.finish_with_message("Finished fetching stargazers");
+
+    Ok(())
+}
+
+/// Collects the forks of a repository
+pub async fn collect_forks(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_spinner("Fetching forks");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let handler = octocrab.repos(&repo.owner, &repo.name);
+        let forks = retry(|| {
+            handler
+                .list_forks()
+                .per_page(100)
+                .page(page)
+                .send()
+        })
+        .await
+        .into_diagnostic()?;
+
+        if forks.items.is_empty() {
+            break;
+        }
+
+        for fork in &forks.items {
+            storage.create_fork(repo.id, fork).await.map_err(|err| {
+                miette!("Error creating fork db record: {}", err)
+            })?;
+            progress_bar.inc(1);
+        }
+        page += 1;
+    }
+    progress_bar.finish_with_message("Finished fetching forks");
+
+    Ok(())
+}
+
+/// How often to ask for contributor statistics while GitHub is still computing them
+const STATS_ATTEMPTS: u32 = 10;
+
+/// Collects contributors with their commit counts and weekly activity
+pub async fn collect_contributors(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_spinner("Fetching contributors");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let route = format!("/repos/{}/{}/contributors", repo.owner, repo.name);
+    let mut contributors: Vec<Contributor> = Vec::new();
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("anon", "1"), ("per_page", "100"), ("page", page_str.as_str())];
+        let batch: Vec<Contributor> = retry(|| octocrab.get(&route, Some(&params)))
+            .await.into_diagnostic()?;
+
+        if batch.is_empty() {
+            break;
+        }
+        progress_bar.inc(batch.len() as u64);
+        contributors.extend(batch);
+        page += 1;
+    }
+
+    // GitHub answers 202 with an empty body until the statistics are computed
+    progress_bar.set_message("Waiting for GitHub to compute contributor statistics");
+    let route = format!("/repos/{}/{}/stats/contributors", repo.owner, repo.name);
+    let mut stats: Vec<ContributorStats> = Vec::new();
+    for attempt in 1..=STATS_ATTEMPTS {
+        let response = retry(|| async {
+            octocrab::map_github_error(octocrab._get(route.as_str()).await?).await
+        })
+        .await
+        .into_diagnostic()?;
+
+        if response.status() == http::StatusCode::ACCEPTED {
+            if attempt == STATS_ATTEMPTS {
+                return Err(miette!("GitHub is still computing contributor statistics; try again in a minute"));
+            }
+            sleep(Duration::from_secs(3)).await;
+            continue;
+        }
+
+        // Empty repositories have no statistics at all
+        if response.status() != http::StatusCode::NO_CONTENT {
+            let body = octocrab.body_to_string(response).await.into_diagnostic()?;
+            stats = serde_json::from_str(&body).into_diagnostic()?;
+        }
+        break;
+    }
+
+    storage.clear_contributors(repo.id).await.into_diagnostic()?;
+    for contributor in &contributors {
+        storage.create_contributor(repo.id, contributor).await.map_err(|err| {
+            miette!("Error creating contributor db record: {}", err)
+        })?;
+    }
+    for contributor_stats in &stats {
+        storage.create_contributor_weeks(repo.id, contributor_stats).await.map_err(|err| {
+            miette!("Error creating contributor activity db record: {}", err)
+        })?;
+    }
+    progress_bar.finish_with_message("Finished fetching contributors");
+
+    Ok(())
+}
+
+/// Collects who has push access to a repository, directly or through a team
+///
+/// Each run replaces what was stored before, so people who lost access are no
+/// longer counted as maintainers. Listing collaborators needs a token with push
+/// access; repositories owned by a user have no teams.
+pub async fn collect_maintainers(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_spinner("Fetching maintainers");
+    let mut limiter = RateLimiter::new(&progress_bar);
+
+    let route = format!("/repos/{}/{}/collaborators", repo.owner, repo.name);
+    let mut collaborators: Vec<Collaborator> = Vec::new();
+    let mut page = 1u32;
+    loop {
+        limiter.wait().await;
+        let page_str = page.to_string();
+        let params = [("affiliation", "all"), ("permission", "push"), ("per_page", "100"), ("page", page_str.as_str())];
+        let batch: Vec<Collaborator> = retry(|| octocrab.get(&route, Some(&params))).await.map_err(|err| {
+            miette!("Unable to list collaborators; this needs a token with push access to {}: {}", project_name, err)
+        })?;
+
+        if batch.is_empty() {
+            break;
+        }
+        progress_bar.inc(batch.len() as u64);
+        collaborators.extend(batch);
+        page += 1;
+    }
+
+    // Login to the slugs of the teams giving it push access
+    let mut teams: HashMap<String, Vec<String>> = HashMap::new();
+    let route = format!("/repos/{}/{}/teams", repo.owner, repo.name);
+    let params = [("per_page", "100")];
+    let repository_teams: Vec<RepositoryTeam> = match retry(|| octocrab.get(&route, Some(&params))).await {
+        Err(octocrab::Error::GitHub { source, .. }) if source.status_code == http::StatusCode::NOT_FOUND => Vec::new(),
+        result => result.into_diagnostic()?,
+    };
+    for team in repository_teams.iter().filter(|team| team.can_push()) {
+        let route = format!("/orgs/{}/teams/{}/members", repo.owner, team.slug);
+        let mut page = 1u32;
+        loop {
+            limiter.wait().await;
+            let page_str = page.to_string();
+            let params = [("per_page", "100"), ("page", page_str.as_str())];
+            let batch: Vec<Collaborator> = retry(|| octocrab.get(&route, Some(&params))).await.into_diagnostic()?;
+
+            if batch.is_empty() {
+                break;
+            }
+            for member in batch {
+                teams.entry(member.login).or_default().push(team.slug.clone());
+            }
+            page += 1;
+        }
+    }
+
+    storage.clear_maintainers(repo.id).await.into_diagnostic()?;
+    for collaborator in &collaborators {
+        let member_of = teams.get(&collaborator.login).map(Vec::as_slice).unwrap_or_default();
+        storage
+            .create_maintainer(repo.id, &collaborator.login, collaborator.permission(), member_of)
+            .await
+            .map_err(|err| miette!("Error creating maintainer db record: {}", err))?;
+    }
+    progress_bar.finish_with_message("Finished fetching maintainers");
+
+    Ok(())
+}
+
+/// Collects daily views and clones for the last 14 days
+///
+/// GitHub only keeps two weeks of traffic, so days already stored are kept and
+/// running this regularly builds up a longer history.
+pub async fn collect_traffic(options: &CollectOptions) -> Result<()> {
+    let project_name = &options.repository;
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(project_name).await.into_diagnostic()?;
+
+    let octocrab = github_client().await?;
+
+    let progress_bar = get_spinner("Fetching traffic");
+
+    for kind in ["views", "clones"] {
+        let route = format!("/repos/{}/{}/traffic/{}", repo.owner, repo.name, kind);
+        let traffic: Traffic = retry(|| octocrab.get(&route, None::<&()>)).await.map_err(|err| {
+            miette!("Unable to fetch {}; traffic needs a token with push access to {}: {}", kind, project_name, err)
+        })?;
+
+        for day in &traffic.days {
+            storage.create_traffic_day(repo.id, kind, day).await.map_err(|err| {
+                miette!("Error creating traffic db record: {}", err)
+            })?;
+        }
+        progress_bar.inc(traffic.days.len() as u64);
+    }
+    progress_bar.finish_with_message("Finished fetching traffic");
+
+    Ok(())
+}
+
+/// Replaces the stored assignees and pending review requests of a pull request
+async fn store_assignments(
+    storage: &dyn Storage,
+    issue_pull_id: i64,
+    pull: &octocrab::models::pulls::PullRequest,
+) -> Result<()> {
+    storage.set_pull_request_assignees(issue_pull_id, &assignee_logins(pull)).await.into_diagnostic()?;
+    let (users, teams) = requested_reviewers(pull);
+    storage.set_requested_reviewers(issue_pull_id, &users, &teams).await.into_diagnostic()?;
+
+    Ok(())
+}
+
+/// Where an incremental collector starts: only things updated after the returned time are fetched
+///
+/// `--full` starts from scratch and `--since` overrides the cursor left by the
+/// last completed run.
+async fn collection_start(
+    options: &CollectOptions,
+    storage: &dyn Storage,
+    repo_id: u32,
+    cursor: &str,
+) -> Result<Option<DateTime<Utc>>> {
+    if options.full {
+        return Ok(None);
+    }
+    if let Some(since) = options.since {
+        return Ok(Some(since));
+    }
+    let updated_at = storage.get_cursor(repo_id, &shard_cursor(cursor)).await.into_diagnostic()?;
+
+    Ok(updated_at)
+}
+
+/// Stored pull requests whose events or reviews may have changed since the last completed run
+async fn pulls_to_refresh(
+    options: &CollectOptions,
+    storage: &dyn Storage,
+    repo_id: u32,
+    cursor: &str,
+) -> Result<Vec<PullRequest>> {
+    match collection_start(options, storage, repo_id, cursor).await? {
+        Some(since) => storage.get_pull_requests_updated_since(repo_id, since).await,
+        None => storage.get_pull_requests(repo_id, &[]).await,
+    }
+    .into_diagnostic()
+}
+
+/// Moves a cursor forward to the latest `updated_at` a completed run handled
+async fn advance_cursor(
+    storage: &dyn Storage,
+    repo_id: u32,
+    cursor: &str,
+    latest: Option<DateTime<Utc>>,
+) -> Result<()> {
+    let Some(latest) = latest else {
+        return Ok(());
+    };
+    let cursor = shard_cursor(cursor);
+    let current = storage.get_cursor(repo_id, &cursor).await.into_diagnostic()?;
+    if current.is_none_or(|at| at < latest) {
+        storage.set_cursor(repo_id, &cursor, latest).await.into_diagnostic()?;
+    }
+
+    Ok(())
+}
+
+/// How far a run got through the pull requests, which `--resume` continues after
+///
+/// Stored as text such as `number=1234 updated_at=2024-05-01T12:00:00Z page=3 latest=2024-06-01T08:00:00Z`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Checkpoint {
+    /// The last pull request gone through
+    number: u32,
+    /// When it was last updated, for runs going through the most recently updated first
+    updated_at: Option<DateTime<Utc>>,
+    /// Page of GitHub's listing it was on, for runs paging through one
+    page: Option<u32>,
+    /// Latest `updated_at` the run covers, which is as far as the cursor moves
+    /// once a run resuming it completes
+    latest: Option<DateTime<Utc>>,
+}
+
+impl Checkpoint {
+    /// Whether the run had gone through a pull request when the checkpoint was saved
+    ///
+    /// Runs going through the most recently updated first had gone through
+    /// everything updated later, except what was updated since they started,
+    /// which the cursor leaves to the next run. Others go by number.
+    fn covers(&self, number: u32, updated_at: Option<DateTime<Utc>>) -> bool {
+        match (self.updated_at, updated_at) {
+            (Some(checkpoint), Some(updated_at)) => {
+                updated_at > checkpoint || (updated_at == checkpoint && number == self.number)
+            }
+            _ => number <= self.number,
+        }
+    }
+}
+
+impl std::fmt::Display for Checkpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let time = |time: DateTime<Utc>| time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+        write!(f, "number={}", self.number)?;
+        if let Some(updated_at) = self.updated_at {
+            write!(f, " updated_at={}", time(updated_at))?;
+        }
+        if let Some(page) = self.page {
+            write!(f, " page={}", page)?;
+        }
+        if let Some(latest) = self.latest {
+            write!(f, " latest={}", time(latest))?;
+        }
+        Ok(())
+    }
+}
+
+impl std::str::FromStr for Checkpoint {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let invalid = || format!("\"{}\" is not a checkpoint", s);
+        let time = |value: &str| DateTime::parse_from_rfc3339(value).map(|time| time.with_timezone(&Utc)).map_err(|_| invalid());
+        let mut number = None;
+        let mut checkpoint = Self { number: 0, updated_at: None, page: None, latest: None };
+        for field in s.split_whitespace() {
+            match field.split_once('=').ok_or_else(invalid)? {
+                ("number", value) => number = Some(value.parse().map_err(|_| invalid())?),
+                ("updated_at", value) => checkpoint.updated_at = Some(time(value)?),
+                ("page", value) => checkpoint.page = Some(value.parse().map_err(|_| invalid())?),
+                ("latest", value) => checkpoint.latest = Some(time(value)?),
+                _ => return Err(invalid()),
+            }
+        }
+        checkpoint.number = number.ok_or_else(invalid)?;
+
+        Ok(checkpoint)
+    }
+}
+
+/// What a run covers: the collector and state its cursor is named after, the
+/// shard and the range. `--resume` only continues a run that covered the same.
+fn checkpoint_scope(cursor: &str, range: Option<NumberRange>) -> String {
+    match range {
+        Some(range) => format!("{} {}", shard_cursor(cursor), range),
+        None => shard_cursor(cursor),
+    }
+}
+
+/// The checkpoint `--resume` continues after, from the previous run covering
+/// `scope` if it didn't finish
+///
+/// The scope and checkpoint are recorded for the current run straight away, so
+/// resuming again works even if this run fails before getting any further, and
+/// a run that fails before its first checkpoint isn't resumed from an older one.
+async fn resume_from(
+    options: &CollectOptions,
+    storage: &dyn Storage,
+    repo_id: u32,
+    collector: &str,
+    scope: &str,
+) -> Result<Option<Checkpoint>> {
+    let run = CURRENT_RUN.try_with(|id| *id).ok();
+    let checkpoint = match options.resume {
+        true => storage
+            .get_unfinished_checkpoint(repo_id, collector, scope, run)
+            .await
+            .into_diagnostic()?
+            .and_then(|checkpoint| checkpoint.parse::<Checkpoint>().ok()),
+        false => None,
+    };
+    if options.resume && checkpoint.is_none() {
+        println!("No unfinished {} run to resume; starting from the beginning", collector);
+    }
+
+    if let Some(run) = run {
+        let saved = checkpoint.map(|checkpoint| checkpoint.to_string()).unwrap_or_default();
+        storage.set_run_checkpoint(run, scope, &saved).await.into_diagnostic()?;
+    }
+
+    Ok(checkpoint)
+}
+
+/// Records how far the current run got
+async fn save_checkpoint(storage: &dyn Storage, scope: &str, checkpoint: &Checkpoint) -> Result<()> {
+    if let Ok(run) = CURRENT_RUN.try_with(|id| *id) {
+        storage.set_run_checkpoint(run, scope, &checkpoint.to_string()).await.into_diagnostic()?;
+    }
+
+    Ok(())
+}
+
+/// Pull request numbers a collector is limited to by `--range`
+///
+/// The end is excluded, as in `1000..2000`, so consecutive slices don't overlap,
+/// and either end may be left out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NumberRange {
+    pub start: u32,
+    pub end: Option<u32>,
+}
+
+impl NumberRange {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || "Must be a range of pull request numbers such as 1000..2000".to_string();
+        let (start, end) = s.split_once("..").ok_or_else(invalid)?;
+        let number = |n: &str| n.trim().parse::<u32>().map_err(|_| invalid());
+        let start = if start.trim().is_empty() { 0 } else { number(start)? };
+        let end = if end.trim().is_empty() { None } else { Some(number(end)?) };
+        if end.is_some_and(|end| end <= start) {
+            return Err("The end of the range must come after its start".to_string());
+        }
+
+        Ok(Self { start, end })
+    }
+
+    pub fn contains(&self, number: u32) -> bool {
+        number >= self.start && self.end.is_none_or(|end| number < end)
+    }
+}
+
+impl std::fmt::Display for NumberRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.end {
+            Some(end) => write!(f, "{}..{}", self.start, end),
+            None => write!(f, "{}..", self.start),
+        }
+    }
+}
+
+/// The share of pull requests one of several workers collects with `--shard`
+///
+/// `2/5` is the second of five shards. Pull requests are dealt out by number,
+/// so each shard gets about as many however they are spread over repositories,
+/// and together the shards collect every pull request exactly once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Shard {
+    pub index: u32,
+    pub count: u32,
+}
+
+impl Shard {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || "Must be a shard such as 2/5, the second of five".to_string();
+        let (index, count) = s.split_once('/').ok_or_else(invalid)?;
+        let index = index.trim().parse::<u32>().map_err(|_| invalid())?;
+        let count = count.trim().parse::<u32>().ok().filter(|count| *count > 0).ok_or_else(invalid)?;
+        if index == 0 || index > count {
+            return Err(format!("The shard must be between 1 and {}", count));
+        }
+
+        Ok(Self { index, count })
+    }
+
+    pub fn contains(&self, number: u32) -> bool {
+        number % self.count == self.index - 1
+    }
+
+    /// The first shard also runs the collectors that aren't per pull request
+    pub fn is_first(&self) -> bool {
+        self.index == 1
+    }
+}
+
+impl std::fmt::Display for Shard {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}/{}", self.index, self.count)
+    }
+}
+
+/// Shard of the pull requests the running command collects, if any
+pub fn shard() -> Option<Shard> {
+    Context::current().shard
+}
+
+/// Whether the pull request with this number belongs to the shard being collected, if any
+fn in_shard(number: u32) -> bool {
+    shard().is_none_or(|shard| shard.contains(number))
+}
+
+/// Cursors are kept per shard, as each shard's runs cover different pull requests
+fn shard_cursor(cursor: &str) -> String {
+    match shard() {
+        Some(shard) => format!("{}@{}", cursor, shard),
+        None => cursor.to_string(),
+    }
+}
+
+/// Whether `--strict` or `collect.strict` asks collectors to fail on incomplete items
+fn strict(options: &CollectOptions) -> Result<bool> {
+    if options.strict {
+        return Ok(true);
+    }
+
+    Ok(Config::load().into_diagnostic()?.collect.strict.unwrap_or(false))
+}
+
+/// Whether `--keep-raw` or `collect.keep_raw` asks collectors to store the JSON of every item
+fn keep_raw(options: &CollectOptions) -> Result<bool> {
+    if options.keep_raw {
+        return Ok(true);
+    }
+
+    Ok(Config::load().into_diagnostic()?.collect.keep_raw.unwrap_or(false))
+}
+
+/// In strict mode, fails when an item lacks fields that would otherwise be
+/// stored as empty strings; `missing` pairs each field with whether it is absent
+fn require_fields(strict: bool, item: &str, missing: &[(&str, bool)]) -> Result<()> {
+    let missing: Vec<&str> = missing.iter().filter(|(_, absent)| *absent).map(|(field, _)| *field).collect();
+    if !strict || missing.is_empty() {
+        return Ok(());
+    }
+
+    Err(miette!(
+        "{} is missing {}; strict mode stores nothing incomplete. Run without --strict to store it anyway.",
+        item,
+        missing.join(", ")
+    ))
+}
+
+/// Fields of a pull request that `PullRequest::create_many` would store as empty strings
+fn pull_missing_fields(pull: &octocrab::models::pulls::PullRequest) -> [(&'static str, bool); 3] {
+    [
+        ("created_at", pull.created_at.is_none()),
+        ("updated_at", pull.updated_at.is_none()),
+        ("author", pull.user.is_none()),
+    ]
+}
+
+/// Number of pull requests `--concurrency` allows to be fetched at the same time,
+/// capped by the `max_concurrency` of the API host
+fn concurrency(options: &CollectOptions) -> usize {
+    allowed_concurrency(options.concurrency).max(1) as usize
+}
+
+/// Paces requests by GitHub's rate limit; other forges have limits of their own gdfm doesn't read
+fn rate_limiter(repo: &Repository, progress_bar: &ProgressBar) -> Option<RateLimiter> {
+    (repo.forge == GITHUB).then(|| RateLimiter::new(progress_bar))
+}
+
+/// ETags stored by earlier runs for a repository; none with `--full`
+async fn stored_etags(storage: &dyn Storage, repo_id: u32, options: &CollectOptions) -> Result<Etags> {
+    if options.full {
+        return Ok(Etags::new());
+    }
+
+    storage.get_etags(repo_id).await.into_diagnostic()
+}
+
+/// Lists the reviews or events of a batch of pull requests at the same time,
+/// returning them in the order of the pull requests
+///
+/// Listings are stored only once the whole batch is in, so checkpoints still
+/// follow pull request numbers and `--resume` never skips one that failed.
+/// The limiter is waited for before every page of every listing.
+async fn fetch_listings<T, F>(
+    limiter: &mut Option<RateLimiter>,
+    batch: &[PullRequest],
+    list: impl Fn(u32) -> F,
+) -> Result<Vec<Listing<T>>>
+where
+    F: Future<Output = std::result::Result<Listing<T>, ForgeError>>,
+{
+    let listings = try_join_all(batch.iter().map(|pull| list(pull.number)));
+
+    RateLimiter::pace_pages(limiter, listings).await.into_diagnostic()
+}
+
+/// Stores the ETags a listing came with, once what it contained has been stored
+async fn remember_etags(storage: &dyn Storage, repo_id: u32, etags: Vec<(String, String)>) -> Result<()> {
+    for (route, etag) in etags {
+        storage.set_etag(repo_id, &route, &etag).await.into_diagnostic()?;
+    }
+
+    Ok(())
+}
+
+/// Creates a standard progress bar with a custom message
+pub fn get_progress_bar(total: u64, message: &str) -> ProgressBar {
+    let pb = ProgressBar::new(total);
+    pb.set_style(ProgressStyle::default_bar()
+        .template("{prefix}[{elapsed_precise}] {bar:40.cyan/blue} {pos:>7}/{len:7} {msg}",)
+        .unwrap()
+        .progress_chars("##-"));
+    pb.set_message(message.to_string());
+    track(pb)
+}
+
+/// Spinner for collectors that don't know how many items they will fetch
+pub fn get_spinner(message: &str) -> ProgressBar {
+    let pb = ProgressBar::new_spinner();
+    pb.set_style(ProgressStyle::default_spinner()
+        .template("{prefix}{spinner} {msg}")
+        .unwrap());
+    pb.set_message(message.to_string());
+    track(pb)
+}
+
+tokio::task_local! {
+    /// Set while several repositories are collected at once, so their progress
+    /// bars stack up under a prefix instead of overwriting each other
+    pub static PROGRESS: (MultiProgress, String);
+}
+
+fn track(pb: ProgressBar) -> ProgressBar {
+    match PROGRESS.try_with(|progress| progress.clone()) {
+        Ok((multi, prefix)) => {
+            pb.set_prefix(prefix);
+            multi.add(pb)
+        }
+        Err(_) => pb,
+    }
+}
+
+pub async fn get_total_pull_requests(
+    octocrab: &Octocrab,
+    owner: &str,
+    repo: &str,
+    state: State,
+    since: Option<DateTime<Utc>>,
+) -> Result<u32, octocrab::Error> {
+    let qualifier = match state {
+        State::Open => " is:open",
+        State::Closed => " is:closed",
+        _ => "",
+    };
+    let updated = match since {
+        Some(since) => format!(" updated:>{}", since.format("%Y-%m-%dT%H:%M:%SZ")),
+        None => "".to_string(),
+    };
+    let search = format!("repo:{}/{} is:pr{}{}", owner, repo, qualifier, updated);
+    let results = retry(|| {
+        octocrab
+            .search()
+            .issues_and_pull_requests(&search)
+            .per_page(1)
+            .send()
+    })
+    .await?;
+
+    if let Some(count) = results.total_count {
+        Ok(count as u32)
+    } else {
+        Ok(0u32)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use async_trait::async_trait;
+    use octocrab::models::Event;
+
+    use super::*;
+    use crate::database::get_db_path;
+    use crate::forge::{ForgeComment, ForgeEvent, ForgePull, ForgeReview, GITLAB};
+
+    /// A forge with one pull request, whose timeline has one event
+    struct OneEvent;
+
+    #[async_trait]
+    impl Forge for OneEvent {
+        async fn list_pulls(&self, _since: Option<DateTime<Utc>>) -> Result<Vec<ForgePull>, ForgeError> {
+            let now = Utc::now();
+            Ok(vec![ForgePull {
+                id: 7,
+                number: 1,
+                title: "Fix the build".to_string(),
+                open: true,
+                created_at: now,
+                updated_at: now,
+                closed_at: None,
+                merged_at: None,
+                author: "alice".to_string(),
+                author_association: "MEMBER".to_string(),
+                base_ref: "main".to_string(),
+                head_ref: "fix".to_string(),
+                merged_by: String::new(),
+                merge_commit_sha: String::new(),
+            }])
+        }
+
+        async fn list_reviews(&self, _number: u32, _etags: &Etags) -> Result<Listing<ForgeReview>, ForgeError> {
+            Ok(Listing::changed(Vec::new()))
+        }
+
+        async fn list_events(&self, _number: u32, _etags: &Etags) -> Result<Listing<ForgeEvent>, ForgeError> {
+            Ok(Listing::changed(vec![ForgeEvent {
+                id: Some(42),
+                event: Event::Closed,
+                actor: "bob".to_string(),
+                author_association: "MEMBER".to_string(),
+                created_at: Some(Utc::now()),
+                requested_reviewer: String::new(),
+                raw: Some(serde_json::json!({ "id": 42, "event": "closed" })),
+            }]))
+        }
+
+        async fn list_comments(&self, _number: u32) -> Result<Vec<ForgeComment>, ForgeError> {
+            Ok(Vec::new())
+        }
+    }
+
+    #[test]
+    fn checkpoints_are_read_back() {
+        let time = |s: &str| s.parse::<DateTime<Utc>>().unwrap();
+        let checkpoint = Checkpoint {
+            number: 1234,
+            updated_at: Some(time("2024-05-01T12:00:00Z")),
+            page: Some(3),
+            latest: Some(time("2024-06-01T08:00:00Z")),
+        };
+        let text = checkpoint.to_string();
+        assert_eq!(text, "number=1234 updated_at=2024-05-01T12:00:00Z page=3 latest=2024-06-01T08:00:00Z");
+        assert_eq!(text.parse::<Checkpoint>(), Ok(checkpoint));
+        assert_eq!("number=7".parse::<Checkpoint>().map(|checkpoint| checkpoint.number), Ok(7));
+        // Pages saved before checkpoints named the pull request can't be resumed from
+        assert!("3".parse::<Checkpoint>().is_err());
+    }
+
+    #[test]
+    fn checkpoints_cover_what_was_gone_through() {
+        let time = |s: &str| Some(s.parse::<DateTime<Utc>>().unwrap());
+        let updated = Checkpoint { number: 50, updated_at: time("2024-05-01T12:00:00Z"), page: Some(2), latest: None };
+        // Updated later, so listed before it, or the pull request itself
+        assert!(updated.covers(10, time("2024-05-02T00:00:00Z")));
+        assert!(updated.covers(50, time("2024-05-01T12:00:00Z")));
+        // Updated at the same time or earlier, whatever the number
+        assert!(!updated.covers(60, time("2024-05-01T12:00:00Z")));
+        assert!(!updated.covers(10, time("2024-04-30T00:00:00Z")));
+
+        let numbered = Checkpoint { number: 50, updated_at: None, page: None, latest: None };
+        assert!(numbered.covers(50, None));
+        assert!(!numbered.covers(51, None));
+    }
+
+    #[tokio::test]
+    async fn keep_raw_of_collect_all_stores_raw_payloads() {
+        let matches = crate::command()
+            .try_get_matches_from(["gdfm", "collect", "all", "octo/repo", "--keep-raw"])
+            .unwrap();
+        let (_, collect_matches) = matches.subcommand().unwrap();
+        let (_, all_matches) = collect_matches.subcommand().unwrap();
+        let options = CollectOptions::from_matches(all_matches);
+        assert!(options.keep_raw);
+
+        let context = Context::default().with_project(&format!("test-keep-raw-{}", std::process::id()));
+        let path = context.clone().scope(async { get_db_path() }).await;
+        let stored = context
+            .scope(async {
+                let storage = open_storage().await.unwrap();
+                let repo = storage.create_repository("octo/repo").await.unwrap();
+                storage.set_repository_forge(repo.id, GITLAB).await.unwrap();
+                let repo = storage.get_repository("octo/repo").await.unwrap();
+                storage.create_forge_pulls(repo.id, &OneEvent.list_pulls(None).await.unwrap()).await.unwrap();
+
+                collect_pull_events(&options, &repo, &OneEvent).await.unwrap();
+
+                let pool = crate::database::setup_db().await.unwrap();
+                sqlx::query_as::<_, (String, i64)>("SELECT kind, github_id FROM raw_payloads")
+                    .fetch_all(&pool)
+                    .await
+                    .unwrap()
+            })
+            .await;
+
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+        assert_eq!(stored, vec![("event".to_string(), 42)]);
+    }
 }
@@ -0,0 +1,146 @@
+/// Module holding the `db` commands
+///
+/// `db shell` opens the gdfm database in the `sqlite3` shell when it is
+/// installed, or in a small built-in shell otherwise. Both have foreign keys
+/// enabled and a few temporary views that save joining tables by hand.
+/// `db import` merges databases collected elsewhere, such as by the workers of
+/// a `--shard` run.
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::Command;
+
+use clap::ArgMatches;
+use console::style;
+use miette::{miette, IntoDiagnostic, Result};
+use sqlx::pool::PoolConnection;
+use sqlx::{Column, Row, Sqlite};
+
+use crate::constants::{CLI_ARGS_BUILTIN, CLI_ARGS_PATH};
+use crate::database::{get_db_path, merge_database, row_values, setup_db, CellValue, TABLES};
+
+/// Statements run when a shell opens; the views are temporary so they never
+/// end up in the database itself
+pub const SHELL_SETUP: &[&str] = &[
+    "PRAGMA foreign_keys = ON",
+    "CREATE TEMP VIEW pull_requests AS
+        SELECT r.owner || '/' || r.name AS repository, p.*
+        FROM pulls p JOIN repositories r ON r.id = p.repo_id
+        WHERE p.is_issue = 0",
+    "CREATE TEMP VIEW issues AS
+        SELECT r.owner || '/' || r.name AS repository, p.*
+        FROM pulls p JOIN repositories r ON r.id = p.repo_id
+        WHERE p.is_issue = 1",
+];
+
+pub async fn shell(matches: &ArgMatches) -> Result<()> {
+    // Creates the database and brings its schema up to date before anyone looks at it
+    let pool = setup_db().await.into_diagnostic()?;
+    let path = get_db_path();
+
+    if !matches.get_flag(CLI_ARGS_BUILTIN) {
+        let mut sqlite3 = Command::new("sqlite3");
+        for statement in SHELL_SETUP {
+            sqlite3.arg("-cmd").arg(format!("{};", statement));
+        }
+        // sqlite3 reports failed statements itself, so its exit status is not checked
+        match sqlite3.arg(&path).status() {
+            Ok(_) => return Ok(()),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                println!("{}", style("sqlite3 is not installed, using the built-in shell").yellow());
+            }
+            Err(err) => return Err(err).into_diagnostic(),
+        }
+    }
+
+    let mut connection = pool.acquire().await.into_diagnostic()?;
+    for statement in SHELL_SETUP {
+        sqlx::query(statement).execute(&mut *connection).await.into_diagnostic()?;
+    }
+
+    println!("Connected to {}", style(path.display()).bold().cyan());
+    println!("End statements with \";\". Type .tables to list tables and .quit to leave.");
+
+    let stdin = std::io::stdin();
+    let mut statement = String::new();
+    loop {
+        print!("{}", if statement.is_empty() { "gdfm> " } else { "  ...> " });
+        std::io::stdout().flush().into_diagnostic()?;
+
+        let mut line = String::new();
+        if stdin.lock().read_line(&mut line).into_diagnostic()? == 0 {
+            println!();
+            break;
+        }
+        let line = line.trim();
+
+        if statement.is_empty() {
+            match line {
+                "" => continue,
+                ".quit" | ".exit" => break,
+                ".tables" => {
+                    println!("{}", TABLES.join("  "));
+                    continue;
+                }
+                _ => {}
+            }
+        }
+
+        statement.push_str(line);
+        statement.push('\n');
+        if line.ends_with(';') {
+            if let Err(err) = run_statement(&mut connection, &statement).await {
+                println!("{}", style(err).red());
+            }
+            statement.clear();
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs one statement and prints its rows separated by `|`, as `sqlite3` does
+async fn run_statement(connection: &mut PoolConnection<Sqlite>, statement: &str) -> Result<(), sqlx::Error> {
+    let rows = sqlx::query(statement).fetch_all(&mut **connection).await?;
+    let Some(first) = rows.first() else {
+        return Ok(());
+    };
+
+    let header: Vec<&str> = first.columns().iter().map(|column| column.name()).collect();
+    println!("{}", style(header.join("|")).bold());
+    for row in &rows {
+        let values: Vec<String> = row_values(row)?
+            .into_iter()
+            .map(|value| match value {
+                CellValue::Null => String::new(),
+                CellValue::Integer(value) => value.to_string(),
+                CellValue::Real(value) => value.to_string(),
+                CellValue::Text(value) => value,
+            })
+            .collect();
+        println!("{}", values.join("|"));
+    }
+
+    Ok(())
+}
+
+pub async fn import(matches: &ArgMatches) -> Result<()> {
+    let pool = setup_db().await.into_diagnostic()?;
+    let own = get_db_path().canonicalize().into_diagnostic()?;
+
+    for path in matches.get_many::<String>(CLI_ARGS_PATH).expect("a database is required") {
+        // Attaching a file that doesn't exist would create it
+        let path = Path::new(path)
+            .canonicalize()
+            .map_err(|err| miette!("Could not open {}: {}", path, err))?;
+        if path == own {
+            return Err(miette!("{} is the database being imported into", path.display()));
+        }
+
+        let rows = merge_database(&pool, &path.to_string_lossy(), false)
+            .await
+            .map_err(|err| miette!("Could not import {}: {}", path.display(), err))?;
+        println!("Imported {} new rows from {}", style(rows).bold().cyan(), path.display());
+    }
+
+    Ok(())
+}
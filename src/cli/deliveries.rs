@@ -0,0 +1,168 @@
+/// Module holding the `deliveries` commands
+///
+/// Helps debug the webhook listener: list what was received, show a payload,
+/// find deliveries GitHub sent that never arrived and ask GitHub to send one again.
+use std::collections::HashSet;
+
+use clap::ArgMatches;
+use console::style;
+use miette::{miette, Result, IntoDiagnostic};
+use octocrab::Octocrab;
+use serde::Deserialize;
+
+use crate::client::authenticated_client;
+use crate::constants::{CLI_ARGS_DELIVERY, CLI_ARGS_HOOK, CLI_ARGS_LIMIT, CLI_ARGS_REPO};
+use crate::database::{setup_db, WebhookDelivery};
+use crate::terminal::Table;
+
+/// A delivery from the `/repos/{owner}/{repo}/hooks/{hook_id}/deliveries` endpoint
+#[derive(Debug, Deserialize)]
+struct HookDelivery {
+    id: i64,
+    guid: String,
+    delivered_at: String,
+    status_code: i64,
+    event: String,
+    action: Option<String>,
+}
+
+pub async fn list_deliveries(matches: &ArgMatches) -> Result<()> {
+    let repository = matches.get_one::<String>(CLI_ARGS_REPO);
+    let limit = *matches.get_one::<u32>(CLI_ARGS_LIMIT).expect("limit has a default");
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let deliveries = WebhookDelivery::fetch_recent(&pool, repository.map(String::as_str), limit)
+        .await
+        .into_diagnostic()?;
+
+    if deliveries.is_empty() {
+        println!("No deliveries received yet");
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Received", "Delivery", "Repository", "Event", "Signature"]);
+    for delivery in &deliveries {
+        let signature = if delivery.signature_valid {
+            style("verified").green()
+        } else {
+            style("bad signature").red()
+        };
+        let event = if delivery.action.is_empty() {
+            delivery.event.clone()
+        } else {
+            format!("{}.{}", delivery.event, delivery.action)
+        };
+        table.push(vec![
+            delivery.received_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            style(&delivery.delivery_id).dim().to_string(),
+            delivery.repository.clone(),
+            event,
+            signature.to_string(),
+        ]);
+    }
+    table.print();
+
+    Ok(())
+}
+
+pub async fn show_delivery(matches: &ArgMatches) -> Result<()> {
+    let delivery_id = matches
+        .get_one::<String>(CLI_ARGS_DELIVERY)
+        .expect("delivery id is required");
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let delivery = WebhookDelivery::fetch(&pool, delivery_id)
+        .await
+        .map_err(|_| miette!("No delivery with id {} was received", delivery_id))?;
+
+    if !delivery.signature_valid {
+        return Err(miette!("Delivery {} had a bad signature, so its payload wasn't kept", delivery_id));
+    }
+
+    let payload: serde_json::Value = serde_json::from_str(&delivery.payload).into_diagnostic()?;
+    println!("{}", serde_json::to_string_pretty(&payload).into_diagnostic()?);
+
+    Ok(())
+}
+
+/// Lists recent deliveries GitHub attempted that were never received or failed verification
+pub async fn missed_deliveries(matches: &ArgMatches) -> Result<()> {
+    let (octocrab, route, pool) = hook_client(matches).await?;
+    let repository = matches.get_one::<String>(CLI_ARGS_REPO).expect("repository is required");
+
+    let known: HashSet<String> = WebhookDelivery::fetch_known_ids(&pool, repository)
+        .await
+        .into_diagnostic()?
+        .into_iter()
+        .collect();
+    let deliveries: Vec<HookDelivery> = octocrab.get(&route, Some(&[("per_page", "100")]))
+        .await.into_diagnostic()?;
+
+    let missed: Vec<&HookDelivery> = deliveries.iter().filter(|delivery| !known.contains(&delivery.guid)).collect();
+    if missed.is_empty() {
+        println!("Every recent delivery was received");
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Delivered", "Delivery", "Event", "Status"]).align_right(3);
+    for delivery in missed {
+        table.push(vec![
+            delivery.delivered_at.to_string(),
+            style(&delivery.guid).dim().to_string(),
+            format!(
+                "{}{}",
+                delivery.event,
+                delivery.action.as_ref().map(|action| format!(".{}", action)).unwrap_or_default()
+            ),
+            delivery.status_code.to_string(),
+        ]);
+    }
+    table.print();
+
+    Ok(())
+}
+
+/// Asks GitHub to send a delivery again
+pub async fn redeliver(matches: &ArgMatches) -> Result<()> {
+    let (octocrab, route, _) = hook_client(matches).await?;
+    let guid = matches
+        .get_one::<String>(CLI_ARGS_DELIVERY)
+        .expect("delivery id is required");
+
+    let deliveries: Vec<HookDelivery> = octocrab.get(&route, Some(&[("per_page", "100")]))
+        .await.into_diagnostic()?;
+    let delivery = deliveries
+        .iter()
+        .find(|delivery| delivery.guid == *guid)
+        .ok_or_else(|| miette!("Delivery {} is not among the hook's 100 most recent deliveries", guid))?;
+
+    let response = octocrab
+        ._post(format!("{}/{}/attempts", route, delivery.id), None::<&()>)
+        .await
+        .into_diagnostic()?;
+    octocrab::map_github_error(response).await.into_diagnostic()?;
+
+    println!("Redelivery of {} requested", style(guid).bold().cyan());
+
+    Ok(())
+}
+
+/// Builds a client and the deliveries route for the hook of a repository
+///
+/// The hook id comes from `--hook` or from the last delivery received for the repository.
+async fn hook_client(matches: &ArgMatches) -> Result<(Octocrab, String, sqlx::SqlitePool)> {
+    let repository = matches.get_one::<String>(CLI_ARGS_REPO).expect("repository is required");
+    let pool = setup_db().await.into_diagnostic()?;
+
+    let hook_id = match matches.get_one::<i64>(CLI_ARGS_HOOK) {
+        Some(hook_id) => *hook_id,
+        None => WebhookDelivery::latest_hook_id(&pool, repository)
+            .await
+            .into_diagnostic()?
+            .ok_or_else(|| miette!("No deliveries received for {}; pass --hook with the hook id", repository))?,
+    };
+
+    let octocrab = authenticated_client()?;
+
+    Ok((octocrab, format!("/repos/{}/hooks/{}/deliveries", repository, hook_id), pool))
+}
@@ -0,0 +1,304 @@
+/// Module holding the `doctor` command
+///
+/// Checks the database for what interrupted collections, imports and older
+/// versions of gdfm can leave behind: rows whose pull request or repository is
+/// gone, pull requests whose events were never collected, duplicate rows,
+/// timestamps stored as empty strings, and tables, columns or a schema version
+/// gdfm doesn't know.
+/// The checks only read the database. With `--fix`, it is migrated when an
+/// older version of gdfm wrote it, and the problems that can be repaired
+/// without collecting again are, in one transaction; the rest say what to run
+/// instead.
+use std::collections::HashSet;
+
+use clap::ArgMatches;
+use console::style;
+use miette::{miette, IntoDiagnostic, Result};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::constants::CLI_ARGS_FIX;
+use crate::database::{
+    get_db_path, open_db_read_only, setup_db, stored_schema_version, table_info, SCHEMA, SCHEMA_VERSION, TABLES,
+};
+use crate::terminal::Table;
+
+/// Tables where rows alike in everything but their id are copies of one item
+///
+/// Events are left out: several labels added in the same second are stored as
+/// events that look the same.
+const DUPLICATE_TABLES: &[&str] = &["issue_pull_reviews", "issue_pull_comments", "issue_pull_review_comments"];
+
+/// Something wrong with the database, with how it can be repaired when it can
+#[derive(Debug)]
+struct Problem {
+    check: &'static str,
+    /// Rows affected, for problems that are about rows
+    rows: Option<i64>,
+    detail: String,
+    fix: Option<Fix>,
+}
+
+/// How `--fix` repairs a problem
+#[derive(Debug)]
+enum Fix {
+    /// A statement run along with those of the other problems
+    Statement(String),
+    /// Migrating the database to the schema of this version of gdfm
+    Migration,
+}
+
+pub async fn doctor(matches: &ArgMatches) -> Result<()> {
+    let fix = matches.get_flag(CLI_ARGS_FIX);
+    let path = get_db_path();
+    let Some(version) = stored_schema_version(&path).await.into_diagnostic()? else {
+        println!("There is no database at {} to check yet", style(path.display()).bold().cyan());
+        return Ok(());
+    };
+    // Only `--fix` writes to the database, migrating it first
+    let outdated = version < SCHEMA_VERSION;
+    let pool = if fix {
+        setup_db().await.into_diagnostic()?
+    } else {
+        open_db_read_only(&path).await.into_diagnostic()?
+    };
+    let schema_version = if fix { SCHEMA_VERSION.max(version) } else { version };
+
+    let mut problems = integrity(&pool).await.into_diagnostic()?;
+    problems.extend(schema_mismatches(&pool, schema_version).await.into_diagnostic()?);
+    problems.extend(orphans(&pool).await.into_diagnostic()?);
+    problems.extend(duplicates(&pool).await.into_diagnostic()?);
+    problems.extend(empty_timestamps(&pool).await.into_diagnostic()?);
+    problems.extend(missing_events(&pool).await.into_diagnostic()?);
+
+    println!("Checked {}", style(path.display()).bold().cyan());
+    if fix && outdated {
+        println!(
+            "{}",
+            style(format!(
+                "Migrated from schema version {} to {}, as an older version of gdfm last wrote the database",
+                version, SCHEMA_VERSION
+            ))
+            .yellow()
+        );
+    }
+    if problems.is_empty() {
+        println!("{}", style("No problems found").green());
+        return Ok(());
+    }
+
+    let mut table = Table::new(&["Check", "Rows", "Problem", "Fix"]).align_right(1);
+    for problem in &problems {
+        let repair = match (&problem.fix, fix) {
+            (Some(_), true) => style("fixed".to_string()).green(),
+            (Some(_), false) => style("--fix".to_string()).yellow(),
+            (None, _) => style("by hand".to_string()).red(),
+        };
+        let rows = problem.rows.map(|rows| rows.to_string()).unwrap_or_default();
+        table.push(vec![problem.check.to_string(), rows, problem.detail.clone(), repair.to_string()]);
+    }
+
+    if fix {
+        let mut transaction = pool.begin().await.into_diagnostic()?;
+        for problem in &problems {
+            if let Some(Fix::Statement(statement)) = &problem.fix {
+                sqlx::query(statement).execute(&mut *transaction).await.into_diagnostic()?;
+            }
+        }
+        transaction.commit().await.into_diagnostic()?;
+    }
+    table.print();
+
+    let by_hand = problems.iter().filter(|problem| problem.fix.is_none()).count();
+    let fixable = problems.len() - by_hand;
+    if fix {
+        return match by_hand {
+            0 => Ok(()),
+            _ => Err(miette!("{} problems need fixing by hand", by_hand)),
+        };
+    }
+    match fixable {
+        0 => Err(miette!("{} problems found", problems.len())),
+        _ => Err(miette!("{} problems found; `gdfm doctor --fix` repairs {} of them", problems.len(), fixable)),
+    }
+}
+
+/// Damage SQLite itself finds in the database file
+async fn integrity(pool: &Pool<Sqlite>) -> Result<Vec<Problem>, sqlx::Error> {
+    let messages: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check").fetch_all(pool).await?;
+
+    Ok(messages
+        .into_iter()
+        .filter(|message| message != "ok")
+        .map(|message| Problem { check: "integrity", rows: None, detail: message, fix: None })
+        .collect())
+}
+
+/// Tables, columns and a schema version that differ from those this version
+/// of gdfm describes, as when the database was last written by a newer one
+///
+/// or by an older one and not migrated yet
+async fn schema_mismatches(pool: &Pool<Sqlite>, version: i64) -> Result<Vec<Problem>, sqlx::Error> {
+    let mut problems = Vec::new();
+    if version < SCHEMA_VERSION {
+        // What the migration adds isn't listed on its own
+        problems.push(Problem {
+            check: "schema",
+            rows: None,
+            detail: format!(
+                "Schema version {} was written by an older gdfm and needs migrating to {}",
+                version, SCHEMA_VERSION
+            ),
+            fix: Some(Fix::Migration),
+        });
+        return Ok(problems);
+    }
+
+    let problem = |detail: String| Problem { check: "schema", rows: None, detail, fix: None };
+    if version > SCHEMA_VERSION {
+        problems.push(problem(format!(
+            "Schema version {} was written by a newer gdfm than this one, which knows up to {}; upgrade gdfm",
+            version, SCHEMA_VERSION
+        )));
+    }
+
+    let tables: Vec<String> = sqlx::query_scalar(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )
+    .fetch_all(pool)
+    .await?;
+    for table in tables.iter().filter(|table| !TABLES.contains(&table.as_str())) {
+        problems.push(problem(format!("Table {} is unknown to this version of gdfm", table)));
+    }
+
+    for doc in SCHEMA {
+        let columns: HashSet<String> =
+            table_info(pool, doc.table).await?.into_iter().map(|column| column.name).collect();
+        for column in doc.columns.iter().filter(|column| !columns.contains(column.name)) {
+            problems.push(problem(format!("Column {}.{} is missing", doc.table, column.name)));
+        }
+        let known: HashSet<&str> = doc.columns.iter().map(|column| column.name).collect();
+        let mut unknown: Vec<&String> = columns.iter().filter(|column| !known.contains(column.as_str())).collect();
+        unknown.sort();
+        for column in unknown {
+            problems.push(problem(format!("Column {}.{} is unknown to this version of gdfm", doc.table, column)));
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Rows pointing at a pull request, repository or other row that no longer exists
+async fn orphans(pool: &Pool<Sqlite>) -> Result<Vec<Problem>, sqlx::Error> {
+    let orphans: Vec<(String, String, i64)> = sqlx::query_as(
+        "SELECT \"table\", parent, COUNT(*) FROM pragma_foreign_key_check GROUP BY 1, 2 ORDER BY 1, 2",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(orphans
+        .into_iter()
+        .map(|(table, parent, rows)| Problem {
+            check: "orphans",
+            rows: Some(rows),
+            detail: format!("Rows of {} point at {} that don't exist", table, parent),
+            fix: Some(Fix::Statement(format!(
+                "DELETE FROM {table} WHERE rowid IN (SELECT rowid FROM pragma_foreign_key_check('{table}'))"
+            ))),
+        })
+        .collect())
+}
+
+/// Copies of the same review or comment, of which `--fix` keeps the first
+async fn duplicates(pool: &Pool<Sqlite>) -> Result<Vec<Problem>, sqlx::Error> {
+    let mut problems = Vec::new();
+    for table in DUPLICATE_TABLES {
+        let columns: Vec<String> = table_info(pool, table)
+            .await?
+            .into_iter()
+            .filter(|column| !column.primary_key)
+            .map(|column| column.name)
+            .collect();
+        // Missing from databases old enough not to have the table yet
+        if columns.is_empty() {
+            continue;
+        }
+        let copies = format!(
+            "{table} WHERE id NOT IN (SELECT MIN(id) FROM {table} GROUP BY {})",
+            columns.join(", ")
+        );
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", copies)).fetch_one(pool).await?;
+        if rows > 0 {
+            problems.push(Problem {
+                check: "duplicates",
+                rows: Some(rows),
+                detail: format!("Rows of {} are copies of another row", table),
+                fix: Some(Fix::Statement(format!("DELETE FROM {}", copies))),
+            });
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Times stored as empty strings rather than left empty, which `IS NULL` checks
+/// miss and `julianday()` can't read; those in nullable columns can be fixed
+async fn empty_timestamps(pool: &Pool<Sqlite>) -> Result<Vec<Problem>, sqlx::Error> {
+    let mut problems = Vec::new();
+    for table in TABLES {
+        for column in table_info(pool, table).await?.into_iter().filter(|column| column.name.ends_with("_at")) {
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {} WHERE {} = ''", table, column.name))
+                .fetch_one(pool)
+                .await?;
+            if rows == 0 {
+                continue;
+            }
+            problems.push(Problem {
+                check: "empty dates",
+                rows: Some(rows),
+                detail: format!("{}.{} is an empty string", table, column.name),
+                fix: (!column.not_null).then(|| {
+                    Fix::Statement(format!("UPDATE {} SET {} = NULL WHERE {} = ''", table, column.name, column.name))
+                }),
+            });
+        }
+    }
+
+    Ok(problems)
+}
+
+/// Pull requests and issues of each repository whose events were never
+/// collected, usually because a collection of events stopped before reaching
+/// them; those without any events to collect don't count
+///
+/// Databases not migrated yet don't record which were collected, and are left out.
+async fn missing_events(pool: &Pool<Sqlite>) -> Result<Vec<Problem>, sqlx::Error> {
+    let columns = table_info(pool, "pulls").await?;
+    if !columns.iter().any(|column| column.name == "events_collected_at") {
+        return Ok(Vec::new());
+    }
+    let missing: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT r.owner || '/' || r.name, COUNT(*)
+        FROM pulls p
+        JOIN repositories r ON r.id = p.repo_id
+        WHERE p.events_collected_at IS NULL
+        GROUP BY r.id
+        ORDER BY r.owner, r.name",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(missing
+        .into_iter()
+        .map(|(repository, rows)| Problem {
+            check: "missing events",
+            rows: Some(rows),
+            detail: format!(
+                "Events of pull requests and issues of {} were never collected; \
+                run `gdfm collect events {} --missing-only`",
+                repository, repository
+            ),
+            fix: None,
+        })
+        .collect())
+}
@@ -0,0 +1,486 @@
+/// Module holding the `export` command
+///
+/// Exports the collected data into formats that are easier to consume
+/// outside of gdfm than the SQLite database itself, and a provenance manifest
+/// describing how it was collected. Parquet keeps column types, timestamps
+/// included, for data warehouses to load without a schema of their own.
+/// Postgres receives a copy of every table, which collections also refresh on
+/// their own once they finish when `export.postgres_after_collect` is set.
+use std::fs::{create_dir_all, File};
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use arrow_array::{ArrayRef, Float64Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray};
+use arrow_schema::{DataType, Field, Schema, TimeUnit};
+use clap::ArgMatches;
+use console::style;
+use futures_util::TryStreamExt;
+use miette::{miette, Result, IntoDiagnostic};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde::Serialize;
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::config::Config;
+use crate::client::api_url;
+use crate::constants::{APP_NAME, CLI_ARGS_ALL_TABLES, CLI_ARGS_OUTPUT, CLI_ARGS_PATH, CLI_ARGS_TABLE, DATABASE_URL_ENV};
+use crate::database::{
+    fetch_table,
+    row_values,
+    setup_db,
+    table_columns,
+    table_info,
+    CellValue,
+    ColumnInfo,
+    ColumnType,
+    Repository,
+    TABLES,
+};
+use crate::report::parse_timestamp;
+
+/// Directory `export csv --all` writes to when no `--output` is given
+const DEFAULT_CSV_DIR: &str = "gdfm-csv";
+
+/// Rows written to a Parquet file at a time, each batch becoming a row group
+const PARQUET_BATCH_ROWS: usize = 10_000;
+
+/// Headline metrics for each repository, shown on the first sheet of the workbook
+const SUMMARY_QUERY: &str = "
+    SELECT r.owner || '/' || r.name AS repository,
+        SUM(p.is_issue = 0) AS pull_requests,
+        SUM(p.is_issue = 1) AS issues,
+        SUM(p.merged_at IS NOT NULL) AS merged,
+        COUNT(DISTINCT p.author) AS authors,
+        (SELECT COUNT(*) FROM issue_pull_reviews v JOIN pulls vp ON vp.id = v.issue_pull_id
+            WHERE vp.repo_id = r.id) AS reviews,
+        AVG(CASE WHEN p.merged_at IS NOT NULL
+            THEN (julianday(p.merged_at) - julianday(p.created_at)) * 24
+        END) AS avg_hours_to_merge
+    FROM repositories r
+    LEFT JOIN pulls p ON p.repo_id = r.id
+    GROUP BY r.id
+    ORDER BY repository
+";
+
+pub async fn export_excel(matches: &ArgMatches) -> Result<()> {
+    let output = matches
+        .get_one::<String>(CLI_ARGS_OUTPUT)
+        .expect("output is required");
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let mut workbook = Workbook::new();
+    let bold = Format::new().set_bold();
+
+    let summary = workbook.add_worksheet().set_name("summary").into_diagnostic()?;
+    let rows = sqlx::query(SUMMARY_QUERY).fetch_all(&pool).await.into_diagnostic()?;
+    let summary_rows: Vec<Vec<CellValue>> = rows
+        .iter()
+        .map(crate::database::row_values)
+        .collect::<Result<_, _>>()
+        .into_diagnostic()?;
+    let summary_columns = [
+        "repository", "pull_requests", "issues", "merged", "authors", "reviews", "avg_hours_to_merge",
+    ].map(String::from);
+    write_sheet(summary, &bold, &summary_columns, &summary_rows).into_diagnostic()?;
+
+    for table in TABLES {
+        let columns = table_columns(&pool, table).await.into_diagnostic()?;
+        let rows = fetch_table(&pool, table).await.into_diagnostic()?;
+
+        // Sheet names are limited to 31 characters
+        let name: String = table.chars().take(31).collect();
+        let sheet = workbook.add_worksheet().set_name(name).into_diagnostic()?;
+        write_sheet(sheet, &bold, &columns, &rows).into_diagnostic()?;
+    }
+
+    workbook.save(output).into_diagnostic()?;
+    println!("Workbook written to {}", style(output).bold().cyan());
+
+    Ok(())
+}
+
+/// Writes a header row followed by the data rows
+fn write_sheet(
+    sheet: &mut Worksheet,
+    header_format: &Format,
+    columns: &[String],
+    rows: &[Vec<CellValue>],
+) -> Result<(), XlsxError> {
+    for (col, name) in columns.iter().enumerate() {
+        sheet.write_string_with_format(0, col as u16, name, header_format)?;
+    }
+
+    for (index, row) in rows.iter().enumerate() {
+        let row_num = index as u32 + 1;
+        for (col, value) in row.iter().enumerate() {
+            let col = col as u16;
+            match value {
+                CellValue::Null => {}
+                CellValue::Integer(value) => {
+                    sheet.write_number(row_num, col, *value as f64)?;
+                }
+                CellValue::Real(value) => {
+                    sheet.write_number(row_num, col, *value)?;
+                }
+                CellValue::Text(value) => {
+                    sheet.write_string(row_num, col, value)?;
+                }
+            }
+        }
+    }
+    sheet.autofit();
+
+    Ok(())
+}
+
+pub async fn export_csv(matches: &ArgMatches) -> Result<()> {
+    let output = matches.get_one::<String>(CLI_ARGS_OUTPUT);
+    let pool = setup_db().await.into_diagnostic()?;
+
+    if matches.get_flag(CLI_ARGS_ALL_TABLES) {
+        let dir = PathBuf::from(output.map(String::as_str).unwrap_or(DEFAULT_CSV_DIR));
+        create_dir_all(&dir).into_diagnostic()?;
+        for table in TABLES {
+            let file = File::create(dir.join(format!("{}.csv", table))).into_diagnostic()?;
+            write_csv(&pool, table, BufWriter::new(file)).await?;
+        }
+        println!("{} tables written to {}", TABLES.len(), style(dir.display()).bold().cyan());
+
+        return Ok(());
+    }
+
+    let table = matches
+        .get_one::<String>(CLI_ARGS_TABLE)
+        .expect("table is required without --all");
+    match output.map(String::as_str) {
+        // Only the rows go to stdout, so they can be piped straight into other tools
+        Some("-") => {
+            write_csv(&pool, table, std::io::stdout().lock()).await?;
+        }
+        path => {
+            let path = path.map(PathBuf::from).unwrap_or_else(|| PathBuf::from(format!("{}.csv", table)));
+            let file = File::create(&path).into_diagnostic()?;
+            let rows = write_csv(&pool, table, BufWriter::new(file)).await?;
+            println!("{} rows written to {}", rows, style(path.display()).bold().cyan());
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a table as CSV with a header row, one row at a time so tables of any
+/// size fit in memory, and returns the number of rows written
+///
+/// `table` must be one of [`TABLES`]; it is interpolated into the query.
+async fn write_csv(pool: &Pool<Sqlite>, table: &str, mut out: impl Write) -> Result<u64> {
+    let columns = table_columns(pool, table).await.into_diagnostic()?;
+    let header: Vec<String> = columns.iter().map(|column| csv_field(column)).collect();
+    writeln!(out, "{}", header.join(",")).into_diagnostic()?;
+
+    let query = format!("SELECT * FROM {} ORDER BY rowid", table);
+    let mut rows = sqlx::query(&query).fetch(pool);
+    let mut written = 0;
+    while let Some(row) = rows.try_next().await.into_diagnostic()? {
+        let fields: Vec<String> = row_values(&row)
+            .into_diagnostic()?
+            .into_iter()
+            .map(|value| match value {
+                CellValue::Null => String::new(),
+                CellValue::Integer(value) => value.to_string(),
+                CellValue::Real(value) => value.to_string(),
+                CellValue::Text(value) => csv_field(&value),
+            })
+            .collect();
+        writeln!(out, "{}", fields.join(",")).into_diagnostic()?;
+        written += 1;
+    }
+    out.flush().into_diagnostic()?;
+
+    Ok(written)
+}
+
+/// Quotes a field when it holds a separator, quote or line break, as RFC 4180 describes
+pub fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The type a column is written to Parquet with
+#[derive(Debug, Clone, Copy)]
+enum ParquetType {
+    Integer,
+    Real,
+    /// Text columns named `*_at` whose values are all timestamps, stored in UTC
+    Timestamp,
+    Text,
+}
+
+impl ParquetType {
+    fn data_type(self) -> DataType {
+        match self {
+            Self::Integer => DataType::Int64,
+            Self::Real => DataType::Float64,
+            Self::Timestamp => DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+            Self::Text => DataType::Utf8,
+        }
+    }
+}
+
+pub async fn export_parquet(matches: &ArgMatches) -> Result<()> {
+    let dir = PathBuf::from(matches.get_one::<String>(CLI_ARGS_PATH).expect("directory is required"));
+    let pool = setup_db().await.into_diagnostic()?;
+
+    create_dir_all(&dir).into_diagnostic()?;
+    for table in TABLES {
+        let file = File::create(dir.join(format!("{}.parquet", table))).into_diagnostic()?;
+        write_parquet(&pool, table, file).await?;
+    }
+    println!("{} tables written to {}", TABLES.len(), style(dir.display()).bold().cyan());
+
+    Ok(())
+}
+
+/// Picks the Parquet type of a column from its declared type, and for text
+/// columns named `*_at`, from whether every value is a timestamp
+///
+/// `table` must be one of [`TABLES`]; it is interpolated into the query.
+async fn parquet_type(pool: &Pool<Sqlite>, table: &str, column: &ColumnInfo) -> Result<ParquetType> {
+    match ColumnType::from_declared(&column.data_type) {
+        ColumnType::Integer => Ok(ParquetType::Integer),
+        ColumnType::Real => Ok(ParquetType::Real),
+        ColumnType::Text if column.name.ends_with("_at") => {
+            // Empty values become nulls; anything else that isn't a timestamp keeps the column text
+            let others: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM {} WHERE {} != ''
+                    AND {} NOT GLOB '[0-9][0-9][0-9][0-9]-[0-9][0-9]-[0-9][0-9] [0-9][0-9]:[0-9][0-9]:[0-9][0-9]*'",
+                table, column.name, column.name
+            ))
+            .fetch_one(pool)
+            .await
+            .into_diagnostic()?;
+
+            Ok(if others == 0 { ParquetType::Timestamp } else { ParquetType::Text })
+        }
+        ColumnType::Text => Ok(ParquetType::Text),
+    }
+}
+
+/// Writes a table as a Parquet file, a batch of rows at a time so tables of any
+/// size fit in memory, and returns the number of rows written
+///
+/// `table` must be one of [`TABLES`]; it is interpolated into the query.
+async fn write_parquet(pool: &Pool<Sqlite>, table: &str, file: File) -> Result<u64> {
+    let columns = table_info(pool, table).await.into_diagnostic()?;
+    let mut types = Vec::with_capacity(columns.len());
+    for column in &columns {
+        types.push(parquet_type(pool, table, column).await?);
+    }
+    let fields: Vec<Field> = columns
+        .iter()
+        .zip(&types)
+        .map(|(column, column_type)| Field::new(&column.name, column_type.data_type(), true))
+        .collect();
+    let schema = Arc::new(Schema::new(fields));
+
+    let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+    let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(properties)).into_diagnostic()?;
+
+    let query = format!("SELECT * FROM {} ORDER BY rowid", table);
+    let mut rows = sqlx::query(&query).fetch(pool);
+    let mut batch = Vec::with_capacity(PARQUET_BATCH_ROWS);
+    let mut written = 0;
+    while let Some(row) = rows.try_next().await.into_diagnostic()? {
+        batch.push(row_values(&row).into_diagnostic()?);
+        if batch.len() == PARQUET_BATCH_ROWS {
+            writer.write(&record_batch(&schema, &types, &batch)?).into_diagnostic()?;
+            written += batch.len() as u64;
+            batch.clear();
+        }
+    }
+    if !batch.is_empty() {
+        writer.write(&record_batch(&schema, &types, &batch)?).into_diagnostic()?;
+        written += batch.len() as u64;
+    }
+    writer.close().into_diagnostic()?;
+
+    Ok(written)
+}
+
+/// Converts rows into the columns of an Arrow record batch
+fn record_batch(schema: &Arc<Schema>, types: &[ParquetType], rows: &[Vec<CellValue>]) -> Result<RecordBatch> {
+    let arrays: Vec<ArrayRef> = types
+        .iter()
+        .enumerate()
+        .map(|(index, column_type)| {
+            let values = rows.iter().map(move |row| &row[index]);
+            let array: ArrayRef = match column_type {
+                ParquetType::Integer => Arc::new(Int64Array::from_iter(values.map(CellValue::as_integer))),
+                ParquetType::Real => Arc::new(Float64Array::from_iter(values.map(CellValue::as_real))),
+                ParquetType::Timestamp => Arc::new(
+                    TimestampMicrosecondArray::from_iter(values.map(|value| {
+                        let text = value.as_text()?;
+                        parse_timestamp(&text).map(|at| at.and_utc().timestamp_micros())
+                    }))
+                    .with_timezone("UTC"),
+                ),
+                ParquetType::Text => Arc::new(StringArray::from_iter(values.map(CellValue::as_text))),
+            };
+            array
+        })
+        .collect();
+
+    RecordBatch::try_new(schema.clone(), arrays).into_diagnostic()
+}
+
+/// Postgres database named by `DATABASE_URL`; other URLs are left to whatever
+/// else reads that variable
+fn postgres_url() -> Option<String> {
+    std::env::var(DATABASE_URL_ENV)
+        .ok()
+        .filter(|url| url.starts_with("postgres://") || url.starts_with("postgresql://"))
+}
+
+pub async fn export_postgres(_matches: &ArgMatches) -> Result<()> {
+    let url = postgres_url()
+        .ok_or_else(|| miette!("Set {} to a postgres:// URL to export the database to Postgres", DATABASE_URL_ENV))?;
+
+    write_postgres(&url).await
+}
+
+/// Exports the database to Postgres after a collection when `export.postgres_after_collect` asks for it
+pub async fn export_after_collection() -> Result<()> {
+    let config = Config::load().into_diagnostic()?;
+    if !config.export.postgres_after_collect.unwrap_or(false) {
+        return Ok(());
+    }
+
+    let url = postgres_url().ok_or_else(|| {
+        miette!(
+            "export.postgres_after_collect is set, but {} is not a postgres:// URL",
+            DATABASE_URL_ENV
+        )
+    })?;
+    write_postgres(&url).await
+}
+
+#[cfg(feature = "postgres")]
+async fn write_postgres(url: &str) -> Result<()> {
+    let pool = setup_db().await.into_diagnostic()?;
+    println!("Exporting the database to Postgres...");
+    let rows = crate::postgres::export(&pool, url)
+        .await
+        .map_err(|err| miette!("Could not export the database to Postgres: {}", err))?;
+    println!("Exported {} rows from {} tables to Postgres", style(rows).bold().cyan(), TABLES.len());
+
+    Ok(())
+}
+
+#[cfg(not(feature = "postgres"))]
+async fn write_postgres(_url: &str) -> Result<()> {
+    Err(miette!(
+        "{} points at Postgres but gdfm was built without Postgres support. \
+        Rebuild it with `cargo install --features postgres`.",
+        DATABASE_URL_ENV
+    ))
+}
+
+/// Where a dataset came from, for citing it in papers and replication packages
+#[derive(Debug, Serialize)]
+struct Provenance {
+    tool: &'static str,
+    version: &'static str,
+    generated_at: String,
+    api_host: String,
+    /// Timeline event types `collect events` keeps, from the config
+    event_types: Vec<String>,
+    repositories: Vec<RepositoryProvenance>,
+}
+
+#[derive(Debug, Serialize)]
+struct RepositoryProvenance {
+    repository: String,
+    pull_requests: i64,
+    issues: i64,
+    /// Earliest and latest creation time of the stored pull requests and issues
+    first_created_at: Option<String>,
+    last_created_at: Option<String>,
+    collectors: Vec<CollectorProvenance>,
+}
+
+/// The collection runs of one collector, from `collection_runs`
+#[derive(Debug, Serialize, sqlx::FromRow)]
+struct CollectorProvenance {
+    collector: String,
+    runs: i64,
+    first_started_at: String,
+    last_started_at: String,
+    /// When the most recent successful run finished, if any did
+    last_finished_at: Option<String>,
+}
+
+/// Writes a JSON manifest of the repositories, date ranges and collection runs in the database
+pub async fn export_provenance(matches: &ArgMatches) -> Result<()> {
+    let output = matches
+        .get_one::<String>(CLI_ARGS_OUTPUT)
+        .expect("output has a default");
+
+    let pool = setup_db().await.into_diagnostic()?;
+
+    let mut repositories = Vec::new();
+    for repo in Repository::fetch_all(&pool).await.into_diagnostic()? {
+        let (pull_requests, issues, first_created_at, last_created_at): (i64, i64, Option<String>, Option<String>) =
+            sqlx::query_as(
+                "SELECT COUNT(*) FILTER (WHERE is_issue = 0), COUNT(*) FILTER (WHERE is_issue = 1),
+                    MIN(NULLIF(created_at, '')), MAX(NULLIF(created_at, ''))
+                FROM pulls WHERE repo_id = $1",
+            )
+            .bind(repo.id)
+            .fetch_one(&pool)
+            .await
+            .into_diagnostic()?;
+
+        let collectors: Vec<CollectorProvenance> = sqlx::query_as(
+            "SELECT collector, COUNT(*) AS runs, MIN(started_at) AS first_started_at,
+                MAX(started_at) AS last_started_at,
+                MAX(CASE WHEN status = 'finished' THEN finished_at END) AS last_finished_at
+            FROM collection_runs
+            WHERE repo_id = $1 AND status != 'skipped'
+            GROUP BY collector
+            ORDER BY collector",
+        )
+        .bind(repo.id)
+        .fetch_all(&pool)
+        .await
+        .into_diagnostic()?;
+
+        repositories.push(RepositoryProvenance {
+            repository: format!("{}/{}", repo.owner, repo.name),
+            pull_requests,
+            issues,
+            first_created_at,
+            last_created_at,
+            collectors,
+        });
+    }
+
+    let provenance = Provenance {
+        tool: APP_NAME,
+        version: env!("CARGO_PKG_VERSION"),
+        generated_at: chrono::Utc::now().to_string(),
+        api_host: api_url(),
+        event_types: Config::load().into_diagnostic()?.collect.event_types(),
+        repositories,
+    };
+    let json = serde_json::to_string_pretty(&provenance).into_diagnostic()?;
+    std::fs::write(output, json + "\n").into_diagnostic()?;
+    println!("Provenance manifest written to {}", style(output).bold().cyan());
+
+    Ok(())
+}
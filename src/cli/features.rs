@@ -0,0 +1,45 @@
+/// Module holding the `features` command
+///
+/// Shows which optional APIs the GitHub host offers, as used to skip
+/// collectors a GitHub Enterprise Server is too old for.
+use clap::ArgMatches;
+use console::style;
+use miette::{IntoDiagnostic, Result};
+
+use crate::client::{api_host, api_version};
+use crate::constants::CLI_ARGS_REFRESH;
+use crate::features::host_features;
+use crate::storage::open_storage;
+use crate::terminal::Table;
+
+pub async fn list_features(matches: &ArgMatches) -> Result<()> {
+    let refresh = matches.get_flag(CLI_ARGS_REFRESH);
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let features = host_features(storage.as_ref(), refresh).await.into_diagnostic()?;
+    if features.is_empty() {
+        println!("Nothing is known about {} yet, and it could not be reached", style(api_host()).bold());
+        return Ok(());
+    }
+
+    println!(
+        "{} (REST API version {})",
+        style(api_host()).bold(),
+        api_version().unwrap_or_else(|| "not sent".to_string())
+    );
+    let mut table = Table::new(&["Feature", "Status"]);
+    for feature in &features {
+        let status = if feature.available {
+            style("available".to_string()).green()
+        } else {
+            style(format!("missing: {}", feature.reason)).yellow()
+        };
+        table.push(vec![feature.feature.clone(), status.to_string()]);
+    }
+    table.print();
+    if let Some(checked_at) = features.iter().map(|feature| &feature.checked_at).min() {
+        println!("{}", style(format!("Checked at {}; pass --refresh to check again", checked_at)).dim());
+    }
+
+    Ok(())
+}
@@ -5,26 +5,71 @@
 /// 
 use console::style;
 use clap::ArgMatches;
-use miette::{Result, IntoDiagnostic};
+use miette::{miette, Result, IntoDiagnostic};
 
-use crate::constants::CLI_ARGS_REPO;
-use crate::database::{setup_db, Repository};
+use crate::cli::collect::CollectOptions;
+use crate::cli::sync::run_collect_command;
+use crate::constants::{CLI_ARGS_ARCHIVE, CLI_ARGS_COLLECT, CLI_ARGS_FORGE, CLI_ARGS_REPO};
+use crate::database::project;
+use crate::forge::GITLAB;
+use crate::storage::open_storage;
+
+/// Collectors run by `init --collect`, in dependency order
+const INIT_COLLECTORS: &[&str] = &["pulls", "events", "reviews"];
 
 pub async fn init(matches: &ArgMatches) -> Result<()> {
     let repo_path: &String = matches
         .get_one::<String>(CLI_ARGS_REPO)
         .expect("repos are required");
 
-    let pool = setup_db().await.into_diagnostic()?;
+    let storage = open_storage().await.into_diagnostic()?;
+    let existing = storage.get_repository(repo_path).await.ok();
+
+    if matches.get_flag(CLI_ARGS_ARCHIVE) {
+        let repo = existing.ok_or_else(|| miette!("{} is not tracked", repo_path))?;
+        storage.set_repository_archived(repo.id, true).await.into_diagnostic()?;
 
-    let repo = Repository::create(&pool, repo_path).await.into_diagnostic()?;
+        println!();
+        println!(
+            "Archived {}/{}. Its collected data is kept; run `gdfm init {}` to track it again.",
+            style(repo.owner).bold().cyan(),
+            style(repo.name).bold().cyan(),
+            repo_path
+        );
+        return Ok(());
+    }
+
+    let mut repo = storage.create_repository(repo_path).await.into_diagnostic()?;
+    if let Some(forge) = matches.get_one::<String>(CLI_ARGS_FORGE) {
+        storage.set_repository_forge(repo.id, forge).await.into_diagnostic()?;
+        repo.forge = forge.clone();
+    }
 
     println!();
+    let message = match existing {
+        Some(previous) if previous.archived => "Restored the following archived",
+        Some(_) => "Already tracking the following",
+        None => "Tracking the following",
+    };
+    let kind = if repo.forge == GITLAB { "GitLab project" } else { "GitHub repo" };
+    let project = project().map(|name| format!(" in project {}", style(name).bold())).unwrap_or_default();
     println!(
-        "Tracking the following GitHub repo: {}/{}",
+        "{} {}: {}/{}{}",
+        message,
+        kind,
         style(repo.owner).bold().cyan(),
-        style(repo.name).bold().cyan()
+        style(repo.name).bold().cyan(),
+        project
     );
 
+    if matches.get_flag(CLI_ARGS_COLLECT) {
+        // Later collectors work on the stored pull requests, so stop at the first failure
+        for collector in INIT_COLLECTORS {
+            println!();
+            println!("Collecting {}", style(collector).bold());
+            run_collect_command(collector, &CollectOptions::new(repo_path)).await?;
+        }
+    }
+
     Ok(())
 }
@@ -0,0 +1,73 @@
+/// Module holding the `listen` command
+///
+/// Runs an HTTP server that accepts GitHub webhook deliveries on `POST /`,
+/// verifies their signature and records them in the database, writing pull
+/// requests, reviews and comments straight into the collected data.
+use std::sync::Arc;
+
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::{HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::Router;
+use clap::ArgMatches;
+use console::style;
+use miette::{Result, IntoDiagnostic};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::config::{Config, WebhookConfig};
+use crate::constants::{CLI_ARGS_PORT, CLI_ARGS_SECRET};
+use crate::database::setup_db;
+use crate::webhook::{receive, Outcome, WebhookError};
+
+struct ListenState {
+    pool: Pool<Sqlite>,
+    config: WebhookConfig,
+}
+
+pub async fn listen(matches: &ArgMatches) -> Result<()> {
+    let port = *matches.get_one::<u16>(CLI_ARGS_PORT).expect("port has a default");
+
+    let mut config = Config::load().into_diagnostic()?;
+    if let Some(secret) = matches.get_one::<String>(CLI_ARGS_SECRET) {
+        config.webhook.secret = Some(secret.clone());
+    }
+    let pool = setup_db().await.into_diagnostic()?;
+    let state = Arc::new(ListenState { pool, config: config.webhook });
+
+    let app = Router::new()
+        .route("/", post(handle_delivery))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind(("0.0.0.0", port)).await.into_diagnostic()?;
+    println!("Listening for webhook deliveries on port {} (Ctrl-C to stop)", style(port).bold().cyan());
+    axum::serve(listener, app).await.into_diagnostic()?;
+
+    Ok(())
+}
+
+async fn handle_delivery(
+    State(state): State<Arc<ListenState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, String) {
+    match receive(&state.pool, &state.config, &headers, &body).await {
+        Ok(Outcome::Accepted) => (StatusCode::OK, "accepted".to_string()),
+        // Still a success so GitHub doesn't keep retrying it
+        Ok(Outcome::Duplicate) => (StatusCode::OK, "duplicate delivery ignored".to_string()),
+        Ok(Outcome::NotIngested(reason)) => {
+            eprintln!("{} {}", style("Stored a delivery without ingesting it:").yellow(), reason);
+            (StatusCode::OK, "accepted".to_string())
+        }
+        Err(err) => {
+            eprintln!("{} {}", style("Rejected delivery:").red(), err);
+            let status = match err {
+                WebhookError::InvalidSignature(_) => StatusCode::UNAUTHORIZED,
+                WebhookError::Database(_) => StatusCode::INTERNAL_SERVER_ERROR,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            (status, err.to_string())
+        }
+    }
+}
@@ -1,5 +1,20 @@
 /// Holds submodules which correspond to CLI subcommands
+pub mod backup;
 pub mod collect;
 pub mod clean;
+pub mod db;
+pub mod deliveries;
+pub mod doctor;
+pub mod export;
+pub mod features;
 pub mod init;
-pub mod report;
\ No newline at end of file
+pub mod listen;
+pub mod query;
+pub mod release_notes;
+pub mod report;
+pub mod runs;
+pub mod schema;
+pub mod serve;
+pub mod simulate;
+pub mod sync;
+pub mod watch;
\ No newline at end of file
@@ -0,0 +1,100 @@
+/// Module holding the `query` command
+///
+/// Runs a statement against the gdfm database and prints its rows as a table,
+/// JSON or CSV. The database is opened read-only, so a query can't change the
+/// collected data, and the views of `db shell` are there to use too.
+use clap::ArgMatches;
+use miette::{miette, IntoDiagnostic, Result};
+use serde_json::{Map, Value};
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{Column, Connection, Executor, SqliteConnection};
+
+use crate::cli::db::SHELL_SETUP;
+use crate::cli::export::csv_field;
+use crate::constants::{CLI_ARGS_FORMAT, CLI_ARGS_SQL};
+use crate::database::{get_db_path, row_values, setup_db, CellValue};
+use crate::terminal::Table;
+
+/// Characters shown of a value in a table; JSON and CSV hold values in full
+const MAX_WIDTH: usize = 60;
+
+pub async fn query(matches: &ArgMatches) -> Result<()> {
+    let sql = matches.get_one::<String>(CLI_ARGS_SQL).expect("SQL is required");
+    let format = matches.get_one::<String>(CLI_ARGS_FORMAT).expect("format has a default");
+
+    // Creates the database and brings its schema up to date before it is opened read-only
+    setup_db().await.into_diagnostic()?.close().await;
+    let options = SqliteConnectOptions::new().filename(get_db_path()).read_only(true);
+    let mut connection = SqliteConnection::connect_with(&options).await.into_diagnostic()?;
+    for statement in SHELL_SETUP {
+        connection.execute(*statement).await.into_diagnostic()?;
+    }
+
+    let failed = |err: sqlx::Error| miette!("Query failed: {}", err);
+    let columns: Vec<String> = connection
+        .describe(sql)
+        .await
+        .map_err(failed)?
+        .columns()
+        .iter()
+        .map(|column| column.name().to_string())
+        .collect();
+    let mut rows = Vec::new();
+    for row in connection.fetch_all(sql.as_str()).await.map_err(failed)? {
+        rows.push(row_values(&row).into_diagnostic()?);
+    }
+    connection.close().await.into_diagnostic()?;
+
+    match format.as_str() {
+        "json" => {
+            let rows: Vec<Map<String, Value>> = rows
+                .into_iter()
+                .map(|row| columns.iter().cloned().zip(row.into_iter().map(CellValue::into_json)).collect())
+                .collect();
+            println!("{}", serde_json::to_string_pretty(&rows).into_diagnostic()?);
+        }
+        "csv" => {
+            let header: Vec<String> = columns.iter().map(|column| csv_field(column)).collect();
+            println!("{}", header.join(","));
+            for row in rows {
+                let fields: Vec<String> = row
+                    .iter()
+                    .map(|value| value.as_text().map(|text| csv_field(&text)).unwrap_or_default())
+                    .collect();
+                println!("{}", fields.join(","));
+            }
+        }
+        _ => print_table(&columns, &rows),
+    }
+
+    Ok(())
+}
+
+/// Prints rows in aligned columns, with numeric columns right-aligned and NULL shown as blank
+fn print_table(columns: &[String], rows: &[Vec<CellValue>]) {
+    if columns.is_empty() {
+        return;
+    }
+    let headers: Vec<&str> = columns.iter().map(String::as_str).collect();
+    let mut table = Table::new(&headers);
+    for index in 0..columns.len() {
+        let mut values = rows.iter().map(|row| &row[index]).filter(|value| !matches!(value, CellValue::Null)).peekable();
+        if values.peek().is_some() && values.all(|value| matches!(value, CellValue::Integer(_) | CellValue::Real(_))) {
+            table = table.align_right(index);
+        }
+    }
+    for row in rows {
+        table.push(row.iter().map(|value| cell(value.as_text().unwrap_or_default())).collect());
+    }
+    table.print();
+    println!("({} {})", rows.len(), if rows.len() == 1 { "row" } else { "rows" });
+}
+
+/// A value as shown in a table: cut short after [`MAX_WIDTH`] characters
+fn cell(text: String) -> String {
+    if text.chars().count() > MAX_WIDTH {
+        format!("{}…", text.chars().take(MAX_WIDTH - 1).collect::<String>())
+    } else {
+        text
+    }
+}
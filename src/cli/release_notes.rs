@@ -0,0 +1,172 @@
+/// Module holding the `release-notes` command
+///
+/// Drafts Markdown release notes from the pull requests merged since a release.
+/// Pull requests are grouped by the `type` their title declares under
+/// `report.title_pattern` (conventional commits by default) or, failing that,
+/// by their labels. Authors whose first merged pull request is among them are
+/// credited as new contributors.
+use std::collections::{BTreeMap, HashMap};
+
+use clap::ArgMatches;
+use console::style;
+use miette::{miette, Result, IntoDiagnostic};
+use regex::Regex;
+
+use crate::config::Config;
+use crate::constants::{CLI_ARGS_OUTPUT, CLI_ARGS_PATH, CLI_ARGS_SINCE};
+use crate::database::{setup_db, Release, Repository};
+use crate::report::titles::CONVENTIONAL_COMMIT_PATTERN;
+
+/// Headings of the groups pull requests are sorted into, in the order they are
+/// listed, with the conventional commit types and label words that put a pull
+/// request under them
+const GROUPS: &[(&str, &[&str])] = &[
+    ("Breaking changes", &["breaking"]),
+    ("Features", &["feat", "feature", "enhancement"]),
+    ("Bug fixes", &["fix", "bug", "bugfix"]),
+    ("Performance", &["perf", "performance"]),
+    ("Documentation", &["docs", "doc", "documentation"]),
+    ("Maintenance", &["refactor", "chore", "build", "ci", "test", "style", "deps", "dependencies"]),
+];
+
+/// Heading of pull requests no group claims
+const OTHER_CHANGES: &str = "Other changes";
+
+#[derive(Debug, sqlx::FromRow)]
+struct MergedPull {
+    id: i64,
+    number: u32,
+    title: String,
+    author: String,
+    /// Whether the author had no pull request merged before this one
+    first_contribution: bool,
+}
+
+pub async fn release_notes(matches: &ArgMatches) -> Result<()> {
+    let repo_path = matches
+        .get_one::<String>(CLI_ARGS_PATH)
+        .expect("repository path is required");
+
+    let config = Config::load().into_diagnostic()?;
+    let title_pattern = match &config.report.title_pattern {
+        Some(pattern) => Regex::new(pattern)
+            .map_err(|err| miette!("Invalid report.title_pattern in config: {}", err))?,
+        None => Regex::new(CONVENTIONAL_COMMIT_PATTERN).expect("conventional commit pattern is valid"),
+    };
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let repo = Repository::from(&pool, repo_path).await.into_diagnostic()?;
+
+    let releases = Release::fetch_published(&pool, repo.id).await.into_diagnostic()?;
+    let since = match matches.get_one::<String>(CLI_ARGS_SINCE) {
+        Some(tag) => Some(releases.iter().find(|release| release.tag_name == *tag).ok_or_else(|| {
+            miette!("No published release tagged {}. Run `gdfm collect releases {}` first.", tag, repo_path)
+        })?),
+        None => releases.last(),
+    };
+    let since_at = since.and_then(|release| release.published_at).map(|at| at.naive_utc()).unwrap_or_default();
+
+    let pulls: Vec<MergedPull> = sqlx::query_as(
+        "SELECT p.id, p.number, p.title, p.author,
+            NOT EXISTS (
+                SELECT 1 FROM pulls earlier
+                WHERE earlier.repo_id = p.repo_id AND earlier.author = p.author AND earlier.is_issue = 0
+                    AND earlier.merged_at IS NOT NULL AND earlier.merged_at < p.merged_at
+            ) AS first_contribution
+        FROM pulls p
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.merged_at IS NOT NULL AND p.merged_at > $2
+        ORDER BY p.merged_at",
+    )
+    .bind(repo.id)
+    .bind(since_at)
+    .fetch_all(&pool)
+    .await
+    .into_diagnostic()?;
+
+    // (pull request id, label name)
+    let labels: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT pl.issue_pull_id, l.name
+        FROM issue_pull_labels pl
+        JOIN labels l ON l.id = pl.label_id
+        JOIN pulls p ON p.id = pl.issue_pull_id
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.merged_at > $2",
+    )
+    .bind(repo.id)
+    .bind(since_at)
+    .fetch_all(&pool)
+    .await
+    .into_diagnostic()?;
+    let mut labels_of: HashMap<i64, Vec<String>> = HashMap::new();
+    for (pull_id, name) in labels {
+        labels_of.entry(pull_id).or_default().push(name.to_lowercase());
+    }
+
+    let mut groups: BTreeMap<usize, Vec<String>> = BTreeMap::new();
+    for pull in &pulls {
+        let (group, summary) = classify(&title_pattern, &pull.title, labels_of.get(&pull.id));
+        groups.entry(group).or_default().push(format!("- {} by @{} in #{}", summary, pull.author, pull.number));
+    }
+
+    let mut notes = match since {
+        Some(release) => format!(
+            "# Changes since {}\n\n{} pull requests were merged since {} was published.\n",
+            release.tag_name,
+            pulls.len(),
+            release.tag_name
+        ),
+        None => format!("# Changes\n\n{} pull requests were merged.\n", pulls.len()),
+    };
+    for (group, entries) in &groups {
+        let heading = GROUPS.get(*group).map(|(heading, _)| *heading).unwrap_or(OTHER_CHANGES);
+        notes.push_str(&format!("\n## {}\n\n{}\n", heading, entries.join("\n")));
+    }
+
+    let newcomers: Vec<String> = pulls
+        .iter()
+        .filter(|pull| pull.first_contribution && !pull.author.is_empty() && !pull.author.ends_with("[bot]"))
+        .map(|pull| format!("- @{} made their first contribution in #{}", pull.author, pull.number))
+        .collect();
+    if !newcomers.is_empty() {
+        notes.push_str(&format!("\n## New contributors\n\n{}\n", newcomers.join("\n")));
+    }
+
+    match matches.get_one::<String>(CLI_ARGS_OUTPUT) {
+        Some(path) => {
+            std::fs::write(path, &notes).into_diagnostic()?;
+            println!("Release notes written to {}", style(path).bold().cyan());
+        }
+        None => print!("{}", notes),
+    }
+
+    Ok(())
+}
+
+/// Index into `GROUPS` a pull request belongs under, `GROUPS.len()` for other
+/// changes, and its title without the conventional commit prefix
+fn classify(title_pattern: &Regex, title: &str, labels: Option<&Vec<String>>) -> (usize, String) {
+    if let Some(captures) = title_pattern.captures(title) {
+        let (prefix, summary) = title.split_once(": ").unwrap_or(("", title));
+        if prefix.ends_with('!') {
+            return (0, summary.to_string());
+        }
+        let pull_type = captures.name("type").map(|m| m.as_str().to_lowercase()).unwrap_or_default();
+        if let Some(group) = GROUPS.iter().position(|(_, words)| words.contains(&pull_type.as_str())) {
+            return (group, summary.to_string());
+        }
+    }
+
+    let group = labels
+        .into_iter()
+        .flatten()
+        .filter_map(|label| {
+            GROUPS.iter().position(|(_, words)| {
+                label
+                    .split(|c: char| !c.is_alphanumeric())
+                    .any(|word| words.contains(&word))
+            })
+        })
+        .min()
+        .unwrap_or(GROUPS.len());
+
+    (group, title.to_string())
+}
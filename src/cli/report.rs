@@ -1,10 +1,209 @@
 /// Module holding the `report` command
 /// 
 /// This module writes a simple report as an HTML file to the current working directory.
-
 use clap::ArgMatches;
+use console::style;
+use miette::{miette, Result, IntoDiagnostic};
+use regex::Regex;
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::client::authenticated_client;
+use crate::config::Config;
+use crate::constants::{
+    CLI_ARGS_APPENDIX, CLI_ARGS_BUCKET, CLI_ARGS_EMAIL, CLI_ARGS_LOCALE, CLI_ARGS_NOTES, CLI_ARGS_PATH,
+    CLI_ARGS_PUBLISH,
+};
+use crate::database::{setup_db, Repository};
+use crate::email::send_html;
+use crate::publish::Destination;
+use crate::report::caveats::DataCoverage;
+use crate::report::caveats::Source::{Events, Reviews};
+use crate::report::ecosystems::{self, Ecosystems};
+use crate::report::locale::Locale;
+use crate::report::notes::Notes;
+use crate::report::periods::Bucket;
+use crate::report::size::SizeBuckets;
+use crate::report::time_investment::{self, TimeWeights};
+use crate::report::{absence, appendix, branches, bus_factor, checklists, completeness, conversion, coverage, delivery, demand, dependencies, discussions, docs, duplicates, governance, growth, labels, merge_time, milestones, overlap, periods, progression, queue, reciprocity, releases, render, review_churn, review_depth, review_wait, size, slo, stale_bots, taxonomy, titles, unanswered, ReportOptions};
+
+pub async fn report(matches: &ArgMatches) -> Result<()> {
+    let repo_path: &String = matches
+        .get_one::<String>(CLI_ARGS_PATH)
+        .expect("repository path is required");
+
+    let locale = matches
+        .get_one::<String>(CLI_ARGS_LOCALE)
+        .and_then(|tag| Locale::from_tag(tag))
+        .unwrap_or_default();
+    let config = Config::load().into_diagnostic()?;
+    let bucket = *matches.get_one::<Bucket>(CLI_ARGS_BUCKET).expect("bucket has a default");
+    let mut options = report_options(&config, locale, bucket)?;
+    if let Some(path) = matches.get_one::<String>(CLI_ARGS_NOTES) {
+        let markdown = std::fs::read_to_string(path)
+            .map_err(|err| miette!("Could not read the notes file {}: {}", path, err))?;
+        options.notes = Some(Notes::parse(&markdown));
+    }
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let (repo, html) = generate_report(&pool, repo_path, &options, matches.get_flag(CLI_ARGS_APPENDIX)).await?;
+
+    let file_name = report_file_name(&repo);
+    std::fs::write(&file_name, &html).into_diagnostic()?;
+
+    println!("Report written to {}", style(&file_name).bold().cyan());
+
+    if let Some(branch) = matches.get_one::<String>(CLI_ARGS_PUBLISH) {
+        let destination = Destination::new(&config.publish, &repo, branch).into_diagnostic()?;
+        let message = format!("Publish gdfm report for {}/{}", repo.owner, repo.name);
+        let published = destination
+            .publish(&authenticated_client()?, &html, &message)
+            .await
+            .into_diagnostic()?;
+        let target = format!("{}/{}:{}/{}", destination.owner, destination.name, destination.branch, destination.path);
+        match published {
+            Some(commit) => {
+                let short = commit.get(..7).unwrap_or(&commit);
+                println!("Report published to {} in {}", style(target).bold().cyan(), short);
+            }
+            None => println!("Report at {} is already up to date", style(target).bold().cyan()),
+        }
+    }
+
+    if matches.get_flag(CLI_ARGS_EMAIL) {
+        email_report(&config, &repo, html).await?;
+    }
+
+    Ok(())
+}
+
+/// Emails a report to the recipients of the `[email]` settings
+pub async fn email_report(config: &Config, repo: &Repository, html: String) -> Result<()> {
+    let subject = format!("gdfm report for {}/{}", repo.owner, repo.name);
+    send_html(&config.email, &subject, html).await.into_diagnostic()?;
+    println!(
+        "Report of {}/{} emailed to {}",
+        repo.owner,
+        repo.name,
+        style(config.email.recipients.join(", ")).bold().cyan()
+    );
+
+    Ok(())
+}
+
+/// Report options from the `[report]` and `[slo]` settings in the config
+pub fn report_options(config: &Config, locale: Locale, bucket: Bucket) -> Result<ReportOptions> {
+    let mut options = ReportOptions { locale, bucket, ..Default::default() };
+
+    if let Some(pattern) = &config.report.title_pattern {
+        options.title_pattern = Regex::new(pattern)
+            .map_err(|err| miette!("Invalid report.title_pattern in config: {}", err))?;
+    }
+    if let Some(pattern) = &config.report.docs_pattern {
+        options.docs_pattern = Regex::new(pattern)
+            .map_err(|err| miette!("Invalid report.docs_pattern in config: {}", err))?;
+    }
+    options.ecosystems = Ecosystems::new(&config.report.ecosystems, &options.docs_pattern)
+        .map_err(|err| miette!("Invalid report.ecosystems in config: {}", err))?;
+    if let Some(buckets) = &config.report.size_buckets {
+        let measure = config.report.size_measure.unwrap_or_default();
+        options.size_buckets = SizeBuckets::new(measure, buckets.clone())
+            .map_err(|err| miette!("Invalid report.size_buckets in config: {}", err))?;
+    } else if let Some(measure) = config.report.size_measure {
+        options.size_buckets.measure = measure;
+    }
+
+    options.slo_policies = slo::SloPolicies::new(&config.slo)
+        .map_err(|err| miette!("Invalid slo in config: {}", err))?;
+    if let Some(contributions) = config.report.absence_min_contributions {
+        options.absence_min_contributions = contributions;
+    }
+    if let Some(months) = config.report.absence_months {
+        options.absence_months = months;
+    }
+    let weights = &config.report.time_weights;
+    let defaults = TimeWeights::default();
+    options.time_weights = TimeWeights {
+        review: weights.review.unwrap_or(defaults.review),
+        review_comment: weights.review_comment.unwrap_or(defaults.review_comment),
+        triage: weights.triage.unwrap_or(defaults.triage),
+        merge: weights.merge.unwrap_or(defaults.merge),
+    }
+    .validate()
+    .map_err(|err| miette!("Invalid report.time_weights in config: {}", err))?;
+
+    Ok(options)
+}
+
+/// Name of the file a repository's report is saved as
+pub fn report_file_name(repo: &Repository) -> String {
+    format!("gdfm-{}-{}.html", repo.owner, repo.name)
+}
+
+/// Builds every report section for a repository and renders them as an HTML page
+pub async fn generate_report(
+    pool: &Pool<Sqlite>,
+    repo_path: &str,
+    options: &ReportOptions,
+    with_appendix: bool,
+) -> Result<(Repository, String)> {
+    let repo = Repository::from(pool, repo_path).await.into_diagnostic()?;
+    if repo.archived {
+        return Err(miette!(
+            "{} is archived. Run `gdfm init {}` to track it again.",
+            repo_path,
+            repo_path
+        ));
+    }
+
+    // Sections that count events or reviews say how many pull requests those have been collected for
+    let collected = DataCoverage::measure(pool, &repo).await.into_diagnostic()?;
+    let partial = |section, sources: &[_]| collected.annotate(section, sources, options);
+
+    let mut sections = vec![
+        partial(queue::queue_history(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        partial(periods::activity_by_period(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        partial(unanswered::closed_without_response(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        partial(stale_bots::stale_bot_audit(pool, &repo, options).await.into_diagnostic()?, &[Events]),
+        partial(coverage::response_coverage(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        partial(slo::response_slos(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        merge_time::merge_time_estimates(pool, &repo, options).await.into_diagnostic()?,
+        duplicates::duplicate_pulls(pool, &repo, options).await.into_diagnostic()?,
+        discussions::discussion_load(pool, &repo, options).await.into_diagnostic()?,
+        partial(review_depth::review_comment_depth(pool, &repo, options).await.into_diagnostic()?, &[Reviews]),
+        partial(review_churn::review_request_churn(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        partial(
+            review_wait::review_request_response(pool, &repo, options).await.into_diagnostic()?,
+            &[Events, Reviews],
+        ),
+        partial(reciprocity::review_reciprocity(pool, &repo, options).await.into_diagnostic()?, &[Reviews]),
+        conversion::issue_conversion(pool, &repo, options).await.into_diagnostic()?,
+        partial(size::size_breakdown(pool, &repo, options).await.into_diagnostic()?, &[Reviews]),
+        partial(branches::branch_breakdown(pool, &repo, options).await.into_diagnostic()?, &[Events]),
+        labels::label_breakdown(pool, &repo, options).await.into_diagnostic()?,
+        taxonomy::label_taxonomy(pool, &repo, options).await.into_diagnostic()?,
+        milestones::milestone_throughput(pool, &repo, options).await.into_diagnostic()?,
+        titles::title_compliance(pool, &repo, options).await.into_diagnostic()?,
+        checklists::checklist_completion(pool, &repo, options).await.into_diagnostic()?,
+        docs::documentation_share(pool, &repo, options).await.into_diagnostic()?,
+        ecosystems::ecosystem_breakdown(pool, &repo, options).await.into_diagnostic()?,
+        governance::governance_changes(pool, &repo, options).await.into_diagnostic()?,
+        partial(growth::repository_growth(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        releases::release_cadence(pool, &repo, options).await.into_diagnostic()?,
+        delivery::delivery_performance(pool, &repo, options).await.into_diagnostic()?,
+        demand::most_demanded(pool, &repo, options).await.into_diagnostic()?,
+        bus_factor::bus_factor(pool, &repo, options).await.into_diagnostic()?,
+        partial(absence::quiet_contributors(pool, &repo, options).await.into_diagnostic()?, &[Reviews]),
+        progression::contributor_progression(pool, &repo, options).await.into_diagnostic()?,
+        partial(overlap::contributor_overlap(pool, &repo, options).await.into_diagnostic()?, &[Reviews]),
+        partial(time_investment::time_investment(pool, &repo, options).await.into_diagnostic()?, &[Events, Reviews]),
+        dependencies::dependency_overlap(pool, &repo, options).await.into_diagnostic()?,
+        completeness::missing_data(pool, &repo, options).await.into_diagnostic()?,
+    ];
+    if with_appendix {
+        sections.push(appendix::raw_data(pool, &repo, options).await.into_diagnostic()?);
+    }
 
-pub async fn report(matches: &ArgMatches) {
-    println!("Generating a report");
-    println!("{:?}", matches);
-}
\ No newline at end of file
+    let html = render(&repo, &sections, options);
+    Ok((repo, html))
+}
@@ -0,0 +1,128 @@
+/// Module holding the `runs` command and the audit of every collect run
+///
+/// API cost is measured from GitHub's rate-limit counters before and after a
+/// run, so anything else using the same token at the same time is counted too.
+use std::collections::HashMap;
+use std::future::Future;
+
+use clap::ArgMatches;
+use console::style;
+use miette::{Result, IntoDiagnostic};
+use octocrab::models::Rate;
+
+use crate::client::rate_limit;
+use crate::constants::{CLI_ARGS_LIMIT, CLI_ARGS_REPO};
+use crate::database::{setup_db, CollectionRun, Repository};
+use crate::terminal::Table;
+
+tokio::task_local! {
+    /// Id of the collection run the current task belongs to, for saving checkpoints
+    pub static CURRENT_RUN: u32;
+}
+
+/// Points used between two readings of the same rate limit
+///
+/// When the window reset in between, only the points used since the reset are known.
+fn points_used(before: &Rate, after: &Rate) -> i64 {
+    if before.reset == after.reset {
+        after.used as i64 - before.used as i64
+    } else {
+        after.used as i64
+    }
+}
+
+/// Runs a collector, recording when it ran, whether it failed and the API budget it used
+///
+/// Runs for repositories that aren't tracked aren't recorded; the collector
+/// reports that error itself.
+pub async fn record_run<F>(collector: &str, repository: &str, run: F) -> Result<()>
+where
+    F: Future<Output = Result<()>>,
+{
+    let pool = setup_db().await.into_diagnostic()?;
+    let Ok(repo) = Repository::from(&pool, repository).await else {
+        return run.await;
+    };
+
+    let run_id = CollectionRun::start(&pool, repo.id, collector).await.into_diagnostic()?;
+    let before = rate_limit().await;
+
+    let result = CURRENT_RUN.scope(run_id, run).await;
+
+    let after = rate_limit().await;
+    let (rest_calls, graphql_points) = match (&before, &after) {
+        (Some(before), Some(after)) => (
+            Some(points_used(&before.resources.core, &after.resources.core)),
+            before
+                .resources
+                .graphql
+                .as_ref()
+                .zip(after.resources.graphql.as_ref())
+                .map(|(before, after)| points_used(before, after)),
+        ),
+        _ => (None, None),
+    };
+    // octocrab errors carry a backtrace after the message
+    let error = result
+        .as_ref()
+        .err()
+        .map(|err| err.to_string().lines().next().unwrap_or_default().to_string());
+    CollectionRun::finish(&pool, run_id, error.as_deref(), rest_calls, graphql_points)
+        .await
+        .into_diagnostic()?;
+
+    result
+}
+
+pub async fn list_runs(matches: &ArgMatches) -> Result<()> {
+    let limit = *matches.get_one::<u32>(CLI_ARGS_LIMIT).expect("limit has a default");
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let repositories: HashMap<u32, String> = Repository::fetch_all(&pool)
+        .await
+        .into_diagnostic()?
+        .into_iter()
+        .map(|repo| (repo.id, format!("{}/{}", repo.owner, repo.name)))
+        .collect();
+    let repo_id = match matches.get_one::<String>(CLI_ARGS_REPO) {
+        Some(path) => Some(Repository::from(&pool, path).await.into_diagnostic()?.id),
+        None => None,
+    };
+
+    let runs = CollectionRun::fetch_recent(&pool, repo_id, limit).await.into_diagnostic()?;
+    if runs.is_empty() {
+        println!("No collection runs recorded yet");
+        return Ok(());
+    }
+
+    let points = |points: Option<i64>| points.map(|points| points.to_string()).unwrap_or("?".to_string());
+    let mut table = Table::new(&["Started", "Repository", "Collector", "Status", "REST calls", "GraphQL points", "Notes"])
+        .align_right(4)
+        .align_right(5);
+    for run in &runs {
+        let status = match run.status.as_str() {
+            "finished" => style(run.status.as_str()).green(),
+            "failed" => style(run.status.as_str()).red(),
+            _ => style(run.status.as_str()).yellow(),
+        };
+        let notes = if !run.error.is_empty() {
+            run.error.clone()
+        } else if run.status != "finished" && !run.checkpoint.is_empty() {
+            format!("stopped at checkpoint {}; continue with --resume", run.checkpoint)
+        } else {
+            String::new()
+        };
+        table.push(vec![
+            run.started_at.format("%Y-%m-%d %H:%M:%S").to_string(),
+            repositories.get(&run.repo_id).cloned().unwrap_or("?".to_string()),
+            run.collector.clone(),
+            status.to_string(),
+            points(run.rest_calls),
+            points(run.graphql_points),
+            style(notes).dim().to_string(),
+        ]);
+    }
+    table.print();
+
+    Ok(())
+}
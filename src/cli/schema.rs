@@ -0,0 +1,115 @@
+/// Module holding the `schema` command
+///
+/// Prints every table of the database with its columns. Column types come from
+/// SQLite itself; descriptions come from the doc comments on the model structs.
+use clap::ArgMatches;
+use miette::{Result, IntoDiagnostic};
+use serde::Serialize;
+
+use crate::constants::CLI_ARGS_FORMAT;
+use crate::database::{doc_text, setup_db, table_info, SCHEMA, TABLES};
+use crate::terminal::Table;
+
+#[derive(Debug, Serialize)]
+struct SchemaTable {
+    name: String,
+    description: String,
+    columns: Vec<SchemaColumn>,
+}
+
+#[derive(Debug, Serialize)]
+struct SchemaColumn {
+    name: String,
+    #[serde(rename = "type")]
+    data_type: String,
+    nullable: bool,
+    primary_key: bool,
+    description: String,
+}
+
+pub async fn schema(matches: &ArgMatches) -> Result<()> {
+    let format = matches
+        .get_one::<String>(CLI_ARGS_FORMAT)
+        .expect("format has a default");
+
+    let pool = setup_db().await.into_diagnostic()?;
+
+    let mut tables = Vec::new();
+    for table in TABLES {
+        let doc = SCHEMA.iter().find(|doc| doc.table == *table);
+        let columns = table_info(&pool, table).await.into_diagnostic()?
+            .into_iter()
+            .map(|column| {
+                let description = doc
+                    .and_then(|doc| doc.columns.iter().find(|col| col.name == column.name))
+                    .map(|col| doc_text(col.description))
+                    .unwrap_or_default();
+                SchemaColumn {
+                    name: column.name,
+                    data_type: column.data_type,
+                    nullable: !column.not_null && !column.primary_key,
+                    primary_key: column.primary_key,
+                    description,
+                }
+            })
+            .collect();
+
+        tables.push(SchemaTable {
+            name: table.to_string(),
+            description: doc.map(|doc| doc_text(doc.description)).unwrap_or_default(),
+            columns,
+        });
+    }
+
+    let output = match format.as_str() {
+        "json" => serde_json::to_string_pretty(&tables).into_diagnostic()?,
+        "markdown" => markdown(&tables),
+        _ => text(&tables),
+    };
+    println!("{}", output);
+
+    Ok(())
+}
+
+fn text(tables: &[SchemaTable]) -> String {
+    let mut output = String::new();
+    for table in tables {
+        output.push_str(&format!("{}\n", table.name));
+        if !table.description.is_empty() {
+            output.push_str(&format!("  {}\n", table.description));
+        }
+        let mut columns = Table::new(&["Column", "Type", "Description"]);
+        for column in &table.columns {
+            let null = if column.nullable { " NULL" } else { "" };
+            columns.push(vec![
+                column.name.clone(),
+                format!("{}{}", column.data_type, null),
+                column.description.clone(),
+            ]);
+        }
+        output.push_str(&columns.render());
+        output.push_str("\n\n");
+    }
+    output.trim_end().to_string()
+}
+
+fn markdown(tables: &[SchemaTable]) -> String {
+    let mut output = String::from("# gdfm database schema\n");
+    for table in tables {
+        output.push_str(&format!("\n## `{}`\n\n", table.name));
+        if !table.description.is_empty() {
+            output.push_str(&format!("{}\n\n", table.description));
+        }
+        output.push_str("| Column | Type | Nullable | Description |\n|---|---|---|---|\n");
+        for column in &table.columns {
+            output.push_str(&format!(
+                "| `{}` | {} | {} | {} |\n",
+                column.name,
+                column.data_type,
+                if column.nullable { "yes" } else { "no" },
+                column.description.replace('|', "\\|")
+            ));
+        }
+    }
+    output
+}
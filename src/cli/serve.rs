@@ -0,0 +1,272 @@
+/// Module holding the `serve` command
+///
+/// Runs an HTTP server other tools can ask for fresh reports. `POST /reports`
+/// starts generating a report in the background and answers straight away with
+/// a link; `GET /reports/{id}` answers `202 Accepted` until the report is ready
+/// and then serves it. Finished reports are kept in memory for `JOB_EXPIRY`,
+/// and at most `MAX_JOBS` at once. The server has no authentication, so it
+/// listens on localhost unless `--bind` says otherwise, and report ids are
+/// random so one client can't guess another's.
+///
+/// With `--public` every report is anonymized and the raw data appendix is
+/// refused, so the server can back a dashboard anyone can see.
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Path, State};
+use axum::http::{header, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use clap::ArgMatches;
+use console::style;
+use miette::{IntoDiagnostic, Result};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::cli::report::{generate_report, report_file_name, report_options};
+use crate::config::Config;
+use crate::constants::{CLI_ARGS_BIND, CLI_ARGS_PORT, CLI_ARGS_PUBLIC};
+use crate::database::{setup_db, Repository};
+use crate::report::anonymize::Anonymizer;
+use crate::report::locale::Locale;
+use crate::report::periods::Bucket;
+
+/// Most reports kept at once, running or finished
+const MAX_JOBS: usize = 100;
+
+/// How long a finished report can be downloaded
+const JOB_EXPIRY: Duration = Duration::from_secs(60 * 60);
+
+enum Job {
+    Running,
+    /// File name and HTML of the finished report
+    Done(String, String),
+    Failed(String),
+}
+
+struct ServeState {
+    pool: Pool<Sqlite>,
+    /// Every report with when it was requested or, once it is finished, when it finished
+    jobs: Mutex<HashMap<String, (Job, Instant)>>,
+    /// Set by `--public` to the secret pseudonyms come from when the config has none
+    public: Option<String>,
+}
+
+/// Drops finished reports older than `JOB_EXPIRY`, then the oldest finished
+/// ones while there are `MAX_JOBS` or more
+fn expire_jobs(jobs: &mut HashMap<String, (Job, Instant)>) {
+    jobs.retain(|_, (job, at)| matches!(job, Job::Running) || at.elapsed() < JOB_EXPIRY);
+    while jobs.len() >= MAX_JOBS {
+        let oldest = jobs
+            .iter()
+            .filter(|(_, (job, _))| !matches!(job, Job::Running))
+            .min_by_key(|(_, (_, at))| *at)
+            .map(|(id, _)| id.clone());
+        match oldest {
+            Some(id) => jobs.remove(&id),
+            None => break,
+        };
+    }
+}
+
+/// 32 random hex digits
+fn random_hex() -> String {
+    let mut bytes = [0u8; 16];
+    getrandom::getrandom(&mut bytes).expect("the operating system provides random numbers");
+    hex::encode(bytes)
+}
+
+/// Body of `POST /reports`; every field but the repository is optional
+#[derive(Debug, Deserialize)]
+struct ReportRequest {
+    /// `owner/name` of a tracked repository
+    repository: String,
+    /// E.g. `de-DE`; defaults to `en-US`
+    locale: Option<String>,
+    /// Same values as `gdfm report --bucket`; defaults to `month`
+    bucket: Option<String>,
+    #[serde(default)]
+    appendix: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct JobStatus {
+    id: String,
+    /// `running`, `done` or `failed`
+    status: &'static str,
+    /// Where the report can be downloaded once it is done
+    url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+pub async fn serve(matches: &ArgMatches) -> Result<()> {
+    let port = *matches.get_one::<u16>(CLI_ARGS_PORT).expect("port has a default");
+    let address = *matches.get_one::<IpAddr>(CLI_ARGS_BIND).expect("bind has a default");
+
+    // Only used without a configured secret, so it just has to be hard to guess
+    let public = matches.get_flag(CLI_ARGS_PUBLIC).then(random_hex);
+    if public.is_some() && Config::load().into_diagnostic()?.report.anonymize_secret.is_none() {
+        println!(
+            "{} report.anonymize_secret is not set, so pseudonyms change whenever the server restarts",
+            style("WARNING:").yellow().bold()
+        );
+    }
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let state = Arc::new(ServeState {
+        pool,
+        jobs: Mutex::new(HashMap::new()),
+        public,
+    });
+
+    let app = Router::new()
+        .route("/reports", post(request_report))
+        .route("/reports/{id}", get(fetch_report))
+        .with_state(state);
+
+    let listener = tokio::net::TcpListener::bind((address, port)).await.into_diagnostic()?;
+    println!(
+        "Serving reports on {} (Ctrl-C to stop)",
+        style(listener.local_addr().into_diagnostic()?).bold().cyan()
+    );
+    axum::serve(listener, app).await.into_diagnostic()?;
+
+    Ok(())
+}
+
+fn status(id: &str, job: &Job) -> JobStatus {
+    let (status, error) = match job {
+        Job::Running => ("running", None),
+        Job::Done(..) => ("done", None),
+        Job::Failed(error) => ("failed", Some(error.clone())),
+    };
+    JobStatus { id: id.to_string(), status, url: format!("/reports/{}", id), error }
+}
+
+fn bad_request(error: String) -> Response {
+    (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": error }))).into_response()
+}
+
+async fn request_report(State(state): State<Arc<ServeState>>, Json(request): Json<ReportRequest>) -> Response {
+    if state.public.is_some() && request.appendix {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(serde_json::json!({ "error": "The raw data appendix is not available on a public server" })),
+        )
+            .into_response();
+    }
+    let locale = match request.locale.as_deref() {
+        Some(tag) => match Locale::from_tag(tag) {
+            Some(locale) => locale,
+            None => return bad_request(format!("Unknown locale {}", tag)),
+        },
+        None => Locale::default(),
+    };
+    let bucket = match Bucket::parse(request.bucket.as_deref().unwrap_or("month")) {
+        Ok(bucket) => bucket,
+        Err(err) => return bad_request(err),
+    };
+    if let Err(sqlx::Error::RowNotFound) = Repository::from(&state.pool, &request.repository).await {
+        return (
+            StatusCode::NOT_FOUND,
+            Json(serde_json::json!({ "error": format!("{} is not tracked", request.repository) })),
+        )
+            .into_response();
+    }
+    // The config is read for every report so edits apply without a restart
+    let options = Config::load().into_diagnostic().and_then(|config| {
+        let mut options = report_options(&config, locale, bucket)?;
+        if let Some(fallback) = &state.public {
+            let secret = config.report.anonymize_secret.as_deref().unwrap_or(fallback);
+            options.anonymizer = Some(Anonymizer::new(secret));
+        }
+        Ok(options)
+    });
+    let options = match options {
+        Ok(options) => options,
+        Err(err) => return (StatusCode::INTERNAL_SERVER_ERROR, Json(serde_json::json!({ "error": err.to_string() })))
+            .into_response(),
+    };
+
+    let id = random_hex();
+    {
+        let mut jobs = state.jobs.lock().expect("jobs lock is never poisoned");
+        expire_jobs(&mut jobs);
+        if jobs.len() >= MAX_JOBS {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(serde_json::json!({ "error": "Too many reports are being generated; try again later" })),
+            )
+                .into_response();
+        }
+        jobs.insert(id.clone(), (Job::Running, Instant::now()));
+    }
+
+    let job_state = state.clone();
+    let job_id = id.clone();
+    tokio::spawn(async move {
+        let job = match generate_report(&job_state.pool, &request.repository, &options, request.appendix).await {
+            Ok((repo, html)) => Job::Done(report_file_name(&repo), html),
+            Err(err) => Job::Failed(err.to_string()),
+        };
+        job_state.jobs.lock().expect("jobs lock is never poisoned").insert(job_id, (job, Instant::now()));
+    });
+
+    (StatusCode::ACCEPTED, Json(status(&id, &Job::Running))).into_response()
+}
+
+async fn fetch_report(State(state): State<Arc<ServeState>>, Path(id): Path<String>) -> Response {
+    let mut jobs = state.jobs.lock().expect("jobs lock is never poisoned");
+    expire_jobs(&mut jobs);
+    match jobs.get(&id).map(|(job, _)| job) {
+        None => (StatusCode::NOT_FOUND, Json(serde_json::json!({ "error": "No such report" }))).into_response(),
+        Some(Job::Done(file_name, html)) => (
+            [
+                (header::CONTENT_TYPE, "text/html; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", file_name)),
+            ],
+            html.clone(),
+        )
+            .into_response(),
+        Some(job @ Job::Running) => (StatusCode::ACCEPTED, Json(status(&id, job))).into_response(),
+        Some(job @ Job::Failed(_)) => (StatusCode::INTERNAL_SERVER_ERROR, Json(status(&id, job))).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expire_jobs_drops_old_and_excess_finished_reports() {
+        let now = Instant::now();
+        let mut jobs = HashMap::new();
+        jobs.insert("expired".to_string(), (Job::Failed(String::new()), now - JOB_EXPIRY));
+        jobs.insert("running".to_string(), (Job::Running, now - JOB_EXPIRY));
+        for index in 0..MAX_JOBS {
+            let at = now - Duration::from_secs((MAX_JOBS - index) as u64);
+            jobs.insert(format!("done-{}", index), (Job::Done(String::new(), String::new()), at));
+        }
+
+        expire_jobs(&mut jobs);
+
+        assert_eq!(jobs.len(), MAX_JOBS - 1);
+        assert!(jobs.contains_key("running"));
+        assert!(!jobs.contains_key("expired"));
+        // The oldest finished reports made room
+        assert!(!jobs.contains_key("done-0"));
+        assert!(!jobs.contains_key("done-1"));
+        assert!(jobs.contains_key("done-2"));
+    }
+
+    #[test]
+    fn report_ids_are_random() {
+        assert_eq!(random_hex().len(), 32);
+        assert_ne!(random_hex(), random_hex());
+    }
+}
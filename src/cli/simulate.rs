@@ -0,0 +1,189 @@
+/// Module holding the `simulate` commands
+///
+/// Replays collected history under a different policy to estimate its effect.
+/// `simulate rotation` assigns the pull requests opened in a recent window to a
+/// list of reviewers in turn and compares the resulting load with what
+/// actually happened.
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use clap::ArgMatches;
+use console::style;
+use miette::{miette, Result, IntoDiagnostic};
+
+use crate::constants::{CLI_ARGS_DAYS, CLI_ARGS_PATH, CLI_ARGS_REVIEWERS};
+use crate::database::{setup_db, Repository};
+use crate::report::{median, parse_timestamp};
+use crate::terminal::Table;
+
+/// A pull request and when one reviewer first reviewed it
+#[derive(Debug, sqlx::FromRow)]
+struct ReviewedPull {
+    number: u32,
+    author: String,
+    created_at: String,
+    reviewer: Option<String>,
+    first_review_at: Option<String>,
+}
+
+/// Per reviewer numbers, for the actual history and the simulated rotation
+#[derive(Debug, Default)]
+struct ReviewerLoad {
+    actual: u32,
+    simulated: u32,
+    latencies: Vec<f64>,
+}
+
+pub async fn simulate_rotation(matches: &ArgMatches) -> Result<()> {
+    let repo_path = matches
+        .get_one::<String>(CLI_ARGS_PATH)
+        .expect("repository path is required");
+    let reviewers: Vec<String> = matches
+        .get_many::<String>(CLI_ARGS_REVIEWERS)
+        .expect("reviewers are required")
+        .cloned()
+        .collect();
+    let days = *matches.get_one::<u32>(CLI_ARGS_DAYS).expect("days has a default");
+
+    let pool = setup_db().await.into_diagnostic()?;
+    let repo = Repository::from(&pool, repo_path).await.into_diagnostic()?;
+
+    let rows: Vec<ReviewedPull> = sqlx::query_as(
+        "SELECT p.number, p.author, p.created_at, r.reviewer, MIN(r.submitted_at) AS first_review_at
+        FROM pulls p
+        LEFT JOIN issue_pull_reviews r
+            ON r.issue_pull_id = p.id AND r.reviewer != p.author AND r.submitted_at IS NOT NULL
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.created_at IS NOT NULL
+        GROUP BY p.id, r.reviewer
+        ORDER BY p.created_at, p.id",
+    )
+    .bind(repo.id)
+    .fetch_all(&pool)
+    .await
+    .into_diagnostic()?;
+
+    let window_start = (Utc::now() - Duration::days(days.into())).naive_utc();
+    let mut loads: HashMap<&str, ReviewerLoad> = reviewers
+        .iter()
+        .map(|reviewer| (reviewer.as_str(), ReviewerLoad::default()))
+        .collect();
+
+    // Pull requests in the window, in the order they were opened, with the
+    // hours until their first review by anyone
+    let mut window: Vec<(u32, &str, Option<f64>)> = vec![];
+    let mut all_latencies: Vec<f64> = vec![];
+
+    for row in &rows {
+        let Some(created) = parse_timestamp(&row.created_at) else {
+            continue;
+        };
+        let in_window = created >= window_start;
+        if in_window && window.last().map(|(number, _, _)| *number) != Some(row.number) {
+            window.push((row.number, &row.author, None));
+        }
+
+        let (Some(reviewer), Some(reviewed)) = (
+            row.reviewer.as_deref(),
+            row.first_review_at.as_deref().and_then(parse_timestamp),
+        ) else {
+            continue;
+        };
+        let hours = (reviewed - created).num_minutes() as f64 / 60.0;
+        all_latencies.push(hours);
+
+        if in_window {
+            if let Some((_, _, first)) = window.last_mut() {
+                *first = Some(first.map_or(hours, |first: f64| first.min(hours)));
+            }
+        }
+        if let Some(load) = loads.get_mut(reviewer) {
+            load.latencies.push(hours);
+            if in_window {
+                load.actual += 1;
+            }
+        }
+    }
+
+    if window.is_empty() {
+        return Err(miette!("No pull requests were opened in the last {} days", days));
+    }
+
+    let fallback_latency = median(&mut all_latencies).unwrap_or(0.0);
+    let reviewer_latency: HashMap<&str, f64> = loads
+        .iter_mut()
+        .map(|(reviewer, load)| (*reviewer, median(&mut load.latencies).unwrap_or(fallback_latency)))
+        .collect();
+
+    // Round robin, passing over reviewers who authored the pull request
+    let mut next = 0;
+    let mut simulated_latencies: Vec<f64> = vec![];
+    for (_, author, _) in &window {
+        let Some(offset) = (0..reviewers.len())
+            .find(|offset| reviewers[(next + offset) % reviewers.len()] != *author)
+        else {
+            continue;
+        };
+        let reviewer = reviewers[(next + offset) % reviewers.len()].as_str();
+        next = (next + offset + 1) % reviewers.len();
+
+        if let Some(load) = loads.get_mut(reviewer) {
+            load.simulated += 1;
+        }
+        simulated_latencies.push(reviewer_latency[reviewer]);
+    }
+    let mut actual_latencies: Vec<f64> = window.iter().filter_map(|(_, _, first)| *first).collect();
+
+    println!(
+        "Simulated rotation for {} over the last {} days ({} pull requests)\n",
+        style(repo_path).bold().cyan(),
+        days,
+        window.len()
+    );
+    let mut table = Table::new(&["Reviewer", "Actual", "Simulated", "Median latency"])
+        .align_right(1)
+        .align_right(2)
+        .align_right(3);
+    for reviewer in &reviewers {
+        let load = &loads[reviewer.as_str()];
+        table.push(vec![
+            reviewer.clone(),
+            load.actual.to_string(),
+            load.simulated.to_string(),
+            format!("{:.1}h", reviewer_latency[reviewer.as_str()]),
+        ]);
+    }
+    table.print();
+
+    println!(
+        "\nLoad spread (busiest / least busy reviewer): actual {}, simulated {}",
+        spread(reviewers.iter().map(|reviewer| loads[reviewer.as_str()].actual)),
+        spread(reviewers.iter().map(|reviewer| loads[reviewer.as_str()].simulated))
+    );
+    println!(
+        "Median time to first review: actual {}, simulated {}",
+        format_hours(median(&mut actual_latencies)),
+        format_hours(median(&mut simulated_latencies))
+    );
+    println!(
+        "\n{}",
+        style("Simulated latencies assume each reviewer keeps their historical median response time.").dim()
+    );
+
+    Ok(())
+}
+
+/// Ratio between the highest and lowest load, or `-` when someone had none
+fn spread(loads: impl Iterator<Item = u32>) -> String {
+    let loads: Vec<u32> = loads.collect();
+    match (loads.iter().max(), loads.iter().min()) {
+        (Some(max), Some(min)) if *min > 0 => format!("{:.1}x", *max as f64 / *min as f64),
+        _ => "-".to_string(),
+    }
+}
+
+fn format_hours(hours: Option<f64>) -> String {
+    match hours {
+        Some(hours) => format!("{:.1}h", hours),
+        None => "-".to_string(),
+    }
+}
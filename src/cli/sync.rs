@@ -0,0 +1,547 @@
+/// Module holding the `sync` and `collect all` commands
+///
+/// `sync` runs the core collectors for several repositories at once. Each
+/// repository runs its collectors in order, while up to `--jobs` repositories
+/// are synced concurrently, each with its own progress bars. With `--every` it
+/// keeps running, syncing again on schedule, and with `--email` it emails a
+/// report of every repository after each sync. `collect all` runs
+/// every collector for a single repository, skipping those the token can't serve.
+/// Any collector given `owner/*` runs for every repository of that owner.
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use clap::ArgMatches;
+use console::style;
+use http::StatusCode;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use miette::{miette, Result, IntoDiagnostic};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+
+use crate::cli::collect::{run_collector, shard, CollectOptions, PROGRESS};
+use crate::cli::export::export_after_collection;
+use crate::cli::report::{email_report, generate_report, report_options};
+use crate::cli::runs::record_run;
+use crate::client::{github_client, quiet_client, retry};
+use crate::config::Config;
+use crate::context::Context;
+use crate::constants::{CLI_ARGS_EMAIL, CLI_ARGS_EVERY, CLI_ARGS_JOBS, CLI_ARGS_REPO, CLI_ARGS_TOPIC, CLI_ARGS_VISIBILITY};
+use crate::database::{setup_db, CollectionRun, Repository, TABLES};
+use crate::features;
+use crate::forge::{FORGE_COLLECTORS, GITHUB};
+use crate::storage::open_storage;
+use crate::terminal::Table;
+
+/// Collectors run for every repository, in dependency order
+pub const SYNC_COLLECTORS: &[&str] = &["pulls", "issues", "events", "reviews", "comments"];
+
+/// Every collector run by `collect all`, in dependency order
+///
+/// Collectors that work on stored pull requests come after `pulls` and `issues`.
+pub const ALL_COLLECTORS: &[&str] = &[
+    "pulls",
+    "issues",
+    "events",
+    "reviews",
+    "comments",
+    "review-comments",
+    "files",
+    "pull-details",
+    "labels",
+    "milestones",
+    "commits",
+    "governance",
+    "releases",
+    "deployments",
+    "stars",
+    "forks",
+    "contributors",
+    "maintainers",
+    "demand",
+    "dependencies",
+    "repo-events",
+    "wiki",
+    "discussions",
+    "traffic",
+];
+
+/// Collectors that split their work by pull request, which every `--shard` runs;
+/// the rest cover the whole repository and only run on the first shard
+const SHARDED_COLLECTORS: &[&str] =
+    &["pulls", "events", "reviews", "comments", "review-comments", "files", "pull-details"];
+
+/// Ending of a repository argument that stands for every repository of an owner, as in `conda/*`
+const OWNER_WILDCARD: &str = "/*";
+
+/// What happened to one repository
+struct RepositorySync {
+    repository: String,
+    succeeded: usize,
+    /// Collector name and the first line of its error
+    failures: Vec<(String, String)>,
+    /// Seconds taken by each collector
+    durations: Vec<(String, u64)>,
+    seconds: u64,
+}
+
+pub async fn sync(matches: &ArgMatches) -> Result<()> {
+    let every = matches
+        .get_one::<u32>(CLI_ARGS_EVERY)
+        .map(|hours| Duration::from_secs(u64::from(*hours) * 60 * 60));
+    // Loaded up front, so a broken config shows before hours of collecting
+    let email = match matches.get_flag(CLI_ARGS_EMAIL) {
+        true => Some(Config::load().into_diagnostic()?),
+        false => None,
+    };
+
+    let Some(every) = every else {
+        // The caller exports the database once the command is done
+        let repositories = sync_repositories(matches).await?;
+        return match &email {
+            Some(config) => email_reports(config, &repositories).await,
+            None => Ok(()),
+        };
+    };
+
+    loop {
+        let started = Instant::now();
+        let run = async {
+            let repositories = sync_repositories(matches).await?;
+            export_after_collection().await?;
+            match &email {
+                Some(config) => email_reports(config, &repositories).await,
+                None => Ok(()),
+            }
+        };
+        // A failed run is tried again at the next one
+        if let Err(err) = run.await {
+            println!("{}", style(format!("Sync failed: {}", err)).red());
+        }
+
+        let wait = every.saturating_sub(started.elapsed());
+        let next = Local::now() + chrono::Duration::from_std(wait).into_diagnostic()?;
+        println!("\nNext sync at {}", style(next.format("%Y-%m-%d %H:%M")).bold());
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Emails a report of each repository to the recipients of the `[email]` settings
+async fn email_reports(config: &Config, repositories: &[String]) -> Result<()> {
+    let options = report_options(config, Default::default(), Default::default())?;
+    let pool = setup_db().await.into_diagnostic()?;
+    for repository in repositories {
+        let (repo, html) = generate_report(&pool, repository, &options, false).await?;
+        email_report(config, &repo, html).await?;
+    }
+
+    Ok(())
+}
+
+/// Syncs every repository given, or else every tracked one, and returns them
+async fn sync_repositories(matches: &ArgMatches) -> Result<Vec<String>> {
+    let jobs = *matches.get_one::<u32>(CLI_ARGS_JOBS).expect("jobs has a default") as usize;
+
+    let repositories: Vec<String> = match matches.get_many::<String>(CLI_ARGS_REPO) {
+        Some(repositories) => repositories.cloned().collect(),
+        None => {
+            let pool = setup_db().await.into_diagnostic()?;
+            Repository::fetch_all(&pool)
+                .await
+                .into_diagnostic()?
+                .into_iter()
+                .filter(|repo| !repo.archived)
+                .map(|repo| format!("{}/{}", repo.owner, repo.name))
+                .collect()
+        }
+    };
+
+    if repositories.is_empty() {
+        return Err(miette!("No repositories to sync. Add one with `gdfm init owner/name` first."));
+    }
+
+    let multi = MultiProgress::new();
+    let overall = multi.add(ProgressBar::new(repositories.len() as u64));
+    overall.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {bar:40.green} {pos}/{len} repositories synced")
+            .unwrap(),
+    );
+
+    let width = repositories.iter().map(String::len).max().unwrap_or(0);
+    let semaphore = Arc::new(Semaphore::new(jobs.max(1)));
+    // Spawned tasks don't inherit the context of the command
+    let context = Context::current();
+    let mut tasks = JoinSet::new();
+    for repository in repositories.clone() {
+        let semaphore = semaphore.clone();
+        let prefix = format!("{:width$} ", repository, width = width);
+        let multi = multi.clone();
+        let overall = overall.clone();
+        let context = context.clone();
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await.expect("semaphore is never closed");
+            let sync = PROGRESS.scope((multi, prefix), sync_repository(CollectOptions::new(&repository), SYNC_COLLECTORS, None));
+            let result = context.scope(sync).await;
+            overall.inc(1);
+            result
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(result) = tasks.join_next().await {
+        results.push(result.into_diagnostic()?);
+    }
+    overall.finish();
+
+    results.sort_by(|a, b| a.repository.cmp(&b.repository));
+    println!();
+    let mut table = Table::new(&["Repository", "Collectors", "Seconds", "Status"]).align_right(1).align_right(2);
+    for result in &results {
+        let status = if result.failures.is_empty() {
+            style("ok".to_string()).green()
+        } else {
+            style(format!("{} failed", result.failures.len())).red()
+        };
+        table.push(vec![
+            result.repository.clone(),
+            format!("{}/{}", result.succeeded, result.durations.len()),
+            result.seconds.to_string(),
+            status.to_string(),
+        ]);
+    }
+    table.print();
+    for result in &results {
+        for (collector, error) in &result.failures {
+            println!("{} {}: {}", result.repository, collector, style(error).dim());
+        }
+    }
+
+    Ok(repositories)
+}
+
+/// Runs every collector for one repository with the same options and summarizes what was stored
+pub async fn collect_all(options: &CollectOptions) -> Result<()> {
+    let pool = setup_db().await.into_diagnostic()?;
+    let repo = Repository::from(&pool, &options.repository).await.into_diagnostic()?;
+
+    let skipped = unavailable_collectors(&repo).await;
+    for (collector, reason) in &skipped {
+        println!("{}", style(format!("Skipping {}: {}", collector, reason)).yellow());
+        CollectionRun::skip(&pool, repo.id, collector, reason).await.into_diagnostic()?;
+    }
+    let collectors: Vec<&str> = ALL_COLLECTORS
+        .iter()
+        .copied()
+        .filter(|collector| !skipped.iter().any(|(name, _)| name == collector))
+        .filter(|collector| shard().is_none_or(|shard| shard.is_first()) || SHARDED_COLLECTORS.contains(collector))
+        .collect();
+
+    let rows_before = row_counts(&pool).await.into_diagnostic()?;
+
+    let multi = MultiProgress::new();
+    let overall = multi.add(ProgressBar::new(collectors.len() as u64));
+    overall.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] {bar:40.green} {pos}/{len} collectors {msg}")
+            .unwrap(),
+    );
+
+    let result = PROGRESS
+        .scope((multi, String::new()), sync_repository(options.clone(), &collectors, Some(overall.clone())))
+        .await;
+    overall.finish_with_message("done");
+
+    let rows_after = row_counts(&pool).await.into_diagnostic()?;
+
+    println!();
+    let mut table = Table::new(&["Collector", "Seconds", "Status"]).align_right(1);
+    for (collector, seconds) in &result.durations {
+        let status = match result.failures.iter().find(|(failed, _)| failed == collector) {
+            Some((_, error)) => style(error.clone()).red(),
+            None => style("ok".to_string()).green(),
+        };
+        table.push(vec![collector.to_string(), seconds.to_string(), status.to_string()]);
+    }
+    for (collector, reason) in &skipped {
+        let status = style(format!("skipped: {}", reason)).yellow();
+        table.push(vec![collector.to_string(), String::new(), status.to_string()]);
+    }
+    table.print();
+
+    println!();
+    let mut stored = Table::new(&["Table", "Rows stored"]).align_right(1);
+    for ((table, before), after) in TABLES.iter().zip(&rows_before).zip(&rows_after) {
+        // Every collector adds a run, which isn't data that was fetched
+        if before != after && *table != "collection_runs" {
+            stored.push(vec![table.to_string(), format!("{:+}", after - before)]);
+        }
+    }
+    if stored.is_empty() {
+        println!("No rows stored");
+    } else {
+        stored.print();
+    }
+    print!("\n{}/{} collectors succeeded", result.succeeded, collectors.len());
+    if !skipped.is_empty() {
+        print!(", {} skipped", skipped.len());
+    }
+    println!(" in {}s", result.seconds);
+
+    Ok(())
+}
+
+/// Collectors the current token or API host can't serve for a repository, with the reason
+///
+/// Projects on other forges only have the `FORGE_COLLECTORS`. Discussions go
+/// through GraphQL, which always needs a token, and traffic and maintainers
+/// need push access.
+/// A GitHub Enterprise Server may also be too old for some APIs. When the
+/// repository can't be fetched nothing more is skipped and the collectors
+/// report the problem themselves.
+async fn unavailable_collectors(repo: &Repository) -> Vec<(&'static str, String)> {
+    if repo.forge != GITHUB {
+        return ALL_COLLECTORS
+            .iter()
+            .copied()
+            .filter(|collector| !FORGE_COLLECTORS.contains(collector))
+            .map(|collector| (collector, format!("not available for {} projects", repo.forge)))
+            .collect();
+    }
+
+    let mut skipped = match open_storage().await {
+        Ok(storage) => features::unavailable_collectors(storage.as_ref()).await.unwrap_or_default(),
+        Err(_) => Vec::new(),
+    };
+    let mut skip = |collector: &'static str, reason: String| {
+        if !skipped.iter().any(|(name, _)| *name == collector) {
+            skipped.push((collector, reason));
+        }
+    };
+
+    if std::env::var("GITHUB_TOKEN").is_err() {
+        skip("discussions", "GraphQL needs GITHUB_TOKEN to be set".to_string());
+        skip("traffic", "traffic needs GITHUB_TOKEN with push access".to_string());
+        skip("maintainers", "listing collaborators needs GITHUB_TOKEN with push access".to_string());
+        return skipped;
+    }
+
+    let Ok(octocrab) = quiet_client() else {
+        return skipped;
+    };
+    let Ok(github_repo) = octocrab.repos(&repo.owner, &repo.name).get().await else {
+        return skipped;
+    };
+
+    if let Some(permissions) = github_repo.permissions {
+        if !permissions.push {
+            skip("traffic", format!("the token has no push access to {}/{}", repo.owner, repo.name));
+            skip("maintainers", format!("the token has no push access to {}/{}", repo.owner, repo.name));
+        }
+    }
+
+    skipped
+}
+
+/// Counts the rows of every table, in the order of [`TABLES`]
+async fn row_counts(pool: &Pool<Sqlite>) -> Result<Vec<i64>, sqlx::Error> {
+    let mut counts = Vec::new();
+    for table in TABLES {
+        counts.push(
+            sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table))
+                .fetch_one(pool)
+                .await?,
+        );
+    }
+
+    Ok(counts)
+}
+
+/// Runs `gdfm collect <collector>` with the given options, recording the run
+pub async fn run_collect_command(collector: &str, options: &CollectOptions) -> Result<()> {
+    record_run(collector, &options.repository, run_collector(collector, options)).await
+}
+
+/// Runs the given collectors for one repository, carrying on past failures
+///
+/// `overall` advances once per collector and shows the one currently running.
+async fn sync_repository(
+    options: CollectOptions,
+    collectors: &[&str],
+    overall: Option<ProgressBar>,
+) -> RepositorySync {
+    let repository = options.repository.clone();
+    let started = Instant::now();
+    let mut sync = RepositorySync {
+        repository: repository.clone(),
+        succeeded: 0,
+        failures: Vec::new(),
+        durations: Vec::new(),
+        seconds: 0,
+    };
+
+    // Projects on other forges only have some of the collectors
+    let forge = match open_storage().await {
+        Ok(storage) => storage.get_repository(&repository).await.map(|repo| repo.forge).ok(),
+        Err(_) => None,
+    };
+    for collector in collectors {
+        if forge.as_deref().is_some_and(|forge| forge != GITHUB) && !FORGE_COLLECTORS.contains(collector) {
+            continue;
+        }
+        if let Some(overall) = &overall {
+            overall.set_message(collector.to_string());
+        }
+        let collector_started = Instant::now();
+        match run_collect_command(collector, &options).await {
+            Ok(()) => sync.succeeded += 1,
+            // octocrab errors carry a backtrace after the message
+            Err(err) => sync.failures.push((
+                collector.to_string(),
+                err.to_string().lines().next().unwrap_or_default().to_string(),
+            )),
+        }
+        sync.durations.push((collector.to_string(), collector_started.elapsed().as_secs()));
+        if let Some(overall) = &overall {
+            overall.inc(1);
+        }
+    }
+
+    if let Err(err) = record_weekly_metrics(&repository).await {
+        sync.failures.push(("metrics".to_string(), err.to_string()));
+    }
+
+    sync.seconds = started.elapsed().as_secs();
+    sync
+}
+
+/// Keeps a weekly row of headline metrics, whether or not a report is generated
+async fn record_weekly_metrics(repository: &str) -> Result<()> {
+    let storage = open_storage().await.into_diagnostic()?;
+    let repo = storage.get_repository(repository).await.into_diagnostic()?;
+    storage.record_weekly_metrics(repo.id).await.into_diagnostic()
+}
+
+/// The owner when the repository argument is `owner/*`
+pub fn wildcard_owner(matches: &ArgMatches) -> Option<&str> {
+    matches.get_one::<String>(CLI_ARGS_REPO)?.strip_suffix(OWNER_WILDCARD)
+}
+
+/// Runs a collector, or `collect all`, for every repository of an owner,
+/// tracking the ones gdfm doesn't know yet
+///
+/// `args` is the command line that had `owner/*` in it; every repository runs
+/// with the same options. Repositories archived on GitHub or in gdfm are left
+/// out, and a failure for one repository doesn't stop the others.
+pub async fn collect_owner(collector: &str, matches: &ArgMatches, args: &[String]) -> Result<()> {
+    let owner = wildcard_owner(matches).expect("repository is owner/*");
+    let wildcard = format!("{}{}", owner, OWNER_WILDCARD);
+    let topic = matches.get_one::<String>(CLI_ARGS_TOPIC);
+    let visibility = matches
+        .get_one::<String>(CLI_ARGS_VISIBILITY)
+        .expect("visibility has a default");
+
+    let storage = open_storage().await.into_diagnostic()?;
+    let mut repositories = Vec::new();
+    for github_repo in owner_repositories(owner).await? {
+        let private = github_repo.private.unwrap_or(false);
+        let wanted = !github_repo.archived.unwrap_or(false)
+            && match visibility.as_str() {
+                "public" => !private,
+                "private" => private,
+                _ => true,
+            }
+            && topic.is_none_or(|topic| github_repo.topics.as_ref().is_some_and(|topics| topics.contains(topic)));
+        if !wanted {
+            continue;
+        }
+
+        let repository = format!("{}/{}", owner, github_repo.name);
+        match storage.get_repository(&repository).await {
+            Ok(repo) if repo.archived => continue,
+            Ok(_) => {}
+            Err(_) => {
+                storage.create_repository(&repository).await.into_diagnostic()?;
+                println!("Tracking {}", style(&repository).bold().cyan());
+            }
+        }
+        repositories.push(repository);
+    }
+
+    if repositories.is_empty() {
+        return Err(miette!("{} has no repositories matching the filters", owner));
+    }
+
+    let mut failures = Vec::new();
+    for repository in &repositories {
+        println!();
+        println!("Collecting {} for {}", style(collector).bold(), style(repository).bold().cyan());
+        let repository_args: Vec<&str> = args
+            .iter()
+            .map(|arg| if *arg == wildcard { repository.as_str() } else { arg.as_str() })
+            .collect();
+        if let Err(err) = run_repository_args(collector, &repository_args).await {
+            // octocrab errors carry a backtrace after the message
+            let error = err.to_string().lines().next().unwrap_or_default().to_string();
+            println!("{}", style(&error).red());
+            failures.push((repository, error));
+        }
+    }
+
+    println!();
+    println!("{}/{} repositories collected", repositories.len() - failures.len(), repositories.len());
+    for (repository, error) in &failures {
+        println!("    {}: {}", repository, style(error).dim());
+    }
+
+    Ok(())
+}
+
+/// Parses a `gdfm collect` command line for one repository and runs it
+async fn run_repository_args(collector: &str, args: &[&str]) -> Result<()> {
+    let matches = crate::command().try_get_matches_from(args).map_err(|err| miette!("{}", err))?;
+    let (_, collect_matches) = matches.subcommand().expect("collect was given");
+    let (_, collector_matches) = collect_matches.subcommand().expect("collector was given");
+
+    let options = CollectOptions::from_matches(collector_matches);
+    if collector == "all" {
+        collect_all(&options).await
+    } else {
+        run_collect_command(collector, &options).await
+    }
+}
+
+/// Every repository of an organization, or the public ones of a user
+async fn owner_repositories(owner: &str) -> Result<Vec<octocrab::models::Repository>> {
+    let octocrab = github_client().await?;
+
+    let mut route = format!("/orgs/{}/repos", owner);
+    let mut repositories = Vec::new();
+    let mut page = 1u32;
+    loop {
+        let page_str = page.to_string();
+        let params = [("per_page", "100"), ("page", page_str.as_str())];
+        let result: octocrab::Result<Vec<octocrab::models::Repository>> =
+            retry(|| octocrab.get(&route, Some(&params))).await;
+        let batch = match result {
+            // Users aren't organizations and have their repositories listed elsewhere
+            Err(octocrab::Error::GitHub { source, .. })
+                if source.status_code == StatusCode::NOT_FOUND && route.starts_with("/orgs/") =>
+            {
+                route = format!("/users/{}/repos", owner);
+                continue;
+            }
+            result => result.into_diagnostic()?,
+        };
+
+        if batch.is_empty() {
+            break;
+        }
+        repositories.extend(batch);
+        page += 1;
+    }
+
+    Ok(repositories)
+}
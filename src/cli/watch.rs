@@ -0,0 +1,101 @@
+/// Module holding the `watch` command
+///
+/// Polls GitHub for a handful of pull requests and prints new timeline events
+/// and reviews as they show up. Meant as a lightweight triage companion, so
+/// nothing is written to the database.
+use std::collections::HashSet;
+
+use clap::ArgMatches;
+use console::style;
+use miette::{miette, Result, IntoDiagnostic};
+use tokio::time::{interval, Duration};
+
+use crate::client::github_client;
+use crate::constants::{CLI_ARGS_INTERVAL, CLI_ARGS_NUMBER, CLI_ARGS_REPO};
+
+pub async fn watch_pulls(matches: &ArgMatches) -> Result<()> {
+    let project_name = matches
+        .get_one::<String>(CLI_ARGS_REPO)
+        .expect("repository is required");
+    let (owner, name) = project_name
+        .split_once('/')
+        .ok_or_else(|| miette!("Repository should be given as owner/name"))?;
+
+    let pr_numbers: Vec<u32> = matches
+        .get_many(CLI_ARGS_NUMBER)
+        .expect("pull request numbers are required")
+        .copied()
+        .collect();
+    let seconds = *matches.get_one::<u32>(CLI_ARGS_INTERVAL).expect("interval has a default");
+
+    let octocrab = github_client().await?;
+
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut ticker = interval(Duration::from_secs(seconds.max(1).into()));
+    let mut first_poll = true;
+
+    println!(
+        "Watching {} pull request(s) in {} every {}s (Ctrl-C to stop)",
+        pr_numbers.len(),
+        style(project_name).bold().cyan(),
+        seconds
+    );
+
+    loop {
+        ticker.tick().await;
+
+        for number in &pr_numbers {
+            let number = *number as u64;
+            let mut updates: Vec<(String, String)> = vec![];
+
+            let mut page = 1u32;
+            loop {
+                let events = octocrab.issues(owner, name)
+                    .list_timeline_events(number)
+                    .page(page)
+                    .per_page(100)
+                    .send()
+                    .await.into_diagnostic()?;
+                if events.items.is_empty() {
+                    break;
+                }
+
+                for event in events.items {
+                    let Some(id) = event.id else { continue };
+                    if seen.insert(format!("event-{}", id)) {
+                        let actor = event.actor.map(|actor| actor.login).unwrap_or_default();
+                        let created_at = event.created_at.map(|at| at.to_string()).unwrap_or_default();
+                        updates.push((created_at, format!("{:?} by {}", event.event, actor)));
+                    }
+                }
+                page += 1;
+            }
+
+            let reviews = octocrab.pulls(owner, name)
+                .list_reviews(number)
+                .per_page(100)
+                .send()
+                .await.into_diagnostic()?;
+            for review in reviews.items {
+                if seen.insert(format!("review-{}", review.id)) {
+                    let reviewer = review.user.map(|user| user.login).unwrap_or_default();
+                    let state = review.state.map(|state| format!("{:?}", state)).unwrap_or_default();
+                    let submitted_at = review.submitted_at.map(|at| at.to_string()).unwrap_or_default();
+                    updates.push((submitted_at, format!("review {} by {}", state, reviewer)));
+                }
+            }
+
+            // The first poll only establishes what already happened
+            if first_poll {
+                continue;
+            }
+
+            updates.sort();
+            for (at, description) in updates {
+                println!("{} {} {}", style(at).dim(), style(format!("#{}", number)).bold(), description);
+            }
+        }
+
+        first_poll = false;
+    }
+}
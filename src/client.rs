@@ -0,0 +1,671 @@
+/// Builds the GitHub client shared by every command that talks to the API
+///
+/// A `GITHUB_TOKEN` is used when set. Without one the client runs anonymously,
+/// which is enough to try gdfm on a tiny repository but limited by GitHub to
+/// 60 requests per hour, every one of which counts: GitHub only answers
+/// conditional requests for free when they are authenticated. Every client asks for the REST API version in
+/// `GITHUB_API_VERSION`, so responses don't change shape when GitHub releases
+/// a new one.
+use std::future::Future;
+use std::ops::Deref;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use chrono::{DateTime, Days, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use console::style;
+use http::header::{HeaderMap, HeaderName, HeaderValue, ETAG, IF_NONE_MATCH};
+use http::StatusCode;
+use indicatif::ProgressBar;
+use miette::{miette, IntoDiagnostic, Result};
+use octocrab::models::RateLimit;
+use octocrab::{DefaultOctocrabBuilderConfig, FromResponse, NoAuth, NoSvc, NotLayerReady, Octocrab, OctocrabBuilder};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer};
+use tokio::time::{sleep, Duration};
+
+use crate::context::Context;
+
+/// Requests per hour GitHub allows without authentication
+pub const ANONYMOUS_RATE_LIMIT: usize = 60;
+
+/// REST requests left untouched, so other tools sharing the token aren't starved
+const RATE_LIMIT_RESERVE: usize = 5;
+
+/// Requests made between two checks of the rate limit
+const RATE_LIMIT_CHECK_EVERY: u32 = 25;
+
+/// Attempts made at a request before giving up, unless `collect.max_attempts` says otherwise
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 4;
+
+/// Wait before the first retry of a server error, doubled for every further attempt
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait between two attempts at a server error
+const RETRY_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// GitHub asks clients to wait at least a minute after hitting a secondary rate limit
+const SECONDARY_RATE_LIMIT_DELAY: Duration = Duration::from_secs(60);
+
+/// Share of the rate limit below which collectors start spreading what is left over the window
+const THROTTLE_BELOW_SHARE: f64 = 0.5;
+
+/// Longest pause the adaptive throttle makes between two requests or batches
+const THROTTLE_MAX_PAUSE: Duration = Duration::from_secs(10);
+
+/// Pause added after a secondary rate limit, doubled for every further one
+const THROTTLE_SECONDARY_PAUSE: Duration = Duration::from_secs(1);
+
+/// Local hours in which collectors may spend the API budget, e.g. `22:00-06:00`
+///
+/// Leaves the daytime budget of a shared token to other automation during a
+/// long backfill. A window that ends before it starts runs past midnight.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScheduleWindow {
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl ScheduleWindow {
+    /// Parses `HH:MM-HH:MM` in local time
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("\"{}\" is not a window of local times such as 22:00-06:00", s);
+        let (start, end) = s.split_once('-').ok_or_else(invalid)?;
+        let start = NaiveTime::parse_from_str(start.trim(), "%H:%M").map_err(|_| invalid())?;
+        let end = NaiveTime::parse_from_str(end.trim(), "%H:%M").map_err(|_| invalid())?;
+        if start == end {
+            return Err("A schedule window must not start and end at the same time".to_string());
+        }
+
+        Ok(Self { start, end })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+
+    /// When the window next opens after `now`, or `None` while it is open
+    pub fn next_opening(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        if self.contains(now.time()) {
+            return None;
+        }
+        let today = now.date().and_time(self.start);
+
+        Some(if today > now { today } else { today + Days::new(1) })
+    }
+}
+
+/// Sleeps until the schedule window of the command opens, when one is set and
+/// it is closed, after telling `announce` when that will be; returns whether it slept
+///
+/// [`retry`] calls this before every attempt, so requests outside the window
+/// wait whichever collector or forge makes them.
+async fn wait_for_schedule_window(announce: impl FnOnce(DateTime<Local>)) -> bool {
+    let Some(window) = Context::current().schedule_window else {
+        return false;
+    };
+    let now = Local::now();
+    let Some(opens) = window.next_opening(now.naive_local()) else {
+        return false;
+    };
+
+    // Times skipped by a daylight saving change open the window an hour late at worst
+    let opens = Local.from_local_datetime(&opens).earliest().unwrap_or(now + Duration::from_secs(3600));
+    announce(opens);
+    let seconds = (opens - now).num_seconds().max(0) as u64 + 1;
+    sleep(Duration::from_secs(seconds)).await;
+
+    true
+}
+
+/// Root of the REST API every client talks to
+pub fn api_url() -> String {
+    Context::current().api_url
+}
+
+/// REST API version sent with every request, if any
+pub fn api_version() -> Option<String> {
+    let context = Context::current();
+    if context.host_state.api_version_rejected.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    context.api_version
+}
+
+/// Stops sending `X-GitHub-Api-Version` from clients the command builds
+/// afterwards, for servers that reject it
+pub fn disable_api_version() {
+    Context::current().host_state.api_version_rejected.store(true, Ordering::Relaxed);
+}
+
+/// Starts a client rooted at `base_uri` that asks for [`api_version`]
+fn client_builder(
+    base_uri: &str,
+) -> Result<OctocrabBuilder<NoSvc, DefaultOctocrabBuilderConfig, NoAuth, NotLayerReady>> {
+    let builder = Octocrab::builder().base_uri(base_uri).into_diagnostic()?;
+
+    Ok(match api_version() {
+        Some(version) => builder.add_header(HeaderName::from_static("x-github-api-version"), version),
+        None => builder,
+    })
+}
+
+/// Builds a client, falling back to anonymous access when no token is set
+pub async fn github_client() -> Result<Octocrab> {
+    if std::env::var("GITHUB_TOKEN").is_err() {
+        return anonymous_client().await;
+    }
+
+    quiet_client()
+}
+
+/// Same as [`github_client`] but without the warnings about anonymous access
+pub fn quiet_client() -> Result<Octocrab> {
+    match std::env::var("GITHUB_TOKEN") {
+        Ok(github_api_token) => client_builder(&api_url())?.personal_token(github_api_token).build(),
+        Err(_) => client_builder(&api_url())?.build(),
+    }
+    .into_diagnostic()
+}
+
+/// Current rate-limit usage, or `None` if GitHub can't be reached
+///
+/// Checking the rate limit does not count against it.
+pub async fn rate_limit() -> Option<RateLimit> {
+    quiet_client().ok()?.ratelimit().get().await.ok()
+}
+
+/// Builds a client for endpoints that need authentication
+pub fn authenticated_client() -> Result<Octocrab> {
+    let github_api_token = std::env::var("GITHUB_TOKEN")
+        .map_err(|_| miette!("GitHub token not found. Please set GITHUB_TOKEN environment variable"))?;
+
+    client_builder(&api_url())?
+        .personal_token(github_api_token)
+        .build()
+        .into_diagnostic()
+}
+
+/// Builds a client for GraphQL queries, which always need authentication
+///
+/// GitHub Enterprise Server answers GraphQL at `/api/graphql` rather than
+/// under the REST API root, so the client is rooted at `/api` there.
+pub fn graphql_client() -> Result<Octocrab> {
+    let github_api_token = std::env::var("GITHUB_TOKEN")
+        .map_err(|_| miette!("GitHub token not found. Please set GITHUB_TOKEN environment variable"))?;
+    let api_url = api_url();
+    let base_uri = api_url.strip_suffix("/v3").unwrap_or(&api_url);
+
+    client_builder(base_uri)?
+        .personal_token(github_api_token)
+        .build()
+        .into_diagnostic()
+}
+
+/// Host of the API collectors talk to, as used for `[hosts]` in the config,
+/// e.g. `github.com`
+pub fn api_host() -> String {
+    Context::current().api_host()
+}
+
+/// Caps a requested number of concurrent requests at what the API host allows
+pub fn allowed_concurrency(requested: u32) -> u32 {
+    match Context::current().max_concurrency {
+        Some(max) => requested.min(max),
+        None => requested,
+    }
+}
+
+/// An error [`retry`] can judge: worth another attempt after a while, or not
+pub trait Retryable: std::fmt::Display {
+    /// How long to wait before trying a failed request again, or `None` if it's not worth it
+    fn retry_delay(&self, attempt: u32) -> Option<Duration>;
+}
+
+/// Runs a request until it succeeds, fails for good or runs out of attempts
+///
+/// Server errors are retried after an exponential, jittered backoff and
+/// secondary rate limits after at least a minute. Any other error, such as a
+/// missing repository or a bad token, is returned straight away.
+pub async fn retry<T, E, F, Fut>(mut request: F) -> std::result::Result<T, E>
+where
+    E: Retryable,
+    F: FnMut() -> Fut,
+    Fut: Future<Output = std::result::Result<T, E>>,
+{
+    let max_attempts = Context::current().max_attempts;
+    let mut attempt = 1;
+    loop {
+        wait_for_schedule_window(|opens| {
+            let message = format!("Outside the schedule window; waiting until {}", opens.format("%H:%M"));
+            eprintln!("{}", style(message).yellow());
+        })
+        .await;
+        let err = match request().await {
+            Err(err) if attempt < max_attempts => err,
+            result => return result,
+        };
+        let Some(delay) = err.retry_delay(attempt) else {
+            return Err(err);
+        };
+        eprintln!(
+            "{}",
+            style(format!(
+                "{}; retrying in {}s (attempt {} of {})",
+                err.to_string().lines().next().unwrap_or_default(),
+                delay.as_secs(),
+                attempt + 1,
+                max_attempts
+            ))
+            .yellow()
+        );
+        sleep(delay).await;
+        attempt += 1;
+    }
+}
+
+impl Retryable for octocrab::Error {
+    fn retry_delay(&self, attempt: u32) -> Option<Duration> {
+        let octocrab::Error::GitHub { source, .. } = self else {
+            return None;
+        };
+        let status = source.status_code;
+
+        if status.is_server_error() {
+            Some(backoff(attempt))
+        } else if (status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS)
+            && source.message.to_lowercase().contains("secondary rate limit")
+        {
+            Context::current().host_state.secondary_limits_hit.fetch_add(1, Ordering::Relaxed);
+            Some(jitter(SECONDARY_RATE_LIMIT_DELAY * attempt))
+        } else {
+            None
+        }
+    }
+}
+
+/// Wait before another attempt at a server error, doubled for every attempt made
+pub(crate) fn backoff(attempt: u32) -> Duration {
+    let backoff = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt - 1));
+    jitter(backoff.min(RETRY_MAX_DELAY))
+}
+
+/// Adds up to half of a delay again, so collectors running side by side don't retry in lockstep
+fn jitter(delay: Duration) -> Duration {
+    let mut bytes = [0u8; 4];
+    // Without random numbers the delay goes unjittered rather than failing the request
+    let share = match getrandom::getrandom(&mut bytes) {
+        Ok(()) => u32::from_le_bytes(bytes) as f64 / u32::MAX as f64,
+        Err(_) => 0.0,
+    };
+
+    delay + delay.mul_f64(share / 2.0)
+}
+
+/// Response to a request made with the ETag of an earlier response
+pub enum Conditional<T> {
+    /// The resource changed since the ETag was stored, or none was stored
+    Modified { value: T, etag: Option<String> },
+    /// GitHub answered `304 Not Modified`
+    NotModified,
+}
+
+/// An item of a response along with the JSON it was read from, which keeps
+/// the fields gdfm doesn't parse for `--keep-raw`
+#[derive(Debug)]
+pub struct Raw<T> {
+    pub item: T,
+    pub json: serde_json::Value,
+}
+
+impl<'de, T: DeserializeOwned> Deserialize<'de> for Raw<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let json = serde_json::Value::deserialize(deserializer)?;
+        let item = T::deserialize(&json).map_err(serde::de::Error::custom)?;
+
+        Ok(Self { item, json })
+    }
+}
+
+impl<T> Deref for Raw<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.item
+    }
+}
+
+/// Sends a `GET`, made conditional with `If-None-Match` when an earlier ETag is known
+///
+/// GitHub doesn't count `304 Not Modified` responses against the rate limit.
+pub async fn get_if_modified<T: FromResponse>(
+    octocrab: &Octocrab,
+    route: &str,
+    etag: Option<&str>,
+) -> octocrab::Result<Conditional<T>> {
+    let mut headers = HeaderMap::new();
+    if let Some(etag) = etag.and_then(|etag| HeaderValue::from_str(etag).ok()) {
+        headers.insert(IF_NONE_MATCH, etag);
+    }
+
+    let response = octocrab._get_with_headers(route, Some(headers)).await?;
+    if response.status() == StatusCode::NOT_MODIFIED {
+        return Ok(Conditional::NotModified);
+    }
+    let etag = response
+        .headers()
+        .get(ETAG)
+        .and_then(|etag| etag.to_str().ok())
+        .map(str::to_string);
+    let response = octocrab::map_github_error(response).await?;
+
+    Ok(Conditional::Modified { value: T::from_response(response).await?, etag })
+}
+
+async fn anonymous_client() -> Result<Octocrab> {
+    let octocrab = quiet_client()?;
+
+    eprintln!(
+        "{}",
+        style(format!(
+            "WARNING: GITHUB_TOKEN is not set, running unauthenticated. GitHub allows only {} \
+            requests per hour without a token, counting unchanged pages too; this is enough for \
+            tiny repositories only.",
+            ANONYMOUS_RATE_LIMIT
+        ))
+        .yellow()
+        .bold()
+    );
+
+    // Checking the rate limit does not count against it
+    let limit = octocrab.ratelimit().get().await.into_diagnostic()?;
+    let reset = Local
+        .timestamp_opt(limit.resources.core.reset as i64, 0)
+        .single()
+        .map(|reset| reset.format("%H:%M").to_string())
+        .unwrap_or_default();
+    eprintln!(
+        "{}",
+        style(format!(
+            "WARNING: {} of {} anonymous requests left until {}.",
+            limit.resources.core.remaining, limit.resources.core.limit, reset
+        ))
+        .yellow()
+        .bold()
+    );
+
+    if limit.resources.core.remaining == 0 {
+        return Err(miette!(
+            "The anonymous rate limit is used up until {}. Set GITHUB_TOKEN to continue.",
+            reset
+        ));
+    }
+
+    Ok(octocrab)
+}
+
+tokio::task_local! {
+    /// Rate limiter every page of a listing waits for, set by [`RateLimiter::pace_pages`]
+    static PAGE_LIMITER: Arc<tokio::sync::Mutex<RateLimiter>>;
+}
+
+/// Waits for the rate limiter of [`RateLimiter::pace_pages`], if any; call
+/// before requesting a page of a listing
+pub async fn wait_for_page() {
+    if let Ok(limiter) = PAGE_LIMITER.try_with(Arc::clone) {
+        limiter.lock().await.wait().await;
+    }
+}
+
+/// Pauses a collector until the REST rate limit resets instead of letting it
+/// fail halfway, and shows the remaining budget after the progress bar message
+///
+/// The budget is checked every `RATE_LIMIT_CHECK_EVERY` requests and counted
+/// down in between, since anything else using the token spends it too.
+pub struct RateLimiter {
+    progress: ProgressBar,
+    /// Requests left, requests per window and when the window resets (unix seconds), once known
+    limit: Option<(usize, usize, u64)>,
+    unchecked: u32,
+    /// Status last appended to the progress bar message
+    status: String,
+}
+
+impl RateLimiter {
+    pub fn new(progress: &ProgressBar) -> Self {
+        Self {
+            progress: progress.clone(),
+            limit: None,
+            unchecked: RATE_LIMIT_CHECK_EVERY,
+            status: String::new(),
+        }
+    }
+
+    /// Waits, if needed, until one more request fits in the budget; call before every request
+    pub async fn wait(&mut self) {
+        self.wait_for_window().await;
+
+        if self.unchecked >= RATE_LIMIT_CHECK_EVERY
+            || self.limit.is_some_and(|(remaining, _, _)| remaining <= RATE_LIMIT_RESERVE)
+        {
+            self.check().await;
+        }
+
+        while let Some((remaining, _, reset)) = self.limit {
+            if remaining > RATE_LIMIT_RESERVE {
+                break;
+            }
+            let reset_at = Local
+                .timestamp_opt(reset as i64, 0)
+                .single()
+                .map(|reset| reset.format("%H:%M").to_string())
+                .unwrap_or_default();
+            self.show(format!("rate limit reached, waiting until {}", reset_at));
+            // A second of slack for clocks that are slightly apart
+            let seconds = (reset as i64 - Utc::now().timestamp()).max(0) as u64 + 1;
+            sleep(Duration::from_secs(seconds)).await;
+            self.check().await;
+        }
+
+        if let Some((remaining, _, _)) = &mut self.limit {
+            *remaining = remaining.saturating_sub(1);
+            let remaining = *remaining;
+            self.show(format!("{} requests left", remaining));
+        }
+        self.unchecked += 1;
+    }
+
+    /// Runs `future` waiting for `limiter` before every page it asks for through
+    /// [`wait_for_page`], for listings fetched side by side whose number of
+    /// pages isn't known beforehand
+    pub async fn pace_pages<F: Future>(limiter: &mut Option<Self>, future: F) -> F::Output {
+        let Some(taken) = limiter.take() else {
+            return future.await;
+        };
+        let shared = Arc::new(tokio::sync::Mutex::new(taken));
+        let output = PAGE_LIMITER.scope(Arc::clone(&shared), future).await;
+        let shared = Arc::try_unwrap(shared).ok().expect("nothing waits for pages once the listings are in");
+        *limiter = Some(shared.into_inner());
+
+        output
+    }
+
+    /// Pauses between two requests or batches of requests; call where a
+    /// collector would otherwise go straight on to the next pull request
+    ///
+    /// `fixed` (from `--throttle-ms`) always pauses that long. Otherwise the
+    /// pause adapts: none while more than half the rate limit is left, then
+    /// long enough to spread what is left until the window resets, plus a
+    /// pause that doubles with every secondary rate limit hit and halves
+    /// every time the rate limit is checked again. Neither goes below the
+    /// host's `min_delay_ms`.
+    pub async fn throttle(&mut self, fixed: Option<Duration>) {
+        let min_delay = Context::current().min_delay;
+        let pause = fixed.unwrap_or_else(|| self.adaptive_pause()).max(min_delay);
+        if !pause.is_zero() {
+            sleep(pause).await;
+        }
+    }
+
+    fn adaptive_pause(&self) -> Duration {
+        let spread = match self.limit {
+            Some((remaining, limit, reset)) if (remaining as f64) < limit as f64 * THROTTLE_BELOW_SHARE => {
+                let window_left = (reset as i64 - Utc::now().timestamp()).max(0) as f64;
+                Duration::from_secs_f64(window_left / remaining.saturating_sub(RATE_LIMIT_RESERVE).max(1) as f64)
+            }
+            _ => Duration::ZERO,
+        };
+        let secondary = match Context::current().host_state.secondary_limits_hit.load(Ordering::Relaxed) {
+            0 => Duration::ZERO,
+            hits => THROTTLE_SECONDARY_PAUSE.saturating_mul(2u32.saturating_pow(hits - 1)),
+        };
+
+        (spread + secondary).min(THROTTLE_MAX_PAUSE)
+    }
+
+    /// Reads the rate limit from GitHub; when it can't be reached, requests go ahead unchecked
+    async fn check(&mut self) {
+        self.unchecked = 0;
+        let _ = Context::current()
+            .host_state
+            .secondary_limits_hit
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |hits| Some(hits / 2));
+        self.limit = rate_limit().await.map(|limit| {
+            (limit.resources.core.remaining, limit.resources.core.limit, limit.resources.core.reset)
+        });
+    }
+
+    /// Sleeps until the schedule window opens, when one is set and it is closed,
+    /// saying so after the progress bar message rather than below it
+    async fn wait_for_window(&mut self) {
+        let waited = wait_for_schedule_window(|opens| {
+            self.show(format!("outside the schedule window, waiting until {}", opens.format("%H:%M")));
+        })
+        .await;
+        if waited {
+            // Whatever else used the token meanwhile is unknown
+            self.unchecked = RATE_LIMIT_CHECK_EVERY;
+        }
+    }
+
+    /// Replaces the status after the progress bar message, keeping whatever message the collector set
+    fn show(&mut self, status: String) {
+        let message = self.progress.message();
+        let base = message.strip_suffix(&self.status).unwrap_or(&message).to_string();
+        self.status = format!(" · {}", status);
+        self.progress.set_message(format!("{}{}", base, self.status));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    fn time(s: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(s, "%H:%M").unwrap()
+    }
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    #[test]
+    fn schedule_windows_are_parsed() {
+        let window = ScheduleWindow::parse(" 09:30 - 17:00 ").unwrap();
+        assert_eq!(window, ScheduleWindow { start: time("09:30"), end: time("17:00") });
+
+        assert!(ScheduleWindow::parse("22:00").is_err());
+        assert!(ScheduleWindow::parse("22:00-25:00").is_err());
+        assert!(ScheduleWindow::parse("night-morning").is_err());
+        assert!(ScheduleWindow::parse("06:00-06:00").is_err());
+    }
+
+    #[test]
+    fn schedule_windows_hold_their_start_but_not_their_end() {
+        let day = ScheduleWindow::parse("09:00-17:00").unwrap();
+        assert!(day.contains(time("09:00")));
+        assert!(day.contains(time("12:00")));
+        assert!(!day.contains(time("17:00")));
+        assert!(!day.contains(time("08:59")));
+        assert!(!day.contains(time("23:00")));
+    }
+
+    #[test]
+    fn schedule_windows_ending_before_they_start_wrap_midnight() {
+        let night = ScheduleWindow::parse("22:00-06:00").unwrap();
+        assert!(night.contains(time("22:00")));
+        assert!(night.contains(time("23:59")));
+        assert!(night.contains(time("00:00")));
+        assert!(night.contains(time("05:59")));
+        assert!(!night.contains(time("06:00")));
+        assert!(!night.contains(time("12:00")));
+        assert!(!night.contains(time("21:59")));
+
+        assert_eq!(night.next_opening(at("2025-03-29 23:00")), None);
+        assert_eq!(night.next_opening(at("2025-03-30 03:00")), None);
+        assert_eq!(night.next_opening(at("2025-03-29 12:00")), Some(at("2025-03-29 22:00")));
+    }
+
+    #[test]
+    fn closed_windows_open_today_or_tomorrow() {
+        let day = ScheduleWindow::parse("09:00-17:00").unwrap();
+        assert_eq!(day.next_opening(at("2025-03-29 12:00")), None);
+        assert_eq!(day.next_opening(at("2025-03-29 07:00")), Some(at("2025-03-29 09:00")));
+        assert_eq!(day.next_opening(at("2025-03-29 18:00")), Some(at("2025-03-30 09:00")));
+    }
+
+    #[test]
+    fn backoff_doubles_up_to_its_cap() {
+        for attempt in 1..=10 {
+            let base = RETRY_BASE_DELAY.saturating_mul(2u32.pow(attempt - 1)).min(RETRY_MAX_DELAY);
+            let delay = backoff(attempt);
+            assert!(delay >= base && delay <= base.mul_f64(1.5), "attempt {}: {:?}", attempt, delay);
+        }
+        assert!(backoff(2) > backoff(1));
+        assert!(backoff(100) <= RETRY_MAX_DELAY.mul_f64(1.5));
+    }
+
+    #[test]
+    fn jitter_varies() {
+        let delays: HashSet<Duration> = (0..10).map(|_| jitter(Duration::from_secs(10))).collect();
+        assert!(delays.len() > 1);
+    }
+
+    #[tokio::test]
+    async fn every_page_of_listings_fetched_together_waits_for_the_limiter() {
+        let mut limiter = RateLimiter::new(&ProgressBar::hidden());
+        limiter.limit = Some((100, 100, 0));
+        limiter.unchecked = 0;
+        let listing = |pages: usize| async move {
+            for _ in 0..pages {
+                wait_for_page().await;
+            }
+        };
+
+        let mut limiter = Some(limiter);
+        RateLimiter::pace_pages(&mut limiter, futures_util::future::join(listing(3), listing(2))).await;
+        let limiter = limiter.unwrap();
+        assert_eq!(limiter.limit, Some((95, 100, 0)));
+        assert_eq!(limiter.unchecked, 5);
+    }
+
+    #[tokio::test]
+    async fn requests_wait_for_the_schedule_window() {
+        let now = Local::now().time();
+        let closed = ScheduleWindow {
+            start: now + chrono::Duration::hours(1),
+            end: now + chrono::Duration::hours(2),
+        };
+        let context = Context { schedule_window: Some(closed), ..Context::default() };
+        let request = retry(|| async { Ok::<_, octocrab::Error>(()) });
+
+        let finished = context.scope(tokio::time::timeout(Duration::from_millis(100), request)).await;
+        assert!(finished.is_err());
+    }
+}
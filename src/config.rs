@@ -0,0 +1,431 @@
+/// Holds the user configuration loaded from `config.toml`
+///
+/// The file lives in the platform config directory (e.g. `~/.config/gdfm/config.toml`).
+/// A missing file is not an error; every setting has a default.
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::constants::{APP_NAME, CONFIG_FILE, DEFAULT_EVENT_TYPES, DEFAULT_GOVERNANCE_FILES, WEBHOOK_SECRET_ENV};
+
+#[derive(Debug, Error)]
+pub enum ConfigError {
+    #[error("Unable to read config file {path}: {source}")]
+    Read { path: PathBuf, source: std::io::Error },
+    #[error("Invalid config file {path}: {source}")]
+    Parse { path: PathBuf, source: toml::de::Error },
+    #[error("Alias \"{0}\" expands to nothing")]
+    EmptyAlias(String),
+    #[error("Alias \"{0}\" has a quote that is never closed")]
+    UnclosedQuote(String),
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// User-defined shortcuts, e.g. `weekly = "report conda/conda"`
+    pub aliases: HashMap<String, String>,
+    pub report: ReportConfig,
+    pub slo: SloConfig,
+    pub collect: CollectConfig,
+    pub email: EmailConfig,
+    pub publish: PublishConfig,
+    pub export: ExportConfig,
+    pub webhook: WebhookConfig,
+    /// Address of a GitHub Enterprise Server to collect from instead of github.com
+    pub github_url: Option<String>,
+    /// Address of the GitLab instance projects tracked with `--forge gitlab` live on
+    pub gitlab_url: Option<String>,
+    /// Settings for each API host, keyed by host name, e.g. `github.com`
+    pub hosts: HashMap<String, HostConfig>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CollectConfig {
+    /// Timeline event types (e.g. `ready_for_review`) to store. Defaults to
+    /// `DEFAULT_EVENT_TYPES` when not set.
+    pub event_types: Option<Vec<String>>,
+    /// Attempts made at a request failing with a server error or a secondary
+    /// rate limit. Defaults to `DEFAULT_MAX_ATTEMPTS`.
+    pub max_attempts: Option<u32>,
+    /// Whether `collect pulls` records how many checklist items in each pull
+    /// request description are ticked. Defaults to `true`.
+    pub checklists: Option<bool>,
+    /// Paths (e.g. `LICENSE`) whose history `collect governance` fetches.
+    /// Defaults to `DEFAULT_GOVERNANCE_FILES` when not set.
+    pub governance_files: Option<Vec<String>>,
+    /// Local hours collectors may make requests in, e.g. `22:00-06:00`;
+    /// `--schedule-window` overrides it. Requests are made at any time when not set.
+    pub schedule_window: Option<String>,
+    /// Whether collectors fail on items missing fields such as `created_at` or
+    /// the author instead of storing them empty; `--strict` turns it on for one
+    /// run. Defaults to `false`.
+    pub strict: Option<bool>,
+    /// Whether `collect pulls`, `events` and `reviews` also store the JSON of
+    /// every item in `raw_payloads`; `--keep-raw` turns it on for one run.
+    /// Defaults to `false`.
+    pub keep_raw: Option<bool>,
+}
+
+/// How hard collectors may use one API host, so a GitHub Enterprise Server
+/// with a weaker rate limit can be collected politely
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct HostConfig {
+    /// Most pull requests fetched at the same time, whatever `--concurrency` asks for
+    pub max_concurrency: Option<u32>,
+    /// Shortest pause between two pull requests or batches of them, in milliseconds
+    pub min_delay_ms: Option<u64>,
+    /// Overrides `collect.max_attempts` for this host
+    pub max_attempts: Option<u32>,
+    /// REST API version to ask for instead of `GITHUB_API_VERSION`; empty to
+    /// send no version at all
+    pub api_version: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ReportConfig {
+    /// Regex pull request titles should match; a `type` capture group is used
+    /// for the breakdown. Defaults to conventional commits.
+    pub title_pattern: Option<String>,
+    /// Regex matched against changed file paths to tell documentation apart
+    /// from code
+    pub docs_pattern: Option<String>,
+    /// What pull request size buckets measure, `lines` (the default) or `files`
+    pub size_measure: Option<SizeMeasure>,
+    /// Size buckets from smallest to largest. Defaults to `SizeBuckets::default`.
+    pub size_buckets: Option<Vec<SizeBucket>>,
+    /// Contributions someone needs before they are listed as a quiet contributor
+    pub absence_min_contributions: Option<u32>,
+    /// Months without any contribution before a contributor counts as quiet
+    pub absence_months: Option<u32>,
+    /// Secret the pseudonyms of anonymized reports are derived from; whoever
+    /// knows it can tell which login a pseudonym belongs to
+    pub anonymize_secret: Option<String>,
+    /// Minutes each maintainer action is assumed to take in the time investment estimate
+    pub time_weights: TimeWeightsConfig,
+    /// Ecosystems changed files are tagged with, checked before the built-in ones
+    pub ecosystems: Vec<EcosystemRule>,
+}
+
+/// Minutes per action, each defaulting to `TimeWeights::default`
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct TimeWeightsConfig {
+    pub review: Option<f64>,
+    pub review_comment: Option<f64>,
+    /// Labeling, assigning, closing and other triage events
+    pub triage: Option<f64>,
+    pub merge: Option<f64>,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeMeasure {
+    /// Lines added plus lines deleted
+    #[default]
+    Lines,
+    /// Number of files changed
+    Files,
+}
+
+/// A named pull request size bucket
+#[derive(Debug, Clone, Deserialize)]
+pub struct SizeBucket {
+    pub name: String,
+    /// Largest size in the bucket; the bucket without one takes everything larger
+    pub max: Option<u32>,
+}
+
+/// An ecosystem, such as `Packaging`, and the regex its file paths match
+#[derive(Debug, Clone, Deserialize)]
+pub struct EcosystemRule {
+    pub name: String,
+    pub pattern: String,
+}
+
+/// First response targets checked by the report, written like `24h` or `14d`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct SloConfig {
+    /// Target for items without a label listed in `labels`. Defaults to
+    /// `DEFAULT_RESPONSE_TARGET`.
+    pub response: Option<String>,
+    /// Targets for items with a given label, keyed by label name
+    pub labels: HashMap<String, String>,
+}
+
+/// Where `gdfm report --publish <branch>` commits the report
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PublishConfig {
+    /// Repository (`owner/name`) holding the site; defaults to the reported repository
+    pub repository: Option<String>,
+    /// Path of the report in the branch, where `{owner}` and `{name}` stand for
+    /// the reported repository. Defaults to `DEFAULT_PUBLISH_PATH`.
+    pub path: Option<String>,
+}
+
+/// Settings of `gdfm export`
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct ExportConfig {
+    /// Whether `collect` and `sync` commands export the database to the Postgres
+    /// database `DATABASE_URL` points at once they finish. Defaults to `false`,
+    /// which leaves exporting to `gdfm export postgres`.
+    pub postgres_after_collect: Option<bool>,
+}
+
+/// SMTP settings used by `gdfm report --email` and `gdfm sync --email`
+///
+/// The password is read from the `GDFM_SMTP_PASSWORD` environment variable so
+/// it doesn't have to be stored in the config file.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct EmailConfig {
+    pub smtp_host: Option<String>,
+    /// Defaults to 587 (STARTTLS); port 465 uses implicit TLS instead
+    pub smtp_port: Option<u16>,
+    pub username: Option<String>,
+    pub from: Option<String>,
+    pub recipients: Vec<String>,
+}
+
+/// Secrets used to verify webhook deliveries received by `gdfm listen`
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    /// Secret for repositories without their own entry in `secrets`
+    pub secret: Option<String>,
+    /// Per repository secrets, keyed by `owner/name`
+    pub secrets: HashMap<String, String>,
+}
+
+/// Returns the path of the config file, whether or not it exists
+pub fn get_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_NAME).join(CONFIG_FILE))
+}
+
+impl CollectConfig {
+    /// The timeline event types `collect events` should store
+    pub fn event_types(&self) -> Vec<String> {
+        match &self.event_types {
+            Some(event_types) => event_types.clone(),
+            None => DEFAULT_EVENT_TYPES.iter().map(|event| event.to_string()).collect(),
+        }
+    }
+
+    /// The paths `collect governance` should fetch the history of
+    pub fn governance_files(&self) -> Vec<String> {
+        match &self.governance_files {
+            Some(paths) => paths.clone(),
+            None => DEFAULT_GOVERNANCE_FILES.iter().map(|path| path.to_string()).collect(),
+        }
+    }
+}
+
+impl Config {
+    /// The settings for an API host, or the defaults when it has none
+    pub fn host(&self, host: &str) -> HostConfig {
+        self.hosts.get(host).cloned().unwrap_or_default()
+    }
+}
+
+impl WebhookConfig {
+    /// The secret for a repository, falling back to the shared secret and then
+    /// to the `GDFM_WEBHOOK_SECRET` environment variable
+    pub fn secret_for(&self, repository: &str) -> Option<String> {
+        self.secrets
+            .get(repository)
+            .or(self.secret.as_ref())
+            .cloned()
+            .or_else(|| std::env::var(WEBHOOK_SECRET_ENV).ok())
+    }
+}
+
+impl Config {
+    pub fn load() -> Result<Self, ConfigError> {
+        match get_config_path() {
+            Some(path) if path.exists() => Self::from_file(path),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    pub fn from_file(path: PathBuf) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(&path)
+            .map_err(|source| ConfigError::Read { path: path.clone(), source })?;
+
+        toml::from_str(&contents).map_err(|source| ConfigError::Parse { path, source })
+    }
+
+    /// Replaces the subcommand with its alias expansion if one is defined
+    ///
+    /// The subcommand is the first argument that isn't a global option, such as
+    /// `--project work`; `value_options` are the global options followed by a
+    /// value of their own. Expansions are split into arguments the way a shell
+    /// would, so quoted arguments may hold spaces. Built-in subcommands always
+    /// win over aliases so an alias can never shadow a real command.
+    pub fn expand_aliases(
+        &self,
+        args: Vec<String>,
+        builtins: &[&str],
+        value_options: &[&str],
+    ) -> Result<Vec<String>, ConfigError> {
+        let mut index = 1;
+        while let Some(arg) = args.get(index) {
+            if arg == "--" || !arg.starts_with('-') {
+                break;
+            }
+            index += if value_options.contains(&arg.as_str()) { 2 } else { 1 };
+        }
+        let Some(name) = args.get(index).filter(|arg| *arg != "--") else {
+            return Ok(args);
+        };
+
+        if builtins.contains(&name.as_str()) {
+            return Ok(args);
+        }
+
+        match self.aliases.get(name) {
+            Some(expansion) => {
+                let expanded = split_words(expansion).ok_or_else(|| ConfigError::UnclosedQuote(name.clone()))?;
+                if expanded.is_empty() {
+                    return Err(ConfigError::EmptyAlias(name.clone()));
+                }
+
+                let mut new_args = args[..index].to_vec();
+                new_args.extend(expanded);
+                new_args.extend(args.into_iter().skip(index + 1));
+                Ok(new_args)
+            }
+            None => Ok(args),
+        }
+    }
+}
+
+/// Splits an alias into arguments as a POSIX shell would, minus expansions:
+/// whitespace separates them, single quotes keep everything as it is, double
+/// quotes keep all but backslash escapes of `"`, `\`, `$` and `` ` ``, and a
+/// backslash outside quotes keeps the next character
+///
+/// Returns `None` when a quote is never closed.
+fn split_words(s: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    // The word being read, if one has started; `""` starts an empty one
+    let mut word: Option<String> = None;
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => words.extend(word.take()),
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        c => word.push(c),
+                    }
+                }
+            }
+            '"' => {
+                let word = word.get_or_insert_with(String::new);
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => match chars.next()? {
+                            c @ ('"' | '\\' | '$' | '`') => word.push(c),
+                            '\n' => {}
+                            c => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                        },
+                        c => word.push(c),
+                    }
+                }
+            }
+            '\\' => {
+                let word = word.get_or_insert_with(String::new);
+                match chars.next() {
+                    Some('\n') => {}
+                    Some(c) => word.push(c),
+                    None => word.push('\\'),
+                }
+            }
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+
+    Some(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    fn expand(line: &[&str]) -> Result<Vec<String>, ConfigError> {
+        let config = Config {
+            aliases: HashMap::from([
+                ("weekly".to_string(), "query 'SELECT COUNT(*) FROM pulls' --format csv".to_string()),
+                ("broken".to_string(), "report \"conda/conda".to_string()),
+            ]),
+            ..Config::default()
+        };
+
+        config.expand_aliases(args(line), &["report", "query"], &["--project", "--github-url"])
+    }
+
+    #[test]
+    fn aliases_are_split_like_a_shell_would() {
+        assert_eq!(split_words("a  b\tc").unwrap(), args(&["a", "b", "c"]));
+        assert_eq!(split_words(r#"--title "Weekly \"report\"" it's\ here"#), None);
+        assert_eq!(split_words(r#"--title "Weekly \"report\"" it\'s\ here"#).unwrap(), args(&[
+            "--title",
+            "Weekly \"report\"",
+            "it's here"
+        ]));
+        assert_eq!(split_words(r#"'$HOME \n' "" x''y"#).unwrap(), args(&["$HOME \\n", "", "xy"]));
+        assert_eq!(split_words("  ").unwrap(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn aliases_are_found_after_global_options() {
+        let weekly = ["query", "SELECT COUNT(*) FROM pulls", "--format", "csv"];
+        let expanded = |before: &[&str], after: &[&str]| {
+            let mut line = vec!["gdfm"];
+            line.extend(before);
+            line.extend(weekly);
+            line.extend(after);
+            args(&line)
+        };
+
+        assert_eq!(expand(&["gdfm", "weekly"]).unwrap(), expanded(&[], &[]));
+        assert_eq!(expand(&["gdfm", "weekly", "--project", "work"]).unwrap(), expanded(&[], &["--project", "work"]));
+        assert_eq!(
+            expand(&["gdfm", "--project", "work", "--no-color", "weekly"]).unwrap(),
+            expanded(&["--project", "work", "--no-color"], &[])
+        );
+        assert_eq!(expand(&["gdfm", "--project=work", "weekly"]).unwrap(), expanded(&["--project=work"], &[]));
+    }
+
+    #[test]
+    fn only_the_subcommand_is_expanded() {
+        let unchanged = |line: &[&str]| assert_eq!(expand(line).unwrap(), args(line));
+
+        unchanged(&["gdfm"]);
+        unchanged(&["gdfm", "--project", "weekly", "report"]);
+        unchanged(&["gdfm", "query", "weekly"]);
+        unchanged(&["gdfm", "--", "weekly"]);
+        unchanged(&["gdfm", "--no-color"]);
+        assert!(matches!(expand(&["gdfm", "broken"]), Err(ConfigError::UnclosedQuote(_))));
+    }
+}
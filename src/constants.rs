@@ -6,7 +6,121 @@ pub static APP_NAME: &str = "gdfm";
 pub static CLI_ARGS_REPO: &str = "REPOSITORY";
 pub static CLI_ARGS_PATH: &str = "PATH";
 pub static CLI_ARGS_YES: &str = "yes";
+pub static CLI_ARGS_DRY_RUN: &str = "dry-run";
 pub static CLI_ARGS_NUMBER: &str = "number";
+pub static CLI_ARGS_OUTPUT: &str = "output";
+pub static CLI_ARGS_LOCALE: &str = "locale";
+pub static CLI_ARGS_INTERVAL: &str = "interval";
+pub static CLI_ARGS_EVERY: &str = "every";
+pub static CLI_ARGS_ALL_EVENTS: &str = "all-events";
+pub static CLI_ARGS_EMAIL: &str = "email";
+pub static CLI_ARGS_FORMAT: &str = "format";
+pub static CLI_ARGS_REVIEWERS: &str = "reviewers";
+pub static CLI_ARGS_DAYS: &str = "days";
+pub static CLI_ARGS_PORT: &str = "port";
+pub static CLI_ARGS_BIND: &str = "bind";
+pub static CLI_ARGS_PUBLIC: &str = "public";
+pub static CLI_ARGS_HOOK: &str = "hook";
+pub static CLI_ARGS_DELIVERY: &str = "DELIVERY";
+pub static CLI_ARGS_LIMIT: &str = "limit";
+pub static CLI_ARGS_JOBS: &str = "jobs";
+pub static CLI_ARGS_ARCHIVE: &str = "archive";
+pub static CLI_ARGS_MISSING_ONLY: &str = "missing-only";
+pub static CLI_ARGS_NOTES: &str = "notes";
+pub static CLI_ARGS_APPENDIX: &str = "appendix";
+pub static CLI_ARGS_STATE: &str = "state";
+pub static CLI_ARGS_SINCE: &str = "since";
+pub static CLI_ARGS_FULL: &str = "full";
+pub static CLI_ARGS_RESUME: &str = "resume";
+pub static CLI_ARGS_COLLECT: &str = "collect";
+pub static CLI_ARGS_CONCURRENCY: &str = "concurrency";
+pub static CLI_ARGS_BUCKET: &str = "bucket";
+pub static CLI_ARGS_THROTTLE_MS: &str = "throttle-ms";
+pub static CLI_ARGS_SCHEDULE_WINDOW: &str = "schedule-window";
+pub static CLI_ARGS_RANGE: &str = "range";
+pub static CLI_ARGS_SHARD: &str = "shard";
+pub static CLI_ARGS_STRICT: &str = "strict";
+pub static CLI_ARGS_KEEP_RAW: &str = "keep-raw";
+pub static CLI_ARGS_TABLE: &str = "table";
+pub static CLI_ARGS_ALL_TABLES: &str = "all";
+pub static CLI_ARGS_TOPIC: &str = "topic";
+pub static CLI_ARGS_VISIBILITY: &str = "visibility";
+pub static CLI_ARGS_GITHUB_URL: &str = "github-url";
+pub static CLI_ARGS_PROJECT: &str = "project";
+pub static CLI_ARGS_NO_COLOR: &str = "no-color";
+pub static CLI_ARGS_BUILTIN: &str = "builtin";
+pub static CLI_ARGS_SECRET: &str = "secret";
+pub static CLI_ARGS_FORGE: &str = "forge";
+pub static CLI_ARGS_REFRESH: &str = "refresh";
+pub static CLI_ARGS_SQL: &str = "SQL";
+pub static CLI_ARGS_PUBLISH: &str = "publish";
+pub static CLI_ARGS_FIX: &str = "fix";
+
+/// GitHub REST API host every collector talks to
+pub static GITHUB_API_URL: &str = "https://api.github.com";
+
+/// REST API version sent in `X-GitHub-Api-Version` unless the config picks another
+pub static GITHUB_API_VERSION: &str = "2022-11-28";
+
+/// GitLab instance used when `gitlab_url` isn't set in the config
+pub static GITLAB_URL: &str = "https://gitlab.com";
+pub static GITLAB_TOKEN_ENV: &str = "GITLAB_TOKEN";
+
+/// Config constants
+pub static CONFIG_FILE: &str = "config.toml";
+
+/// Environment variable holding the SMTP password for `report --email` and `sync --email`
+pub static SMTP_PASSWORD_ENV: &str = "GDFM_SMTP_PASSWORD";
+
+/// Environment variable holding the webhook secret used when none is configured
+pub static WEBHOOK_SECRET_ENV: &str = "GDFM_WEBHOOK_SECRET";
+
+/// Environment variable naming a Postgres database to publish collected data to
+pub static DATABASE_URL_ENV: &str = "DATABASE_URL";
+
+/// Paths whose history `collect governance` fetches unless configured otherwise
+pub static DEFAULT_GOVERNANCE_FILES: &[&str] = &[
+    "LICENSE",
+    "LICENSE.md",
+    "LICENSE.txt",
+    "COPYING",
+    "GOVERNANCE.md",
+    "GOVERNANCE",
+    "MAINTAINERS",
+    "MAINTAINERS.md",
+    "CODEOWNERS",
+    ".github/CODEOWNERS",
+    "CODE_OF_CONDUCT.md",
+];
+
+/// Timeline event types stored by `collect events` unless configured otherwise.
+/// Leaves out noise like `subscribed` and `mentioned` that bloats the events table.
+pub static DEFAULT_EVENT_TYPES: &[&str] = &[
+    "assigned",
+    "closed",
+    "commented",
+    "committed",
+    "connected",
+    "convert_to_draft",
+    "cross-referenced",
+    "demilestoned",
+    "head_ref_force_pushed",
+    "labeled",
+    "locked",
+    "merged",
+    "milestoned",
+    "ready_for_review",
+    "renamed",
+    "reopened",
+    "review_dismissed",
+    "review_request_removed",
+    "review_requested",
+    "reviewed",
+    "transferred",
+    "unassigned",
+    "unlabeled",
+    "unlocked",
+];
 
 /// Database constants
 pub static DB_FILE: &str = "gdfm.db";
@@ -0,0 +1,185 @@
+/// Holds the `Context` a command runs with
+///
+/// The project, the API host and how hard to use it, the schedule window and
+/// the shard are settings of one command rather than of the process, so
+/// commands run side by side from a library can each have their own. A
+/// context is set for a task with [`Context::scope`], the way
+/// [`crate::cli::collect::PROGRESS`] is, and everything the command runs reads
+/// it from there. Tasks spawned along the way need to be scoped again.
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, AtomicU32};
+use std::sync::{Arc, LazyLock};
+
+use miette::{miette, Result};
+use tokio::time::Duration;
+
+use crate::cli::collect::Shard;
+use crate::client::{ScheduleWindow, DEFAULT_MAX_ATTEMPTS};
+use crate::config::{Config, HostConfig};
+use crate::constants::{GITHUB_API_URL, GITHUB_API_VERSION};
+
+tokio::task_local! {
+    static CONTEXT: Context;
+}
+
+/// Context of code running outside of [`Context::scope`]
+static DEFAULT: LazyLock<Context> = LazyLock::new(Context::default);
+
+/// Settings of one command, such as `--project` and `--github-url`
+#[derive(Debug, Clone)]
+pub struct Context {
+    /// Project whose database is used instead of the default one
+    pub project: Option<String>,
+    /// Root of the REST API every client talks to
+    pub api_url: String,
+    /// REST API version sent with every request, if any
+    pub api_version: Option<String>,
+    /// Attempts made at a request before giving up
+    pub max_attempts: u32,
+    /// Shortest pause the rate limiter makes
+    pub min_delay: Duration,
+    /// Most pull requests fetched at the same time, whatever `--concurrency` asks for
+    pub max_concurrency: Option<u32>,
+    /// Local hours collectors may make requests in, when limited to some
+    pub schedule_window: Option<ScheduleWindow>,
+    /// Share of the pull requests collectors are limited to, when collecting with others
+    pub shard: Option<Shard>,
+    /// What the command found out about the API host, shared by every clone
+    pub(crate) host_state: Arc<HostState>,
+}
+
+/// What a command learns about its API host while it runs
+#[derive(Debug, Default)]
+pub(crate) struct HostState {
+    /// Secondary rate limits hit since the throttle last eased off
+    pub(crate) secondary_limits_hit: AtomicU32,
+    /// Set for servers that predate `X-GitHub-Api-Version`
+    pub(crate) api_version_rejected: AtomicBool,
+}
+
+impl Default for Context {
+    fn default() -> Self {
+        Self {
+            project: None,
+            api_url: GITHUB_API_URL.to_string(),
+            api_version: Some(GITHUB_API_VERSION.to_string()),
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            min_delay: Duration::ZERO,
+            max_concurrency: None,
+            schedule_window: None,
+            shard: None,
+            host_state: Arc::default(),
+        }
+    }
+}
+
+impl Context {
+    /// The settings `config` has for the API host, which is `github_url` when
+    /// given and otherwise the `github_url` of the config or github.com
+    pub fn from_config(config: &Config, github_url: Option<&str>) -> Result<Self> {
+        let mut context = match github_url.or(config.github_url.as_deref()) {
+            Some(url) => Self::default().with_github_url(url),
+            None => Self::default(),
+        };
+        let host = config.host(&context.api_host());
+        context = context.with_host_config(&host, config.collect.max_attempts.unwrap_or(DEFAULT_MAX_ATTEMPTS));
+        context.schedule_window = config
+            .collect
+            .schedule_window
+            .as_deref()
+            .map(|window| {
+                ScheduleWindow::parse(window).map_err(|err| miette!("Invalid collect.schedule_window in config: {}", err))
+            })
+            .transpose()?;
+
+        Ok(context)
+    }
+
+    /// Keeps the data in the database of a project instead of the default one
+    pub fn with_project(mut self, name: &str) -> Self {
+        self.project = Some(name.to_string());
+        self
+    }
+
+    /// Talks to a GitHub Enterprise Server instead of github.com
+    ///
+    /// Takes the server's address, e.g. `https://github.example.com`, or its REST
+    /// API root, `https://github.example.com/api/v3`. github.com itself is left as is.
+    pub fn with_github_url(mut self, url: &str) -> Self {
+        let url = url.trim_end_matches('/');
+        let host = url.split("://").nth(1).unwrap_or(url).split('/').next().unwrap_or_default();
+        if host == "github.com" || host == "api.github.com" {
+            return self;
+        }
+
+        self.api_url = if url.ends_with("/api/v3") {
+            url.to_string()
+        } else {
+            format!("{}/api/v3", url)
+        };
+        self
+    }
+
+    /// Applies the config for the API host: attempts per request, shortest pause,
+    /// most pull requests fetched at the same time and the REST API version
+    pub fn with_host_config(mut self, host: &HostConfig, default_max_attempts: u32) -> Self {
+        if let Some(version) = &host.api_version {
+            self.api_version = (!version.is_empty()).then(|| version.clone());
+        }
+        self.max_attempts = host.max_attempts.unwrap_or(default_max_attempts).max(1);
+        self.min_delay = Duration::from_millis(host.min_delay_ms.unwrap_or(0));
+        self.max_concurrency = host.max_concurrency.filter(|max| *max > 0);
+        self
+    }
+
+    /// Host of the API, as used for `[hosts]` in the config, e.g. `github.com`
+    pub fn api_host(&self) -> String {
+        let host = self
+            .api_url
+            .split("://")
+            .last()
+            .unwrap_or_default()
+            .split('/')
+            .next()
+            .unwrap_or_default();
+
+        host.strip_prefix("api.").unwrap_or(host).to_string()
+    }
+
+    /// Runs `future` with this context
+    pub async fn scope<F: Future>(self, future: F) -> F::Output {
+        CONTEXT.scope(self, future).await
+    }
+
+    /// Context of the running task, or the defaults outside of [`Context::scope`]
+    pub fn current() -> Self {
+        CONTEXT.try_with(Self::clone).unwrap_or_else(|_| DEFAULT.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enterprise_servers_get_their_api_root() {
+        let context = Context::default().with_github_url("https://github.example.com/");
+        assert_eq!(context.api_url, "https://github.example.com/api/v3");
+        assert_eq!(context.api_host(), "github.example.com");
+
+        let context = Context::default().with_github_url("https://github.com");
+        assert_eq!(context.api_url, GITHUB_API_URL);
+        assert_eq!(context.api_host(), "github.com");
+    }
+
+    #[tokio::test]
+    async fn each_scope_sees_its_own_context() {
+        let project = |name: &str| Context::default().with_project(name);
+        let read = || async { Context::current().project };
+
+        let (first, second) = tokio::join!(project("first").scope(read()), project("second").scope(read()));
+        assert_eq!(first.as_deref(), Some("first"));
+        assert_eq!(second.as_deref(), Some("second"));
+        assert_eq!(Context::current().project, None);
+    }
+}
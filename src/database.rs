@@ -1,72 +1,993 @@
 #![allow(dead_code)]
 /// Holds functions and methods used for database operations
 // use rusqlite::{Connection, Error as RusqliteError};
-
 use std::fs::create_dir_all;
+use chrono::{DateTime, Datelike, NaiveDateTime, Utc};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 use sqlx::migrate::MigrateDatabase;
-use sqlx::sqlite::Sqlite;
-use sqlx::Pool;
+use sqlx::sqlite::{Sqlite, SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{Column, Pool, Row, TypeInfo, ValueRef};
 
 use crate::constants::{DB_FILE, APP_NAME};
+use crate::context::Context;
 
-#[derive(Debug, sqlx::FromRow)]
-pub struct Repository {
-    pub id: u32,
-    pub owner: String,
-    pub name: String,
+/// Documentation of a model's table, taken from the doc comments in this file
+#[derive(Debug)]
+pub struct TableDoc {
+    pub table: &'static str,
+    pub description: &'static [&'static str],
+    pub columns: &'static [ColumnDoc],
 }
 
-#[derive(Debug, sqlx::FromRow)]
-pub struct PullRequest {
-    pub id: u32,
-    pub repo_id: u32,
-    pub number: u32,
-    pub title: String,
-    pub state: String,
-    pub created_at: String,
-    pub updated_at: String,
-    pub closed_at: Option<String>,
-    pub merged_at: Option<String>,
-    pub author: String,
-    pub author_association: String
+/// Documentation of a single column, taken from the doc comment on its field
+#[derive(Debug)]
+pub struct ColumnDoc {
+    pub name: &'static str,
+    pub description: &'static [&'static str],
 }
 
-#[derive(Debug, sqlx::FromRow)]
-pub struct PullRequestEvent {
-    pub id: u32,
-    pub issue_pull_id: u32,
-    pub event_type: String,
-    pub actor: String,
-    pub created_at: String
+/// Joins the lines of a doc comment into a single sentence-like string
+pub fn doc_text(lines: &[&str]) -> String {
+    lines.iter().map(|line| line.trim()).filter(|line| !line.is_empty()).collect::<Vec<_>>().join(" ")
 }
 
-#[derive(Debug, sqlx::FromRow)]
-pub struct PullRequestReview {
-    pub id: u32,
-    pub issue_pull_id: u32,
-    pub reviewer: String,
-    pub state: String,
-    pub author_association: String,
-    pub submitted_at: String
+/// Defines a model struct and keeps its doc comments around as a [`TableDoc`]
+///
+/// This is what `gdfm schema` prints, so the documentation of the database
+/// can't drift away from the structs used to read it.
+macro_rules! model {
+    (
+        $(#[doc = $doc:literal])*
+        #[table = $table:literal]
+        pub struct $name:ident {
+            $(
+                $(#[doc = $field_doc:literal])*
+                pub $field:ident: $ty:ty
+            ),* $(,)?
+        }
+    ) => {
+        $(#[doc = $doc])*
+        #[derive(Debug, sqlx::FromRow)]
+        pub struct $name {
+            $(
+                $(#[doc = $field_doc])*
+                pub $field: $ty,
+            )*
+        }
+
+        impl $name {
+            pub const SCHEMA: TableDoc = TableDoc {
+                table: $table,
+                description: &[$($doc),*],
+                columns: &[$(ColumnDoc { name: stringify!($field), description: &[$($field_doc),*] }),*],
+            };
+        }
+    };
+}
+
+model! {
+    /// A repository registered with `gdfm init`
+    #[table = "repositories"]
+    pub struct Repository {
+        pub id: u32,
+        /// User or organization owning the repository
+        pub owner: String,
+        /// Repository name without the owner
+        pub name: String,
+        /// Archived repositories keep their data but are left out of reports
+        pub archived: bool,
+        /// Where the repository is hosted, `github` or `gitlab`
+        pub forge: String,
+    }
+}
+
+model! {
+    /// Someone with push access to at least one tracked repository
+    #[table = "maintainers"]
+    pub struct Maintainer {
+        pub id: u32,
+        /// GitHub login
+        pub login: String,
+    }
+}
+
+model! {
+    /// Who had push access to a repository when `gdfm collect maintainers` last ran
+    #[table = "repository_maintainers"]
+    pub struct RepositoryMaintainer {
+        pub id: u32,
+        pub repo_id: u32,
+        /// Id in `maintainers`
+        pub maintainer_id: u32,
+        /// Highest permission held: `admin`, `maintain` or `push`
+        pub permission: String,
+        /// Comma-separated slugs of the teams granting push access; empty when only granted directly
+        pub teams: String,
+        /// NULL for maintainers collected before gdfm recorded when
+        pub collected_at: Option<DateTime<Utc>>,
+    }
+}
+
+model! {
+    /// A pull request or an issue; both live in the same table
+    #[table = "pulls"]
+    pub struct PullRequest {
+        /// GitHub id of a pull request, or a local id below zero for issues,
+        /// whose ids GitHub draws from another sequence, and for pull requests
+        /// read through another forge
+        pub id: i64,
+        pub repo_id: u32,
+        /// Number shown on GitHub, unique per repository
+        pub number: u32,
+        pub title: String,
+        /// `open` or `closed`
+        pub state: String,
+        /// NULL when GitHub gave none, which only permissive collections store
+        pub created_at: Option<DateTime<Utc>>,
+        pub updated_at: DateTime<Utc>,
+        /// NULL while open
+        pub closed_at: Option<DateTime<Utc>>,
+        /// NULL for issues and for pull requests closed without merging
+        pub merged_at: Option<DateTime<Utc>>,
+        /// GitHub login of whoever opened it
+        pub author: String,
+        /// The author's relationship to the repository, e.g. `MEMBER` or `CONTRIBUTOR`
+        pub author_association: String,
+        /// Issues are stored in the same table as pull requests
+        pub is_issue: bool,
+        /// Lines added; empty for issues and until `gdfm collect pull-details` has run
+        pub additions: Option<u32>,
+        /// Lines deleted
+        pub deletions: Option<u32>,
+        /// Number of files changed
+        pub changed_files: Option<u32>,
+        /// Number of commits on the branch
+        pub commits: Option<u32>,
+        /// Branch the pull request targets, e.g. `main`; empty for issues
+        pub base_ref: String,
+        /// Branch the changes come from
+        pub head_ref: String,
+        /// Login of whoever merged it; only `gdfm collect pull-details` and webhooks know
+        pub merged_by: String,
+        /// Commit created by the merge, or that would be while the pull request is open
+        pub merge_commit_sha: String,
+        /// Id on the forge it was read from; empty for GitHub pull requests, whose id is `id`
+        pub forge_id: Option<i64>,
+        /// When `gdfm collect events` last went through its timeline; NULL until
+        /// it has, even when webhooks stored some of its events
+        pub events_collected_at: Option<DateTime<Utc>>,
+    }
+}
+
+model! {
+    /// A timeline event (labeled, merged, review_requested, ...) on a pull request or issue
+    #[table = "issue_pull_events"]
+    pub struct PullRequestEvent {
+        /// GitHub event id when it has one, otherwise a local row id; below zero
+        /// for events read through another forge
+        pub id: i64,
+        pub issue_pull_id: i64,
+        /// Timeline event type in snake case, e.g. `ready_for_review`
+        pub event_type: String,
+        /// GitHub login of whoever triggered the event
+        pub actor: String,
+        pub author_association: String,
+        /// NULL for events GitHub gives no time for, such as commits
+        pub created_at: Option<DateTime<Utc>>,
+        /// Login of the reviewer asked for or taken off a `review_requested` or
+        /// `review_request_removed` event; empty for other events and team requests
+        pub requested_reviewer: String,
+    }
+}
+
+model! {
+    /// A submitted pull request review
+    #[table = "issue_pull_reviews"]
+    pub struct PullRequestReview {
+        pub id: u32,
+        pub issue_pull_id: i64,
+        /// GitHub review id, used to match inline review comments
+        pub github_id: Option<i64>,
+        pub reviewer: String,
+        /// E.g. `Approved`, `ChangesRequested` or `Commented`
+        pub state: String,
+        pub author_association: String,
+        /// NULL for reviews that are still pending
+        pub submitted_at: Option<DateTime<Utc>>,
+    }
+}
+
+model! {
+    /// Snapshot of the community demand signal for a single open issue
+    #[table = "issue_demand"]
+    pub struct IssueDemand {
+        pub id: u32,
+        pub repo_id: u32,
+        /// Issue number
+        pub number: u32,
+        pub title: String,
+        pub comments: u32,
+        /// Number of 👍 reactions
+        pub plus_one: u32,
+        /// 👍, ❤️, 🎉, 🚀 and 👀 reactions combined
+        pub positive_reactions: u32,
+        /// Comments plus positive reactions, with 👍 weighted double
+        pub demand_score: u32,
+        /// When the snapshot was taken
+        pub collected_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// A package a repository depends on according to GitHub's dependency graph
+    #[table = "dependencies"]
+    pub struct Dependency {
+        pub id: u32,
+        pub repo_id: u32,
+        /// Package manager, e.g. `pypi` or `npm`
+        pub ecosystem: String,
+        pub name: String,
+        pub version: String,
+    }
+}
+
+model! {
+    /// An issue a pull request claims to close via a closing keyword in its body
+    #[table = "linked_issues"]
+    pub struct LinkedIssue {
+        pub id: u32,
+        pub issue_pull_id: i64,
+        /// Number of the issue in the same repository
+        pub issue_number: u32,
+    }
+}
+
+model! {
+    /// Task list checkboxes (`- [ ]` and `- [x]`) in a pull request description
+    #[table = "pull_checklists"]
+    pub struct PullRequestChecklist {
+        pub id: u32,
+        pub issue_pull_id: i64,
+        /// Checkboxes in the description
+        pub total: u32,
+        /// Checkboxes ticked
+        pub completed: u32,
+    }
+}
+
+model! {
+    /// A repository-level issue event such as an issue being transferred elsewhere
+    #[table = "repository_events"]
+    pub struct RepositoryEvent {
+        /// GitHub event id
+        pub id: i64,
+        pub repo_id: u32,
+        pub issue_number: u32,
+        pub event_type: String,
+        pub actor: String,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// A conversation comment on a pull request or issue
+    #[table = "issue_pull_comments"]
+    pub struct PullRequestComment {
+        /// GitHub comment id, or a local id below zero for comments read through another forge
+        pub id: i64,
+        pub issue_pull_id: i64,
+        pub author: String,
+        pub author_association: String,
+        /// Length of the comment in characters; the text itself isn't stored
+        pub body_length: u32,
+        pub created_at: DateTime<Utc>,
+        /// Id on the forge it was read from; empty for GitHub pull requests, whose id is `id`
+        pub forge_id: Option<i64>,
+    }
+}
+
+model! {
+    /// A line-level comment left on the diff of a pull request
+    #[table = "issue_pull_review_comments"]
+    pub struct PullRequestReviewComment {
+        /// GitHub comment id
+        pub id: i64,
+        pub issue_pull_id: i64,
+        /// GitHub id of the review the comment belongs to, see `issue_pull_reviews.github_id`
+        pub review_id: Option<i64>,
+        /// Id of the comment this one replies to, empty for the start of a thread
+        pub in_reply_to_id: Option<i64>,
+        pub author: String,
+        pub author_association: String,
+        /// File the comment was left on
+        pub path: String,
+        /// Length of the comment in characters; the text itself isn't stored
+        pub body_length: u32,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// A commit, and whether it can be reached from the default branch
+    #[table = "commits"]
+    pub struct Commit {
+        pub id: u32,
+        pub repo_id: u32,
+        pub sha: String,
+        /// GitHub login of the author, or the git author name if it isn't linked to an account
+        pub author: String,
+        /// GitHub login of the committer, or the git committer name
+        pub committer: String,
+        /// NULL when the commit has no author signature
+        pub authored_at: Option<DateTime<Utc>>,
+        /// NULL when the commit has no committer signature
+        pub committed_at: Option<DateTime<Utc>>,
+        /// False for commits only found on other branches
+        pub on_default_branch: bool,
+    }
+}
+
+model! {
+    /// A commit changing a license or governance file, e.g. `LICENSE` or `MAINTAINERS`
+    #[table = "governance_changes"]
+    pub struct GovernanceChange {
+        pub id: u32,
+        pub repo_id: u32,
+        /// Path of the file as configured in `collect.governance_files`
+        pub path: String,
+        pub sha: String,
+        /// GitHub login of the author, or the git author name if it isn't linked to an account
+        pub author: String,
+        pub committed_at: Option<DateTime<Utc>>,
+        /// First line of the commit message
+        pub summary: String,
+    }
+}
+
+model! {
+    /// A published (or draft) GitHub release
+    #[table = "releases"]
+    pub struct Release {
+        /// GitHub release id
+        pub id: i64,
+        pub repo_id: u32,
+        pub tag_name: String,
+        /// Release title, empty when only the tag is used
+        pub name: String,
+        pub author: String,
+        pub prerelease: bool,
+        pub draft: bool,
+        /// NULL for drafts, which aren't published yet
+        pub published_at: Option<DateTime<Utc>>,
+    }
+}
+
+model! {
+    /// A deployment of a commit to an environment
+    #[table = "deployments"]
+    pub struct Deployment {
+        /// GitHub deployment id
+        pub id: i64,
+        pub repo_id: u32,
+        pub sha: String,
+        /// Branch, tag or commit that was deployed
+        pub git_ref: String,
+        pub environment: String,
+        pub creator: String,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// A status a deployment went through, such as `in_progress`, `success` or `failure`
+    #[table = "deployment_statuses"]
+    pub struct DeploymentStatus {
+        /// GitHub deployment status id
+        pub id: i64,
+        pub deployment_id: i64,
+        pub state: String,
+        pub creator: String,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// A user currently starring a repository; only holds the latest snapshot
+    #[table = "stargazers"]
+    pub struct Stargazer {
+        pub id: u32,
+        pub repo_id: u32,
+        /// GitHub login of the stargazer
+        pub login: String,
+        pub starred_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// A fork of a repository
+    #[table = "forks"]
+    pub struct Fork {
+        /// GitHub id of the fork
+        pub id: i64,
+        pub repo_id: u32,
+        /// User or organization owning the fork
+        pub owner: String,
+        /// Fork name, which can differ from the upstream name
+        pub name: String,
+        /// NULL when GitHub leaves it out
+        pub created_at: Option<DateTime<Utc>>,
+    }
+}
+
+model! {
+    /// A webhook delivery received by `gdfm listen`
+    #[table = "webhook_deliveries"]
+    pub struct WebhookDelivery {
+        pub id: u32,
+        /// Value of the `X-GitHub-Delivery` header, used to drop replayed deliveries
+        pub delivery_id: String,
+        /// Value of the `X-GitHub-Hook-ID` header, needed to ask GitHub for a redelivery
+        pub hook_id: Option<i64>,
+        /// `owner/name` of the repository the delivery is about
+        pub repository: String,
+        /// Value of the `X-GitHub-Event` header, e.g. `pull_request`
+        pub event: String,
+        /// The `action` field of the payload, e.g. `opened`
+        pub action: String,
+        /// False only for deliveries with a bad signature that older versions kept without their payload
+        pub signature_valid: bool,
+        pub received_at: DateTime<Utc>,
+        /// Raw JSON body of the delivery
+        pub payload: String,
+    }
+}
+
+model! {
+    /// A contributor and their total commit count; only holds the latest snapshot
+    #[table = "contributors"]
+    pub struct Contributor {
+        pub id: u32,
+        pub repo_id: u32,
+        /// GitHub login, or the git author name for commits not linked to an account
+        pub login: String,
+        /// Commits on the default branch
+        pub contributions: u32,
+    }
+}
+
+model! {
+    /// Weekly commit activity of the top 100 contributors; only holds the latest snapshot
+    #[table = "contributor_weeks"]
+    pub struct ContributorWeek {
+        pub id: u32,
+        pub repo_id: u32,
+        pub login: String,
+        /// First day of the week
+        pub week: String,
+        pub commits: u32,
+        /// Lines added
+        pub additions: u32,
+        /// Lines deleted
+        pub deletions: u32,
+    }
+}
+
+model! {
+    /// A label defined on a repository
+    #[table = "labels"]
+    pub struct Label {
+        /// GitHub label id
+        pub id: i64,
+        pub repo_id: u32,
+        pub name: String,
+        /// Hex color without the leading `#`
+        pub color: String,
+        pub description: String,
+    }
+}
+
+model! {
+    /// A label applied to a pull request or issue
+    #[table = "issue_pull_labels"]
+    pub struct PullRequestLabel {
+        pub id: u32,
+        pub issue_pull_id: i64,
+        pub label_id: i64,
+    }
+}
+
+model! {
+    /// Someone a pull request or issue is currently assigned to
+    #[table = "issue_pull_assignees"]
+    pub struct PullRequestAssignee {
+        pub id: u32,
+        pub issue_pull_id: i64,
+        pub login: String,
+    }
+}
+
+model! {
+    /// A review requested on a pull request and not yet given; GitHub drops
+    /// reviewers from the list once they review. When each request was made is
+    /// in the `review_requested` rows of `issue_pull_events`.
+    #[table = "issue_pull_requested_reviewers"]
+    pub struct PullRequestRequestedReviewer {
+        pub id: u32,
+        pub issue_pull_id: i64,
+        /// Login of a user, or slug of a team
+        pub reviewer: String,
+        pub is_team: bool,
+    }
+}
+
+model! {
+    /// A file changed by a pull request
+    #[table = "issue_pull_files"]
+    pub struct PullRequestFile {
+        pub id: u32,
+        pub issue_pull_id: i64,
+        pub path: String,
+        /// `Added`, `Modified`, `Removed`, `Renamed`...
+        pub status: String,
+        /// Lines added
+        pub additions: u32,
+        /// Lines deleted
+        pub deletions: u32,
+    }
+}
+
+model! {
+    /// A wiki page created or edited, from the repository activity feed
+    #[table = "wiki_edits"]
+    pub struct WikiEdit {
+        pub id: u32,
+        pub repo_id: u32,
+        /// GitHub id of the event; one event can touch several pages
+        pub event_id: String,
+        pub page_name: String,
+        /// `created` or `edited`
+        pub action: String,
+        pub actor: String,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// A milestone defined on a repository
+    #[table = "milestones"]
+    pub struct Milestone {
+        /// GitHub milestone id
+        pub id: i64,
+        pub repo_id: u32,
+        pub number: u32,
+        pub title: String,
+        /// `open` or `closed`
+        pub state: String,
+        /// NULL when the milestone has no due date
+        pub due_on: Option<DateTime<Utc>>,
+        pub created_at: DateTime<Utc>,
+        /// NULL while open
+        pub closed_at: Option<DateTime<Utc>>,
+    }
+}
+
+model! {
+    /// The milestone a pull request or issue belongs to
+    #[table = "issue_pull_milestones"]
+    pub struct PullRequestMilestone {
+        pub id: u32,
+        /// A pull request or issue has at most one milestone
+        pub issue_pull_id: i64,
+        pub milestone_id: i64,
+    }
+}
+
+model! {
+    /// A GitHub Discussions thread
+    #[table = "discussions"]
+    pub struct Discussion {
+        /// GitHub discussion id
+        pub id: i64,
+        pub repo_id: u32,
+        pub number: u32,
+        pub title: String,
+        pub category: String,
+        /// Whether the category accepts answers, i.e. it is for questions
+        pub answerable: bool,
+        pub author: String,
+        pub author_association: String,
+        pub created_at: DateTime<Utc>,
+        pub closed: bool,
+        /// GitHub id of the comment marked as the answer
+        pub answer_id: Option<i64>,
+        /// When the answer was marked; NULL when unanswered
+        pub answer_chosen_at: Option<DateTime<Utc>>,
+    }
+}
+
+model! {
+    /// A top-level comment on a discussion (replies to comments aren't collected)
+    #[table = "discussion_comments"]
+    pub struct DiscussionComment {
+        /// GitHub comment id
+        pub id: i64,
+        pub discussion_id: i64,
+        pub author: String,
+        pub author_association: String,
+        pub created_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// Daily views or clones of a repository; kept after GitHub's 14-day window passes
+    #[table = "traffic"]
+    pub struct TrafficDay {
+        pub id: u32,
+        pub repo_id: u32,
+        /// `views` or `clones`
+        pub kind: String,
+        pub day: String,
+        pub count: u32,
+        /// Unique visitors or cloners
+        pub uniques: u32,
+        /// When the counts were last updated; the current day is still partial
+        pub collected_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// How far a collector got for a repository, so the next run only fetches what changed
+    #[table = "collection_cursors"]
+    pub struct CollectionCursor {
+        pub id: u32,
+        pub repo_id: u32,
+        /// Name of the collect subcommand, e.g. `pulls:closed` or `reviews`
+        pub collector: String,
+        /// Latest pull request `updated_at` handled by a completed run
+        pub updated_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// Headline metrics for one week, kept by `gdfm sync` so trends survive
+    /// pruning the data they were computed from
+    #[table = "metric_history"]
+    pub struct MetricSnapshot {
+        pub id: u32,
+        pub repo_id: u32,
+        /// Monday the week starts on, e.g. `2025-03-24`
+        pub week: String,
+        pub opened_pulls: u32,
+        pub merged_pulls: u32,
+        pub opened_issues: u32,
+        pub closed_issues: u32,
+        /// Pull requests still open when the week ended
+        pub open_pulls: u32,
+        /// Issues still open when the week ended
+        pub open_issues: u32,
+        pub reviews: u32,
+        /// People (bots left out) who opened, reviewed or commented on something
+        pub active_contributors: u32,
+        /// When the row was written
+        pub recorded_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// An author association someone was seen with, to follow contributors
+    /// becoming members over time
+    #[table = "association_history"]
+    pub struct AssociationChange {
+        pub id: u32,
+        pub repo_id: u32,
+        pub login: String,
+        /// As GitHub names it, e.g. `FIRST_TIME_CONTRIBUTOR` or `MEMBER`
+        pub association: String,
+        /// Time of the earliest pull request, issue, review, comment or event carrying this association
+        pub first_seen_at: DateTime<Utc>,
+        /// When gdfm first recorded the association
+        pub recorded_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// The ETag GitHub sent with a response, so the next request for the same
+    /// URL can be conditional
+    #[table = "http_cache"]
+    pub struct HttpCacheEntry {
+        pub id: u32,
+        pub repo_id: u32,
+        /// API route including the query string, e.g. `/repos/o/r/pulls/1/reviews?per_page=100`
+        pub url: String,
+        pub etag: String,
+        /// When the response was stored
+        pub fetched_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// Whether an API host offers an API some collectors depend on, as found by
+    /// `GET /meta`
+    #[table = "host_features"]
+    pub struct HostFeature {
+        pub id: u32,
+        /// Host as used for `[hosts]` in the config, e.g. `github.example.com`
+        pub host: String,
+        /// One of `FEATURES`, e.g. `discussions`
+        pub feature: String,
+        pub available: bool,
+        /// Why the feature is missing, e.g. the server's release being too old
+        pub reason: String,
+        /// When the host was last asked
+        pub checked_at: DateTime<Utc>,
+    }
+}
+
+model! {
+    /// One run of a `gdfm collect` subcommand and the API budget it used
+    #[table = "collection_runs"]
+    pub struct CollectionRun {
+        pub id: u32,
+        pub repo_id: u32,
+        /// Name of the collect subcommand, e.g. `pulls`
+        pub collector: String,
+        pub started_at: DateTime<Utc>,
+        /// NULL while the run is in progress
+        pub finished_at: Option<DateTime<Utc>>,
+        /// `running`, `finished`, `failed` or `skipped`
+        pub status: String,
+        /// Error the run failed with, or why it was skipped
+        pub error: String,
+        /// REST rate-limit points used; every REST call costs one point
+        pub rest_calls: Option<i64>,
+        /// GraphQL rate-limit points used
+        pub graphql_points: Option<i64>,
+        /// How far the run got: the last pull request stored by `pulls`, `events` and `reviews`
+        pub checkpoint: String,
+        /// What the run covered, e.g. `pulls:closed@2/5 1000..2000`; a checkpoint
+        /// only resumes a run covering the same
+        pub checkpoint_scope: String,
+    }
+}
+
+model! {
+    /// The JSON GitHub sent for a pull request, event or review, stored with
+    /// `--keep-raw` so fields gdfm doesn't parse can be read later without
+    /// fetching everything again
+    #[table = "raw_payloads"]
+    pub struct RawPayload {
+        pub id: u32,
+        pub repo_id: u32,
+        /// `pull`, `event` or `review`
+        pub kind: String,
+        /// GitHub id of the pull request, event or review
+        pub github_id: i64,
+        /// Pull request the item belongs to; the pull request itself for `pull`
+        pub issue_pull_id: i64,
+        /// The item as GitHub sent it, e.g. `{"id": 1, "draft": false, ...}`
+        pub payload: String,
+        /// When the item was last fetched
+        pub collected_at: DateTime<Utc>,
+    }
+}
+
+/// Documentation of every model, in the same order as [`TABLES`]
+pub const SCHEMA: &[TableDoc] = &[
+    Repository::SCHEMA,
+    Maintainer::SCHEMA,
+    RepositoryMaintainer::SCHEMA,
+    PullRequest::SCHEMA,
+    PullRequestEvent::SCHEMA,
+    PullRequestReview::SCHEMA,
+    IssueDemand::SCHEMA,
+    Dependency::SCHEMA,
+    LinkedIssue::SCHEMA,
+    PullRequestChecklist::SCHEMA,
+    RepositoryEvent::SCHEMA,
+    PullRequestComment::SCHEMA,
+    PullRequestReviewComment::SCHEMA,
+    Commit::SCHEMA,
+    GovernanceChange::SCHEMA,
+    Release::SCHEMA,
+    Deployment::SCHEMA,
+    DeploymentStatus::SCHEMA,
+    Stargazer::SCHEMA,
+    Fork::SCHEMA,
+    WebhookDelivery::SCHEMA,
+    Contributor::SCHEMA,
+    ContributorWeek::SCHEMA,
+    Label::SCHEMA,
+    PullRequestLabel::SCHEMA,
+    PullRequestAssignee::SCHEMA,
+    PullRequestRequestedReviewer::SCHEMA,
+    PullRequestFile::SCHEMA,
+    WikiEdit::SCHEMA,
+    Milestone::SCHEMA,
+    PullRequestMilestone::SCHEMA,
+    Discussion::SCHEMA,
+    DiscussionComment::SCHEMA,
+    TrafficDay::SCHEMA,
+    CollectionCursor::SCHEMA,
+    MetricSnapshot::SCHEMA,
+    AssociationChange::SCHEMA,
+    HttpCacheEntry::SCHEMA,
+    HostFeature::SCHEMA,
+    CollectionRun::SCHEMA,
+    RawPayload::SCHEMA,
+];
+
+/// Every table created by `setup_db`, in an order that respects foreign keys
+pub const TABLES: &[&str] = &[
+    "repositories",
+    "maintainers",
+    "repository_maintainers",
+    "pulls",
+    "issue_pull_events",
+    "issue_pull_reviews",
+    "issue_demand",
+    "dependencies",
+    "linked_issues",
+    "pull_checklists",
+    "repository_events",
+    "issue_pull_comments",
+    "issue_pull_review_comments",
+    "commits",
+    "governance_changes",
+    "releases",
+    "deployments",
+    "deployment_statuses",
+    "stargazers",
+    "forks",
+    "webhook_deliveries",
+    "contributors",
+    "contributor_weeks",
+    "labels",
+    "issue_pull_labels",
+    "issue_pull_assignees",
+    "issue_pull_requested_reviewers",
+    "issue_pull_files",
+    "wiki_edits",
+    "milestones",
+    "issue_pull_milestones",
+    "discussions",
+    "discussion_comments",
+    "traffic",
+    "collection_cursors",
+    "metric_history",
+    "association_history",
+    "http_cache",
+    "host_features",
+    "collection_runs",
+    "raw_payloads",
+];
+
+/// A single value read from a table when we don't know its type ahead of time
+#[derive(Debug, Clone)]
+pub enum CellValue {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+}
+
+impl CellValue {
+    pub fn as_integer(&self) -> Option<i64> {
+        match self {
+            Self::Null => None,
+            Self::Integer(value) => Some(*value),
+            Self::Real(value) => Some(*value as i64),
+            Self::Text(value) => value.trim().parse().ok(),
+        }
+    }
+
+    pub fn as_real(&self) -> Option<f64> {
+        match self {
+            Self::Null => None,
+            Self::Integer(value) => Some(*value as f64),
+            Self::Real(value) => Some(*value),
+            Self::Text(value) => value.trim().parse().ok(),
+        }
+    }
+
+    pub fn as_text(&self) -> Option<String> {
+        match self {
+            Self::Null => None,
+            Self::Integer(value) => Some(value.to_string()),
+            Self::Real(value) => Some(value.to_string()),
+            Self::Text(value) => Some(value.clone()),
+        }
+    }
+
+    /// The value as JSON; reals that JSON can't represent, such as NaN, become null
+    pub fn into_json(self) -> serde_json::Value {
+        match self {
+            Self::Null => serde_json::Value::Null,
+            Self::Integer(value) => serde_json::Value::Number(value.into()),
+            Self::Real(value) => serde_json::Number::from_f64(value)
+                .map(serde_json::Value::Number)
+                .unwrap_or(serde_json::Value::Null),
+            Self::Text(value) => serde_json::Value::String(value),
+        }
+    }
+}
+
+/// The kind of values a column holds, from its declared type
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl ColumnType {
+    /// Follows SQLite's rules for the affinity of a declared column type
+    pub fn from_declared(declared: &str) -> Self {
+        let declared = declared.to_uppercase();
+        if declared.contains("INT") {
+            Self::Integer
+        } else if ["REAL", "FLOA", "DOUB"].iter().any(|name| declared.contains(name)) {
+            Self::Real
+        } else {
+            Self::Text
+        }
+    }
+}
+
+/// Project chosen with `--project` for the running command, whose data is
+/// kept in a database of its own
+pub fn project() -> Option<String> {
+    Context::current().project
+}
+
+/// Location of the database file
+pub fn get_db_path() -> PathBuf {
+    let data_dir = dirs::data_dir().expect("Data directory should exist").join(APP_NAME);
+    create_dir_all(&data_dir).expect("Data directory should be created");
+
+    match project() {
+        Some(name) => data_dir.join(format!("{}.db", name)),
+        None => data_dir.join(DB_FILE),
+    }
 }
 
 /// Function used to get the database URI while creating its directory if it doesn't exist
 /// 
 /// TODO: maybe there's better error handling we could add for this?
 fn get_db_uri() -> String {
-    let data_dir = dirs::data_dir().expect("Data directory should exist").join(APP_NAME);
-    create_dir_all(&data_dir).expect("Data directory should be created");
-    let db_file = data_dir.join(DB_FILE);
+    let db_file = get_db_path();
     let db_uri = format!("sqlite://{}", db_file.to_str().expect("Path should be a string"));
 
     db_uri
 }
 
+/// How long a connection waits for another process to finish writing before
+/// giving up with "database is locked"
+const BUSY_TIMEOUT: Duration = Duration::from_secs(30);
+
 pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
-    let db_uri = get_db_uri();
+    open_db(&get_db_path()).await
+}
+
+/// Opens the database at `path`, creating it and the tables it doesn't have
+/// yet, and migrating those written by older versions
+pub async fn open_db(path: &Path) -> Result<sqlx::SqlitePool, sqlx::Error> {
+    // WAL lets reports and queries read while a collector writes, and lets
+    // concurrent collectors wait their turn instead of failing. Foreign keys
+    // are what makes the ON DELETE CASCADE constraints below do anything.
+    let options = SqliteConnectOptions::new()
+        .filename(path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Wal)
+        .busy_timeout(BUSY_TIMEOUT)
+        .foreign_keys(true);
+    let pool = SqlitePoolOptions::new().connect_with(options).await?;
+    create_schema(&pool).await?;
+
+    Ok(pool)
+}
 
-    Sqlite::create_database(&db_uri).await?;
-    let pool = sqlx::sqlite::SqlitePool::connect(&db_uri).await?;
+async fn create_schema(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
 
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS repositories (
@@ -75,7 +996,7 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             name TEXT NOT NULL
         )",
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     sqlx::query(
@@ -83,7 +1004,19 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             ON repositories (owner, name)
         ",
     )
-    .execute(&pool)
+    .execute(pool)
+    .await?;
+
+    add_column_if_missing(pool, "repositories", "archived", "INTEGER NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(pool, "repositories", "forge", "TEXT NOT NULL DEFAULT 'github'").await?;
+
+    sqlx::query(
+        "CREATE TABLE IF NOT EXISTS maintainers (
+            id INTEGER PRIMARY KEY,
+            login TEXT NOT NULL UNIQUE
+        )",
+    )
+    .execute(pool)
     .await?;
 
     sqlx::query(
@@ -93,7 +1026,7 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             maintainer_id INTEGER NOT NULL
         )",
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     sqlx::query(
@@ -101,9 +1034,13 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             ON repository_maintainers (repo_id, maintainer_id)
         ",
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
+    add_column_if_missing(pool, "repository_maintainers", "permission", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "repository_maintainers", "teams", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "repository_maintainers", "collected_at", "TEXT").await?;
+
     // We store issues and pull requests in the same table
     sqlx::query(
         "CREATE TABLE IF NOT EXISTS pulls(
@@ -112,24 +1049,37 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             number INTEGER NOT NULL,
             title TEXT NOT NULL,
             state TEXT NOT NULL,
-            created_at TEXT NOT NULL,
+            created_at TEXT,
             updated_at TEXT NOT NULL,
             closed_at TEXT,
             merged_at TEXT,
             author TEXT NOT NULL,
             author_association TEXT NOT NULL,
+            is_issue INTEGER NOT NULL DEFAULT 0,
             FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
         )",
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
+    add_column_if_missing(pool, "pulls", "is_issue", "INTEGER NOT NULL DEFAULT 0").await?;
+    add_column_if_missing(pool, "pulls", "forge_id", "INTEGER").await?;
+    add_column_if_missing(pool, "pulls", "additions", "INTEGER").await?;
+    add_column_if_missing(pool, "pulls", "deletions", "INTEGER").await?;
+    add_column_if_missing(pool, "pulls", "changed_files", "INTEGER").await?;
+    add_column_if_missing(pool, "pulls", "commits", "INTEGER").await?;
+    add_column_if_missing(pool, "pulls", "base_ref", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "pulls", "head_ref", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "pulls", "merged_by", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "pulls", "merge_commit_sha", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "pulls", "events_collected_at", "TEXT").await?;
+
     sqlx::query(
         "CREATE UNIQUE INDEX IF NOT EXISTS idx_pulls_repository_id_number
             ON pulls (repo_id, number)
         ",
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
     sqlx::query(
@@ -139,20 +1089,15 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
         event_type TEXT NOT NULL,
         actor TEXT NOT NULL,
         author_association TEXT NOT NULL,
-        created_at TEXT NOT NULL,
+        created_at TEXT,
         FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
         )",
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
-    sqlx::query(
-        "CREATE INDEX IF NOT EXISTS idx_issue_pull_events_issue_pull_id 
-            ON issue_pull_events (issue_pull_id)
-        ",
-    )
-    .execute(&pool)
-    .await?;
+    // Tells review requests for different reviewers apart
+    add_column_if_missing(pool, "issue_pull_events", "requested_reviewer", "TEXT NOT NULL DEFAULT ''").await?;
 
     sqlx::query(
     "CREATE TABLE IF NOT EXISTS issue_pull_reviews (
@@ -161,11 +1106,22 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
         reviewer TEXT NOT NULL,
         state TEXT NOT NULL,
         author_association TEXT NOT NULL,
-        submitted_at TEXT NOT NULL,
+        submitted_at TEXT,
         FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
         )",
     )
-    .execute(&pool)
+    .execute(pool)
+    .await?;
+
+    // Lets inline review comments be matched to the review they belong to
+    add_column_if_missing(pool, "issue_pull_reviews", "github_id", "INTEGER").await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_issue_pull_events_issue_pull_id 
+            ON issue_pull_events (issue_pull_id)
+        ",
+    )
+    .execute(pool)
     .await?;
 
     sqlx::query(
@@ -173,271 +1129,3738 @@ pub async fn setup_db() -> Result<sqlx::SqlitePool, sqlx::Error> {
             ON issue_pull_reviews (issue_pull_id)
         ",
     )
-    .execute(&pool)
+    .execute(pool)
     .await?;
 
-    Ok(pool)
-}
+    // Older versions stored a review again every time it was fetched; keep the latest copy
+    if !index_exists(pool, "idx_issue_pull_reviews_issue_pull_id_github_id").await? {
+        sqlx::query(
+            "DELETE FROM issue_pull_reviews WHERE github_id IS NOT NULL AND id NOT IN (
+                SELECT MAX(id) FROM issue_pull_reviews WHERE github_id IS NOT NULL GROUP BY issue_pull_id, github_id
+            )",
+        )
+        .execute(pool)
+        .await?;
+    }
 
-pub async fn destroy_db() -> Result<(), sqlx::Error> {
-    let db_uri = get_db_uri();
-    Sqlite::drop_database(&db_uri).await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_issue_pull_reviews_issue_pull_id_github_id
+            ON issue_pull_reviews (issue_pull_id, github_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
 
-    Ok(())
-}
+    // Only holds the most recent snapshot of open issues for each repository
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_demand (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        number INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        comments INTEGER NOT NULL,
+        plus_one INTEGER NOT NULL,
+        positive_reactions INTEGER NOT NULL,
+        demand_score INTEGER NOT NULL,
+        collected_at TEXT NOT NULL DEFAULT (datetime('now')),
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
 
-impl Repository {
-    pub async fn from(pool: &Pool<Sqlite>, path: &str) -> Result<Self, sqlx::Error> {
-        let owner = path.split("/").nth(0).expect("Repository owner should exist");
-        let name = path.split("/").last().expect("Repository name should exist");
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_issue_demand_repo_id_number
+            ON issue_demand (repo_id, number)
+        ",
+    )
+    .execute(pool)
+    .await?;
 
-        let repository: Self = sqlx::query_as("SELECT id, owner, name FROM repositories WHERE owner = $1 AND name = $2")
-            .bind(owner)
-            .bind(name)
-            .fetch_one(pool)
-            .await?;
+    // Like `issue_demand`, this only holds the latest snapshot for each repository
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS dependencies (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        ecosystem TEXT NOT NULL,
+        name TEXT NOT NULL,
+        version TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
 
-        Ok(repository)
-    }
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_dependencies_repo_id_ecosystem_name
+            ON dependencies (repo_id, ecosystem, name)
+        ",
+    )
+    .execute(pool)
+    .await?;
 
-    pub async fn create(pool: &Pool<Sqlite>, path: &str) -> Result<Self, sqlx::Error> {
-        let owner = path.split("/").nth(0).expect("Repository owner should exist");
-        let name = path.split("/").last().expect("Repository name should exist");
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS linked_issues (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL,
+        issue_number INTEGER NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
 
-        let id = sqlx::query(
-            "INSERT INTO repositories (owner, name) VALUES ($1, $2)")
-            .bind(owner)
-            .bind(name)
-            .execute(pool)
-            .await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_linked_issues_issue_pull_id_issue_number
+            ON linked_issues (issue_pull_id, issue_number)
+        ",
+    )
+    .execute(pool)
+    .await?;
 
-        let repository = sqlx::query_as("SELECT id, owner, name FROM repositories WHERE id = $1")
-            .bind(id.last_insert_rowid())
-            .fetch_one(pool)
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS pull_checklists (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL UNIQUE,
+        total INTEGER NOT NULL,
+        completed INTEGER NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS repository_events (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        issue_number INTEGER NOT NULL,
+        event_type TEXT NOT NULL,
+        actor TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_repository_events_repo_id_issue_number
+            ON repository_events (repo_id, issue_number)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_pull_comments (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL,
+        author TEXT NOT NULL,
+        author_association TEXT NOT NULL,
+        body_length INTEGER NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_issue_pull_comments_issue_pull_id
+            ON issue_pull_comments (issue_pull_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    add_column_if_missing(pool, "issue_pull_comments", "forge_id", "INTEGER").await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_issue_pull_comments_issue_pull_id_forge_id
+            ON issue_pull_comments (issue_pull_id, forge_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    // `review_id` is the GitHub review id and joins on `issue_pull_reviews.github_id`
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_pull_review_comments (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL,
+        review_id INTEGER,
+        in_reply_to_id INTEGER,
+        author TEXT NOT NULL,
+        author_association TEXT NOT NULL,
+        path TEXT NOT NULL,
+        body_length INTEGER NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_issue_pull_review_comments_issue_pull_id
+            ON issue_pull_review_comments (issue_pull_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS commits (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        sha TEXT NOT NULL,
+        author TEXT NOT NULL,
+        committer TEXT NOT NULL,
+        authored_at TEXT,
+        committed_at TEXT,
+        on_default_branch INTEGER NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_commits_repo_id_sha
+            ON commits (repo_id, sha)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS governance_changes (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        sha TEXT NOT NULL,
+        author TEXT NOT NULL,
+        committed_at TEXT,
+        summary TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_governance_changes_repo_id_path_sha
+            ON governance_changes (repo_id, path, sha)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    // `id` is the GitHub release id, so re-collecting updates renamed releases
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS releases (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        tag_name TEXT NOT NULL,
+        name TEXT NOT NULL,
+        author TEXT NOT NULL,
+        prerelease INTEGER NOT NULL,
+        draft INTEGER NOT NULL,
+        published_at TEXT,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS deployments (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        sha TEXT NOT NULL,
+        git_ref TEXT NOT NULL,
+        environment TEXT NOT NULL,
+        creator TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS deployment_statuses (
+        id INTEGER PRIMARY KEY,
+        deployment_id INTEGER NOT NULL,
+        state TEXT NOT NULL,
+        creator TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (deployment_id) REFERENCES deployments (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_deployment_statuses_deployment_id
+            ON deployment_statuses (deployment_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    // Unstarring removes a user from the list, so this is replaced on every run
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS stargazers (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        login TEXT NOT NULL,
+        starred_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_stargazers_repo_id_login
+            ON stargazers (repo_id, login)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS forks (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        owner TEXT NOT NULL,
+        name TEXT NOT NULL,
+        created_at TEXT,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS webhook_deliveries (
+        id INTEGER PRIMARY KEY,
+        delivery_id TEXT NOT NULL,
+        hook_id INTEGER,
+        repository TEXT NOT NULL,
+        event TEXT NOT NULL,
+        action TEXT NOT NULL,
+        signature_valid INTEGER NOT NULL,
+        received_at TEXT NOT NULL,
+        payload TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_webhook_deliveries_delivery_id
+            ON webhook_deliveries (delivery_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    // Like `issue_demand`, these only hold the latest snapshot for each repository
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS contributors (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        login TEXT NOT NULL,
+        contributions INTEGER NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS contributor_weeks (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        login TEXT NOT NULL,
+        week TEXT NOT NULL,
+        commits INTEGER NOT NULL,
+        additions INTEGER NOT NULL,
+        deletions INTEGER NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_contributor_weeks_repo_id
+            ON contributor_weeks (repo_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS labels (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        color TEXT NOT NULL,
+        description TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_pull_labels (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL,
+        label_id INTEGER NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE,
+        FOREIGN KEY (label_id) REFERENCES labels (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_issue_pull_labels_issue_pull_id_label_id
+            ON issue_pull_labels (issue_pull_id, label_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_pull_assignees (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL,
+        login TEXT NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_issue_pull_assignees_issue_pull_id_login
+            ON issue_pull_assignees (issue_pull_id, login)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_pull_requested_reviewers (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL,
+        reviewer TEXT NOT NULL,
+        is_team INTEGER NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_issue_pull_requested_reviewers_issue_pull_id_reviewer_is_team
+            ON issue_pull_requested_reviewers (issue_pull_id, reviewer, is_team)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_pull_files (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL,
+        path TEXT NOT NULL,
+        status TEXT NOT NULL,
+        additions INTEGER NOT NULL,
+        deletions INTEGER NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_issue_pull_files_issue_pull_id_path
+            ON issue_pull_files (issue_pull_id, path)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS wiki_edits (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        event_id TEXT NOT NULL,
+        page_name TEXT NOT NULL,
+        action TEXT NOT NULL,
+        actor TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_wiki_edits_event_id_page_name
+            ON wiki_edits (event_id, page_name)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS milestones (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        number INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        state TEXT NOT NULL,
+        due_on TEXT,
+        created_at TEXT NOT NULL,
+        closed_at TEXT,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS issue_pull_milestones (
+        id INTEGER PRIMARY KEY,
+        issue_pull_id INTEGER NOT NULL UNIQUE,
+        milestone_id INTEGER NOT NULL,
+        FOREIGN KEY (issue_pull_id) REFERENCES pulls (id) ON DELETE CASCADE,
+        FOREIGN KEY (milestone_id) REFERENCES milestones (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS discussions (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        number INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        category TEXT NOT NULL,
+        answerable INTEGER NOT NULL,
+        author TEXT NOT NULL,
+        author_association TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        closed INTEGER NOT NULL,
+        answer_id INTEGER,
+        answer_chosen_at TEXT,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS discussion_comments (
+        id INTEGER PRIMARY KEY,
+        discussion_id INTEGER NOT NULL,
+        author TEXT NOT NULL,
+        author_association TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        FOREIGN KEY (discussion_id) REFERENCES discussions (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE INDEX IF NOT EXISTS idx_discussion_comments_discussion_id
+            ON discussion_comments (discussion_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS traffic (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        day TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        uniques INTEGER NOT NULL,
+        collected_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_traffic_repo_id_kind_day
+            ON traffic (repo_id, kind, day)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS collection_cursors (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        collector TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_collection_cursors_repo_id_collector
+            ON collection_cursors (repo_id, collector)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS metric_history (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        week TEXT NOT NULL,
+        opened_pulls INTEGER NOT NULL,
+        merged_pulls INTEGER NOT NULL,
+        opened_issues INTEGER NOT NULL,
+        closed_issues INTEGER NOT NULL,
+        open_pulls INTEGER NOT NULL,
+        open_issues INTEGER NOT NULL,
+        reviews INTEGER NOT NULL,
+        active_contributors INTEGER NOT NULL,
+        recorded_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_metric_history_repo_id_week
+            ON metric_history (repo_id, week)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    // Databases from before association snapshots get them from what is already stored
+    let backfill_associations = table_columns(pool, "association_history").await?.is_empty();
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS association_history (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        login TEXT NOT NULL,
+        association TEXT NOT NULL,
+        first_seen_at TEXT NOT NULL,
+        recorded_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    // The backfill runs on the connection that created the index: SQLite
+    // rejects the upsert on one that hasn't seen the index yet
+    let mut connection = pool.acquire().await?;
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_association_history_repo_id_login_association
+            ON association_history (repo_id, login, association)
+        ",
+    )
+    .execute(&mut *connection)
+    .await?;
+
+    if backfill_associations {
+        for repo in Repository::fetch_all(pool).await? {
+            AssociationChange::snapshot(&mut *connection, repo.id).await?;
+        }
+    }
+    drop(connection);
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS http_cache (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        url TEXT NOT NULL UNIQUE,
+        etag TEXT NOT NULL,
+        fetched_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS host_features (
+        id INTEGER PRIMARY KEY,
+        host TEXT NOT NULL,
+        feature TEXT NOT NULL,
+        available INTEGER NOT NULL,
+        reason TEXT NOT NULL DEFAULT '',
+        checked_at TEXT NOT NULL
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_host_features_host_feature
+            ON host_features (host, feature)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS collection_runs (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        collector TEXT NOT NULL,
+        started_at TEXT NOT NULL,
+        finished_at TEXT,
+        status TEXT NOT NULL,
+        error TEXT NOT NULL DEFAULT '',
+        rest_calls INTEGER,
+        graphql_points INTEGER,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    add_column_if_missing(pool, "collection_runs", "checkpoint", "TEXT NOT NULL DEFAULT ''").await?;
+    add_column_if_missing(pool, "collection_runs", "checkpoint_scope", "TEXT NOT NULL DEFAULT ''").await?;
+
+    sqlx::query(
+    "CREATE TABLE IF NOT EXISTS raw_payloads (
+        id INTEGER PRIMARY KEY,
+        repo_id INTEGER NOT NULL,
+        kind TEXT NOT NULL,
+        github_id INTEGER NOT NULL,
+        issue_pull_id INTEGER NOT NULL,
+        payload TEXT NOT NULL,
+        collected_at TEXT NOT NULL,
+        FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+        )",
+    )
+    .execute(pool)
+    .await?;
+
+    sqlx::query(
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_raw_payloads_kind_github_id
+            ON raw_payloads (kind, github_id)
+        ",
+    )
+    .execute(pool)
+    .await?;
+
+    let version: i64 = sqlx::query_scalar("PRAGMA user_version").fetch_one(pool).await?;
+    if version < 1 {
+        migrate_timestamps(pool, None).await?;
+    } else if version < 2 {
+        // `pulls.created_at` may be NULL from version 2 on
+        migrate_timestamps(pool, Some("pulls")).await?;
+    }
+    if version < 3 {
+        // Older versions only left their events behind; those with some were collected
+        // at some point, which can't have been before the last of them
+        sqlx::query(
+            "UPDATE pulls SET events_collected_at = (
+                SELECT COALESCE(MAX(e.created_at), pulls.updated_at)
+                FROM issue_pull_events e WHERE e.issue_pull_id = pulls.id
+            )
+            WHERE events_collected_at IS NULL
+                AND EXISTS (SELECT 1 FROM issue_pull_events e WHERE e.issue_pull_id = pulls.id)",
+        )
+        .execute(pool)
+        .await?;
+    }
+    if version < SCHEMA_VERSION {
+        sqlx::query(&format!("PRAGMA user_version = {}", SCHEMA_VERSION)).execute(pool).await?;
+    }
+    migrate_forge_ids(pool).await?;
+
+    Ok(())
+}
+
+/// SQL giving the next local id of a table: rows read through a forge other
+/// than GitHub, and issues, count down from -1, so they never meet GitHub's ids
+fn next_local_id(table: &str) -> String {
+    format!("(SELECT MIN(COALESCE(MIN(id), 0), 0) - 1 FROM {})", table)
+}
+
+/// Moves issues and pull requests older versions stored under their forge's
+/// ids to local ones, along with the events and comments of those read through
+/// another forge than GitHub
+///
+/// Those ids could equal a GitHub pull request's, which the row would then
+/// take the place of: GitHub numbers issues apart from pull requests, and
+/// other forges apart from GitHub. This only happens once: afterwards no issue,
+/// nor pull request of another forge, has an id above zero.
+async fn migrate_forge_ids(pool: &Pool<Sqlite>) -> Result<(), sqlx::Error> {
+    let other_forges = "SELECT id FROM repositories WHERE forge <> 'github'";
+    let forge_pulls = format!("pulls WHERE id > 0 AND (is_issue = 1 OR repo_id IN ({}))", other_forges);
+    let count: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", forge_pulls))
+        .fetch_one(pool)
+        .await?;
+    if count == 0 {
+        return Ok(());
+    }
+
+    let mut transaction = pool.begin().await?;
+    // Rows pointing at a pull request are moved along with it before the check at commit
+    sqlx::query("PRAGMA defer_foreign_keys = ON").execute(&mut *transaction).await?;
+    sqlx::query(&format!(
+        "CREATE TEMP TABLE forge_pull_ids AS
+        SELECT id AS old_id, {} + 1 - ROW_NUMBER() OVER (ORDER BY id) AS new_id FROM {}",
+        next_local_id("pulls"),
+        forge_pulls
+    ))
+    .execute(&mut *transaction)
+    .await?;
+
+    for doc in SCHEMA.iter().filter(|doc| doc.columns.iter().any(|column| column.name == "issue_pull_id")) {
+        sqlx::query(&format!(
+            "UPDATE {} SET issue_pull_id = (SELECT new_id FROM forge_pull_ids WHERE old_id = issue_pull_id)
+            WHERE issue_pull_id IN (SELECT old_id FROM forge_pull_ids)",
+            doc.table
+        ))
+        .execute(&mut *transaction)
+        .await?;
+    }
+    sqlx::query(
+        "UPDATE pulls SET forge_id = id, id = (SELECT new_id FROM forge_pull_ids WHERE old_id = id)
+        WHERE id IN (SELECT old_id FROM forge_pull_ids)",
+    )
+    .execute(&mut *transaction)
+    .await?;
+
+    // Events and comments go below the lowest id of their table, keeping their
+    // order. Those of GitHub issues have GitHub's ids, which stay.
+    for (table, keep) in [("issue_pull_events", ""), ("issue_pull_comments", "forge_id = id, ")] {
+        let lowest: i64 = sqlx::query_scalar(&format!("SELECT MIN(COALESCE(MIN(id), 0), 0) FROM {}", table))
+            .fetch_one(&mut *transaction)
+            .await?;
+        sqlx::query(&format!(
+            "UPDATE {table} SET {keep}id = $1 - id
+            WHERE id > 0 AND issue_pull_id IN (SELECT new_id FROM forge_pull_ids)
+                AND issue_pull_id IN (SELECT id FROM pulls WHERE repo_id IN ({other_forges}))"
+        ))
+        .bind(lowest)
+        .execute(&mut *transaction)
+        .await?;
+    }
+    sqlx::query("DROP TABLE forge_pull_ids").execute(&mut *transaction).await?;
+
+    transaction.commit().await
+}
+
+/// Version of the schema this build of gdfm writes, kept in the database's
+/// `user_version` so each migration runs once; databases written before
+/// versions were recorded are at 0
+pub const SCHEMA_VERSION: i64 = 3;
+
+/// Opens the database at `path` as it is, without creating, migrating or
+/// otherwise writing to it, for commands that only look at it
+pub async fn open_db_read_only(path: &Path) -> Result<sqlx::SqlitePool, sqlx::Error> {
+    let options = SqliteConnectOptions::new().filename(path).read_only(true).busy_timeout(BUSY_TIMEOUT);
+
+    SqlitePoolOptions::new().connect_with(options).await
+}
+
+/// The schema version recorded in the database at `path`, read without creating
+/// or migrating it, or `None` when there is no database there yet
+pub async fn stored_schema_version(path: &Path) -> Result<Option<i64>, sqlx::Error> {
+    use sqlx::Connection;
+
+    if !path.exists() {
+        return Ok(None);
+    }
+    let options = SqliteConnectOptions::new().filename(path).read_only(true);
+    let mut connection = SqliteConnection::connect_with(&options).await?;
+    let version = sqlx::query_scalar("PRAGMA user_version").fetch_one(&mut connection).await?;
+    connection.close().await?;
+
+    Ok(Some(version))
+}
+
+/// Timestamp columns, which older versions stored as `2025-03-29 14:01:02 UTC`,
+/// or as an empty string when missing
+const TIMESTAMP_COLUMNS: &[(&str, &str)] = &[
+    ("pulls", "created_at"),
+    ("pulls", "updated_at"),
+    ("pulls", "closed_at"),
+    ("pulls", "merged_at"),
+    ("issue_pull_events", "created_at"),
+    ("issue_pull_reviews", "submitted_at"),
+    ("repository_maintainers", "collected_at"),
+    ("issue_demand", "collected_at"),
+    ("repository_events", "created_at"),
+    ("issue_pull_comments", "created_at"),
+    ("issue_pull_review_comments", "created_at"),
+    ("commits", "authored_at"),
+    ("commits", "committed_at"),
+    ("governance_changes", "committed_at"),
+    ("releases", "published_at"),
+    ("deployments", "created_at"),
+    ("deployment_statuses", "created_at"),
+    ("stargazers", "starred_at"),
+    ("forks", "created_at"),
+    ("webhook_deliveries", "received_at"),
+    ("wiki_edits", "created_at"),
+    ("milestones", "due_on"),
+    ("milestones", "created_at"),
+    ("milestones", "closed_at"),
+    ("discussions", "created_at"),
+    ("discussions", "answer_chosen_at"),
+    ("discussion_comments", "created_at"),
+    ("traffic", "collected_at"),
+    ("collection_cursors", "updated_at"),
+    ("metric_history", "recorded_at"),
+    ("association_history", "first_seen_at"),
+    ("association_history", "recorded_at"),
+    ("http_cache", "fetched_at"),
+    ("host_features", "checked_at"),
+    ("collection_runs", "started_at"),
+    ("collection_runs", "finished_at"),
+    ("raw_payloads", "collected_at"),
+];
+
+/// Those of [`TIMESTAMP_COLUMNS`] that are NULL when GitHub gives no time,
+/// several of which older versions declared `NOT NULL`
+const NULLABLE_TIMESTAMP_COLUMNS: &[(&str, &str)] = &[
+    ("repository_maintainers", "collected_at"),
+    ("pulls", "created_at"),
+    ("pulls", "closed_at"),
+    ("pulls", "merged_at"),
+    ("issue_pull_events", "created_at"),
+    ("issue_pull_reviews", "submitted_at"),
+    ("commits", "authored_at"),
+    ("commits", "committed_at"),
+    ("governance_changes", "committed_at"),
+    ("releases", "published_at"),
+    ("forks", "created_at"),
+    ("milestones", "due_on"),
+    ("milestones", "closed_at"),
+    ("discussions", "answer_chosen_at"),
+    ("collection_runs", "finished_at"),
+];
+
+/// SQL converting a value of a [`TIMESTAMP_COLUMNS`] column stored by an older
+/// version to the format [`stored_timestamp`] writes, or `None` for other columns
+///
+/// `row` is the alias of the table the value is read from, such as `o.`, or empty.
+fn legacy_timestamp(table: &str, column: &str, row: &str) -> Option<String> {
+    if !TIMESTAMP_COLUMNS.contains(&(table, column)) {
+        return None;
+    }
+    let converted = |column: &str| format!("NULLIF(replace({row}{column}, ' UTC', ''), '')");
+    // A pull request without an update time was last updated when it was opened
+    if (table, column) == ("pulls", "updated_at") {
+        return Some(format!("COALESCE({}, {})", converted("updated_at"), converted("created_at")));
+    }
+    if !NULLABLE_TIMESTAMP_COLUMNS.contains(&(table, column)) {
+        return Some(format!("replace({row}{column}, ' UTC', '')"));
+    }
+
+    Some(converted(column))
+}
+
+/// Converts the timestamps of databases created by older versions in place,
+/// and lets those of [`NULLABLE_TIMESTAMP_COLUMNS`] be NULL instead of an empty string;
+/// `only` limits it to one table
+///
+/// SQLite can't drop `NOT NULL` from a column, so tables declaring it on one
+/// are copied to new ones without it, as its documentation recommends. Foreign
+/// keys are off meanwhile, as dropping the old table would otherwise delete the
+/// rows pointing at it.
+async fn migrate_timestamps(pool: &Pool<Sqlite>, only: Option<&str>) -> Result<(), sqlx::Error> {
+    let mut connection = pool.acquire().await?;
+    sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *connection).await?;
+    let migrated = convert_timestamps(pool, &mut connection, only).await;
+    sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *connection).await?;
+
+    migrated
+}
+
+async fn convert_timestamps(
+    pool: &Pool<Sqlite>,
+    connection: &mut SqliteConnection,
+    only: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    use sqlx::Connection;
+
+    let mut tables: Vec<&str> = TIMESTAMP_COLUMNS.iter().map(|(table, _)| *table).collect();
+    tables.dedup();
+    tables.retain(|table| only.is_none_or(|only| only == *table));
+
+    let mut transaction = connection.begin().await?;
+    for table in tables {
+        let columns = table_info(pool, table).await?;
+        let values: Vec<String> = columns
+            .iter()
+            .map(|column| legacy_timestamp(table, &column.name, "").unwrap_or_else(|| column.name.clone()))
+            .collect();
+        let required: Vec<&str> = columns
+            .iter()
+            .filter(|column| column.not_null && NULLABLE_TIMESTAMP_COLUMNS.contains(&(table, column.name.as_str())))
+            .map(|column| column.name.as_str())
+            .collect();
+
+        if required.is_empty() {
+            let updates: Vec<String> = columns
+                .iter()
+                .filter_map(|column| Some(format!("{} = {}", column.name, legacy_timestamp(table, &column.name, "")?)))
+                .collect();
+            sqlx::query(&format!("UPDATE {} SET {}", table, updates.join(", ")))
+                .execute(&mut *transaction)
+                .await?;
+            continue;
+        }
+
+        let create: String = sqlx::query_scalar("SELECT sql FROM sqlite_master WHERE type = 'table' AND name = $1")
+            .bind(table)
+            .fetch_one(&mut *transaction)
+            .await?;
+        let mut create = create.replacen(table, &format!("{}_migrated", table), 1);
+        for column in required {
+            create = create
+                .replacen(&format!("{} TEXT NOT NULL DEFAULT ''", column), &format!("{} TEXT", column), 1)
+                .replacen(&format!("{} TEXT NOT NULL", column), &format!("{} TEXT", column), 1);
+        }
+        // Indexes go with the table they are on
+        let indexes: Vec<String> = sqlx::query_scalar(
+            "SELECT sql FROM sqlite_master WHERE type = 'index' AND tbl_name = $1 AND sql IS NOT NULL",
+        )
+        .bind(table)
+        .fetch_all(&mut *transaction)
+        .await?;
+
+        sqlx::query(&create).execute(&mut *transaction).await?;
+        let names: Vec<&str> = columns.iter().map(|column| column.name.as_str()).collect();
+        sqlx::query(&format!(
+            "INSERT INTO {table}_migrated ({}) SELECT {} FROM {table}",
+            names.join(", "),
+            values.join(", ")
+        ))
+        .execute(&mut *transaction)
+        .await?;
+        sqlx::query(&format!("DROP TABLE {}", table)).execute(&mut *transaction).await?;
+        sqlx::query(&format!("ALTER TABLE {table}_migrated RENAME TO {table}"))
+            .execute(&mut *transaction)
+            .await?;
+        for index in indexes {
+            sqlx::query(&index).execute(&mut *transaction).await?;
+        }
+    }
+
+    transaction.commit().await
+}
+
+async fn index_exists(pool: &Pool<Sqlite>, index: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'index' AND name = $1)")
+        .bind(index)
+        .fetch_one(pool)
+        .await
+}
+
+/// Adds a column to a table created by an older version of gdfm
+///
+/// `CREATE TABLE IF NOT EXISTS` leaves existing tables alone, so new columns
+/// have to be added to them explicitly.
+async fn add_column_if_missing(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), sqlx::Error> {
+    let columns = table_columns(pool, table).await?;
+
+    if !columns.iter().any(|existing| existing == column) {
+        sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+pub async fn destroy_db() -> Result<(), sqlx::Error> {
+    let db_uri = get_db_uri();
+    Sqlite::drop_database(&db_uri).await?;
+    // The write-ahead log and its index stay behind when a process didn't close the database
+    for suffix in ["-wal", "-shm"] {
+        let mut path = get_db_path().into_os_string();
+        path.push(suffix);
+        let _ = std::fs::remove_file(path);
+    }
+
+    Ok(())
+}
+
+/// Fails unless `table` is one of [`TABLES`], as the helpers taking a table
+/// name interpolate it into their queries
+fn check_table(table: &str) -> Result<(), sqlx::Error> {
+    if TABLES.contains(&table) {
+        Ok(())
+    } else {
+        Err(sqlx::Error::Protocol(format!("{} is not one of the tables gdfm stores", table)))
+    }
+}
+
+/// Returns the column names of a table in declaration order
+///
+/// Fails when `table` isn't one of [`TABLES`].
+pub async fn table_columns(pool: &Pool<Sqlite>, table: &str) -> Result<Vec<String>, sqlx::Error> {
+    check_table(table)?;
+    let columns: Vec<String> = sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+        .fetch_all(pool)
+        .await?;
+
+    Ok(columns)
+}
+
+/// Condition limiting a table to the repositories with these ids, or `None`
+/// when the table isn't tied to a repository
+///
+/// `ids` is a comma-separated list of repository ids.
+pub fn repository_filter(table: &str, columns: &[String], ids: &str) -> Option<String> {
+    let has = |name: &str| columns.iter().any(|column| column == name);
+
+    match table {
+        "repositories" => Some(format!("id IN ({})", ids)),
+        "maintainers" => Some(format!(
+            "id IN (SELECT maintainer_id FROM repository_maintainers WHERE repo_id IN ({}))",
+            ids
+        )),
+        "deployment_statuses" => Some(format!(
+            "deployment_id IN (SELECT id FROM deployments WHERE repo_id IN ({}))",
+            ids
+        )),
+        "discussion_comments" => Some(format!(
+            "discussion_id IN (SELECT id FROM discussions WHERE repo_id IN ({}))",
+            ids
+        )),
+        "webhook_deliveries" => Some(format!(
+            "repository IN (SELECT owner || '/' || name FROM repositories WHERE id IN ({}))",
+            ids
+        )),
+        _ if has("repo_id") => Some(format!("repo_id IN ({})", ids)),
+        _ if has("issue_pull_id") => Some(format!(
+            "issue_pull_id IN (SELECT id FROM pulls WHERE repo_id IN ({}))",
+            ids
+        )),
+        _ => None,
+    }
+}
+
+/// Conditions selecting everything stored for a repository, one per table it
+/// has rows in, ordered so rows go before the rows they refer to
+///
+/// Maintainers are only included when no other repository lists them.
+async fn repository_rows(
+    connection: &mut sqlx::SqliteConnection,
+    repo_id: u32,
+) -> Result<Vec<(&'static str, String)>, sqlx::Error> {
+    let ids = repo_id.to_string();
+    let mut filters = Vec::new();
+    for table in TABLES.iter().rev() {
+        let columns: Vec<String> = sqlx::query_scalar(&format!("SELECT name FROM pragma_table_info('{}')", table))
+            .fetch_all(&mut *connection)
+            .await?;
+        let filter = if *table == "maintainers" {
+            // Worked out up front, as their repository_maintainers rows go first
+            let exclusive: Vec<i64> = sqlx::query_scalar(
+                "SELECT maintainer_id FROM repository_maintainers WHERE repo_id = $1
+                EXCEPT SELECT maintainer_id FROM repository_maintainers WHERE repo_id != $1",
+            )
+            .bind(repo_id)
+            .fetch_all(&mut *connection)
+            .await?;
+            let exclusive: Vec<String> = exclusive.iter().map(i64::to_string).collect();
+            Some(format!("id IN ({})", exclusive.join(", ")))
+        } else {
+            repository_filter(table, &columns, &ids)
+        };
+        if let Some(filter) = filter {
+            filters.push((*table, filter));
+        }
+    }
+
+    Ok(filters)
+}
+
+/// Counts the rows removing a repository would delete, per table, leaving out empty ones
+pub async fn count_repository_rows(pool: &Pool<Sqlite>, repo_id: u32) -> Result<Vec<(&'static str, i64)>, sqlx::Error> {
+    let mut connection = pool.acquire().await?;
+    let mut counts = Vec::new();
+    for (table, filter) in repository_rows(&mut connection, repo_id).await?.into_iter().rev() {
+        let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {} WHERE {}", table, filter))
+            .fetch_one(&mut *connection)
+            .await?;
+        if rows > 0 {
+            counts.push((table, rows));
+        }
+    }
+
+    Ok(counts)
+}
+
+/// Deletes a repository and everything stored for it in one transaction,
+/// returning the number of rows removed
+///
+/// Data of other repositories, and maintainers they share, is left alone.
+pub async fn delete_repository(pool: &Pool<Sqlite>, repo_id: u32) -> Result<u64, sqlx::Error> {
+    use sqlx::Connection;
+
+    let mut connection = pool.acquire().await?;
+    let mut transaction = connection.begin().await?;
+    let mut deleted = 0;
+    for (table, filter) in repository_rows(&mut transaction, repo_id).await? {
+        deleted += sqlx::query(&format!("DELETE FROM {} WHERE {}", table, filter))
+            .execute(&mut *transaction)
+            .await?
+            .rows_affected();
+    }
+    transaction.commit().await?;
+
+    Ok(deleted)
+}
+
+/// A column as SQLite reports it in `pragma_table_info`
+#[derive(Debug, sqlx::FromRow)]
+pub struct ColumnInfo {
+    pub name: String,
+    #[sqlx(rename = "type")]
+    pub data_type: String,
+    #[sqlx(rename = "notnull")]
+    pub not_null: bool,
+    #[sqlx(rename = "pk")]
+    pub primary_key: bool,
+}
+
+/// Lists the columns of a table with their declared types
+///
+/// Fails when `table` isn't one of [`TABLES`].
+pub async fn table_info(pool: &Pool<Sqlite>, table: &str) -> Result<Vec<ColumnInfo>, sqlx::Error> {
+    check_table(table)?;
+    sqlx::query_as(&format!("SELECT name, type, \"notnull\", pk FROM pragma_table_info('{}')", table))
+        .fetch_all(pool)
+        .await
+}
+
+/// Reads every row of a table without needing a model struct for it
+///
+/// Fails when `table` isn't one of [`TABLES`].
+pub async fn fetch_table(pool: &Pool<Sqlite>, table: &str) -> Result<Vec<Vec<CellValue>>, sqlx::Error> {
+    check_table(table)?;
+    let rows = sqlx::query(&format!("SELECT * FROM {} ORDER BY rowid", table))
+        .fetch_all(pool)
+        .await?;
+
+    rows.iter().map(row_values).collect()
+}
+
+/// Tables left out when merging databases, as their cursors and cached
+/// responses only describe what that database's own runs have seen
+const UNMERGED_TABLES: &[&str] = &["collection_cursors", "http_cache"];
+
+/// Tables whose `id` is the item's id on GitHub, and so the same in every database
+const GITHUB_ID_TABLES: &[&str] = &[
+    "pulls",
+    "issue_pull_events",
+    "repository_events",
+    "issue_pull_comments",
+    "issue_pull_review_comments",
+    "releases",
+    "deployments",
+    "deployment_statuses",
+    "forks",
+    "labels",
+    "milestones",
+    "discussions",
+    "discussion_comments",
+];
+
+/// Tables of [`GITHUB_ID_TABLES`] that also number rows themselves below zero,
+/// for items GitHub gives no id or read through another forge, so such an id
+/// taken in both databases may be two different rows
+const LOCALLY_NUMBERED_TABLES: &[&str] = &["issue_pull_events", "issue_pull_comments"];
+
+/// Tables other tables refer to by their own ids, whose rows are never
+/// replaced, as replacing one would delete every row referring to it
+const REFERENCED_TABLES: &[&str] = &["repositories", "maintainers"];
+
+/// Merges another gdfm database into this one and returns the number of rows
+/// added or replaced
+///
+/// Repositories and maintainers are matched by name, as each database numbers
+/// them itself, and every `repo_id` and `maintainer_id` is translated to this
+/// database's. Rows keyed by a GitHub id are matched by that id; others by
+/// their unique keys, and are skipped when an identical row is stored. Issues
+/// and pull requests under local ids are matched by number, and take a local
+/// id of this database when new, which the rows pointing at them follow. Without
+/// `overwrite`, stored rows are never changed, so merging the same database
+/// twice adds nothing the second time. With it, the other database's rows
+/// replace the stored ones they match, cursors and cached responses included.
+pub async fn merge_database(pool: &Pool<Sqlite>, path: &str, overwrite: bool) -> Result<u64, sqlx::Error> {
+    let mut connection = pool.acquire().await?;
+    sqlx::query("ATTACH DATABASE $1 AS imported").bind(path).execute(&mut *connection).await?;
+    let merged = merge_attached(&mut connection, overwrite).await;
+    sqlx::query("DETACH DATABASE imported").execute(&mut *connection).await?;
+
+    merged
+}
+
+async fn merge_attached(connection: &mut sqlx::SqliteConnection, overwrite: bool) -> Result<u64, sqlx::Error> {
+    use sqlx::Connection;
+
+    let mut transaction = connection.begin().await?;
+    let mut added = 0;
+
+    for table in TABLES.iter().filter(|table| overwrite || !UNMERGED_TABLES.contains(table)) {
+        let imported: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info($1, 'imported')")
+            .bind(table)
+            .fetch_all(&mut *transaction)
+            .await?;
+        if *table == "pulls" {
+            map_pull_ids(&mut transaction, &imported).await?;
+        }
+        let keeps_id = GITHUB_ID_TABLES.contains(table);
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info($1, 'main')")
+            .bind(table)
+            .fetch_all(&mut *transaction)
+            .await?
+            .into_iter()
+            .filter(|column| imported.contains(column) && (keeps_id || column != "id"))
+            .collect();
+        if columns.is_empty() {
+            continue;
+        }
+        let values: Vec<String> = columns
+            .iter()
+            .map(|column| match column.as_str() {
+                "repo_id" => MERGED_REPO_ID.to_string(),
+                "id" if *table == "pulls" => "(SELECT new_id FROM merged_pull_ids WHERE old_id = o.id)".to_string(),
+                "issue_pull_id" => "(SELECT new_id FROM merged_pull_ids WHERE old_id = o.issue_pull_id)".to_string(),
+                "maintainer_id" => "(SELECT m.id FROM main.maintainers m JOIN imported.maintainers i
+                    ON i.login = m.login WHERE i.id = o.maintainer_id)"
+                    .to_string(),
+                column => legacy_timestamp(table, column, "o.").unwrap_or_else(|| format!("o.{}", column)),
+            })
+            .collect();
+
+        let insert = if keeps_id && overwrite {
+            let updated: Vec<&str> = columns.iter().map(String::as_str).filter(|column| *column != "id").collect();
+            let updates: Vec<String> = updated.iter().map(|column| format!("{} = excluded.{}", column, column)).collect();
+            let excluded: Vec<String> = updated.iter().map(|column| format!("excluded.{}", column)).collect();
+            // `WHERE true` tells the parser the upsert clause isn't part of a join, rows
+            // clashing with a different stored row on another unique key are skipped,
+            // and rows that are the same already aren't counted
+            format!(
+                "INSERT OR IGNORE INTO main.{table} ({}) SELECT {} FROM imported.{table} o WHERE true
+                ON CONFLICT (id) DO UPDATE SET {} WHERE ({}) IS NOT ({})",
+                columns.join(", "),
+                values.join(", "),
+                updates.join(", "),
+                updated.join(", "),
+                excluded.join(", ")
+            )
+        } else if keeps_id {
+            format!(
+                "INSERT OR IGNORE INTO main.{table} ({}) SELECT {} FROM imported.{table} o",
+                columns.join(", "),
+                values.join(", ")
+            )
+        } else {
+            let conflict = if overwrite && !REFERENCED_TABLES.contains(table) { "REPLACE" } else { "IGNORE" };
+            format!(
+                "INSERT OR {conflict} INTO main.{table} ({}) SELECT {} FROM imported.{table} o
+                EXCEPT SELECT {} FROM main.{table}",
+                columns.join(", "),
+                values.join(", "),
+                columns.join(", ")
+            )
+        };
+        added += sqlx::query(&insert).execute(&mut *transaction).await?.rows_affected();
+
+        // Rows whose local id was already taken by a different row get one of their own
+        if LOCALLY_NUMBERED_TABLES.contains(table) {
+            let (columns, values): (Vec<&String>, Vec<&String>) =
+                columns.iter().zip(&values).filter(|(column, _)| *column != "id").unzip();
+            let columns: Vec<&str> = columns.into_iter().map(String::as_str).collect();
+            let values: Vec<&str> = values.into_iter().map(String::as_str).collect();
+            let insert = format!(
+                "INSERT OR IGNORE INTO main.{table} (id, {}) SELECT {} + 1 - ROW_NUMBER() OVER (), * FROM (
+                    SELECT {} FROM imported.{table} o WHERE o.id < 0
+                    EXCEPT SELECT {} FROM main.{table}
+                )",
+                columns.join(", "),
+                next_local_id(&format!("main.{}", table)),
+                values.join(", "),
+                columns.join(", ")
+            );
+            added += sqlx::query(&insert).execute(&mut *transaction).await?.rows_affected();
+        }
+    }
+
+    sqlx::query("DROP TABLE IF EXISTS merged_pull_ids").execute(&mut *transaction).await?;
+    transaction.commit().await?;
+
+    Ok(added)
+}
+
+/// SQL translating the `repo_id` of an imported row `o` to this database's
+const MERGED_REPO_ID: &str = "(SELECT r.id FROM main.repositories r JOIN imported.repositories i
+    ON i.owner = r.owner AND i.name = r.name WHERE i.id = o.repo_id)";
+
+/// Fills the temporary table `merged_pull_ids` with the id each imported issue
+/// and pull request is stored under in this database
+///
+/// GitHub pull requests keep their id. Issues and pull requests under local
+/// ids, which each database hands out itself, are matched by number, and
+/// otherwise given a local id of this database. `columns` are those of the
+/// imported `pulls`, which lacks `is_issue` when written by an old version.
+async fn map_pull_ids(connection: &mut SqliteConnection, columns: &[String]) -> Result<(), sqlx::Error> {
+    let is_issue = if columns.iter().any(|column| column == "is_issue") { "o.is_issue = 1" } else { "false" };
+    sqlx::query("CREATE TEMP TABLE merged_pull_ids (old_id INTEGER PRIMARY KEY, new_id INTEGER NOT NULL)")
+        .execute(&mut *connection)
+        .await?;
+    let inserts = [
+        format!("SELECT o.id, o.id FROM imported.pulls o WHERE o.id > 0 AND NOT {is_issue}"),
+        format!(
+            "SELECT o.id, p.id FROM imported.pulls o
+            JOIN main.pulls p ON p.repo_id = {MERGED_REPO_ID} AND p.number = o.number
+            WHERE o.id NOT IN (SELECT old_id FROM merged_pull_ids)"
+        ),
+        format!(
+            "SELECT o.id, {} + 1 - ROW_NUMBER() OVER (ORDER BY o.id DESC) FROM imported.pulls o
+            WHERE o.id NOT IN (SELECT old_id FROM merged_pull_ids)",
+            next_local_id("main.pulls")
+        ),
+    ];
+    for insert in inserts {
+        sqlx::query(&format!("INSERT INTO merged_pull_ids (old_id, new_id) {}", insert))
+            .execute(&mut *connection)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Converts a row into [`CellValue`]s based on SQLite's storage class of each value
+pub fn row_values(row: &sqlx::sqlite::SqliteRow) -> Result<Vec<CellValue>, sqlx::Error> {
+    let mut values = Vec::with_capacity(row.columns().len());
+
+    for column in row.columns() {
+        let index = column.ordinal();
+        let raw = row.try_get_raw(index)?;
+
+        let value = if raw.is_null() {
+            CellValue::Null
+        } else {
+            match raw.type_info().name() {
+                "INTEGER" => CellValue::Integer(row.try_get(index)?),
+                "REAL" => CellValue::Real(row.try_get(index)?),
+                _ => CellValue::Text(row.try_get(index)?),
+            }
+        };
+        values.push(value);
+    }
+
+    Ok(values)
+}
+
+/// Splits `owner/name` at the last slash, so GitLab projects in subgroups
+/// (`group/subgroup/name`) keep their whole namespace as the owner
+fn split_path(path: &str) -> (&str, &str) {
+    path.rsplit_once('/').unwrap_or((path, path))
+}
+
+impl Repository {
+    pub async fn from(pool: &Pool<Sqlite>, path: &str) -> Result<Self, sqlx::Error> {
+        let (owner, name) = split_path(path);
+
+        let repository: Self = sqlx::query_as("SELECT id, owner, name, archived, forge FROM repositories WHERE owner = $1 AND name = $2")
+            .bind(owner)
+            .bind(name)
+            .fetch_one(pool)
+            .await?;
+
+        Ok(repository)
+    }
+
+    /// Starts tracking a repository; tracking it again restores it if it was archived
+    pub async fn create(pool: &Pool<Sqlite>, path: &str) -> Result<Self, sqlx::Error> {
+        let (owner, name) = split_path(path);
+
+        sqlx::query(
+            "INSERT INTO repositories (owner, name) VALUES ($1, $2)
+            ON CONFLICT (owner, name) DO UPDATE SET archived = 0")
+            .bind(owner)
+            .bind(name)
+            .execute(pool)
+            .await?;
+
+        Self::from(pool, path).await
+    }
+
+    pub async fn set_archived(pool: &Pool<Sqlite>, id: u32, archived: bool) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repositories SET archived = $1 WHERE id = $2")
+            .bind(archived)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn set_forge(pool: &Pool<Sqlite>, id: u32, forge: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE repositories SET forge = $1 WHERE id = $2")
+            .bind(forge)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Every tracked repository, ordered by `owner/name`
+    pub async fn fetch_all(pool: &Pool<Sqlite>) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT id, owner, name, archived, forge FROM repositories ORDER BY owner, name")
+            .fetch_all(pool)
+            .await
+    }
+}
+
+/// How timestamps are stored: SQLite's own `YYYY-MM-DD HH:MM:SS` in UTC,
+/// which its date functions read and which sorts in time order
+fn stored_timestamp(at: DateTime<Utc>) -> NaiveDateTime {
+    at.naive_utc()
+}
+
+/// Columns of `pulls` in the order of [`PullRequest`]'s fields
+const PULL_COLUMNS: &str = "id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author, \
+    author_association, is_issue, additions, deletions, changed_files, commits, base_ref, head_ref, merged_by, \
+    merge_commit_sha, forge_id, events_collected_at";
+
+impl PullRequest {
+    /// Stores a page of pull requests in one transaction
+    pub async fn create_many(
+        pool: &Pool<Sqlite>,
+        pulls: &[&octocrab::models::pulls::PullRequest],
+        repo_id: u32,
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        for pull in pulls {
+            Self::insert(&mut transaction, pull, repo_id).await?;
+        }
+        transaction.commit().await
+    }
+
+    async fn insert(
+        connection: &mut SqliteConnection,
+        pull: &octocrab::models::pulls::PullRequest,
+        repo_id: u32,
+    ) -> Result<(), sqlx::Error> {
+        // A pull request never updated was last updated when opened
+        let Some(updated_at) = pull.updated_at.or(pull.created_at) else {
+            return Err(sqlx::Error::Protocol(format!("Pull request #{} has no update time", pull.number)));
+        };
+        let author_login = match &pull.user {
+            Some(user) => user.login.to_string(),
+            None => "".to_string(),
+        };
+        let author_association = match &pull.author_association {
+            Some(association) => format!("{:?}", association),
+            None => "".to_string(),
+        };
+        let state = match &pull.state {
+            Some(state) => format!("{:?}", state),
+            None => "".to_string(),
+        };
+
+        // Pull requests fetched again because they changed are updated in place. Only
+        // single pull requests come with their size and merger, so listings leave
+        // those as they were.
+        sqlx::query(
+            "INSERT INTO pulls (
+                id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author, author_association,
+                additions, deletions, changed_files, commits, base_ref, head_ref, merged_by, merge_commit_sha
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19)
+            ON CONFLICT (id) DO UPDATE SET
+                title = excluded.title,
+                state = excluded.state,
+                updated_at = excluded.updated_at,
+                closed_at = excluded.closed_at,
+                merged_at = excluded.merged_at,
+                author_association = excluded.author_association,
+                additions = COALESCE(excluded.additions, additions),
+                deletions = COALESCE(excluded.deletions, deletions),
+                changed_files = COALESCE(excluded.changed_files, changed_files),
+                commits = COALESCE(excluded.commits, commits),
+                base_ref = excluded.base_ref,
+                head_ref = excluded.head_ref,
+                merged_by = COALESCE(NULLIF(excluded.merged_by, ''), merged_by),
+                merge_commit_sha = excluded.merge_commit_sha",
+        )
+        .bind(pull.id.into_inner() as i64)
+        .bind(repo_id)
+        .bind(pull.number.to_string())
+        .bind(pull.title.clone().unwrap_or("".to_string()))
+        .bind(state)
+        .bind(pull.created_at.map(stored_timestamp))
+        .bind(stored_timestamp(updated_at))
+        .bind(pull.closed_at.map(stored_timestamp))
+        .bind(pull.merged_at.map(stored_timestamp))
+        .bind(author_login)
+        .bind(author_association)
+        .bind(pull.additions.map(|count| count as i64))
+        .bind(pull.deletions.map(|count| count as i64))
+        .bind(pull.changed_files.map(|count| count as i64))
+        .bind(pull.commits.map(|count| count as i64))
+        .bind(&pull.base.ref_field)
+        .bind(&pull.head.ref_field)
+        .bind(pull.merged_by.as_ref().map(|user| user.login.as_str()).unwrap_or_default())
+        .bind(pull.merge_commit_sha.as_deref().unwrap_or_default())
+        .execute(connection)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stores pull requests read through a [`crate::forge::Forge`] in one
+    /// transaction, updating those stored before
+    pub async fn create_many_from_forge(
+        pool: &Pool<Sqlite>,
+        pulls: &[crate::forge::ForgePull],
+        repo_id: u32,
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        for pull in pulls {
+            Self::insert_from_forge(&mut transaction, pull, repo_id).await?;
+        }
+        transaction.commit().await
+    }
+
+    async fn insert_from_forge(
+        connection: &mut SqliteConnection,
+        pull: &crate::forge::ForgePull,
+        repo_id: u32,
+    ) -> Result<(), sqlx::Error> {
+        let state = if pull.open { "Open" } else { "Closed" };
+
+        // Stored under a local id, and found again by number as GitLab's iid is unique per project
+        sqlx::query(&format!(
+            "INSERT INTO pulls (
+                id, forge_id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author,
+                author_association, base_ref, head_ref, merged_by, merge_commit_sha
+            ) VALUES ({}, $1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            ON CONFLICT (repo_id, number) DO UPDATE SET
+                forge_id = excluded.forge_id,
+                title = excluded.title,
+                state = excluded.state,
+                updated_at = excluded.updated_at,
+                closed_at = excluded.closed_at,
+                merged_at = excluded.merged_at,
+                author_association = excluded.author_association,
+                base_ref = excluded.base_ref,
+                head_ref = excluded.head_ref,
+                merged_by = COALESCE(NULLIF(excluded.merged_by, ''), merged_by),
+                merge_commit_sha = excluded.merge_commit_sha",
+            next_local_id("pulls")
+        ))
+        .bind(pull.id)
+        .bind(repo_id)
+        .bind(pull.number)
+        .bind(&pull.title)
+        .bind(state)
+        .bind(stored_timestamp(pull.created_at))
+        .bind(stored_timestamp(pull.updated_at))
+        .bind(pull.closed_at.map(stored_timestamp))
+        .bind(pull.merged_at.map(stored_timestamp))
+        .bind(&pull.author)
+        .bind(&pull.author_association)
+        .bind(&pull.base_ref)
+        .bind(&pull.head_ref)
+        .bind(&pull.merged_by)
+        .bind(&pull.merge_commit_sha)
+        .execute(connection)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Stores a page of issues in one transaction and returns the id each is stored under
+    pub async fn create_many_from_issues(
+        pool: &Pool<Sqlite>,
+        issues: &[&octocrab::models::issues::Issue],
+        repo_id: u32,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        let mut ids = Vec::with_capacity(issues.len());
+        for issue in issues {
+            ids.push(Self::insert_from_issue(&mut transaction, issue, repo_id).await?);
+        }
+        transaction.commit().await?;
+
+        Ok(ids)
+    }
+
+    async fn insert_from_issue(
+        connection: &mut SqliteConnection,
+        issue: &octocrab::models::issues::Issue,
+        repo_id: u32,
+    ) -> Result<i64, sqlx::Error> {
+        // Issue ids can equal those of unrelated pull requests, so issues are
+        // stored under a local id and found again by number, which issues and
+        // pull requests share
+        sqlx::query_scalar(&format!(
+            "INSERT INTO pulls (
+                id, forge_id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author,
+                author_association, is_issue
+            ) VALUES ({}, $1, $2, $3, $4, $5, $6, $7, $8, NULL, $9, $10, 1)
+            ON CONFLICT (repo_id, number) DO UPDATE SET
+                forge_id = excluded.forge_id,
+                title = excluded.title,
+                state = excluded.state,
+                updated_at = excluded.updated_at,
+                closed_at = excluded.closed_at,
+                author_association = excluded.author_association
+            RETURNING id",
+            next_local_id("pulls")
+        ))
+        .bind(issue.id.into_inner() as i64)
+        .bind(repo_id)
+        .bind(issue.number.to_string())
+        .bind(&issue.title)
+        .bind(format!("{:?}", issue.state))
+        .bind(stored_timestamp(issue.created_at))
+        .bind(stored_timestamp(issue.updated_at))
+        .bind(issue.closed_at.map(stored_timestamp))
+        .bind(issue.user.login.to_string())
+        .bind(&issue.author_association)
+        .fetch_one(connection)
+        .await
+    }
+
+    pub async fn fetch(pool: &Pool<Sqlite>, repo_id:u32, number: u32) -> Result<Self, sqlx::Error> {
+        let pull_request: PullRequest = sqlx::query_as(&format!("
+            SELECT {PULL_COLUMNS}
+            FROM pulls WHERE repo_id = $1 AND number = $2
+        "))
+        .bind(repo_id)
+        .bind(number)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(pull_request)
+    }
+
+    pub async fn fetch_many(pool: &Pool<Sqlite>, repo_id:u32, numbers: &[u32]) -> Result<Vec<Self>, sqlx::Error> {
+        let query_str = if numbers.is_empty() {
+            format!("
+                SELECT {PULL_COLUMNS}
+                FROM pulls WHERE repo_id = ?")
+        } else {
+            let params = format!("?{}", ", ?".repeat(numbers.len() - 1));
+            format!("
+                SELECT {PULL_COLUMNS}
+                FROM pulls WHERE repo_id = ? AND number IN ( {params} )
+            ")
+        };
+
+        let mut query = sqlx::query_as(&query_str)
+            .bind(repo_id);
+
+        for number in numbers {
+            query = query.bind(number);
+        }
+        let pull_requests: Vec<PullRequest> = query.fetch_all(pool).await?;
+
+        Ok(pull_requests)
+    }
+
+    /// Pull requests and issues updated after `since`, oldest first
+    pub async fn fetch_updated_since(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(&format!("
+            SELECT {PULL_COLUMNS}
+            FROM pulls WHERE repo_id = $1 AND updated_at > $2
+            ORDER BY updated_at
+        "))
+        .bind(repo_id)
+        .bind(stored_timestamp(since))
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Pull requests and issues without a single row in `table`, which must have an `issue_pull_id` column
+    ///
+    /// Fails when `table` isn't one of [`TABLES`].
+    pub async fn fetch_without(pool: &Pool<Sqlite>, repo_id: u32, table: &str) -> Result<Vec<Self>, sqlx::Error> {
+        check_table(table)?;
+        let query_str = format!("
+            SELECT {PULL_COLUMNS}
+            FROM pulls p
+            WHERE repo_id = $1 AND NOT EXISTS (SELECT 1 FROM {table} t WHERE t.issue_pull_id = p.id)
+        ");
+
+        sqlx::query_as(&query_str)
+            .bind(repo_id)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// Pull requests and issues whose events `gdfm collect events` never went through
+    pub async fn fetch_events_not_collected(pool: &Pool<Sqlite>, repo_id: u32) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(&format!("
+            SELECT {PULL_COLUMNS}
+            FROM pulls WHERE repo_id = $1 AND events_collected_at IS NULL
+        "))
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Records that the events of a pull request or issue were collected just now
+    pub async fn set_events_collected(pool: &Pool<Sqlite>, id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE pulls SET events_collected_at = $1 WHERE id = $2")
+            .bind(stored_timestamp(Utc::now()))
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+const INSERT_REVIEW: &str = "INSERT INTO issue_pull_reviews (
+    issue_pull_id, github_id, reviewer, state, author_association, submitted_at
+) VALUES ($1, $2, $3, $4, $5, $6)";
+
+/// Refreshes a review stored before; reviews without a GitHub id are always added
+const UPSERT_REVIEW: &str = "ON CONFLICT (issue_pull_id, github_id) DO UPDATE SET
+    reviewer = excluded.reviewer,
+    state = excluded.state,
+    author_association = excluded.author_association,
+    submitted_at = excluded.submitted_at";
+
+impl PullRequestReview {
+    /// Replaces the reviews of a pull request with those fetched again, in one transaction
+    pub async fn replace(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        reviews: &[crate::forge::ForgeReview],
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        sqlx::query("DELETE FROM issue_pull_reviews WHERE issue_pull_id = $1")
+            .bind(issue_pull_id)
+            .execute(&mut *transaction)
+            .await?;
+        for review in reviews {
+            sqlx::query(&format!("{} {}", INSERT_REVIEW, UPSERT_REVIEW))
+            .bind(issue_pull_id)
+            .bind(review.id)
+            .bind(&review.reviewer)
+            .bind(format!("{:?}", review.state))
+            .bind(&review.author_association)
+            .bind(stored_timestamp(review.submitted_at))
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await
+    }
+
+    /// Stores a page of reviews of a pull request in one transaction
+    pub async fn create_many(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        reviews: &[&octocrab::models::pulls::Review],
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        for review in reviews {
+            Self::insert(&mut transaction, issue_pull_id, review).await?;
+        }
+        transaction.commit().await
+    }
+
+    async fn insert(
+        connection: &mut SqliteConnection,
+        issue_pull_id: i64,
+        review: &octocrab::models::pulls::Review,
+    ) -> Result<(), sqlx::Error> {
+        let reviewer= match &review.user{
+            Some(user) => user.login.to_string(),
+            None => "".to_string(),
+        };
+        let state = match review.state {
+            Some(state) => format!("{:?}", state),
+            None => "".to_string(),
+        };
+        let author_association = match &review.author_association {
+            Some(association) => format!("{:?}", association),
+            None => "".to_string(),
+        };
+        // Reviews fetched again, e.g. after a dismissal, are updated in place
+        sqlx::query(&format!("{} {}", INSERT_REVIEW, UPSERT_REVIEW))
+        .bind(issue_pull_id)
+        .bind(review.id.into_inner() as i64)
+        .bind(reviewer)
+        .bind(state)
+        .bind(author_association)
+        .bind(review.submitted_at.map(stored_timestamp))
+        .execute(connection)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl PullRequestEvent {
+    /// Stores the events of a pull request fetched again in one transaction,
+    /// removing those other than `timeline`, the forge ids of its whole
+    /// timeline: events GitHub deleted, copies of older versions stored under
+    /// ids of their own, and events without ids, which are listed anew
+    pub async fn replace(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        events: &[&crate::forge::ForgeEvent],
+        timeline: &[i64],
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        sqlx::query("DELETE FROM issue_pull_events WHERE issue_pull_id = $1 AND id NOT IN (SELECT value FROM json_each($2))")
+            .bind(issue_pull_id)
+            .bind(serde_json::to_string(timeline).expect("ids serialize"))
+            .execute(&mut *transaction)
+            .await?;
+        for event in events {
+            Self::insert(&mut transaction, issue_pull_id, event).await?;
+        }
+        transaction.commit().await
+    }
+
+    async fn insert(
+        connection: &mut SqliteConnection,
+        issue_pull_id: i64,
+        event: &crate::forge::ForgeEvent,
+    ) -> Result<(), sqlx::Error> {
+        // Events fetched again are updated in place, which also fills in the
+        // reviewer of events stored before reviewers were kept. Events the
+        // forge gives no id are stored under a local one.
+        sqlx::query(&format!(
+            "INSERT INTO issue_pull_events (
+                id, issue_pull_id, event_type, actor, author_association, created_at, requested_reviewer
+            ) VALUES (COALESCE($1, {}), $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (id) DO UPDATE SET
+                event_type = excluded.event_type,
+                actor = excluded.actor,
+                author_association = excluded.author_association,
+                created_at = excluded.created_at,
+                requested_reviewer = excluded.requested_reviewer",
+            next_local_id("issue_pull_events")
+        ))
+            .bind(event.id)
+            .bind(issue_pull_id)
+            .bind(format!("{:?}", event.event))
+            .bind(&event.actor)
+            .bind(&event.author_association)
+            .bind(event.created_at.map(stored_timestamp))
+            .bind(&event.requested_reviewer)
+            .execute(connection)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl IssueDemand {
+    /// Weight given to each thumbs up reaction when scoring demand; comments and
+    /// other positive reactions count once each.
+    pub const PLUS_ONE_WEIGHT: u32 = 2;
+
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        issue: &crate::github::IssueWithReactions,
+    ) -> Result<Self, sqlx::Error> {
+        let positive_reactions = issue.reactions.positive();
+        let demand_score = issue.comments
+            + positive_reactions
+            + issue.reactions.plus_one * (Self::PLUS_ONE_WEIGHT - 1);
+
+        sqlx::query(
+            "INSERT INTO issue_demand (
+                repo_id, number, title, comments, plus_one, positive_reactions, demand_score
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (repo_id, number) DO UPDATE SET
+                title = excluded.title,
+                comments = excluded.comments,
+                plus_one = excluded.plus_one,
+                positive_reactions = excluded.positive_reactions,
+                demand_score = excluded.demand_score,
+                collected_at = datetime('now')",
+        )
+        .bind(repo_id)
+        .bind(issue.number as u32)
+        .bind(&issue.title)
+        .bind(issue.comments)
+        .bind(issue.reactions.plus_one)
+        .bind(positive_reactions)
+        .bind(demand_score)
+        .execute(pool)
+        .await?;
+
+        let issue_demand: Self = sqlx::query_as(
+            "SELECT id, repo_id, number, title, comments, plus_one, positive_reactions, demand_score, collected_at
+            FROM issue_demand WHERE repo_id = $1 AND number = $2",
+        )
+        .bind(repo_id)
+        .bind(issue.number as u32)
+        .fetch_one(pool)
+        .await?;
+
+        Ok(issue_demand)
+    }
+
+    /// Removes the previous snapshot so closed issues don't linger in the ranking
+    pub async fn clear(pool: &Pool<Sqlite>, repo_id: u32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM issue_demand WHERE repo_id = $1")
+            .bind(repo_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn fetch_top(pool: &Pool<Sqlite>, repo_id: u32, limit: u32) -> Result<Vec<Self>, sqlx::Error> {
+        let issues: Vec<IssueDemand> = sqlx::query_as("
+            SELECT id, repo_id, number, title, comments, plus_one, positive_reactions, demand_score, collected_at
+            FROM issue_demand WHERE repo_id = $1
+            ORDER BY demand_score DESC, number ASC
+            LIMIT $2
+        ")
+        .bind(repo_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await?;
+
+        Ok(issues)
+    }
+}
+
+impl Dependency {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        ecosystem: &str,
+        name: &str,
+        version: &str,
+    ) -> Result<(), sqlx::Error> {
+        // The same package can appear in several manifests of one repository
+        sqlx::query(
+            "INSERT OR IGNORE INTO dependencies (repo_id, ecosystem, name, version) VALUES ($1, $2, $3, $4)",
+        )
+        .bind(repo_id)
+        .bind(ecosystem)
+        .bind(name)
+        .bind(version)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn clear(pool: &Pool<Sqlite>, repo_id: u32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM dependencies WHERE repo_id = $1")
+            .bind(repo_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+}
+
+impl LinkedIssue {
+    pub async fn create(pool: &Pool<Sqlite>, issue_pull_id: i64, issue_number: u32) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO linked_issues (issue_pull_id, issue_number) VALUES ($1, $2)",
+        )
+        .bind(issue_pull_id)
+        .bind(issue_number)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl PullRequestChecklist {
+    /// Records the checklist of a pull request, or removes it when the
+    /// description no longer has one
+    pub async fn set(pool: &Pool<Sqlite>, issue_pull_id: i64, counts: Option<(u32, u32)>) -> Result<(), sqlx::Error> {
+        match counts {
+            Some((total, completed)) => {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO pull_checklists (issue_pull_id, total, completed) VALUES ($1, $2, $3)",
+                )
+                .bind(issue_pull_id)
+                .bind(total)
+                .bind(completed)
+                .execute(pool)
+                .await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM pull_checklists WHERE issue_pull_id = $1")
+                    .bind(issue_pull_id)
+                    .execute(pool)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Label {
+    /// Stores a label, updating its name, color and description if it already exists
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        label: &octocrab::models::Label,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO labels (id, repo_id, name, color, description) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (id) DO UPDATE SET
+                name = excluded.name, color = excluded.color, description = excluded.description",
+        )
+        .bind(label.id.into_inner() as i64)
+        .bind(repo_id)
+        .bind(&label.name)
+        .bind(&label.color)
+        .bind(label.description.clone().unwrap_or_default())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl PullRequestLabel {
+    pub async fn create(pool: &Pool<Sqlite>, issue_pull_id: i64, label_id: i64) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO issue_pull_labels (issue_pull_id, label_id) VALUES ($1, $2)",
+        )
+        .bind(issue_pull_id)
+        .bind(label_id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl PullRequestAssignee {
+    /// Replaces the assignees of a pull request or issue with `logins`
+    pub async fn set(pool: &Pool<Sqlite>, issue_pull_id: i64, logins: &[String]) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM issue_pull_assignees WHERE issue_pull_id = $1")
+            .bind(issue_pull_id)
+            .execute(pool)
+            .await?;
+
+        for login in logins {
+            sqlx::query("INSERT OR IGNORE INTO issue_pull_assignees (issue_pull_id, login) VALUES ($1, $2)")
+                .bind(issue_pull_id)
+                .bind(login)
+                .execute(pool)
+                .await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PullRequestRequestedReviewer {
+    /// Replaces the pending review requests of a pull request
+    pub async fn set(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        users: &[String],
+        teams: &[String],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM issue_pull_requested_reviewers WHERE issue_pull_id = $1")
+            .bind(issue_pull_id)
+            .execute(pool)
+            .await?;
+
+        let requests = users.iter().map(|user| (user, false)).chain(teams.iter().map(|team| (team, true)));
+        for (reviewer, is_team) in requests {
+            sqlx::query(
+                "INSERT OR IGNORE INTO issue_pull_requested_reviewers (issue_pull_id, reviewer, is_team)
+                VALUES ($1, $2, $3)",
+            )
+            .bind(issue_pull_id)
+            .bind(reviewer)
+            .bind(is_team)
+            .execute(pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl PullRequestFile {
+    /// Replaces the files of a pull request with those fetched again, in one
+    /// transaction, so files dropped from an open pull request don't linger
+    pub async fn replace(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        files: &[octocrab::models::repos::DiffEntry],
+    ) -> Result<(), sqlx::Error> {
+        let mut transaction = pool.begin().await?;
+        sqlx::query("DELETE FROM issue_pull_files WHERE issue_pull_id = $1")
+            .bind(issue_pull_id)
+            .execute(&mut *transaction)
+            .await?;
+        for file in files {
+            sqlx::query(
+                "INSERT OR REPLACE INTO issue_pull_files (issue_pull_id, path, status, additions, deletions)
+                VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(issue_pull_id)
+            .bind(&file.filename)
+            .bind(format!("{:?}", file.status))
+            .bind(file.additions as u32)
+            .bind(file.deletions as u32)
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        transaction.commit().await
+    }
+}
+
+impl WikiEdit {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        event: &crate::github::ActivityEvent,
+        page: &crate::github::WikiPage,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT OR IGNORE INTO wiki_edits (repo_id, event_id, page_name, action, actor, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(repo_id)
+        .bind(&event.id)
+        .bind(&page.page_name)
+        .bind(&page.action)
+        .bind(&event.actor.login)
+        .bind(stored_timestamp(event.created_at))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl Milestone {
+    /// Stores a milestone, updating it if it already exists
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        milestone: &octocrab::models::Milestone,
+    ) -> Result<(), sqlx::Error> {
+
+        sqlx::query(
+            "INSERT INTO milestones (id, repo_id, number, title, state, due_on, created_at, closed_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            ON CONFLICT (id) DO UPDATE SET
+                title = excluded.title, state = excluded.state, due_on = excluded.due_on,
+                closed_at = excluded.closed_at",
+        )
+        .bind(milestone.id.into_inner() as i64)
+        .bind(repo_id)
+        .bind(milestone.number)
+        .bind(&milestone.title)
+        .bind(milestone.state.clone().unwrap_or_default())
+        .bind(milestone.due_on.map(stored_timestamp))
+        .bind(stored_timestamp(milestone.created_at))
+        .bind(milestone.closed_at.map(stored_timestamp))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl PullRequestMilestone {
+    /// Sets the milestone of a pull request or issue, or clears it when `milestone_id` is `None`
+    pub async fn set(pool: &Pool<Sqlite>, issue_pull_id: i64, milestone_id: Option<i64>) -> Result<(), sqlx::Error> {
+        match milestone_id {
+            Some(milestone_id) => {
+                sqlx::query(
+                    "INSERT OR REPLACE INTO issue_pull_milestones (issue_pull_id, milestone_id) VALUES ($1, $2)",
+                )
+                .bind(issue_pull_id)
+                .bind(milestone_id)
+                .execute(pool)
+                .await?;
+            }
+            None => {
+                sqlx::query("DELETE FROM issue_pull_milestones WHERE issue_pull_id = $1")
+                    .bind(issue_pull_id)
+                    .execute(pool)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Discussion {
+    /// Stores a discussion and its comments, replacing what was stored for it before
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        discussion: &crate::github::Discussion,
+    ) -> Result<(), sqlx::Error> {
+        let author = match &discussion.author {
+            Some(author) => author.login.to_string(),
+            None => "".to_string(),
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO discussions (
+                id, repo_id, number, title, category, answerable, author, author_association,
+                created_at, closed, answer_id, answer_chosen_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12)",
+        )
+        .bind(discussion.database_id)
+        .bind(repo_id)
+        .bind(discussion.number)
+        .bind(&discussion.title)
+        .bind(&discussion.category.name)
+        .bind(discussion.category.is_answerable)
+        .bind(author)
+        .bind(&discussion.author_association)
+        .bind(stored_timestamp(discussion.created_at))
+        .bind(discussion.closed)
+        .bind(discussion.answer.as_ref().map(|answer| answer.database_id))
+        .bind(discussion.answer_chosen_at.map(stored_timestamp))
+        .execute(pool)
+        .await?;
+
+        sqlx::query("DELETE FROM discussion_comments WHERE discussion_id = $1")
+            .bind(discussion.database_id)
+            .execute(pool)
             .await?;
-        
-        Ok(repository)
+
+        for comment in &discussion.comments.nodes {
+            let author = match &comment.author {
+                Some(author) => author.login.to_string(),
+                None => "".to_string(),
+            };
+            sqlx::query(
+                "INSERT OR REPLACE INTO discussion_comments (id, discussion_id, author, author_association, created_at)
+                VALUES ($1, $2, $3, $4, $5)",
+            )
+            .bind(comment.database_id)
+            .bind(discussion.database_id)
+            .bind(author)
+            .bind(&comment.author_association)
+            .bind(stored_timestamp(comment.created_at))
+            .execute(pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TrafficDay {
+    /// Stores the counts for a day, replacing earlier counts for the same day
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        kind: &str,
+        day: &crate::github::TrafficDay,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO traffic (repo_id, kind, day, count, uniques, collected_at)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (repo_id, kind, day) DO UPDATE
+            SET count = excluded.count, uniques = excluded.uniques, collected_at = excluded.collected_at",
+        )
+        .bind(repo_id)
+        .bind(kind)
+        .bind(day.timestamp.date_naive().to_string())
+        .bind(day.count)
+        .bind(day.uniques)
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl CollectionCursor {
+    /// The high-water mark of a collector, if a run has completed before
+    pub async fn fetch(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        collector: &str,
+    ) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        sqlx::query_scalar("SELECT updated_at FROM collection_cursors WHERE repo_id = $1 AND collector = $2")
+            .bind(repo_id)
+            .bind(collector)
+            .fetch_optional(pool)
+            .await
+    }
+
+    pub async fn set(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        collector: &str,
+        updated_at: DateTime<Utc>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO collection_cursors (repo_id, collector, updated_at) VALUES ($1, $2, $3)
+            ON CONFLICT (repo_id, collector) DO UPDATE SET updated_at = excluded.updated_at",
+        )
+        .bind(repo_id)
+        .bind(collector)
+        .bind(stored_timestamp(updated_at))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl HostFeature {
+    /// What is known about a host, in the order of `FEATURES` as they were recorded
+    pub async fn fetch(pool: &Pool<Sqlite>, host: &str) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM host_features WHERE host = $1 ORDER BY id")
+            .bind(host)
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn set(
+        pool: &Pool<Sqlite>,
+        host: &str,
+        feature: &str,
+        available: bool,
+        reason: &str,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO host_features (host, feature, available, reason, checked_at) VALUES ($1, $2, $3, $4, $5)
+            ON CONFLICT (host, feature) DO UPDATE SET
+                available = excluded.available,
+                reason = excluded.reason,
+                checked_at = excluded.checked_at",
+        )
+        .bind(host)
+        .bind(feature)
+        .bind(available)
+        .bind(reason)
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+/// Turns a stored association into GitHub's name for it; older rows hold the
+/// octocrab variant name (`FirstTimeContributor`) rather than `FIRST_TIME_CONTRIBUTOR`
+const CANONICAL_ASSOCIATION: &str = "CASE upper(replace(association, '_', ''))
+    WHEN 'FIRSTTIMECONTRIBUTOR' THEN 'FIRST_TIME_CONTRIBUTOR'
+    WHEN 'FIRSTTIMER' THEN 'FIRST_TIMER'
+    ELSE upper(association)
+END";
+
+impl MetricSnapshot {
+    /// Records the metrics of every complete week since the last one recorded,
+    /// or of last week when none are
+    ///
+    /// Weeks already recorded are left alone, so pruning raw data later never
+    /// rewrites them.
+    pub async fn record_weeks(pool: &Pool<Sqlite>, repo_id: u32) -> Result<(), sqlx::Error> {
+        let today = chrono::Utc::now().date_naive();
+        let this_week = today - chrono::Duration::days(today.weekday().num_days_from_monday() as i64);
+        let last_week = this_week - chrono::Duration::weeks(1);
+
+        let latest: Option<String> = sqlx::query_scalar("SELECT MAX(week) FROM metric_history WHERE repo_id = $1")
+            .bind(repo_id)
+            .fetch_one(pool)
+            .await?;
+        let mut week = latest
+            .and_then(|week| chrono::NaiveDate::parse_from_str(&week, "%Y-%m-%d").ok())
+            .map(|week| week + chrono::Duration::weeks(1))
+            .unwrap_or(last_week);
+
+        while week <= last_week {
+            Self::record(pool, repo_id, week).await?;
+            week += chrono::Duration::weeks(1);
+        }
+
+        Ok(())
+    }
+
+    /// Records the metrics of the week starting on `week`
+    async fn record(pool: &Pool<Sqlite>, repo_id: u32, week: chrono::NaiveDate) -> Result<(), sqlx::Error> {
+        let start = week.and_time(chrono::NaiveTime::MIN);
+        let end = (week + chrono::Duration::weeks(1)).and_time(chrono::NaiveTime::MIN);
+
+        sqlx::query("
+            INSERT OR IGNORE INTO metric_history (
+                repo_id, week, opened_pulls, merged_pulls, opened_issues, closed_issues,
+                open_pulls, open_issues, reviews, active_contributors, recorded_at
+            )
+            SELECT $1, $2,
+                COUNT(CASE WHEN is_issue = 0 AND created_at >= $3 AND created_at < $4 THEN 1 END),
+                COUNT(CASE WHEN is_issue = 0 AND merged_at >= $3 AND merged_at < $4 THEN 1 END),
+                COUNT(CASE WHEN is_issue = 1 AND created_at >= $3 AND created_at < $4 THEN 1 END),
+                COUNT(CASE WHEN is_issue = 1 AND closed_at >= $3 AND closed_at < $4 THEN 1 END),
+                COUNT(CASE WHEN is_issue = 0 AND created_at < $4 AND (closed_at IS NULL OR closed_at >= $4) THEN 1 END),
+                COUNT(CASE WHEN is_issue = 1 AND created_at < $4 AND (closed_at IS NULL OR closed_at >= $4) THEN 1 END),
+                (SELECT COUNT(*) FROM issue_pull_reviews r JOIN pulls p ON p.id = r.issue_pull_id
+                    WHERE p.repo_id = $1 AND r.submitted_at >= $3 AND r.submitted_at < $4),
+                (SELECT COUNT(DISTINCT login) FROM (
+                    SELECT author AS login, created_at AS at FROM pulls WHERE repo_id = $1
+                    UNION ALL
+                    SELECT r.reviewer, r.submitted_at FROM issue_pull_reviews r
+                    JOIN pulls p ON p.id = r.issue_pull_id WHERE p.repo_id = $1
+                    UNION ALL
+                    SELECT c.author, c.created_at FROM issue_pull_comments c
+                    JOIN pulls p ON p.id = c.issue_pull_id WHERE p.repo_id = $1
+                ) WHERE at >= $3 AND at < $4 AND login != '' AND login NOT LIKE '%[bot]'),
+                $5
+            FROM pulls WHERE repo_id = $1
+        ")
+        .bind(repo_id)
+        .bind(week.to_string())
+        .bind(start)
+        .bind(end)
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl AssociationChange {
+    /// Records every association seen on a repository's stored activity, keeping
+    /// the earliest time each was seen
+    ///
+    /// Stored pull requests and reviews are updated in place when fetched again,
+    /// so snapshotting after every collection keeps associations they no longer show.
+    pub async fn snapshot(executor: impl sqlx::SqliteExecutor<'_>, repo_id: u32) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!("
+            INSERT INTO association_history (repo_id, login, association, first_seen_at, recorded_at)
+            SELECT $1, login, {CANONICAL_ASSOCIATION} AS canonical, MIN(at), $2 FROM (
+                SELECT author AS login, author_association AS association, created_at AS at
+                FROM pulls WHERE repo_id = $1
+                UNION ALL
+                SELECT r.reviewer, r.author_association, r.submitted_at FROM issue_pull_reviews r
+                JOIN pulls p ON p.id = r.issue_pull_id WHERE p.repo_id = $1
+                UNION ALL
+                SELECT c.author, c.author_association, c.created_at FROM issue_pull_comments c
+                JOIN pulls p ON p.id = c.issue_pull_id WHERE p.repo_id = $1
+                UNION ALL
+                SELECT e.actor, e.author_association, e.created_at FROM issue_pull_events e
+                JOIN pulls p ON p.id = e.issue_pull_id WHERE p.repo_id = $1
+            )
+            WHERE login != '' AND login NOT LIKE '%[bot]' AND association != '' AND at IS NOT NULL
+            GROUP BY login, canonical
+            ON CONFLICT (repo_id, login, association)
+                DO UPDATE SET first_seen_at = MIN(first_seen_at, excluded.first_seen_at)
+        "))
+        .bind(repo_id)
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .execute(executor)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl HttpCacheEntry {
+    /// The URLs and ETags stored for a repository
+    pub async fn fetch_all(pool: &Pool<Sqlite>, repo_id: u32) -> Result<Vec<(String, String)>, sqlx::Error> {
+        sqlx::query_as("SELECT url, etag FROM http_cache WHERE repo_id = $1")
+            .bind(repo_id)
+            .fetch_all(pool)
+            .await
+    }
+
+    pub async fn set(pool: &Pool<Sqlite>, repo_id: u32, url: &str, etag: &str) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO http_cache (repo_id, url, etag, fetched_at) VALUES ($1, $2, $3, $4)
+            ON CONFLICT (url) DO UPDATE SET etag = excluded.etag, fetched_at = excluded.fetched_at",
+        )
+        .bind(repo_id)
+        .bind(url)
+        .bind(etag)
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl CollectionRun {
+    /// Records the start of a run, returning its id
+    pub async fn start(pool: &Pool<Sqlite>, repo_id: u32, collector: &str) -> Result<u32, sqlx::Error> {
+        let result = sqlx::query(
+            "INSERT INTO collection_runs (repo_id, collector, started_at, status) VALUES ($1, $2, $3, 'running')",
+        )
+        .bind(repo_id)
+        .bind(collector)
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .execute(pool)
+        .await?;
+
+        Ok(result.last_insert_rowid() as u32)
+    }
+
+    /// Records how a run ended; `error` is `None` when it succeeded
+    pub async fn finish(
+        pool: &Pool<Sqlite>,
+        id: u32,
+        error: Option<&str>,
+        rest_calls: Option<i64>,
+        graphql_points: Option<i64>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "UPDATE collection_runs
+            SET finished_at = $1, status = $2, error = $3, rest_calls = $4, graphql_points = $5
+            WHERE id = $6",
+        )
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .bind(if error.is_some() { "failed" } else { "finished" })
+        .bind(error.unwrap_or_default())
+        .bind(rest_calls)
+        .bind(graphql_points)
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records a collector that was not run, with the reason in place of an error
+    pub async fn skip(pool: &Pool<Sqlite>, repo_id: u32, collector: &str, reason: &str) -> Result<(), sqlx::Error> {
+        let now = stored_timestamp(chrono::Utc::now());
+        sqlx::query(
+            "INSERT INTO collection_runs (repo_id, collector, started_at, finished_at, status, error)
+            VALUES ($1, $2, $3, $3, 'skipped', $4)",
+        )
+        .bind(repo_id)
+        .bind(collector)
+        .bind(now)
+        .bind(reason)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Records how far a run covering `scope` got, so a later run can pick up from there
+    pub async fn set_checkpoint(pool: &Pool<Sqlite>, id: u32, scope: &str, checkpoint: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE collection_runs SET checkpoint_scope = $1, checkpoint = $2 WHERE id = $3")
+            .bind(scope)
+            .bind(checkpoint)
+            .bind(id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The checkpoint of the previous run of a collector covering `scope`, if that run didn't finish
+    pub async fn unfinished_checkpoint(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        collector: &str,
+        scope: &str,
+        before_id: Option<u32>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        let previous: Option<(String, String)> = sqlx::query_as(
+            "SELECT status, checkpoint FROM collection_runs
+            WHERE repo_id = $1 AND collector = $2 AND checkpoint_scope = $3 AND ($4 IS NULL OR id < $4)
+            ORDER BY id DESC
+            LIMIT 1",
+        )
+        .bind(repo_id)
+        .bind(collector)
+        .bind(scope)
+        .bind(before_id)
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(previous
+            .filter(|(status, checkpoint)| status != "finished" && !checkpoint.is_empty())
+            .map(|(_, checkpoint)| checkpoint))
+    }
+
+    /// The most recent runs, optionally for a single repository
+    pub async fn fetch_recent(
+        pool: &Pool<Sqlite>,
+        repo_id: Option<u32>,
+        limit: u32,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, repo_id, collector, started_at, finished_at, status, error, rest_calls, graphql_points, checkpoint,
+                checkpoint_scope
+            FROM collection_runs
+            WHERE $1 IS NULL OR repo_id = $1
+            ORDER BY id DESC
+            LIMIT $2",
+        )
+        .bind(repo_id)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+impl RawPayload {
+    /// Stores the JSON of a page of items in one transaction, replacing what
+    /// was stored for them before; `payloads` holds the GitHub id, the pull
+    /// request and the JSON of each
+    pub async fn create_many(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        kind: &str,
+        payloads: &[(i64, i64, &serde_json::Value)],
+    ) -> Result<(), sqlx::Error> {
+        let collected_at = stored_timestamp(chrono::Utc::now());
+        let mut transaction = pool.begin().await?;
+        for (github_id, issue_pull_id, payload) in payloads {
+            sqlx::query(
+                "INSERT INTO raw_payloads (repo_id, kind, github_id, issue_pull_id, payload, collected_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT (kind, github_id) DO UPDATE SET
+                    issue_pull_id = excluded.issue_pull_id,
+                    payload = excluded.payload,
+                    collected_at = excluded.collected_at",
+            )
+            .bind(repo_id)
+            .bind(kind)
+            .bind(github_id)
+            .bind(issue_pull_id)
+            .bind(payload.to_string())
+            .bind(collected_at)
+            .execute(&mut *transaction)
+            .await?;
+        }
+        transaction.commit().await
+    }
+}
+
+impl RepositoryEvent {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        event: &crate::github::RepositoryIssueEvent,
+    ) -> Result<(), sqlx::Error> {
+        let actor = match &event.actor {
+            Some(actor) => actor.login.clone(),
+            None => "".to_string(),
+        };
+        let issue_number = match &event.issue {
+            Some(issue) => issue.number as u32,
+            None => 0,
+        };
+
+        // Events keep their GitHub id, so re-collecting is harmless
+        sqlx::query(
+            "INSERT OR IGNORE INTO repository_events (
+                id, repo_id, issue_number, event_type, actor, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(event.id)
+        .bind(repo_id)
+        .bind(issue_number)
+        .bind(&event.event)
+        .bind(actor)
+        .bind(stored_timestamp(event.created_at))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl PullRequestComment {
+    pub async fn create_from_forge(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        comment: &crate::forge::ForgeComment,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(&format!(
+            "INSERT OR IGNORE INTO issue_pull_comments (
+                id, forge_id, issue_pull_id, author, author_association, body_length, created_at
+            ) VALUES ({}, $1, $2, $3, $4, $5, $6)",
+            next_local_id("issue_pull_comments")
+        ))
+        .bind(comment.id)
+        .bind(issue_pull_id)
+        .bind(&comment.author)
+        .bind(&comment.author_association)
+        .bind(comment.body_length)
+        .bind(stored_timestamp(comment.created_at))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        comment: &crate::github::IssueComment,
+    ) -> Result<(), sqlx::Error> {
+        let author = match &comment.user {
+            Some(user) => user.login.clone(),
+            None => "".to_string(),
+        };
+        let body_length = match &comment.body {
+            Some(body) => body.chars().count() as u32,
+            None => 0,
+        };
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO issue_pull_comments (
+                id, issue_pull_id, author, author_association, body_length, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(comment.id)
+        .bind(issue_pull_id)
+        .bind(author)
+        .bind(&comment.author_association)
+        .bind(body_length)
+        .bind(stored_timestamp(comment.created_at))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl PullRequestReviewComment {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        issue_pull_id: i64,
+        comment: &crate::github::ReviewComment,
+    ) -> Result<(), sqlx::Error> {
+        let author = match &comment.user {
+            Some(user) => user.login.clone(),
+            None => "".to_string(),
+        };
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO issue_pull_review_comments (
+                id, issue_pull_id, review_id, in_reply_to_id, author, author_association, path, body_length, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)",
+        )
+        .bind(comment.id)
+        .bind(issue_pull_id)
+        .bind(comment.pull_request_review_id)
+        .bind(comment.in_reply_to_id)
+        .bind(author)
+        .bind(&comment.author_association)
+        .bind(&comment.path)
+        .bind(comment.body.chars().count() as u32)
+        .bind(stored_timestamp(comment.created_at))
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl Commit {
+    /// Stores a commit; a commit seen on the default branch stays marked as such
+    /// even when it's later seen again on another branch
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        commit: &crate::github::RepositoryCommit,
+        on_default_branch: bool,
+    ) -> Result<(), sqlx::Error> {
+        let authored_at = commit.commit.author.as_ref().map(|signature| stored_timestamp(signature.date));
+        let committed_at = commit.commit.committer.as_ref().map(|signature| stored_timestamp(signature.date));
+
+        sqlx::query(
+            "INSERT INTO commits (
+                repo_id, sha, author, committer, authored_at, committed_at, on_default_branch
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)
+            ON CONFLICT (repo_id, sha) DO UPDATE SET
+                on_default_branch = MAX(on_default_branch, excluded.on_default_branch)",
+        )
+        .bind(repo_id)
+        .bind(&commit.sha)
+        .bind(commit.author_name())
+        .bind(commit.committer_name())
+        .bind(authored_at)
+        .bind(committed_at)
+        .bind(on_default_branch)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl RepositoryMaintainer {
+    /// Removes the previous snapshot so people who lost access are no longer listed
+    pub async fn clear(pool: &Pool<Sqlite>, repo_id: u32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM repository_maintainers WHERE repo_id = $1")
+            .bind(repo_id)
+            .execute(pool)
+            .await?;
+
+        Ok(())
+    }
+
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        login: &str,
+        permission: &str,
+        teams: &[String],
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query("INSERT INTO maintainers (login) VALUES ($1) ON CONFLICT (login) DO NOTHING")
+            .bind(login)
+            .execute(pool)
+            .await?;
+
+        sqlx::query(
+            "INSERT INTO repository_maintainers (repo_id, maintainer_id, permission, teams, collected_at)
+            SELECT $1, id, $2, $3, $4 FROM maintainers WHERE login = $5
+            ON CONFLICT (repo_id, maintainer_id) DO UPDATE SET
+                permission = excluded.permission, teams = excluded.teams, collected_at = excluded.collected_at",
+        )
+        .bind(repo_id)
+        .bind(permission)
+        .bind(teams.join(","))
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .bind(login)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+}
+
+impl GovernanceChange {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        path: &str,
+        commit: &crate::github::RepositoryCommit,
+    ) -> Result<(), sqlx::Error> {
+        let committed_at = commit.commit.committer.as_ref().map(|signature| stored_timestamp(signature.date));
+
+        sqlx::query(
+            "INSERT INTO governance_changes (repo_id, path, sha, author, committed_at, summary)
+            VALUES ($1, $2, $3, $4, $5, $6)
+            ON CONFLICT (repo_id, path, sha) DO UPDATE SET
+                author = excluded.author, committed_at = excluded.committed_at, summary = excluded.summary",
+        )
+        .bind(repo_id)
+        .bind(path)
+        .bind(&commit.sha)
+        .bind(commit.author_name())
+        .bind(committed_at)
+        .bind(commit.summary())
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Every change recorded for a repository, oldest first
+    pub async fn fetch_all(pool: &Pool<Sqlite>, repo_id: u32) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM governance_changes WHERE repo_id = $1 ORDER BY committed_at, path")
+            .bind(repo_id)
+            .fetch_all(pool)
+            .await
     }
 }
 
-impl PullRequest {
+impl Release {
     pub async fn create(
         pool: &Pool<Sqlite>,
-        pull: &octocrab::models::pulls::PullRequest,
         repo_id: u32,
-    ) -> Result<Self, sqlx::Error> {
-        let updated_at = match pull.updated_at {
-            Some(updated_at) => updated_at.to_string(),
-            None => "".to_string(),
-        };
-        let closed_at = match pull.closed_at {
-            Some(closed_at) => closed_at.to_string(),
-            None => "".to_string(),
-        };
-        let merged_at = match pull.merged_at {
-            Some(merged_at) => merged_at.to_string(),
-            None => "".to_string(),
-        };
-        let author_login = match &pull.user {
-            Some(user) => user.login.to_string(),
-            None => "".to_string(),
-        };
-        let author_association = match &pull.author_association {
-            Some(association) => format!("{:?}", association),
-            None => "".to_string(),
-        };
-        let state = match &pull.state {
-            Some(state) => format!("{:?}", state),
+        release: &octocrab::models::repos::Release,
+    ) -> Result<(), sqlx::Error> {
+        let author = match &release.author {
+            Some(author) => author.login.clone(),
             None => "".to_string(),
         };
 
-        let id = sqlx::query(
-            "INSERT INTO pulls (
-                id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author, author_association
-            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+        sqlx::query(
+            "INSERT OR REPLACE INTO releases (
+                id, repo_id, tag_name, name, author, prerelease, draft, published_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
         )
-        .bind(pull.id.to_string())
+        .bind(release.id.into_inner() as i64)
         .bind(repo_id)
-        .bind(pull.number.to_string())
-        .bind(pull.title.clone().unwrap_or("".to_string()))
-        .bind(state)
-        .bind(pull.created_at.unwrap().to_string()) // "created_at" should always be set
-        .bind(updated_at)
-        .bind(closed_at)
-        .bind(merged_at)
-        .bind(author_login)
-        .bind(author_association)
+        .bind(&release.tag_name)
+        .bind(release.name.clone().unwrap_or_default())
+        .bind(author)
+        .bind(release.prerelease)
+        .bind(release.draft)
+        .bind(release.published_at.map(stored_timestamp))
         .execute(pool)
         .await?;
 
-        let issue_pull: Self = sqlx::query_as(
-            "SELECT id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author, author_association 
-            FROM pulls WHERE id = $1",
+        Ok(())
+    }
+
+    /// Published, non-draft releases, oldest first
+    pub async fn fetch_published(pool: &Pool<Sqlite>, repo_id: u32) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT id, repo_id, tag_name, name, author, prerelease, draft, published_at
+            FROM releases
+            WHERE repo_id = $1 AND draft = 0 AND published_at IS NOT NULL
+            ORDER BY published_at",
         )
-        .bind(id.last_insert_rowid())
-        .fetch_one(pool)
+        .bind(repo_id)
+        .fetch_all(pool)
+        .await
+    }
+}
+
+impl Deployment {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        deployment: &crate::github::Deployment,
+    ) -> Result<(), sqlx::Error> {
+        let creator = match &deployment.creator {
+            Some(creator) => creator.login.clone(),
+            None => "".to_string(),
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO deployments (
+                id, repo_id, sha, git_ref, environment, creator, created_at
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7)",
+        )
+        .bind(deployment.id)
+        .bind(repo_id)
+        .bind(&deployment.sha)
+        .bind(&deployment.git_ref)
+        .bind(&deployment.environment)
+        .bind(creator)
+        .bind(stored_timestamp(deployment.created_at))
+        .execute(pool)
         .await?;
 
-        Ok(issue_pull)
+        Ok(())
     }
+}
 
-    pub async fn fetch(pool: &Pool<Sqlite>, repo_id:u32, number: u32) -> Result<Self, sqlx::Error> {
-        let pull_request: PullRequest = sqlx::query_as("
-            SELECT id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author, author_association
-            FROM pulls WHERE repo_id = $1 AND number = $2
-        ")
-        .bind(repo_id)
-        .bind(number)
-        .fetch_one(pool)
+impl DeploymentStatus {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        deployment_id: i64,
+        status: &crate::github::DeploymentStatus,
+    ) -> Result<(), sqlx::Error> {
+        let creator = match &status.creator {
+            Some(creator) => creator.login.clone(),
+            None => "".to_string(),
+        };
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO deployment_statuses (
+                id, deployment_id, state, creator, created_at
+            ) VALUES ($1, $2, $3, $4, $5)",
+        )
+        .bind(status.id)
+        .bind(deployment_id)
+        .bind(&status.state)
+        .bind(creator)
+        .bind(stored_timestamp(status.created_at))
+        .execute(pool)
         .await?;
 
-        Ok(pull_request)
+        Ok(())
     }
+}
 
-    pub async fn fetch_many(pool: &Pool<Sqlite>, repo_id:u32, numbers: &Vec<u32>) -> Result<Vec<Self>, sqlx::Error> {
-        let query_str: String;
+impl Stargazer {
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        stargazer: &crate::github::Stargazer,
+    ) -> Result<(), sqlx::Error> {
+        let login = match &stargazer.user {
+            Some(user) => user.login.clone(),
+            None => "".to_string(),
+        };
 
-        if numbers.is_empty() {
-            query_str = "
-                SELECT id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author, author_association
-                FROM pulls WHERE repo_id = ?".to_string();
-        } else {
-            let params = format!("?{}", ", ?".repeat(numbers.len() - 1));
-            query_str = format!("
-                SELECT id, repo_id, number, title, state, created_at, updated_at, closed_at, merged_at, author, author_association
-                FROM pulls WHERE repo_id = ? AND number IN ( {} )
-            ", params);
-        }
+        sqlx::query(
+            "INSERT OR IGNORE INTO stargazers (repo_id, login, starred_at) VALUES ($1, $2, $3)",
+        )
+        .bind(repo_id)
+        .bind(login)
+        .bind(stored_timestamp(stargazer.starred_at))
+        .execute(pool)
+        .await?;
 
-        let mut query = sqlx::query_as(&query_str)
-            .bind(repo_id);
+        Ok(())
+    }
 
-        for number in numbers {
-            query = query.bind(number);
-        }
-        let pull_requests: Vec<PullRequest> = query.fetch_all(pool).await?;
+    pub async fn clear(pool: &Pool<Sqlite>, repo_id: u32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM stargazers WHERE repo_id = $1")
+            .bind(repo_id)
+            .execute(pool)
+            .await?;
 
-        Ok(pull_requests)
+        Ok(())
     }
 }
 
-impl PullRequestReview {
+impl Fork {
     pub async fn create(
         pool: &Pool<Sqlite>,
-        issue_pull_id: u32,
-        review: &octocrab::models::pulls::Review,
-    ) -> Result<Self, sqlx::Error> {
-        let reviewer= match &review.user{
-            Some(user) => user.login.to_string(),
-            None => "".to_string(),
-        };
-        let state = match review.state {
-            Some(state) => format!("{:?}", state),
-            None => "".to_string(),
-        };
-        let author_association = match &review.author_association {
-            Some(association) => format!("{:?}", association),
-            None => "".to_string(),
-        };
-        let submitted_at = match review.submitted_at {
-            Some(submitted_at) => submitted_at.to_string(),
+        repo_id: u32,
+        fork: &octocrab::models::Repository,
+    ) -> Result<(), sqlx::Error> {
+        let owner = match &fork.owner {
+            Some(owner) => owner.login.clone(),
             None => "".to_string(),
         };
-        let id = sqlx::query(
-            "INSERT INTO issue_pull_reviews (
-                issue_pull_id, reviewer, state, author_association, submitted_at
-            ) VALUES ($1, $2, $3, $4, $5)",
+
+        sqlx::query(
+            "INSERT OR IGNORE INTO forks (id, repo_id, owner, name, created_at) VALUES ($1, $2, $3, $4, $5)",
         )
-        .bind(issue_pull_id)
-        .bind(reviewer)
-        .bind(state)
-        .bind(author_association)
-        .bind(submitted_at)
+        .bind(fork.id.into_inner() as i64)
+        .bind(repo_id)
+        .bind(owner)
+        .bind(&fork.name)
+        .bind(fork.created_at.map(stored_timestamp))
         .execute(pool)
         .await?;
 
-        let issue_pull_review: Self = sqlx::query_as(
-            "SELECT id, issue_pull_id, reviewer, state, author_association, submitted_at 
-            FROM issue_pull_reviews WHERE id = $1",
+        Ok(())
+    }
+}
+
+impl WebhookDelivery {
+    /// Stores a delivery, returning `false` when one with the same delivery id
+    /// was already stored
+    ///
+    /// Only verified deliveries are stored. One replaces an unverified delivery
+    /// with the same id that older versions kept, so asking GitHub for a
+    /// redelivery still works for those.
+    pub async fn create(
+        pool: &Pool<Sqlite>,
+        delivery_id: &str,
+        hook_id: Option<i64>,
+        repository: &str,
+        event: &str,
+        action: &str,
+        payload: &str,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query("DELETE FROM webhook_deliveries WHERE delivery_id = $1 AND signature_valid = 0")
+            .bind(delivery_id)
+            .execute(pool)
+            .await?;
+
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO webhook_deliveries (
+                delivery_id, hook_id, repository, event, action, signature_valid, received_at, payload
+            ) VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
         )
-        .bind(id.last_insert_rowid())
-        .fetch_one(pool)
+        .bind(delivery_id)
+        .bind(hook_id)
+        .bind(repository)
+        .bind(event)
+        .bind(action)
+        .bind(true)
+        .bind(stored_timestamp(chrono::Utc::now()))
+        .bind(payload)
+        .execute(pool)
         .await?;
 
-        Ok(issue_pull_review)
+        Ok(result.rows_affected() > 0)
+    }
+
+    pub async fn fetch(pool: &Pool<Sqlite>, delivery_id: &str) -> Result<Self, sqlx::Error> {
+        sqlx::query_as("SELECT * FROM webhook_deliveries WHERE delivery_id = $1")
+            .bind(delivery_id)
+            .fetch_one(pool)
+            .await
+    }
+
+    /// Most recent deliveries first, optionally only those about one repository
+    pub async fn fetch_recent(
+        pool: &Pool<Sqlite>,
+        repository: Option<&str>,
+        limit: u32,
+    ) -> Result<Vec<Self>, sqlx::Error> {
+        sqlx::query_as(
+            "SELECT * FROM webhook_deliveries
+            WHERE $1 IS NULL OR repository = $1
+            ORDER BY received_at DESC
+            LIMIT $2",
+        )
+        .bind(repository)
+        .bind(limit)
+        .fetch_all(pool)
+        .await
+    }
+
+    /// Delivery ids already stored for a repository
+    pub async fn fetch_known_ids(pool: &Pool<Sqlite>, repository: &str) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar("SELECT delivery_id FROM webhook_deliveries WHERE repository = $1")
+            .bind(repository)
+            .fetch_all(pool)
+            .await
+    }
+
+    /// The most recently seen hook id for a repository
+    pub async fn latest_hook_id(pool: &Pool<Sqlite>, repository: &str) -> Result<Option<i64>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT hook_id FROM webhook_deliveries
+            WHERE repository = $1 AND hook_id IS NOT NULL
+            ORDER BY received_at DESC
+            LIMIT 1",
+        )
+        .bind(repository)
+        .fetch_optional(pool)
+        .await
+        .map(Option::flatten)
     }
 }
 
-impl PullRequestEvent {
+impl Contributor {
     pub async fn create(
         pool: &Pool<Sqlite>,
-        issue_pull_id: u32,
-        event: &octocrab::models::timelines::TimelineEvent,
-    ) -> Result<Self, sqlx::Error> {
-        let actor = match &event.actor {
-            Some(user) => user.login.to_string(),
-            None => "".to_string(),
-        };
-        let created_at = match event.created_at {
-            Some(created_at) => created_at.to_string(),
-            None => "".to_string(),
-        };
-        let event_type = format!("{:?}", &event.event);
-        let author_association = match &event.author_association {
-            Some(association) => association.clone(),
-            None => "".to_string(),
-        };
+        repo_id: u32,
+        contributor: &crate::github::Contributor,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            "INSERT INTO contributors (repo_id, login, contributions) VALUES ($1, $2, $3)",
+        )
+        .bind(repo_id)
+        .bind(contributor.display_name())
+        .bind(contributor.contributions)
+        .execute(pool)
+        .await?;
+
+        Ok(())
+    }
 
-        let mut has_event_id = false;
-        let mut event_id: u32 = 0;
+    /// Removes the contributors and weekly activity of a repository
+    pub async fn clear(pool: &Pool<Sqlite>, repo_id: u32) -> Result<(), sqlx::Error> {
+        sqlx::query("DELETE FROM contributors WHERE repo_id = $1")
+            .bind(repo_id)
+            .execute(pool)
+            .await?;
+        sqlx::query("DELETE FROM contributor_weeks WHERE repo_id = $1")
+            .bind(repo_id)
+            .execute(pool)
+            .await?;
 
-        if let Some(event_timeline_id) = event.id {
-            has_event_id = true;
-            match event_timeline_id.to_string().parse::<u32>() {
-                Ok(id) => event_id = id,
-                Err(_) => {
-                    has_event_id = false;
-                }
-            }
+        Ok(())
+    }
+}
+
+impl ContributorWeek {
+    /// Stores the weeks of a contributor that had any commits
+    pub async fn create_many(
+        pool: &Pool<Sqlite>,
+        repo_id: u32,
+        stats: &crate::github::ContributorStats,
+    ) -> Result<(), sqlx::Error> {
+        let login = match &stats.author {
+            Some(author) => author.login.clone(),
+            None => "".to_string(),
         };
 
-        let result = if has_event_id {
+        for week in stats.weeks.iter().filter(|week| week.commits > 0) {
+            let Some(start) = chrono::DateTime::from_timestamp(week.week, 0) else {
+                continue;
+            };
             sqlx::query(
-                "INSERT OR IGNORE INTO issue_pull_events (
-                    id, issue_pull_id, event_type, actor, author_association, created_at
-                ) VALUES ($1, $2, $3, $4, $5, $6)",
+                "INSERT INTO contributor_weeks (repo_id, login, week, commits, additions, deletions)
+                VALUES ($1, $2, $3, $4, $5, $6)",
             )
-                .bind(event_id)
-                .bind(issue_pull_id)
-                .bind(event_type)
-                .bind(actor)
-                .bind(author_association)
-                .bind(created_at)
-                .execute(pool)
-                .await?
-        } else {
-            sqlx::query(
-                "INSERT INTO issue_pull_events (
-                    issue_pull_id, event_type, actor, author_association, created_at
-                ) VALUES ($1, $2, $3, $4, $5)",
+            .bind(repo_id)
+            .bind(&login)
+            .bind(start.date_naive().to_string())
+            .bind(week.commits)
+            .bind(week.additions)
+            .bind(week.deletions)
+            .execute(pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use octocrab::models::Event;
+
+    use super::*;
+    use crate::forge::ForgeEvent;
+
+    static DATABASES: AtomicUsize = AtomicUsize::new(0);
+
+    /// A database file in the temporary directory, removed along with its WAL files when dropped
+    struct TestDb {
+        path: PathBuf,
+    }
+
+    impl TestDb {
+        fn new() -> Self {
+            let name = format!("gdfm-test-{}-{}.db", std::process::id(), DATABASES.fetch_add(1, Ordering::SeqCst));
+            let db = Self { path: std::env::temp_dir().join(name) };
+            db.remove();
+            db
+        }
+
+        async fn open(&self) -> Pool<Sqlite> {
+            open_db(&self.path).await.unwrap()
+        }
+
+        fn remove(&self) {
+            for suffix in ["", "-wal", "-shm"] {
+                let mut path = self.path.clone().into_os_string();
+                path.push(suffix);
+                let _ = std::fs::remove_file(path);
+            }
+        }
+    }
+
+    impl Drop for TestDb {
+        fn drop(&mut self) {
+            self.remove();
+        }
+    }
+
+    async fn execute(pool: &Pool<Sqlite>, statements: &[&str]) {
+        for statement in statements {
+            sqlx::query(statement).execute(pool).await.unwrap();
+        }
+    }
+
+    const PULL_VALUES: &str = "'title', 'open', '2025-03-29 14:01:02', '2025-03-29 14:01:02', 'octocat', 'NONE'";
+
+    async fn insert_pull(pool: &Pool<Sqlite>, id: i64, repo_id: i64, number: i64) {
+        sqlx::query(&format!(
+            "INSERT INTO pulls (id, repo_id, number, title, state, created_at, updated_at, author, author_association)
+            VALUES ($1, $2, $3, {})",
+            PULL_VALUES
+        ))
+        .bind(id)
+        .bind(repo_id)
+        .bind(number)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    async fn insert_event(pool: &Pool<Sqlite>, id: i64, issue_pull_id: i64) {
+        sqlx::query(
+            "INSERT INTO issue_pull_events (id, issue_pull_id, event_type, actor, author_association, created_at)
+            VALUES ($1, $2, 'Merged', 'octocat', 'NONE', '2025-03-29 14:01:02')",
+        )
+        .bind(id)
+        .bind(issue_pull_id)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn migrate_forge_ids_moves_issues_and_other_forges_to_local_ids() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &[
+            "INSERT INTO repositories (id, owner, name, forge) VALUES (1, 'octo', 'repo', 'github')",
+            "INSERT INTO repositories (id, owner, name, forge) VALUES (2, 'group', 'project', 'gitlab')",
+        ])
+        .await;
+        insert_pull(&pool, 100, 1, 1).await;
+        insert_pull(&pool, 200, 2, 1).await;
+        insert_pull(&pool, 201, 2, 2).await;
+        insert_pull(&pool, 300, 1, 2).await;
+        execute(&pool, &["UPDATE pulls SET is_issue = 1 WHERE id = 300"]).await;
+        insert_event(&pool, 5, 200).await;
+        insert_event(&pool, 6, 100).await;
+        insert_event(&pool, 8, 300).await;
+        execute(&pool, &[
+            "INSERT INTO issue_pull_comments (id, issue_pull_id, author, author_association, body_length, created_at)
+            VALUES (7, 201, 'octocat', 'NONE', 10, '2025-03-29 14:01:02')",
+        ])
+        .await;
+
+        migrate_forge_ids(&pool).await.unwrap();
+        // Nothing is left to move the second time
+        migrate_forge_ids(&pool).await.unwrap();
+
+        let pulls: Vec<(i64, i64, Option<i64>)> =
+            sqlx::query_as("SELECT id, number, forge_id FROM pulls ORDER BY repo_id, number")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(pulls, vec![(100, 1, None), (-3, 2, Some(300)), (-1, 1, Some(200)), (-2, 2, Some(201))]);
+
+        let events: Vec<(i64, i64)> = sqlx::query_as("SELECT id, issue_pull_id FROM issue_pull_events ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        // GitHub's ids of the events on issues stay
+        assert_eq!(events, vec![(-5, -1), (6, 100), (8, -3)]);
+
+        let comments: Vec<(i64, i64, Option<i64>)> =
+            sqlx::query_as("SELECT id, issue_pull_id, forge_id FROM issue_pull_comments")
+                .fetch_all(&pool)
+                .await
+                .unwrap();
+        assert_eq!(comments, vec![(-7, -2, Some(7))]);
+    }
+
+    #[tokio::test]
+    async fn migrate_timestamps_converts_legacy_databases() {
+        let db = TestDb::new();
+        let options = SqliteConnectOptions::new().filename(&db.path).create_if_missing(true);
+        let legacy = SqlitePoolOptions::new().connect_with(options).await.unwrap();
+        execute(&legacy, &[
+            "CREATE TABLE repositories (id INTEGER PRIMARY KEY, owner TEXT NOT NULL, name TEXT NOT NULL)",
+            "CREATE TABLE pulls(
+                id INTEGER PRIMARY KEY,
+                repo_id INTEGER NOT NULL,
+                number INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                state TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                closed_at TEXT NOT NULL,
+                merged_at TEXT NOT NULL,
+                author TEXT NOT NULL,
+                author_association TEXT NOT NULL,
+                FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+            )",
+            "INSERT INTO repositories VALUES (1, 'octo', 'repo')",
+            "INSERT INTO pulls VALUES (1, 1, 1, 'title', 'closed', '2025-03-29 14:01:02 UTC', '',
+                '2025-03-30 08:00:00 UTC', '', 'octocat', 'NONE')",
+        ])
+        .await;
+        legacy.close().await;
+
+        let pool = db.open().await;
+
+        let pull: (String, String, Option<String>, Option<String>) =
+            sqlx::query_as("SELECT created_at, updated_at, closed_at, merged_at FROM pulls")
+                .fetch_one(&pool)
+                .await
+                .unwrap();
+        assert_eq!(
+            pull,
+            (
+                "2025-03-29 14:01:02".to_string(),
+                "2025-03-29 14:01:02".to_string(),
+                Some("2025-03-30 08:00:00".to_string()),
+                None
             )
-                .bind(issue_pull_id)
-                .bind(event_type)
-                .bind(actor)
-                .bind(author_association)
-                .bind(created_at)
-                .execute(pool)
-                .await?
+        );
+        let columns = table_info(&pool, "pulls").await.unwrap();
+        let merged_at = columns.iter().find(|column| column.name == "merged_at").unwrap();
+        assert!(!merged_at.not_null);
+        let version: i64 = sqlx::query_scalar("PRAGMA user_version").fetch_one(&pool).await.unwrap();
+        assert_eq!(version, SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn version_1_databases_let_pulls_go_without_a_creation_time() {
+        let db = TestDb::new();
+        let options = SqliteConnectOptions::new().filename(&db.path).create_if_missing(true);
+        let old = SqlitePoolOptions::new().connect_with(options).await.unwrap();
+        execute(&old, &[
+            "CREATE TABLE repositories (id INTEGER PRIMARY KEY, owner TEXT NOT NULL, name TEXT NOT NULL)",
+            "CREATE TABLE pulls(
+                id INTEGER PRIMARY KEY,
+                repo_id INTEGER NOT NULL,
+                number INTEGER NOT NULL,
+                title TEXT NOT NULL,
+                state TEXT NOT NULL,
+                created_at TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                closed_at TEXT,
+                merged_at TEXT,
+                author TEXT NOT NULL,
+                author_association TEXT NOT NULL,
+                FOREIGN KEY (repo_id) REFERENCES repositories (id) ON DELETE CASCADE
+            )",
+            "INSERT INTO repositories VALUES (1, 'octo', 'repo')",
+            "INSERT INTO pulls VALUES (1, 1, 1, 'title', 'open', '2025-03-29 14:01:02', '2025-03-29 14:01:02',
+                NULL, NULL, 'octocat', 'NONE')",
+            "PRAGMA user_version = 1",
+        ])
+        .await;
+        old.close().await;
+
+        let pool = db.open().await;
+
+        let columns = table_info(&pool, "pulls").await.unwrap();
+        let created_at = columns.iter().find(|column| column.name == "created_at").unwrap();
+        assert!(!created_at.not_null);
+        let pull = PullRequest::fetch(&pool, 1, 1).await.unwrap();
+        assert_eq!(pull.created_at.map(|at| at.to_rfc3339()), Some("2025-03-29T14:01:02+00:00".to_string()));
+
+        execute(&pool, &["UPDATE pulls SET created_at = NULL"]).await;
+        assert_eq!(PullRequest::fetch(&pool, 1, 1).await.unwrap().created_at, None);
+    }
+
+    #[tokio::test]
+    async fn pulls_with_events_count_as_collected_after_migrating() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &[
+            "INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'repo')",
+            "PRAGMA user_version = 2",
+        ])
+        .await;
+        insert_pull(&pool, 1, 1, 1).await;
+        insert_pull(&pool, 2, 1, 2).await;
+        insert_event(&pool, 1, 1).await;
+        pool.close().await;
+
+        let pool = db.open().await;
+        let numbers = |pulls: Vec<PullRequest>| pulls.iter().map(|pull| pull.number).collect::<Vec<_>>();
+        assert_eq!(numbers(PullRequest::fetch_events_not_collected(&pool, 1).await.unwrap()), [2]);
+
+        PullRequest::set_events_collected(&pool, 2).await.unwrap();
+        assert!(PullRequest::fetch_events_not_collected(&pool, 1).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn read_only_opens_leave_old_databases_alone() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &["PRAGMA user_version = 1"]).await;
+        pool.close().await;
+
+        let pool = open_db_read_only(&db.path).await.unwrap();
+        assert!(sqlx::query("DELETE FROM pulls").execute(&pool).await.is_err());
+        pool.close().await;
+        assert_eq!(stored_schema_version(&db.path).await.unwrap(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn replacing_files_drops_those_no_longer_changed() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &["INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'repo')"]).await;
+        insert_pull(&pool, 1, 1, 1).await;
+        let file = |name: &str| -> octocrab::models::repos::DiffEntry {
+            serde_json::from_value(serde_json::json!({
+                "sha": null, "filename": name, "status": "modified", "additions": 1, "deletions": 2, "changes": 3,
+                "blob_url": null, "raw_url": null, "contents_url": "https://api.github.com/repos/octo/repo/contents/x",
+            }))
+            .unwrap()
         };
+        let paths = || async {
+            sqlx::query_scalar::<_, String>("SELECT path FROM issue_pull_files ORDER BY path")
+                .fetch_all(&pool)
+                .await
+                .unwrap()
+        };
+
+        PullRequestFile::replace(&pool, 1, &[file("README.md"), file("src/lib.rs")]).await.unwrap();
+        assert_eq!(paths().await, ["README.md", "src/lib.rs"]);
+        PullRequestFile::replace(&pool, 1, &[file("src/lib.rs")]).await.unwrap();
+        assert_eq!(paths().await, ["src/lib.rs"]);
+    }
+
+    #[tokio::test]
+    async fn merge_database_translates_repositories_and_adds_nothing_twice() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &["INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'repo')"]).await;
+
+        let other = TestDb::new();
+        let other_pool = other.open().await;
+        execute(&other_pool, &[
+            "INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'other')",
+            "INSERT INTO repositories (id, owner, name) VALUES (2, 'octo', 'repo')",
+        ])
+        .await;
+        insert_pull(&other_pool, 10, 2, 1).await;
+        insert_pull(&other_pool, 11, 1, 1).await;
+        insert_event(&other_pool, 20, 10).await;
+        other_pool.close().await;
+
+        let path = other.path.to_str().unwrap();
+        assert!(merge_database(&pool, path, false).await.unwrap() > 0);
+        assert_eq!(merge_database(&pool, path, false).await.unwrap(), 0);
 
-        let issue_pull_event: Self = sqlx::query_as(
-            "SELECT id, issue_pull_id, event_type, actor, created_at 
-            FROM issue_pull_events WHERE id = $1",
+        let pulls: Vec<(i64, String)> = sqlx::query_as(
+            "SELECT p.id, r.name FROM pulls p JOIN repositories r ON r.id = p.repo_id ORDER BY p.id",
         )
-        .bind(result.last_insert_rowid())
-        .fetch_one(pool)
-        .await?;
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+        assert_eq!(pulls, vec![(10, "repo".to_string()), (11, "other".to_string())]);
+        let repo_id: i64 = sqlx::query_scalar("SELECT repo_id FROM pulls WHERE id = 10")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(repo_id, 1);
+        let events: Vec<(i64, i64)> = sqlx::query_as("SELECT id, issue_pull_id FROM issue_pull_events")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(events, vec![(20, 10)]);
+    }
+
+    #[tokio::test]
+    async fn merge_database_gives_local_ids_of_its_own() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &["INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'repo')"]).await;
+        insert_pull(&pool, -1, 1, 5).await;
+        insert_event(&pool, -1, -1).await;
 
-        Ok(issue_pull_event)
+        let other = TestDb::new();
+        let other_pool = other.open().await;
+        execute(&other_pool, &["INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'repo')"]).await;
+        // The same issue as the stored -1, and another one under the same id
+        insert_pull(&other_pool, -1, 1, 7).await;
+        insert_pull(&other_pool, -2, 1, 5).await;
+        insert_event(&other_pool, -1, -1).await;
+        execute(&other_pool, &[
+            "INSERT INTO issue_pull_comments (id, issue_pull_id, author, author_association, body_length, created_at)
+            VALUES (-1, -1, 'octocat', 'NONE', 10, '2025-03-29 14:01:02')",
+        ])
+        .await;
+        other_pool.close().await;
+
+        let path = other.path.to_str().unwrap();
+        assert!(merge_database(&pool, path, false).await.unwrap() > 0);
+        assert_eq!(merge_database(&pool, path, false).await.unwrap(), 0);
+
+        let pulls: Vec<(i64, i64)> = sqlx::query_as("SELECT id, number FROM pulls ORDER BY id DESC")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(pulls, vec![(-1, 5), (-2, 7)]);
+        let events: Vec<(i64, i64)> = sqlx::query_as("SELECT id, issue_pull_id FROM issue_pull_events ORDER BY id DESC")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(events, vec![(-1, -1), (-2, -2)]);
+        let comments: Vec<(i64, i64)> = sqlx::query_as("SELECT id, issue_pull_id FROM issue_pull_comments")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(comments, vec![(-1, -2)]);
+    }
+
+    #[tokio::test]
+    async fn replacing_events_keeps_the_timeline_only() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &["INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'repo')"]).await;
+        insert_pull(&pool, 100, 1, 1).await;
+        // An event GitHub deleted since, and one stored without an id
+        insert_event(&pool, 1, 100).await;
+        insert_event(&pool, -1, 100).await;
+        insert_event(&pool, 2, 100).await;
+
+        let event = |id| ForgeEvent {
+            id,
+            event: Event::Closed,
+            actor: "octocat".to_string(),
+            author_association: "MEMBER".to_string(),
+            created_at: None,
+            requested_reviewer: String::new(),
+            raw: None,
+        };
+        let (listed, unlisted) = (event(Some(2)), event(None));
+        PullRequestEvent::replace(&pool, 100, &[&listed, &unlisted], &[2]).await.unwrap();
+
+        let events: Vec<(i64, String)> = sqlx::query_as("SELECT id, event_type FROM issue_pull_events ORDER BY id")
+            .fetch_all(&pool)
+            .await
+            .unwrap();
+        assert_eq!(events, vec![(-1, "Closed".to_string()), (2, "Closed".to_string())]);
+    }
+
+    #[tokio::test]
+    async fn unfinished_checkpoints_are_kept_per_scope() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+        execute(&pool, &["INSERT INTO repositories (id, owner, name) VALUES (1, 'octo', 'repo')"]).await;
+
+        let closed = CollectionRun::start(&pool, 1, "pulls").await.unwrap();
+        CollectionRun::set_checkpoint(&pool, closed, "pulls:closed", "number=10").await.unwrap();
+        let open = CollectionRun::start(&pool, 1, "pulls").await.unwrap();
+        CollectionRun::set_checkpoint(&pool, open, "pulls:open", "number=20").await.unwrap();
+        CollectionRun::finish(&pool, open, Some("network error"), None, None).await.unwrap();
+
+        let checkpoint = |scope| CollectionRun::unfinished_checkpoint(&pool, 1, "pulls", scope, None);
+        assert_eq!(checkpoint("pulls:closed").await.unwrap().as_deref(), Some("number=10"));
+        assert_eq!(checkpoint("pulls:open").await.unwrap().as_deref(), Some("number=20"));
+        assert_eq!(checkpoint("pulls:closed@2/5").await.unwrap(), None);
+
+        // A run of the same scope that finished leaves nothing to resume
+        let again = CollectionRun::start(&pool, 1, "pulls").await.unwrap();
+        CollectionRun::set_checkpoint(&pool, again, "pulls:closed", "").await.unwrap();
+        CollectionRun::finish(&pool, again, None, None, None).await.unwrap();
+        assert_eq!(checkpoint("pulls:closed").await.unwrap(), None);
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn table_helpers_reject_unknown_tables() {
+        let db = TestDb::new();
+        let pool = db.open().await;
+
+        assert!(table_columns(&pool, "pulls").await.is_ok());
+        assert!(table_columns(&pool, "pulls') UNION SELECT sql FROM sqlite_master --").await.is_err());
+        assert!(table_info(&pool, "sqlite_master").await.is_err());
+        assert!(fetch_table(&pool, "pulls; DROP TABLE pulls").await.is_err());
+        assert!(PullRequest::fetch_without(&pool, 1, "sqlite_master t, pulls").await.is_err());
+    }
+}
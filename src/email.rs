@@ -0,0 +1,58 @@
+/// Sends generated reports by email over SMTP
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use thiserror::Error;
+
+use crate::config::EmailConfig;
+use crate::constants::SMTP_PASSWORD_ENV;
+
+/// Port used for implicit TLS; every other port upgrades with STARTTLS
+const IMPLICIT_TLS_PORT: u16 = 465;
+const DEFAULT_PORT: u16 = 587;
+
+#[derive(Debug, Error)]
+pub enum EmailError {
+    #[error("Missing email.{0} in config file")]
+    MissingSetting(&'static str),
+    #[error("No recipients configured in email.recipients")]
+    NoRecipients,
+    #[error("Invalid email address: {0}")]
+    Address(#[from] lettre::address::AddressError),
+    #[error("Unable to build email: {0}")]
+    Message(#[from] lettre::error::Error),
+    #[error("Unable to send email: {0}")]
+    Smtp(#[from] lettre::transport::smtp::Error),
+}
+
+/// Emails an HTML document to every configured recipient
+pub async fn send_html(config: &EmailConfig, subject: &str, html: String) -> Result<(), EmailError> {
+    let host = config.smtp_host.as_deref().ok_or(EmailError::MissingSetting("smtp_host"))?;
+    let from = config.from.as_deref().ok_or(EmailError::MissingSetting("from"))?;
+    if config.recipients.is_empty() {
+        return Err(EmailError::NoRecipients);
+    }
+
+    let mut message = Message::builder().from(from.parse()?).subject(subject);
+    for recipient in &config.recipients {
+        message = message.to(recipient.parse()?);
+    }
+    let message = message.header(ContentType::TEXT_HTML).body(html)?;
+
+    let port = config.smtp_port.unwrap_or(DEFAULT_PORT);
+    let mut transport = if port == IMPLICIT_TLS_PORT {
+        AsyncSmtpTransport::<Tokio1Executor>::relay(host)?
+    } else {
+        AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(host)?
+    }
+    .port(port);
+
+    if let Some(username) = &config.username {
+        let password = std::env::var(SMTP_PASSWORD_ENV).unwrap_or_default();
+        transport = transport.credentials(Credentials::new(username.clone(), password));
+    }
+
+    transport.build().send(message).await?;
+
+    Ok(())
+}
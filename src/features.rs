@@ -0,0 +1,110 @@
+/// Finds out which optional APIs the GitHub host offers
+///
+/// github.com has every API gdfm uses, but a GitHub Enterprise Server only has
+/// those of its release. The release is read from `GET /meta` and what it
+/// supports is stored per host in `host_features`, so collectors needing a
+/// missing API are skipped rather than failing halfway, and the host is only
+/// asked again once the answer is a week old.
+use chrono::{Duration, Utc};
+use serde::Deserialize;
+
+use crate::client::{api_host, disable_api_version, quiet_client};
+use crate::database::HostFeature;
+use crate::storage::Storage;
+
+/// `X-GitHub-Api-Version`, which older servers reject
+pub const API_VERSIONS: &str = "api-versions";
+
+/// Days before what is stored about a host is checked again
+const CHECK_EVERY_DAYS: i64 = 7;
+
+/// An API some part of gdfm depends on
+pub struct Feature {
+    pub name: &'static str,
+    /// Collector skipped when the API is missing
+    pub collector: Option<&'static str>,
+    /// First GitHub Enterprise Server release with the API
+    pub since: (u32, u32),
+}
+
+/// Every API checked for, in the order they are listed
+pub const FEATURES: &[Feature] = &[
+    Feature { name: API_VERSIONS, collector: None, since: (3, 9) },
+    Feature { name: "discussions", collector: Some("discussions"), since: (3, 6) },
+    Feature { name: "sbom", collector: Some("dependencies"), since: (3, 9) },
+    Feature { name: "merge-queue", collector: None, since: (3, 12) },
+];
+
+#[derive(Debug, Deserialize)]
+struct Meta {
+    /// Only GitHub Enterprise Server has it, e.g. `3.8.2`
+    installed_version: Option<String>,
+}
+
+/// What the API host supports, asking it when nothing recent is stored or
+/// `refresh` is set
+///
+/// Clients built afterwards leave out `X-GitHub-Api-Version` if the host
+/// predates it. When the host can't be asked, whatever was stored before is
+/// returned, which may be nothing; collectors then try their APIs anyway.
+pub async fn host_features(storage: &dyn Storage, refresh: bool) -> Result<Vec<HostFeature>, sqlx::Error> {
+    let features = detect_features(storage, refresh).await?;
+    if features.iter().any(|feature| feature.feature == API_VERSIONS && !feature.available) {
+        disable_api_version();
+    }
+
+    Ok(features)
+}
+
+async fn detect_features(storage: &dyn Storage, refresh: bool) -> Result<Vec<HostFeature>, sqlx::Error> {
+    let host = &api_host();
+    let stored = storage.get_host_features(host).await?;
+    let stale = Utc::now() - Duration::days(CHECK_EVERY_DAYS);
+    let is_current = stored.len() == FEATURES.len() && stored.iter().all(|feature| feature.checked_at > stale);
+    if is_current && !refresh {
+        return Ok(stored);
+    }
+
+    let Some(meta) = fetch_meta().await else {
+        return Ok(stored);
+    };
+    let release = meta.installed_version.as_deref().and_then(parse_release);
+    for feature in FEATURES {
+        match (release, &meta.installed_version) {
+            (Some(release), Some(version)) if release < feature.since => {
+                let reason = format!(
+                    "needs GitHub Enterprise Server {}.{} or later, {} runs {}",
+                    feature.since.0, feature.since.1, host, version
+                );
+                storage.set_host_feature(host, feature.name, false, &reason).await?;
+            }
+            _ => storage.set_host_feature(host, feature.name, true, "").await?,
+        }
+    }
+
+    storage.get_host_features(host).await
+}
+
+async fn fetch_meta() -> Option<Meta> {
+    quiet_client().ok()?.get("/meta", None::<&()>).await.ok()
+}
+
+/// Major and minor release of a version such as `3.8.2`
+fn parse_release(version: &str) -> Option<(u32, u32)> {
+    let mut parts = version.split('.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+
+    Some((major, minor))
+}
+
+/// Collectors the API host has no API for, with the reason
+pub async fn unavailable_collectors(storage: &dyn Storage) -> Result<Vec<(&'static str, String)>, sqlx::Error> {
+    let features = host_features(storage, false).await?;
+    let missing = |name: &str| features.iter().find(|feature| feature.feature == name && !feature.available);
+
+    Ok(FEATURES
+        .iter()
+        .filter_map(|feature| Some((feature.collector?, missing(feature.name)?.reason.clone())))
+        .collect())
+}
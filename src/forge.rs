@@ -0,0 +1,175 @@
+/// The `Forge` trait collectors use to read from GitHub, GitLab and any other host
+///
+/// A forge lists the pull requests (merge requests on GitLab) of one project
+/// along with their reviews, timeline events and comments, already translated
+/// into what gdfm stores for GitHub: GitHub's event and review state names and
+/// author associations.
+///
+/// `gdfm collect events` and `gdfm collect reviews` go through it for every
+/// forge. Reviews and events are listed with the ETags of earlier runs, which
+/// GitHub answers with `304 Not Modified` for unchanged pull requests; other
+/// forges ignore them. GitHub's pull requests and comments are collected by
+/// collectors of their own, as they list the whole repository at once and
+/// store labels, milestones and checklists no other forge has.
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use http::StatusCode;
+use octocrab::models::pulls::ReviewState;
+use octocrab::models::Event;
+use thiserror::Error;
+use tokio::time::Duration;
+
+use crate::client::{backoff, Retryable};
+use crate::config::ConfigError;
+use crate::database::Repository;
+use crate::github::GitHub;
+use crate::gitlab::GitLab;
+
+/// Forge of repositories tracked before forges were recorded, and the default for `gdfm init`
+pub const GITHUB: &str = "github";
+pub const GITLAB: &str = "gitlab";
+
+/// Every forge `gdfm init --forge` accepts
+pub const FORGES: &[&str] = &[GITHUB, GITLAB];
+
+/// Collectors a forge other than GitHub can run, in dependency order
+pub const FORGE_COLLECTORS: &[&str] = &["pulls", "events", "reviews", "comments"];
+
+#[derive(Debug, Error)]
+pub enum ForgeError {
+    #[error("{0}")]
+    Request(#[from] octocrab::Error),
+    #[error("{0}")]
+    Config(#[from] ConfigError),
+    #[error("{0}")]
+    Client(String),
+    #[error("Unknown forge \"{0}\"")]
+    Unknown(String),
+    /// An error response from a forge other than GitHub
+    #[error("{message} ({status})")]
+    Api {
+        status: StatusCode,
+        message: String,
+        /// From the `Retry-After` header, if sent
+        retry_after: Option<Duration>,
+    },
+}
+
+impl Retryable for ForgeError {
+    fn retry_delay(&self, attempt: u32) -> Option<Duration> {
+        match self {
+            Self::Request(err) => err.retry_delay(attempt),
+            Self::Api { status, .. } if status.is_server_error() => Some(backoff(attempt)),
+            Self::Api { status, retry_after, .. } if *status == StatusCode::TOO_MANY_REQUESTS => {
+                Some(retry_after.unwrap_or_else(|| backoff(attempt)))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A pull request or merge request
+#[derive(Debug, Clone)]
+pub struct ForgePull {
+    /// Unique across the forge, not only the project; stored as `pulls.forge_id`
+    /// on forges other than GitHub
+    pub id: i64,
+    pub number: u32,
+    pub title: String,
+    pub open: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub merged_at: Option<DateTime<Utc>>,
+    pub author: String,
+    /// As GitHub names it, e.g. `MEMBER`
+    pub author_association: String,
+    /// Branch the changes are merged into
+    pub base_ref: String,
+    /// Branch the changes come from
+    pub head_ref: String,
+    /// Login of whoever merged it, if known
+    pub merged_by: String,
+    pub merge_commit_sha: String,
+}
+
+/// A review, or an approval on forges without reviews
+#[derive(Debug, Clone)]
+pub struct ForgeReview {
+    pub id: i64,
+    pub reviewer: String,
+    pub state: ReviewState,
+    pub author_association: String,
+    pub submitted_at: DateTime<Utc>,
+    /// What the forge sent, kept with `--keep-raw`
+    pub raw: Option<serde_json::Value>,
+}
+
+/// A timeline event under the name GitHub gives it
+#[derive(Debug, Clone)]
+pub struct ForgeEvent {
+    /// Id the forge gives the event, if any; events without one are stored under local ids
+    pub id: Option<i64>,
+    pub event: Event,
+    pub actor: String,
+    pub author_association: String,
+    pub created_at: Option<DateTime<Utc>>,
+    /// Whoever a `ReviewRequested` event asked for
+    pub requested_reviewer: String,
+    /// What the forge sent, kept with `--keep-raw`
+    pub raw: Option<serde_json::Value>,
+}
+
+/// A comment in the conversation of a pull request
+#[derive(Debug, Clone)]
+pub struct ForgeComment {
+    pub id: i64,
+    pub author: String,
+    pub author_association: String,
+    pub body_length: u32,
+    pub created_at: DateTime<Utc>,
+}
+
+/// ETags of the pages fetched by earlier runs, keyed by route
+pub type Etags = HashMap<String, String>;
+
+/// The reviews or events of a pull request, listed with the ETags of earlier runs
+#[derive(Debug)]
+pub enum Listing<T> {
+    /// The whole list, and the ETags to store once it is stored
+    Changed { items: Vec<T>, etags: Vec<(String, String)> },
+    /// Nothing changed since the ETags given were stored
+    Unchanged,
+}
+
+impl<T> Listing<T> {
+    /// A list from a forge without conditional requests
+    pub fn changed(items: Vec<T>) -> Self {
+        Self::Changed { items, etags: Vec::new() }
+    }
+}
+
+#[async_trait]
+pub trait Forge: Send + Sync {
+    /// Pull requests updated after `since`, or all of them, most recently updated first
+    async fn list_pulls(&self, since: Option<DateTime<Utc>>) -> Result<Vec<ForgePull>, ForgeError>;
+
+    /// Reviews of a pull request, unless none of it changed since `etags` were stored
+    async fn list_reviews(&self, number: u32, etags: &Etags) -> Result<Listing<ForgeReview>, ForgeError>;
+
+    /// Timeline events of a pull request, unless none of it changed since `etags` were stored
+    async fn list_events(&self, number: u32, etags: &Etags) -> Result<Listing<ForgeEvent>, ForgeError>;
+
+    async fn list_comments(&self, number: u32) -> Result<Vec<ForgeComment>, ForgeError>;
+}
+
+/// Connects to the forge hosting a repository
+pub async fn open_forge(repo: &Repository) -> Result<Box<dyn Forge>, ForgeError> {
+    match repo.forge.as_str() {
+        GITHUB => Ok(Box::new(GitHub::connect(repo).await?)),
+        GITLAB => Ok(Box::new(GitLab::connect(repo).await?)),
+        forge => Err(ForgeError::Unknown(forge.to_string())),
+    }
+}
@@ -0,0 +1,860 @@
+/// Holds types for GitHub API responses that octocrab does not model for us
+///
+/// These are deserialized from raw `octocrab.get` calls when we need fields
+/// (like reaction counts) that are missing from octocrab's own models. The
+/// [`GitHub`] forge at the end reads GitHub through the same trait as GitLab.
+use std::sync::LazyLock;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use octocrab::models::pulls::{PullRequest, Review};
+use octocrab::models::timelines::TimelineEvent;
+use octocrab::models::{Author, IssueState};
+use octocrab::{Octocrab, Page};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::client::{get_if_modified, retry, wait_for_page, Conditional, Raw};
+use crate::database::Repository;
+use crate::forge::{Etags, Forge, ForgeComment, ForgeError, ForgeEvent, ForgePull, ForgeReview, Listing};
+
+/// Largest page GitHub serves
+const PER_PAGE: u32 = 100;
+
+/// GitHub's closing keywords, e.g. "Fixes #123" or "resolves: #45"
+static CLOSING_KEYWORDS: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)\b(?:close[sd]?|fix(?:e[sd])?|resolve[sd]?):?\s+#(\d+)\b")
+        .expect("closing keyword regex should be valid")
+});
+
+/// A task list item as GitHub renders it: a list marker followed by `[ ]` or `[x]`
+static TASK_LIST_ITEM: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?m)^\s*(?:[-*+]|\d+[.)])\s+\[([ xX])\](?:\s|$)")
+        .expect("task list regex should be valid")
+});
+
+/// Reaction counts as returned in the `reactions` object of an issue
+#[derive(Debug, Default, Deserialize)]
+pub struct ReactionRollup {
+    #[serde(rename = "+1")]
+    pub plus_one: u32,
+    pub heart: u32,
+    pub hooray: u32,
+    pub rocket: u32,
+    pub eyes: u32,
+}
+
+/// An issue from the `/repos/{owner}/{repo}/issues` endpoint including its reactions
+#[derive(Debug, Deserialize)]
+pub struct IssueWithReactions {
+    pub number: u64,
+    pub title: String,
+    pub comments: u32,
+    #[serde(default)]
+    pub reactions: ReactionRollup,
+    /// Only present when the "issue" is actually a pull request
+    pub pull_request: Option<serde::de::IgnoredAny>,
+}
+
+impl ReactionRollup {
+    /// Reactions that signal a user wants the issue resolved
+    pub fn positive(&self) -> u32 {
+        self.plus_one + self.heart + self.hooray + self.rocket + self.eyes
+    }
+}
+
+/// Event types that make an issue or pull request vanish from a repository
+pub const DISAPPEARING_EVENT_TYPES: &[&str] = &["transferred", "converted_to_discussion"];
+
+#[derive(Debug, Deserialize)]
+pub struct Login {
+    pub login: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IssueRef {
+    pub number: u64,
+}
+
+/// An event from the repository-wide `/repos/{owner}/{repo}/issues/events` endpoint
+#[derive(Debug, Deserialize)]
+pub struct RepositoryIssueEvent {
+    pub id: i64,
+    pub event: String,
+    pub actor: Option<Login>,
+    pub created_at: DateTime<Utc>,
+    pub issue: Option<IssueRef>,
+}
+
+/// A conversation comment from the `/repos/{owner}/{repo}/issues/comments` endpoint
+///
+/// octocrab's `Comment` model is missing `author_association`, which is what we
+/// need to tell maintainer comments apart.
+#[derive(Debug, Deserialize)]
+pub struct IssueComment {
+    pub id: i64,
+    pub user: Option<Login>,
+    pub author_association: String,
+    pub body: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub issue_url: String,
+}
+
+impl IssueComment {
+    /// The issue or pull request number, taken from the end of `issue_url`
+    pub fn issue_number(&self) -> Option<u32> {
+        self.issue_url.rsplit('/').next()?.parse().ok()
+    }
+}
+
+/// A line-level comment from the `/repos/{owner}/{repo}/pulls/comments` endpoint
+#[derive(Debug, Deserialize)]
+pub struct ReviewComment {
+    pub id: i64,
+    pub pull_request_review_id: Option<i64>,
+    pub in_reply_to_id: Option<i64>,
+    pub user: Option<Login>,
+    pub author_association: String,
+    pub path: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub pull_request_url: String,
+}
+
+impl ReviewComment {
+    /// The pull request number, taken from the end of `pull_request_url`
+    pub fn pull_number(&self) -> Option<u32> {
+        self.pull_request_url.rsplit('/').next()?.parse().ok()
+    }
+}
+
+/// A commit from the `/repos/{owner}/{repo}/commits` endpoint
+#[derive(Debug, Deserialize)]
+pub struct RepositoryCommit {
+    pub sha: String,
+    pub commit: CommitDetails,
+    /// GitHub account of the author, missing when the email isn't linked to one
+    pub author: Option<Login>,
+    pub committer: Option<Login>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CommitDetails {
+    pub author: Option<GitSignature>,
+    pub committer: Option<GitSignature>,
+    #[serde(default)]
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GitSignature {
+    pub name: String,
+    pub date: DateTime<Utc>,
+}
+
+impl RepositoryCommit {
+    /// The author's GitHub login, falling back to the git author name
+    pub fn author_name(&self) -> String {
+        match (&self.author, &self.commit.author) {
+            (Some(user), _) => user.login.clone(),
+            (None, Some(signature)) => signature.name.clone(),
+            (None, None) => "".to_string(),
+        }
+    }
+
+    /// First line of the commit message
+    pub fn summary(&self) -> &str {
+        self.commit.message.lines().next().unwrap_or_default()
+    }
+
+    /// The committer's GitHub login, falling back to the git committer name
+    pub fn committer_name(&self) -> String {
+        match (&self.committer, &self.commit.committer) {
+            (Some(user), _) => user.login.clone(),
+            (None, Some(signature)) => signature.name.clone(),
+            (None, None) => "".to_string(),
+        }
+    }
+}
+
+/// Response of the `/repos/{owner}/{repo}/compare/{base}...{head}` endpoint
+#[derive(Debug, Deserialize)]
+pub struct Comparison {
+    pub commits: Vec<RepositoryCommit>,
+}
+
+/// A collaborator from the `/repos/{owner}/{repo}/collaborators` endpoint
+#[derive(Debug, Deserialize)]
+pub struct Collaborator {
+    pub login: String,
+    pub permissions: Option<CollaboratorPermissions>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct CollaboratorPermissions {
+    pub admin: bool,
+    pub maintain: bool,
+    pub push: bool,
+}
+
+impl Collaborator {
+    /// Highest permission held, as GitHub names it: `admin`, `maintain` or `push`
+    pub fn permission(&self) -> &'static str {
+        match &self.permissions {
+            Some(permissions) if permissions.admin => "admin",
+            Some(permissions) if permissions.maintain => "maintain",
+            _ => "push",
+        }
+    }
+}
+
+/// A team from the `/repos/{owner}/{repo}/teams` endpoint
+#[derive(Debug, Deserialize)]
+pub struct RepositoryTeam {
+    pub slug: String,
+    /// `pull`, `triage`, `push`, `maintain` or `admin`
+    pub permission: String,
+}
+
+impl RepositoryTeam {
+    pub fn can_push(&self) -> bool {
+        matches!(self.permission.as_str(), "push" | "maintain" | "admin")
+    }
+}
+
+/// A branch from the `/repos/{owner}/{repo}/branches` endpoint
+#[derive(Debug, Deserialize)]
+pub struct Branch {
+    pub name: String,
+}
+
+/// Media type that makes the stargazers endpoint include `starred_at`
+pub const STAR_MEDIA_TYPE: &str = "application/vnd.github.star+json";
+
+/// A stargazer from the `/repos/{owner}/{repo}/stargazers` endpoint, requested
+/// with [`STAR_MEDIA_TYPE`]
+#[derive(Debug, Deserialize)]
+pub struct Stargazer {
+    pub starred_at: DateTime<Utc>,
+    pub user: Option<Login>,
+}
+
+/// A deployment from the `/repos/{owner}/{repo}/deployments` endpoint
+#[derive(Debug, Deserialize)]
+pub struct Deployment {
+    pub id: i64,
+    pub sha: String,
+    #[serde(rename = "ref")]
+    pub git_ref: String,
+    pub environment: String,
+    pub creator: Option<Login>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A status from the `/repos/{owner}/{repo}/deployments/{id}/statuses` endpoint
+#[derive(Debug, Deserialize)]
+pub struct DeploymentStatus {
+    pub id: i64,
+    /// `error`, `failure`, `inactive`, `in_progress`, `queued`, `pending` or `success`
+    pub state: String,
+    pub creator: Option<Login>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A contributor from the `/repos/{owner}/{repo}/contributors` endpoint
+///
+/// Requested with `anon=1`, so commits by emails without a GitHub account are
+/// included with a `name` instead of a `login`.
+#[derive(Debug, Deserialize)]
+pub struct Contributor {
+    pub login: Option<String>,
+    pub name: Option<String>,
+    pub contributions: u32,
+}
+
+impl Contributor {
+    pub fn display_name(&self) -> String {
+        self.login.clone().or_else(|| self.name.clone()).unwrap_or_default()
+    }
+}
+
+/// Weekly activity of one contributor from `/repos/{owner}/{repo}/stats/contributors`
+#[derive(Debug, Deserialize)]
+pub struct ContributorStats {
+    pub author: Option<Login>,
+    pub weeks: Vec<ContributorWeek>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ContributorWeek {
+    /// Start of the week as a unix timestamp
+    #[serde(rename = "w")]
+    pub week: i64,
+    #[serde(rename = "a")]
+    pub additions: u32,
+    #[serde(rename = "d")]
+    pub deletions: u32,
+    #[serde(rename = "c")]
+    pub commits: u32,
+}
+
+/// Response of the `/repos/{owner}/{repo}/traffic/views` and `/traffic/clones`
+/// endpoints, covering the last 14 days
+#[derive(Debug, Deserialize)]
+pub struct Traffic {
+    #[serde(rename = "views", alias = "clones")]
+    pub days: Vec<TrafficDay>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TrafficDay {
+    pub timestamp: DateTime<Utc>,
+    pub count: u32,
+    pub uniques: u32,
+}
+
+/// Response of the `/repos/{owner}/{repo}/dependency-graph/sbom` endpoint
+#[derive(Debug, Deserialize)]
+pub struct SbomResponse {
+    pub sbom: Sbom,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Sbom {
+    #[serde(default)]
+    pub packages: Vec<SbomPackage>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SbomPackage {
+    pub version_info: Option<String>,
+    #[serde(default)]
+    pub external_refs: Vec<SbomExternalRef>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SbomExternalRef {
+    pub reference_type: String,
+    pub reference_locator: String,
+}
+
+impl SbomPackage {
+    /// Splits the package URL (e.g. `pkg:npm/%40babel/core@7.0.0`) into ecosystem and name
+    ///
+    /// Packages without a purl are the repository itself or GitHub Actions we can't
+    /// attribute to an ecosystem, so they are skipped.
+    pub fn ecosystem_and_name(&self) -> Option<(String, String)> {
+        let purl = self
+            .external_refs
+            .iter()
+            .find(|reference| reference.reference_type == "purl")?;
+        let rest = purl.reference_locator.strip_prefix("pkg:")?;
+        let (ecosystem, path) = rest.split_once('/')?;
+        let name = path.split('@').next().unwrap_or(path).replace("%40", "@");
+
+        Some((ecosystem.to_string(), name))
+    }
+}
+
+/// An event from the `/repos/{owner}/{repo}/events` activity feed
+///
+/// Only the wiki pages of `GollumEvent`s are read from the payload.
+#[derive(Debug, Deserialize)]
+pub struct ActivityEvent {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: String,
+    pub actor: Login,
+    pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub payload: ActivityPayload,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ActivityPayload {
+    #[serde(default)]
+    pub pages: Vec<WikiPage>,
+}
+
+/// A wiki page created or edited in a `GollumEvent`
+#[derive(Debug, Deserialize)]
+pub struct WikiPage {
+    pub page_name: String,
+    /// `created` or `edited`
+    pub action: String,
+}
+
+/// Event type GitHub uses for wiki changes
+pub const WIKI_EVENT_TYPE: &str = "GollumEvent";
+
+/// GraphQL query listing discussions with their answer and first 100 comments
+pub const DISCUSSIONS_QUERY: &str = "
+query($owner: String!, $name: String!, $cursor: String) {
+  repository(owner: $owner, name: $name) {
+    discussions(first: 50, after: $cursor, orderBy: {field: CREATED_AT, direction: ASC}) {
+      pageInfo { hasNextPage endCursor }
+      nodes {
+        databaseId
+        number
+        title
+        createdAt
+        closed
+        author { login }
+        authorAssociation
+        category { name isAnswerable }
+        answerChosenAt
+        answer { databaseId }
+        comments(first: 100) {
+          nodes { databaseId createdAt author { login } authorAssociation }
+        }
+      }
+    }
+  }
+}";
+
+/// Envelope of every GraphQL response
+#[derive(Debug, Deserialize)]
+pub struct GraphQlResponse<T> {
+    pub data: Option<T>,
+    #[serde(default)]
+    pub errors: Vec<GraphQlError>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct GraphQlError {
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscussionsData {
+    pub repository: Option<DiscussionsRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscussionsRepository {
+    pub discussions: DiscussionConnection,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscussionConnection {
+    pub page_info: PageInfo,
+    pub nodes: Vec<Discussion>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PageInfo {
+    pub has_next_page: bool,
+    pub end_cursor: Option<String>,
+}
+
+/// A discussion thread; `author` is `None` for deleted accounts
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Discussion {
+    pub database_id: i64,
+    pub number: u32,
+    pub title: String,
+    pub created_at: DateTime<Utc>,
+    pub closed: bool,
+    pub author: Option<Login>,
+    pub author_association: String,
+    pub category: DiscussionCategory,
+    pub answer_chosen_at: Option<DateTime<Utc>>,
+    pub answer: Option<DiscussionAnswer>,
+    pub comments: DiscussionComments,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscussionCategory {
+    pub name: String,
+    /// Whether discussions in the category can have an accepted answer
+    pub is_answerable: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscussionAnswer {
+    pub database_id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DiscussionComments {
+    pub nodes: Vec<DiscussionComment>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiscussionComment {
+    pub database_id: i64,
+    pub created_at: DateTime<Utc>,
+    pub author: Option<Login>,
+    pub author_association: String,
+}
+
+/// Logins of whoever a pull request is assigned to
+pub fn assignee_logins(pull: &octocrab::models::pulls::PullRequest) -> Vec<String> {
+    pull.assignees.iter().flatten().map(|user| user.login.clone()).collect()
+}
+
+/// Users and team slugs whose review is still requested on a pull request
+pub fn requested_reviewers(pull: &octocrab::models::pulls::PullRequest) -> (Vec<String>, Vec<String>) {
+    let users = pull.requested_reviewers.iter().flatten().map(|user| user.login.clone()).collect();
+    let teams = pull.requested_teams.iter().flatten().map(|team| team.slug.clone()).collect();
+
+    (users, teams)
+}
+
+/// Returns the issue numbers a pull request body says it closes
+pub fn closing_issue_numbers(body: &str) -> Vec<u32> {
+    let mut numbers: Vec<u32> = CLOSING_KEYWORDS
+        .captures_iter(body)
+        .filter_map(|captures| captures[1].parse().ok())
+        .collect();
+    numbers.sort_unstable();
+    numbers.dedup();
+    numbers
+}
+
+/// Counts the task list checkboxes in a pull request body, returning
+/// `(total, completed)`, or `None` when it has no checklist
+pub fn checklist_counts(body: &str) -> Option<(u32, u32)> {
+    let mut total = 0;
+    let mut completed = 0;
+    for captures in TASK_LIST_ITEM.captures_iter(body) {
+        total += 1;
+        if &captures[1] != " " {
+            completed += 1;
+        }
+    }
+    (total > 0).then_some((total, completed))
+}
+
+/// Returns the name GitHub uses for a timeline event type, e.g. `ready_for_review`
+pub fn event_type_name(event: &octocrab::models::Event) -> String {
+    match serde_json::to_value(event) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => format!("{:?}", event),
+    }
+}
+
+/// Reads pull requests and what happens on them through the [`Forge`] trait
+///
+/// Reviews and timeline events are fetched with conditional requests: every
+/// page is sent with the ETag an earlier run stored for it, and a pull request
+/// none of whose pages changed comes back as [`Listing::Unchanged`] without
+/// using the rate limit. `gdfm collect` keeps collectors of its own for pull
+/// requests and comments, which store details the trait has no room for, such
+/// as labels, assignees and checklists.
+pub struct GitHub {
+    client: Octocrab,
+    /// `owner/name`
+    repository: String,
+}
+
+impl GitHub {
+    pub async fn connect(repo: &Repository) -> Result<Self, ForgeError> {
+        let client = crate::client::github_client().await.map_err(|err| ForgeError::Client(err.to_string()))?;
+
+        Ok(Self { client, repository: format!("{}/{}", repo.owner, repo.name) })
+    }
+
+    /// Fetches the pages of a list until one comes back short, or until `done` says to stop
+    async fn get_pages<T: DeserializeOwned>(
+        &self,
+        route: &str,
+        done: impl Fn(&[T]) -> bool,
+    ) -> Result<Vec<T>, ForgeError> {
+        let separator = if route.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+        for page in 1.. {
+            let url = format!("/repos/{}{}{}per_page={}&page={}", self.repository, route, separator, PER_PAGE, page);
+            wait_for_page().await;
+            let batch: Vec<T> = retry(|| self.client.get(url.as_str(), None::<&()>)).await?;
+            let last = batch.len() < PER_PAGE as usize || done(&batch);
+            items.extend(batch);
+            if last {
+                break;
+            }
+        }
+
+        Ok(items)
+    }
+
+    /// Fetches every page of a list, each with the ETag stored for it
+    ///
+    /// A changed page has another one after it when GitHub links to one or it
+    /// is full, since a full last page only gets a successor later; an
+    /// unchanged page has one when an earlier run stored an ETag for it. With
+    /// one page changed the unchanged ones are fetched again, as the whole
+    /// list replaces what was stored.
+    async fn get_pages_if_modified<T: DeserializeOwned>(
+        &self,
+        route: &str,
+        etags: &Etags,
+    ) -> Result<Listing<Raw<T>>, ForgeError> {
+        let route = format!("/repos/{}{}?per_page={}", self.repository, route, PER_PAGE);
+        let mut pages = Vec::new();
+        for page in 1.. {
+            let url = page_route(&route, page);
+            let etag = etags.get(&url).map(String::as_str);
+            wait_for_page().await;
+            let response: Conditional<Page<Raw<T>>> = retry(|| get_if_modified(&self.client, &url, etag)).await?;
+            let has_next = match &response {
+                Conditional::Modified { value, .. } => value.next.is_some() || value.items.len() >= PER_PAGE as usize,
+                Conditional::NotModified => etags.contains_key(&page_route(&route, page + 1)),
+            };
+            pages.push((url, response));
+            if !has_next {
+                break;
+            }
+        }
+        if pages.iter().all(|(_, page)| matches!(page, Conditional::NotModified)) {
+            return Ok(Listing::Unchanged);
+        }
+
+        let mut items = Vec::new();
+        let mut new_etags = Vec::new();
+        for (url, page) in pages {
+            let page = match page {
+                Conditional::NotModified => {
+                    wait_for_page().await;
+                    retry(|| get_if_modified(&self.client, &url, None)).await?
+                }
+                modified => modified,
+            };
+            if let Conditional::Modified { value, etag } = page {
+                items.extend(value.items);
+                new_etags.extend(etag.map(|etag| (url, etag)));
+            }
+        }
+
+        Ok(Listing::Changed { items, etags: new_etags })
+    }
+}
+
+/// Route of a page of a paginated route; the first page keeps the route as it
+/// is so ETags stored before pages were followed still apply
+fn page_route(route: &str, page: u32) -> String {
+    if page == 1 {
+        route.to_string()
+    } else {
+        format!("{}&page={}", route, page)
+    }
+}
+
+/// Login of a user, or empty when GitHub leaves it out
+fn login(user: Option<&Author>) -> String {
+    user.map(|user| user.login.clone()).unwrap_or_default()
+}
+
+#[async_trait]
+impl Forge for GitHub {
+    async fn list_pulls(&self, since: Option<DateTime<Utc>>) -> Result<Vec<ForgePull>, ForgeError> {
+        let updated = |pull: &PullRequest| pull.updated_at.or(pull.created_at).unwrap_or_default();
+        // Listed most recently updated first, so the first page reaching back to `since` is the last one
+        let pulls: Vec<PullRequest> = self
+            .get_pages("/pulls?state=all&sort=updated&direction=desc", |page: &[PullRequest]| {
+                since.is_some_and(|since| page.iter().any(|pull| updated(pull) <= since))
+            })
+            .await?;
+
+        Ok(pulls
+            .into_iter()
+            .filter(|pull| since.is_none_or(|since| updated(pull) > since))
+            .map(|pull| ForgePull {
+                id: pull.id.into_inner() as i64,
+                number: pull.number as u32,
+                title: pull.title.clone().unwrap_or_default(),
+                open: pull.state == Some(IssueState::Open),
+                created_at: pull.created_at.unwrap_or_default(),
+                updated_at: updated(&pull),
+                closed_at: pull.closed_at,
+                merged_at: pull.merged_at,
+                author: login(pull.user.as_deref()),
+                author_association: pull
+                    .author_association
+                    .as_ref()
+                    .map(|association| format!("{:?}", association))
+                    .unwrap_or_default(),
+                base_ref: pull.base.ref_field.clone(),
+                head_ref: pull.head.ref_field.clone(),
+                merged_by: login(pull.merged_by.as_deref()),
+                merge_commit_sha: pull.merge_commit_sha.clone().unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn list_reviews(&self, number: u32, etags: &Etags) -> Result<Listing<ForgeReview>, ForgeError> {
+        let Listing::Changed { items, etags } =
+            self.get_pages_if_modified::<Review>(&format!("/pulls/{}/reviews", number), etags).await?
+        else {
+            return Ok(Listing::Unchanged);
+        };
+
+        // Pending reviews have neither a state worth storing nor a time
+        let reviews = items
+            .into_iter()
+            .filter_map(|review| {
+                Some(ForgeReview {
+                    id: review.id.into_inner() as i64,
+                    reviewer: login(review.user.as_ref()),
+                    state: review.state?,
+                    author_association: review
+                        .author_association
+                        .as_ref()
+                        .map(|association| format!("{:?}", association))
+                        .unwrap_or_default(),
+                    submitted_at: review.submitted_at?,
+                    raw: Some(review.json),
+                })
+            })
+            .collect();
+
+        Ok(Listing::Changed { items: reviews, etags })
+    }
+
+    async fn list_events(&self, number: u32, etags: &Etags) -> Result<Listing<ForgeEvent>, ForgeError> {
+        let Listing::Changed { items, etags } =
+            self.get_pages_if_modified::<TimelineEvent>(&format!("/issues/{}/timeline", number), etags).await?
+        else {
+            return Ok(Listing::Unchanged);
+        };
+
+        // Commits and other entries without an id of their own are left out
+        let events = items
+            .into_iter()
+            .filter_map(|event| {
+                Some(ForgeEvent {
+                    id: Some(event.id?.into_inner() as i64),
+                    created_at: event.created_at,
+                    actor: login(event.actor.as_ref()),
+                    author_association: event.author_association.clone().unwrap_or_default(),
+                    requested_reviewer: login(event.requested_reviewer.as_ref()),
+                    event: event.item.event,
+                    raw: Some(event.json),
+                })
+            })
+            .collect();
+
+        Ok(Listing::Changed { items: events, etags })
+    }
+
+    async fn list_comments(&self, number: u32) -> Result<Vec<ForgeComment>, ForgeError> {
+        let comments: Vec<IssueComment> =
+            self.get_pages(&format!("/issues/{}/comments", number), |_: &[IssueComment]| false).await?;
+
+        Ok(comments
+            .into_iter()
+            .map(|comment| ForgeComment {
+                id: comment.id,
+                author: comment.user.map(|user| user.login).unwrap_or_default(),
+                author_association: comment.author_association,
+                body_length: comment.body.map(|body| body.chars().count() as u32).unwrap_or_default(),
+                created_at: comment.created_at,
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::*;
+
+    use axum::extract::Query;
+    use axum::http::{header, StatusCode};
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::get;
+    use axum::Router;
+
+    const ROUTE: &str = "/repos/octo/repo/issues/1/timeline?per_page=100";
+
+    /// A timeline of 130 events over two pages, with an ETag per page
+    async fn timeline(Query(query): Query<HashMap<String, String>>, headers: http::HeaderMap) -> Response {
+        let page = query.get("page").map(String::as_str).unwrap_or("1");
+        let etag = format!("\"page-{}\"", page);
+        if headers.get(header::IF_NONE_MATCH).is_some_and(|value| value == etag.as_str()) {
+            return StatusCode::NOT_MODIFIED.into_response();
+        }
+
+        let (ids, link) = match page {
+            "1" => (0..100, Some("<http://localhost/repos/octo/repo/issues/1/timeline?per_page=100&page=2>; rel=\"next\"")),
+            "2" => (100..130, None),
+            _ => (0..0, None),
+        };
+        let events: Vec<serde_json::Value> = ids.map(|id| serde_json::json!({ "id": id })).collect();
+        let mut response = (
+            [(header::ETAG, etag), (header::CONTENT_TYPE, "application/json".to_string())],
+            axum::Json(events),
+        )
+            .into_response();
+        if let Some(link) = link {
+            response.headers_mut().insert(header::LINK, link.parse().unwrap());
+        }
+        response
+    }
+
+    async fn mock_github() -> GitHub {
+        let app = Router::new().route("/repos/octo/repo/issues/1/timeline", get(timeline));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+        let client = Octocrab::builder().base_uri(format!("http://{}", address)).unwrap().build().unwrap();
+        GitHub { client, repository: "octo/repo".to_string() }
+    }
+
+    async fn list(github: &GitHub, etags: &Etags) -> Listing<Raw<serde_json::Value>> {
+        github.get_pages_if_modified("/issues/1/timeline", etags).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn conditional_pages_follow_every_page() {
+        let github = mock_github().await;
+
+        let Listing::Changed { items, etags } = list(&github, &Etags::new()).await else {
+            panic!("nothing was stored, so everything changed");
+        };
+        assert_eq!(items.len(), 130);
+        assert_eq!(
+            etags,
+            vec![
+                (ROUTE.to_string(), "\"page-1\"".to_string()),
+                (format!("{}&page=2", ROUTE), "\"page-2\"".to_string()),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn conditional_pages_refetch_unchanged_pages_next_to_changed_ones() {
+        let github = mock_github().await;
+        let etags = Etags::from([
+            (ROUTE.to_string(), "\"page-1\"".to_string()),
+            (format!("{}&page=2", ROUTE), "\"stale\"".to_string()),
+        ]);
+
+        let Listing::Changed { items, .. } = list(&github, &etags).await else {
+            panic!("the second page changed");
+        };
+        assert_eq!(items.len(), 130);
+    }
+
+    #[tokio::test]
+    async fn conditional_pages_unchanged() {
+        let github = mock_github().await;
+        let etags = Etags::from([
+            (ROUTE.to_string(), "\"page-1\"".to_string()),
+            (format!("{}&page=2", ROUTE), "\"page-2\"".to_string()),
+        ]);
+
+        assert!(matches!(list(&github, &etags).await, Listing::Unchanged));
+    }
+}
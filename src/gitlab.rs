@@ -0,0 +1,414 @@
+/// Reads merge requests, approvals and notes from GitLab
+///
+/// GitLab is reached at `gitlab_url` from the config (gitlab.com by default)
+/// with the token in `GITLAB_TOKEN`, sent as a bearer token. Merge requests are
+/// stored as pull requests. GitLab has no reviews or timeline events as such,
+/// so both are read from the system notes it leaves on a merge request
+/// ("approved this merge request", "requested review from @alice") and from its
+/// state events. Author associations come from project membership.
+use std::collections::HashMap;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use http::header::RETRY_AFTER;
+use http::StatusCode;
+use octocrab::models::pulls::ReviewState;
+use octocrab::models::Event;
+use octocrab::{FromResponse, Octocrab};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use tokio::time::Duration;
+
+use crate::client::retry;
+use crate::config::Config;
+use crate::constants::{GITLAB_TOKEN_ENV, GITLAB_URL};
+use crate::database::Repository;
+use crate::forge::{Etags, Forge, ForgeComment, ForgeError, ForgeEvent, ForgePull, ForgeReview, Listing};
+
+/// Largest page GitLab serves
+const PER_PAGE: u32 = 100;
+
+/// GitLab access levels at or above which a member counts as an `OWNER`, `MEMBER` or `COLLABORATOR`
+const OWNER_ACCESS: u32 = 50;
+const MAINTAINER_ACCESS: u32 = 40;
+const DEVELOPER_ACCESS: u32 = 30;
+
+#[derive(Debug, Deserialize)]
+struct User {
+    username: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Member {
+    username: String,
+    access_level: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct MergeRequest {
+    id: i64,
+    iid: u32,
+    title: String,
+    /// `opened`, `closed`, `locked` or `merged`
+    state: String,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+    closed_at: Option<DateTime<Utc>>,
+    merged_at: Option<DateTime<Utc>>,
+    author: Option<User>,
+    target_branch: String,
+    source_branch: String,
+    merge_user: Option<User>,
+    merge_commit_sha: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Note {
+    id: i64,
+    body: String,
+    author: Option<User>,
+    created_at: DateTime<Utc>,
+    /// Notes GitLab writes itself, e.g. "approved this merge request"
+    system: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct StateEvent {
+    user: Option<User>,
+    created_at: DateTime<Utc>,
+    /// `closed`, `reopened` or `merged`
+    state: String,
+}
+
+pub struct GitLab {
+    client: Octocrab,
+    /// URL-encoded `namespace/name`, which GitLab accepts in place of a project id
+    project: String,
+    /// Access level of every project member, inherited ones included
+    access_levels: HashMap<String, u32>,
+}
+
+impl GitLab {
+    pub async fn connect(repo: &Repository) -> Result<Self, ForgeError> {
+        let config = Config::load()?;
+        let url = config.gitlab_url.as_deref().unwrap_or(GITLAB_URL).trim_end_matches('/');
+        let builder = Octocrab::builder().base_uri(format!("{}/api/v4", url))?;
+        let client = match std::env::var(GITLAB_TOKEN_ENV) {
+            Ok(token) => builder.personal_token(token).build()?,
+            Err(_) => builder.build()?,
+        };
+
+        let mut gitlab = Self {
+            client,
+            project: format!("{}/{}", repo.owner, repo.name).replace('/', "%2F"),
+            access_levels: HashMap::new(),
+        };
+        let members: Vec<Member> = gitlab.get_all(&format!("/projects/{}/members/all", gitlab.project)).await?;
+        for member in members {
+            let level = gitlab.access_levels.entry(member.username).or_default();
+            *level = (*level).max(member.access_level);
+        }
+
+        Ok(gitlab)
+    }
+
+    /// Fetches every page of a list, following GitLab's `X-Next-Page` header
+    async fn get_all<T: DeserializeOwned>(&self, route: &str) -> Result<Vec<T>, ForgeError> {
+        let separator = if route.contains('?') { '&' } else { '?' };
+        let mut items = Vec::new();
+        let mut page = 1;
+        loop {
+            let url = format!("{}{}per_page={}&page={}", route, separator, PER_PAGE, page);
+            let response = retry(|| async {
+                let response = self.client._get(url.as_str()).await?;
+                if response.status().is_success() {
+                    return Ok(response);
+                }
+                let status = response.status();
+                let retry_after = response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|retry_after| retry_after.to_str().ok())
+                    .map(str::to_string);
+                let body = self.client.body_to_string(response).await?;
+                Err(api_error(status, retry_after.as_deref(), &body))
+            })
+            .await?;
+            let next_page = response
+                .headers()
+                .get("x-next-page")
+                .and_then(|next| next.to_str().ok())
+                .and_then(|next| next.parse::<u32>().ok());
+            items.extend(Vec::<T>::from_response(response).await?);
+
+            match next_page {
+                Some(next) => page = next,
+                None => return Ok(items),
+            }
+        }
+    }
+
+    /// The author association GitHub would show for a project member
+    fn association(&self, username: &str) -> String {
+        let association = match self.access_levels.get(username) {
+            Some(level) if *level >= OWNER_ACCESS => "OWNER",
+            Some(level) if *level >= MAINTAINER_ACCESS => "MEMBER",
+            Some(level) if *level >= DEVELOPER_ACCESS => "COLLABORATOR",
+            _ => "NONE",
+        };
+        association.to_string()
+    }
+
+    async fn notes(&self, number: u32) -> Result<Vec<Note>, ForgeError> {
+        self.get_all(&format!(
+            "/projects/{}/merge_requests/{}/notes?sort=asc&order_by=created_at",
+            self.project, number
+        ))
+        .await
+    }
+}
+
+/// Reads an error response, whose body GitLab words as `{"message": ...}`, as
+/// `{"error": ..., "error_description": ...}` for token problems or as plain text
+fn api_error(status: StatusCode, retry_after: Option<&str>, body: &str) -> ForgeError {
+    #[derive(Deserialize)]
+    struct ErrorBody {
+        message: Option<serde_json::Value>,
+        error: Option<String>,
+        error_description: Option<String>,
+    }
+
+    let message = match serde_json::from_str::<ErrorBody>(body) {
+        Ok(ErrorBody { error_description: Some(description), .. }) => description,
+        Ok(ErrorBody { message: Some(serde_json::Value::String(message)), .. }) => message,
+        // Validation errors come as an object of messages per field
+        Ok(ErrorBody { message: Some(message), .. }) => message.to_string(),
+        Ok(ErrorBody { error: Some(error), .. }) => error,
+        _ if !body.trim().is_empty() => body.trim().to_string(),
+        _ => status.canonical_reason().unwrap_or_default().to_string(),
+    };
+
+    ForgeError::Api {
+        status,
+        message,
+        retry_after: retry_after
+            .and_then(|seconds| seconds.trim().parse().ok())
+            .map(Duration::from_secs),
+    }
+}
+
+/// The review a system note records, e.g. an approval for "approved this merge request"
+fn review_state(note: &Note) -> Option<ReviewState> {
+    if !note.system {
+        None
+    } else if note.body.starts_with("approved this merge request") {
+        Some(ReviewState::Approved)
+    } else if note.body.starts_with("requested changes") {
+        Some(ReviewState::ChangesRequested)
+    } else {
+        None
+    }
+}
+
+fn username(user: &Option<User>) -> String {
+    user.as_ref().map(|user| user.username.clone()).unwrap_or_default()
+}
+
+/// Logins mentioned in a system note, e.g. `alice` and `bob` in
+/// "requested review from @alice and @bob"
+fn mentions(body: &str) -> Vec<String> {
+    body.split(|c: char| c.is_whitespace() || c == ',')
+        .filter_map(|word| word.strip_prefix('@'))
+        .map(|login| login.trim_end_matches('.').to_string())
+        .filter(|login| !login.is_empty())
+        .collect()
+}
+
+#[async_trait]
+impl Forge for GitLab {
+    async fn list_pulls(&self, since: Option<DateTime<Utc>>) -> Result<Vec<ForgePull>, ForgeError> {
+        let mut route = format!(
+            "/projects/{}/merge_requests?state=all&order_by=updated_at&sort=desc",
+            self.project
+        );
+        if let Some(since) = since {
+            route.push_str(&format!("&updated_after={}", since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)));
+        }
+        let merge_requests: Vec<MergeRequest> = self.get_all(&route).await?;
+
+        Ok(merge_requests
+            .into_iter()
+            .map(|merge_request| {
+                let author = username(&merge_request.author);
+                ForgePull {
+                    id: merge_request.id,
+                    number: merge_request.iid,
+                    title: merge_request.title,
+                    open: merge_request.state == "opened",
+                    created_at: merge_request.created_at,
+                    updated_at: merge_request.updated_at,
+                    closed_at: merge_request.closed_at.or(merge_request.merged_at),
+                    merged_at: merge_request.merged_at,
+                    author_association: self.association(&author),
+                    author,
+                    base_ref: merge_request.target_branch,
+                    head_ref: merge_request.source_branch,
+                    merged_by: merge_request.merge_user.map(|user| user.username).unwrap_or_default(),
+                    merge_commit_sha: merge_request.merge_commit_sha.unwrap_or_default(),
+                }
+            })
+            .collect())
+    }
+
+    async fn list_reviews(&self, number: u32, _etags: &Etags) -> Result<Listing<ForgeReview>, ForgeError> {
+        let mut reviews = Vec::new();
+        for note in self.notes(number).await? {
+            let Some(state) = review_state(&note) else {
+                continue;
+            };
+            let reviewer = username(&note.author);
+            reviews.push(ForgeReview {
+                id: note.id,
+                author_association: self.association(&reviewer),
+                reviewer,
+                state,
+                submitted_at: note.created_at,
+                raw: None,
+            });
+        }
+
+        Ok(Listing::changed(reviews))
+    }
+
+    async fn list_events(&self, number: u32, _etags: &Etags) -> Result<Listing<ForgeEvent>, ForgeError> {
+        let mut events = Vec::new();
+
+        let state_events: Vec<StateEvent> = self
+            .get_all(&format!("/projects/{}/merge_requests/{}/resource_state_events", self.project, number))
+            .await?;
+        for state_event in state_events {
+            let event = match state_event.state.as_str() {
+                "closed" => Event::Closed,
+                "reopened" => Event::Reopened,
+                "merged" => Event::Merged,
+                _ => continue,
+            };
+            let actor = username(&state_event.user);
+            events.push(ForgeEvent {
+                id: None,
+                event,
+                author_association: self.association(&actor),
+                actor,
+                created_at: Some(state_event.created_at),
+                requested_reviewer: String::new(),
+                raw: None,
+            });
+        }
+
+        for note in self.notes(number).await? {
+            if !note.system {
+                continue;
+            }
+            let actor = username(&note.author);
+            let (event, reviewers) = if note.body.starts_with("requested review from") {
+                (Event::ReviewRequested, mentions(&note.body))
+            } else if note.body.starts_with("removed review request for") {
+                (Event::ReviewRequestRemoved, mentions(&note.body))
+            } else if note.body.starts_with("marked this merge request as **ready**") {
+                (Event::ReadyForReview, vec![String::new()])
+            } else if note.body.starts_with("marked this merge request as **draft**") {
+                (Event::ConvertToDraft, vec![String::new()])
+            } else {
+                continue;
+            };
+            for requested_reviewer in reviewers {
+                events.push(ForgeEvent {
+                    id: None,
+                    event: event.clone(),
+                    actor: actor.clone(),
+                    author_association: self.association(&actor),
+                    created_at: Some(note.created_at),
+                    requested_reviewer,
+                    raw: None,
+                });
+            }
+        }
+
+        events.sort_by_key(|event| event.created_at);
+        Ok(Listing::changed(events))
+    }
+
+    async fn list_comments(&self, number: u32) -> Result<Vec<ForgeComment>, ForgeError> {
+        Ok(self
+            .notes(number)
+            .await?
+            .into_iter()
+            .filter(|note| !note.system)
+            .map(|note| {
+                let author = username(&note.author);
+                ForgeComment {
+                    id: note.id,
+                    author_association: self.association(&author),
+                    author,
+                    body_length: note.body.chars().count() as u32,
+                    created_at: note.created_at,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::client::Retryable;
+
+    fn note(body: &str, system: bool) -> Note {
+        serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "body": body,
+            "author": { "username": "alice" },
+            "created_at": "2024-05-01T12:00:00Z",
+            "system": system,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn approvals_are_read_from_system_notes() {
+        assert_eq!(review_state(&note("approved this merge request", true)), Some(ReviewState::Approved));
+        assert_eq!(review_state(&note("requested changes", true)), Some(ReviewState::ChangesRequested));
+        assert_eq!(review_state(&note("unapproved this merge request", true)), None);
+        assert_eq!(review_state(&note("added 1 commit", true)), None);
+        // Anyone can write the same words in a comment
+        assert_eq!(review_state(&note("approved this merge request", false)), None);
+    }
+
+    #[test]
+    fn error_bodies_are_read() {
+        let error = api_error(StatusCode::NOT_FOUND, None, r#"{"message":"404 Project Not Found"}"#);
+        assert_eq!(error.to_string(), "404 Project Not Found (404 Not Found)");
+
+        let error = api_error(
+            StatusCode::UNAUTHORIZED,
+            None,
+            r#"{"error":"invalid_token","error_description":"Token was revoked."}"#,
+        );
+        assert_eq!(error.to_string(), "Token was revoked. (401 Unauthorized)");
+
+        let error = api_error(StatusCode::BAD_REQUEST, None, r#"{"message":{"state":["is invalid"]}}"#);
+        assert_eq!(error.to_string(), r#"{"state":["is invalid"]} (400 Bad Request)"#);
+
+        let error = api_error(StatusCode::BAD_GATEWAY, None, "");
+        assert_eq!(error.to_string(), "Bad Gateway (502 Bad Gateway)");
+    }
+
+    #[test]
+    fn rate_limits_and_server_errors_are_retried() {
+        let error = api_error(StatusCode::TOO_MANY_REQUESTS, Some("30"), "Retry later");
+        assert_eq!(error.retry_delay(1), Some(Duration::from_secs(30)));
+        assert!(api_error(StatusCode::TOO_MANY_REQUESTS, None, "").retry_delay(1).is_some());
+        assert!(api_error(StatusCode::SERVICE_UNAVAILABLE, None, "").retry_delay(1).is_some());
+        assert_eq!(api_error(StatusCode::NOT_FOUND, None, "").retry_delay(1), None);
+    }
+}
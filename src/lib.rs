@@ -0,0 +1,78 @@
+//! Collects pull requests, issues and what happens on them from GitHub (or
+//! GitLab) into a SQLite database, and reports on how a project's maintainers
+//! keep up with them.
+//!
+//! The `gdfm` binary is a thin wrapper around this crate: [`run`] takes the same
+//! command line, so a service can collect or report without shelling out:
+//!
+//! ```no_run
+//! # async fn collect() -> miette::Result<()> {
+//! let args = ["gdfm", "collect", "pulls", "owner/repo", "--state", "all"];
+//! gdfm::run(args.iter().map(|arg| arg.to_string()).collect()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! The pieces behind the commands are public as well:
+//!
+//! - [`cli`] holds what each command runs, such as the collectors in
+//!   [`cli::collect`] and [`cli::collect::run_collector`] to pick one by name
+//! - [`database`] holds the models, one per table, and the schema they live in
+//! - [`storage`] holds the [`storage::Storage`] trait collectors write through,
+//!   and [`storage::open_storage`] to read what they stored
+//! - [`report`] holds the sections of the HTML report
+//! - [`config`] and [`client`] hold the settings and GitHub client every command shares
+//! - [`context`] holds the settings of one command, such as its project and API host
+//!
+//! Collectors and reports read `config.toml` and `GITHUB_TOKEN` as the binary
+//! does, and write to the same database unless the [`context::Context`] they
+//! run with picks another project. Collectors can also be run without a
+//! command line:
+//!
+//! ```no_run
+//! # use miette::IntoDiagnostic;
+//! use gdfm::cli::collect::{run_collector, CollectOptions};
+//! use gdfm::context::Context;
+//!
+//! # async fn collect() -> miette::Result<()> {
+//! let config = gdfm::config::Config::load().into_diagnostic()?;
+//! let context = Context::from_config(&config, None)?.with_project("conda");
+//! let options = CollectOptions { full: true, ..CollectOptions::new("conda/conda") };
+//! context.scope(run_collector("pulls", &options)).await?;
+//! # Ok(())
+//! # }
+//! ```
+mod app;
+
+/// What each subcommand runs, one module per command
+pub mod cli;
+/// The GitHub client, its retries and rate limiting
+pub mod client;
+/// The user configuration read from `config.toml`
+pub mod config;
+/// Argument names and other constants shared by the commands
+pub mod constants;
+/// The settings of the command being run, such as the project and API host
+pub mod context;
+/// The models stored in the SQLite database and the schema they live in
+pub mod database;
+/// Which optional APIs the GitHub host offers
+pub mod features;
+/// Reading pull requests from hosts other than GitHub
+pub mod forge;
+/// The sections that make up a generated report
+pub mod report;
+/// The `Storage` trait collectors write through
+pub mod storage;
+/// Verification and storage of GitHub webhook deliveries
+pub mod webhook;
+
+mod email;
+mod github;
+mod gitlab;
+#[cfg(feature = "postgres")]
+mod postgres;
+mod publish;
+mod terminal;
+
+pub use app::{command, execute, expand_aliases, run};
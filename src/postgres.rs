@@ -0,0 +1,211 @@
+/// Export of the collected data to a shared Postgres database
+///
+/// Postgres is not a storage backend: gdfm collects into its SQLite database
+/// and reports from it. `gdfm export postgres`, and every collection when
+/// `export.postgres_after_collect` is set, copies each table there with the same
+/// name, columns and types, so several people and dashboards can query one copy
+/// concurrently. Booleans and timestamps keep SQLite's representation (integers
+/// and text), so queries written against one database run on the other.
+///
+/// Rows are read from SQLite a batch at a time and upserted by primary key, and
+/// rows SQLite no longer has are deleted afterwards. Everything happens in one
+/// transaction, and readers keep seeing the previous copy until it commits.
+use futures_util::TryStreamExt;
+use sqlx::postgres::{PgConnection, PgPool, Postgres};
+use sqlx::query_builder::Separated;
+use sqlx::sqlite::Sqlite;
+use sqlx::{Pool, QueryBuilder};
+
+use crate::database::{row_values, table_info, CellValue, ColumnType, TABLES};
+
+/// Most bind parameters Postgres accepts in one statement
+const MAX_PARAMETERS: usize = 65_535;
+
+/// Columns indexed in every table that has them, as most queries filter on them
+const INDEXED_COLUMNS: &[&str] = &["repo_id", "issue_pull_id"];
+
+/// Temporary table holding the primary keys of the rows exported from a table
+const EXPORTED_KEYS: &str = "gdfm_exported_keys";
+
+impl ColumnType {
+    fn postgres_type(self) -> &'static str {
+        match self {
+            Self::Integer => "BIGINT",
+            Self::Real => "DOUBLE PRECISION",
+            Self::Text => "TEXT",
+        }
+    }
+}
+
+/// A table being exported: its quoted column names, their types, and which of
+/// them make up the primary key
+struct ExportedTable {
+    name: &'static str,
+    columns: Vec<String>,
+    types: Vec<ColumnType>,
+    keys: Vec<usize>,
+}
+
+impl ExportedTable {
+    fn key_columns(&self) -> String {
+        self.keys.iter().map(|&index| self.columns[index].as_str()).collect::<Vec<_>>().join(", ")
+    }
+
+    /// Clause updating rows exported before in place
+    fn on_conflict(&self) -> String {
+        let updates: Vec<String> = self
+            .columns
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| !self.keys.contains(index))
+            .map(|(_, column)| format!("{column} = EXCLUDED.{column}"))
+            .collect();
+        if updates.is_empty() {
+            format!(" ON CONFLICT ({}) DO NOTHING", self.key_columns())
+        } else {
+            format!(" ON CONFLICT ({}) DO UPDATE SET {}", self.key_columns(), updates.join(", "))
+        }
+    }
+
+    /// Upserts a batch of rows and remembers their keys
+    async fn write(&self, connection: &mut PgConnection, rows: &[Vec<CellValue>]) -> Result<(), sqlx::Error> {
+        let mut insert = QueryBuilder::<Postgres>::new(format!("INSERT INTO \"{}\" ({}) ", self.name, self.columns.join(", ")));
+        insert.push_values(rows, |mut row, values| {
+            for (value, column_type) in values.iter().zip(&self.types) {
+                push_value(&mut row, value, *column_type);
+            }
+        });
+        if !self.keys.is_empty() {
+            insert.push(self.on_conflict());
+        }
+        insert.build().execute(&mut *connection).await?;
+
+        if self.keys.is_empty() {
+            return Ok(());
+        }
+        let mut keys = QueryBuilder::<Postgres>::new(format!("INSERT INTO {} ({}) ", EXPORTED_KEYS, self.key_columns()));
+        keys.push_values(rows, |mut row, values| {
+            for &index in &self.keys {
+                push_value(&mut row, &values[index], self.types[index]);
+            }
+        });
+        keys.build().execute(&mut *connection).await?;
+
+        Ok(())
+    }
+}
+
+fn push_value(row: &mut Separated<'_, '_, Postgres, &'static str>, value: &CellValue, column_type: ColumnType) {
+    match column_type {
+        ColumnType::Integer => row.push_bind(value.as_integer()),
+        ColumnType::Real => row.push_bind(value.as_real()),
+        ColumnType::Text => row.push_bind(value.as_text()),
+    };
+}
+
+/// Exports every table to the Postgres database at `url`, creating tables and
+/// columns it doesn't have yet, and returns the number of rows exported
+pub async fn export(pool: &Pool<Sqlite>, url: &str) -> Result<usize, sqlx::Error> {
+    let postgres = PgPool::connect(url).await?;
+    let mut transaction = postgres.begin().await?;
+    let mut exported = 0;
+
+    for table in TABLES {
+        let columns = table_info(pool, table).await?;
+        let exported_table = ExportedTable {
+            name: table,
+            columns: columns.iter().map(|column| format!("\"{}\"", column.name)).collect(),
+            types: columns
+                .iter()
+                .map(|column| ColumnType::from_declared(&column.data_type))
+                .collect(),
+            keys: (0..columns.len()).filter(|&index| columns[index].primary_key).collect(),
+        };
+        let definitions: Vec<String> = exported_table
+            .columns
+            .iter()
+            .zip(&exported_table.types)
+            .map(|(column, column_type)| format!("{} {}", column, column_type.postgres_type()))
+            .collect();
+
+        let mut create = format!("CREATE TABLE IF NOT EXISTS \"{}\" ({}", table, definitions.join(", "));
+        if !exported_table.keys.is_empty() {
+            create.push_str(&format!(", PRIMARY KEY ({})", exported_table.key_columns()));
+        }
+        create.push(')');
+        sqlx::query(&create).execute(&mut *transaction).await?;
+
+        // Columns added to the SQLite schema after the table was first exported
+        for definition in &definitions {
+            sqlx::query(&format!("ALTER TABLE \"{}\" ADD COLUMN IF NOT EXISTS {}", table, definition))
+                .execute(&mut *transaction)
+                .await?;
+        }
+        for column in columns.iter().filter(|column| INDEXED_COLUMNS.contains(&column.name.as_str())) {
+            sqlx::query(&format!(
+                "CREATE INDEX IF NOT EXISTS \"idx_{}_{}\" ON \"{}\" (\"{}\")",
+                table, column.name, table, column.name
+            ))
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        if exported_table.keys.is_empty() {
+            // Without a key rows can't be matched up, so they are replaced.
+            // DELETE rather than TRUNCATE, which would lock out readers until the commit
+            sqlx::query(&format!("DELETE FROM \"{}\"", table))
+                .execute(&mut *transaction)
+                .await?;
+        } else {
+            sqlx::query(&format!(
+                "CREATE TEMP TABLE {} AS SELECT {} FROM \"{}\" WITH NO DATA",
+                EXPORTED_KEYS,
+                exported_table.key_columns(),
+                table
+            ))
+            .execute(&mut *transaction)
+            .await?;
+        }
+
+        let batch_rows = (MAX_PARAMETERS / columns.len().max(1)).max(1);
+        let mut batch = Vec::with_capacity(batch_rows);
+        let query = format!("SELECT * FROM {} ORDER BY rowid", table);
+        let mut rows = sqlx::query(&query).fetch(pool);
+        while let Some(row) = rows.try_next().await? {
+            batch.push(row_values(&row)?);
+            if batch.len() == batch_rows {
+                exported_table.write(&mut transaction, &batch).await?;
+                exported += batch.len();
+                batch.clear();
+            }
+        }
+        if !batch.is_empty() {
+            exported_table.write(&mut transaction, &batch).await?;
+            exported += batch.len();
+        }
+
+        if !exported_table.keys.is_empty() {
+            let matched: Vec<String> = exported_table
+                .keys
+                .iter()
+                .map(|&index| format!("k.{column} = t.{column}", column = exported_table.columns[index]))
+                .collect();
+            sqlx::query(&format!(
+                "DELETE FROM \"{}\" t WHERE NOT EXISTS (SELECT 1 FROM {} k WHERE {})",
+                table,
+                EXPORTED_KEYS,
+                matched.join(" AND ")
+            ))
+            .execute(&mut *transaction)
+            .await?;
+            sqlx::query(&format!("DROP TABLE {}", EXPORTED_KEYS))
+                .execute(&mut *transaction)
+                .await?;
+        }
+    }
+
+    transaction.commit().await?;
+    postgres.close().await;
+
+    Ok(exported)
+}
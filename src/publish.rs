@@ -0,0 +1,132 @@
+/// Publishes generated reports to a branch served by GitHub Pages
+///
+/// The report is committed through the Git Data API, so publishing needs no
+/// local clone: the file is written into the branch's tree and the branch is
+/// moved to a new commit. A missing branch is created without any history, as
+/// GitHub Pages branches usually are.
+use octocrab::Octocrab;
+use serde::Deserialize;
+use serde_json::json;
+use thiserror::Error;
+
+use crate::config::PublishConfig;
+use crate::database::Repository;
+
+/// File the report is written to when `publish.path` isn't set
+pub const DEFAULT_PUBLISH_PATH: &str = "index.html";
+
+#[derive(Debug, Error)]
+pub enum PublishError {
+    #[error("Invalid publish.repository in config: {0} is not owner/name")]
+    InvalidRepository(String),
+    #[error("Unable to publish the report: {0}")]
+    GitHub(#[from] octocrab::Error),
+}
+
+#[derive(Debug, Deserialize)]
+struct GitObject {
+    sha: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitRef {
+    object: GitObject,
+}
+
+#[derive(Debug, Deserialize)]
+struct GitCommit {
+    tree: GitObject,
+}
+
+/// Where a report ends up: the repository, branch and path within it
+#[derive(Debug)]
+pub struct Destination {
+    pub owner: String,
+    pub name: String,
+    pub branch: String,
+    pub path: String,
+}
+
+impl Destination {
+    /// Publishes to `branch` of `publish.repository`, or of the reported repository itself
+    ///
+    /// `{owner}` and `{name}` in `publish.path` are replaced with those of the
+    /// reported repository, so several reports can share one site.
+    pub fn new(config: &PublishConfig, repo: &Repository, branch: &str) -> Result<Self, PublishError> {
+        let (owner, name) = match &config.repository {
+            Some(repository) => repository
+                .split_once('/')
+                .filter(|(owner, name)| !owner.is_empty() && !name.is_empty() && !name.contains('/'))
+                .map(|(owner, name)| (owner.to_string(), name.to_string()))
+                .ok_or_else(|| PublishError::InvalidRepository(repository.clone()))?,
+            None => (repo.owner.clone(), repo.name.clone()),
+        };
+        let path = config
+            .path
+            .as_deref()
+            .unwrap_or(DEFAULT_PUBLISH_PATH)
+            .replace("{owner}", &repo.owner)
+            .replace("{name}", &repo.name);
+
+        Ok(Self { owner, name, branch: branch.to_string(), path })
+    }
+
+    /// Commits `html` to the destination and returns the commit, or `None`
+    /// when the branch already holds exactly this file
+    pub async fn publish(
+        &self,
+        octocrab: &Octocrab,
+        html: &str,
+        message: &str,
+    ) -> Result<Option<String>, PublishError> {
+        let route = format!("/repos/{}/{}/git", self.owner, self.name);
+
+        let head: Result<GitRef, _> = octocrab.get(format!("{}/ref/heads/{}", route, self.branch), None::<&()>).await;
+        let head = match head {
+            Ok(head) => Some(head.object.sha),
+            Err(octocrab::Error::GitHub { source, .. }) if source.status_code == http::StatusCode::NOT_FOUND => None,
+            Err(err) => return Err(err.into()),
+        };
+        let base_tree = match &head {
+            Some(sha) => {
+                let commit: GitCommit = octocrab.get(format!("{}/commits/{}", route, sha), None::<&()>).await?;
+                Some(commit.tree.sha)
+            }
+            None => None,
+        };
+
+        let mut tree = json!({
+            "tree": [{ "path": self.path, "mode": "100644", "type": "blob", "content": html }],
+        });
+        if let Some(base_tree) = &base_tree {
+            tree["base_tree"] = json!(base_tree);
+        }
+        let tree: GitObject = octocrab.post(format!("{}/trees", route), Some(&tree)).await?;
+        if base_tree.as_ref() == Some(&tree.sha) {
+            return Ok(None);
+        }
+
+        let parents: Vec<&String> = head.iter().collect();
+        let commit: GitObject = octocrab
+            .post(
+                format!("{}/commits", route),
+                Some(&json!({ "message": message, "tree": tree.sha, "parents": parents })),
+            )
+            .await?;
+
+        if head.is_some() {
+            let _: serde_json::Value = octocrab
+                .patch(format!("{}/refs/heads/{}", route, self.branch), Some(&json!({ "sha": commit.sha })))
+                .await?;
+        } else {
+            let _: serde_json::Value = octocrab
+                .post(
+                    format!("{}/refs", route),
+                    Some(&json!({ "ref": format!("refs/heads/{}", self.branch), "sha": commit.sha })),
+                )
+                .await?;
+        }
+
+        Ok(Some(commit.sha))
+    }
+}
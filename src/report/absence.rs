@@ -0,0 +1,104 @@
+/// Report section listing contributors who have gone quiet
+///
+/// A contribution is a pull request or issue opened, a review, a comment or a
+/// commit on the default branch. Contributors with enough contributions but
+/// none in the last few months are listed so maintainers can reach out or
+/// hand their areas over to someone else.
+use chrono::{Months, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, parse_timestamp, ReportOptions, Section};
+
+/// Contributions needed before someone counts as previously active
+pub const MIN_CONTRIBUTIONS: u32 = 10;
+
+/// Months without any contribution before someone counts as absent
+pub const ABSENT_MONTHS: u32 = 3;
+
+pub async fn quiet_contributors(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    // (login, contributions, last contribution)
+    let contributors: Vec<(String, i64, String)> = sqlx::query_as(
+        "SELECT login, COUNT(*) AS contributions, MAX(at) AS last_at FROM (
+            SELECT author AS login, created_at AS at FROM pulls WHERE repo_id = $1
+            UNION ALL
+            SELECT r.reviewer, r.submitted_at FROM issue_pull_reviews r
+            JOIN pulls p ON p.id = r.issue_pull_id WHERE p.repo_id = $1
+            UNION ALL
+            SELECT c.author, c.created_at FROM issue_pull_comments c
+            JOIN pulls p ON p.id = c.issue_pull_id WHERE p.repo_id = $1
+            UNION ALL
+            SELECT author, authored_at FROM commits WHERE repo_id = $1 AND on_default_branch = 1
+        )
+        WHERE login != '' AND login NOT LIKE '%[bot]' AND at IS NOT NULL
+        GROUP BY login
+        HAVING COUNT(*) >= $2
+        ORDER BY last_at DESC",
+    )
+    .bind(repo.id)
+    .bind(options.absence_min_contributions)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Quiet contributors".to_string();
+
+    if contributors.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>Nobody has made {} or more contributions yet. Run <code>gdfm collect pulls</code>, \
+                <code>gdfm collect reviews</code> and <code>gdfm collect comments</code> first.</p>",
+                options.absence_min_contributions
+            ),
+        });
+    }
+
+    let now = Utc::now().naive_utc();
+    let cutoff = now
+        .checked_sub_months(Months::new(options.absence_months))
+        .unwrap_or(now);
+    let absent: Vec<_> = contributors
+        .iter()
+        .filter(|(_, _, last_at)| parse_timestamp(last_at).is_some_and(|at| at < cutoff))
+        .collect();
+
+    let locale = &options.locale;
+    let summary = format!(
+        "{} of {} contributors with at least {} contributions (pull requests, issues, reviews, \
+        comments and commits) have made none in the last {} months.",
+        locale.format_number(absent.len() as i64),
+        locale.format_number(contributors.len() as i64),
+        locale.format_number(options.absence_min_contributions as i64),
+        locale.format_number(options.absence_months as i64)
+    );
+
+    if absent.is_empty() {
+        return Ok(Section { title, html: format!("<p>{}</p>", summary) });
+    }
+
+    let mut rows = String::new();
+    for (login, contributions, last_at) in absent {
+        let days = parse_timestamp(last_at).map(|at| (now - at).num_days()).unwrap_or(0);
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td>{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&options.login(login)),
+            locale.format_number(*contributions),
+            escape(&locale.format_timestamp(last_at)),
+            locale.format_number(days)
+        ));
+    }
+
+    let html = format!(
+        "<p>{}</p>\n\
+        <table>\n<tr><th>Contributor</th><th>Contributions</th><th>Last contribution</th>\
+        <th>Days quiet</th></tr>\n{}</table>",
+        summary, rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,44 @@
+/// Stable pseudonyms for the usernames shown in reports
+///
+/// A pseudonym is derived from the login with an HMAC, so the same person has
+/// the same pseudonym in every section and every report made with the same
+/// secret, while the login can't be recovered without it. Bots aren't
+/// individuals and keep their names.
+use std::borrow::Cow;
+use std::fmt;
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Characters of the digest kept in a pseudonym
+const PSEUDONYM_LENGTH: usize = 8;
+
+pub struct Anonymizer {
+    secret: Vec<u8>,
+}
+
+// Leaves the secret out of debug output
+impl fmt::Debug for Anonymizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Anonymizer").finish_non_exhaustive()
+    }
+}
+
+impl Anonymizer {
+    pub fn new(secret: &str) -> Self {
+        Self { secret: secret.as_bytes().to_vec() }
+    }
+
+    /// Pseudonym of a login, e.g. `contributor-3f2a9c01`
+    pub fn pseudonym<'a>(&self, login: &'a str) -> Cow<'a, str> {
+        if login.is_empty() || login.ends_with("[bot]") {
+            return Cow::Borrowed(login);
+        }
+
+        let mut mac = Hmac::<Sha256>::new_from_slice(&self.secret).expect("HMAC accepts keys of any length");
+        mac.update(login.as_bytes());
+        let digest = hex::encode(mac.finalize().into_bytes());
+
+        Cow::Owned(format!("contributor-{}", &digest[..PSEUDONYM_LENGTH]))
+    }
+}
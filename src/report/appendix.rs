@@ -0,0 +1,142 @@
+/// Optional report appendix with the raw data behind the other sections
+///
+/// Lists every merged pull request and every review in tables that can be
+/// sorted by clicking a column heading and filtered with a search box. Cells
+/// carry their stored value in `data-sort` so dates sort correctly whatever
+/// the locale they are displayed in.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+/// Sorts a table by the clicked column and hides rows not matching the filter box
+const TABLE_SCRIPT: &str = "<script>
+document.querySelectorAll('table.data').forEach(function (table) {
+  var body = table.tBodies[0];
+  var filter = document.createElement('input');
+  filter.type = 'search';
+  filter.placeholder = 'Filter rows';
+  table.parentNode.insertBefore(filter, table);
+  filter.addEventListener('input', function () {
+    var needle = filter.value.toLowerCase();
+    Array.from(body.rows).forEach(function (row) {
+      row.hidden = needle && row.textContent.toLowerCase().indexOf(needle) === -1;
+    });
+  });
+  Array.from(table.tHead.rows[0].cells).forEach(function (heading, column) {
+    heading.style.cursor = 'pointer';
+    heading.addEventListener('click', function () {
+      var ascending = heading.dataset.order !== 'asc';
+      heading.dataset.order = ascending ? 'asc' : 'desc';
+      var value = function (row) { return row.cells[column].dataset.sort || row.cells[column].textContent; };
+      var rows = Array.from(body.rows).sort(function (a, b) {
+        var x = value(a), y = value(b);
+        var order = (x !== '' && y !== '' && !isNaN(x) && !isNaN(y)) ? x - y : x.localeCompare(y);
+        return ascending ? order : -order;
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+});
+</script>";
+
+pub async fn raw_data(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    // (number, title, author, created_at, first review, merged_at)
+    let pulls: Vec<(u32, String, String, String, Option<String>, String)> = sqlx::query_as(
+        "SELECT p.number, p.title, p.author, p.created_at,
+            (SELECT MIN(r.submitted_at) FROM issue_pull_reviews r
+             WHERE r.issue_pull_id = p.id AND r.reviewer != p.author),
+            p.merged_at
+        FROM pulls p
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.merged_at IS NOT NULL AND p.created_at IS NOT NULL
+        ORDER BY p.merged_at DESC",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    // (number, reviewer, association, state, submitted_at)
+    let reviews: Vec<(u32, String, String, String, Option<String>)> = sqlx::query_as(
+        "SELECT p.number, r.reviewer, r.author_association, r.state, r.submitted_at
+        FROM issue_pull_reviews r JOIN pulls p ON p.id = r.issue_pull_id
+        WHERE p.repo_id = $1
+        ORDER BY r.submitted_at DESC",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Appendix: raw data".to_string();
+
+    if pulls.is_empty() && reviews.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No merged pull requests or reviews. Run <code>gdfm collect pulls</code> \
+                and <code>gdfm collect reviews</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    let locale = &options.locale;
+    let timestamp = |value: &str| {
+        format!(
+            "<td data-sort=\"{}\">{}</td>",
+            escape(value),
+            escape(&locale.format_timestamp(value))
+        )
+    };
+    let pull_link = |number: u32| {
+        format!(
+            "<td data-sort=\"{number}\"><a href=\"https://github.com/{}/{}/pull/{number}\">#{number}</a></td>",
+            escape(&repo.owner),
+            escape(&repo.name)
+        )
+    };
+
+    let mut pull_rows = String::new();
+    for (number, pull_title, author, created_at, first_review, merged_at) in &pulls {
+        pull_rows.push_str(&format!(
+            "<tr>{}<td>{}</td><td>{}</td>{}{}{}</tr>\n",
+            pull_link(*number),
+            escape(pull_title),
+            escape(&options.login(author)),
+            timestamp(created_at),
+            timestamp(first_review.as_deref().unwrap_or_default()),
+            timestamp(merged_at)
+        ));
+    }
+
+    let mut review_rows = String::new();
+    for (number, reviewer, association, state, submitted_at) in &reviews {
+        review_rows.push_str(&format!(
+            "<tr>{}<td>{}</td><td>{}</td><td>{}</td>{}</tr>\n",
+            pull_link(*number),
+            escape(&options.login(reviewer)),
+            escape(association),
+            escape(state),
+            timestamp(submitted_at.as_deref().unwrap_or_default())
+        ));
+    }
+
+    let html = format!(
+        "<p>Click a column heading to sort by it.</p>\n\
+        <h3>Merged pull requests ({})</h3>\n\
+        <table class=\"data\">\n<thead><tr><th>Pull request</th><th>Title</th><th>Author</th>\
+        <th>Opened</th><th>First review</th><th>Merged</th></tr></thead>\n<tbody>\n{}</tbody>\n</table>\n\
+        <h3>Reviews ({})</h3>\n\
+        <table class=\"data\">\n<thead><tr><th>Pull request</th><th>Reviewer</th><th>Association</th>\
+        <th>State</th><th>Submitted</th></tr></thead>\n<tbody>\n{}</tbody>\n</table>\n{}",
+        locale.format_number(pulls.len() as i64),
+        pull_rows,
+        locale.format_number(reviews.len() as i64),
+        review_rows,
+        TABLE_SCRIPT
+    );
+
+    Ok(Section { title, html })
+}
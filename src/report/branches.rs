@@ -0,0 +1,146 @@
+/// Report section on the branches pull requests target and who merges them
+///
+/// Pull requests into release or maintenance branches often follow other rules
+/// than those into the main branch, so each target branch gets its own row.
+/// Mergers come from `gdfm collect pull-details` or webhooks, and otherwise
+/// from the `merged` event stored by `gdfm collect events`.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, median, parse_timestamp, ReportOptions, Section};
+
+/// Number of target branches listed, most pull requests first
+const TOP_BRANCHES: usize = 10;
+
+/// Number of mergers listed, most merges first
+const TOP_MERGERS: usize = 10;
+
+#[derive(Debug, sqlx::FromRow)]
+struct BranchPull {
+    base_ref: String,
+    created_at: String,
+    merged_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct BranchStats {
+    opened: i64,
+    merged: i64,
+    days_to_merge: Vec<f64>,
+}
+
+pub async fn branch_breakdown(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let pulls: Vec<BranchPull> = sqlx::query_as(
+        "SELECT base_ref, created_at, merged_at FROM pulls
+        WHERE repo_id = $1 AND is_issue = 0 AND base_ref != '' AND created_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Target branches and mergers".to_string();
+
+    if pulls.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No target branches stored. Run <code>gdfm collect pulls</code> again to record them.</p>"
+                .to_string(),
+        });
+    }
+
+    // (merger, merges)
+    let mergers: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT merger, COUNT(*) AS merges FROM (
+            SELECT COALESCE(NULLIF(p.merged_by, ''), (
+                SELECT e.actor FROM issue_pull_events e
+                WHERE e.issue_pull_id = p.id AND e.event_type = 'merged'
+                ORDER BY e.created_at DESC LIMIT 1
+            )) AS merger
+            FROM pulls p
+            WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.merged_at IS NOT NULL
+        )
+        WHERE merger IS NOT NULL AND merger != ''
+        GROUP BY merger
+        ORDER BY merges DESC, merger",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut branches: Vec<(String, BranchStats)> = Vec::new();
+    for pull in &pulls {
+        let index = match branches.iter().position(|(name, _)| *name == pull.base_ref) {
+            Some(index) => index,
+            None => {
+                branches.push((pull.base_ref.clone(), BranchStats::default()));
+                branches.len() - 1
+            }
+        };
+        let stats = &mut branches[index].1;
+        stats.opened += 1;
+        let merged_at = pull.merged_at.as_deref().and_then(parse_timestamp);
+        if let (Some(created_at), Some(merged_at)) = (parse_timestamp(&pull.created_at), merged_at) {
+            stats.merged += 1;
+            stats.days_to_merge.push((merged_at - created_at).num_seconds() as f64 / 86_400.0);
+        }
+    }
+    branches.sort_by(|(a_name, a), (b_name, b)| b.opened.cmp(&a.opened).then(a_name.cmp(b_name)));
+
+    let locale = &options.locale;
+
+    let mut branch_rows = String::new();
+    for (name, stats) in branches.iter_mut().take(TOP_BRANCHES) {
+        let days = match median(&mut stats.days_to_merge) {
+            Some(days) => locale.format_decimal(days, 1),
+            None => "n/a".to_string(),
+        };
+        branch_rows.push_str(&format!(
+            "<tr><td><code>{}</code></td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(name),
+            locale.format_number(stats.opened),
+            locale.format_number(stats.merged),
+            days
+        ));
+    }
+
+    let mut html = format!(
+        "<p>Pull requests by the branch they target, the {} most targeted of {} branches. Days to merge \
+        are medians from opening.</p>\n\
+        <table>\n<tr><th>Branch</th><th>Pull requests</th><th>Merged</th><th>Days to merge</th></tr>\n{}</table>\n",
+        locale.format_number(branches.len().min(TOP_BRANCHES) as i64),
+        locale.format_number(branches.len() as i64),
+        branch_rows
+    );
+
+    if mergers.is_empty() {
+        html.push_str(
+            "<p>No mergers known. Run <code>gdfm collect pull-details</code> or \
+            <code>gdfm collect events</code> first.</p>",
+        );
+    } else {
+        let total: i64 = mergers.iter().map(|(_, merges)| merges).sum();
+        let mut merger_rows = String::new();
+        for (merger, merges) in mergers.iter().take(TOP_MERGERS) {
+            merger_rows.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}%</td></tr>\n",
+                escape(&options.login(merger)),
+                locale.format_number(*merges),
+                locale.format_decimal(*merges as f64 / total as f64 * 100.0, 1)
+            ));
+        }
+        html.push_str(&format!(
+            "<p>{} people merged the {} merged pull requests whose merger is known.</p>\n\
+            <table>\n<tr><th>Merged by</th><th>Pull requests</th><th>Share</th></tr>\n{}</table>",
+            locale.format_number(mergers.len() as i64),
+            locale.format_number(total),
+            merger_rows
+        ));
+    }
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,81 @@
+/// Report section estimating the bus factor from contributor activity
+///
+/// The bus factor is the smallest number of people who together authored half
+/// of the commits in the last year, based on the weekly statistics from
+/// `gdfm collect contributors`.
+use chrono::{Duration, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+/// Share of commits the bus factor has to cover
+const BUS_FACTOR_SHARE: f64 = 0.5;
+
+/// Number of contributors listed in the section
+const TOP_CONTRIBUTORS: usize = 10;
+
+pub async fn bus_factor(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::weeks(52)).date_naive().to_string();
+    let contributors: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT login, SUM(commits) AS commits
+        FROM contributor_weeks
+        WHERE repo_id = $1 AND week >= $2
+        GROUP BY login
+        ORDER BY commits DESC",
+    )
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Bus factor".to_string();
+    let total: i64 = contributors.iter().map(|(_, commits)| commits).sum();
+
+    if total == 0 {
+        return Ok(Section {
+            title,
+            html: "<p>No commit activity in the last year. Run <code>gdfm collect contributors</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    let mut covered = 0;
+    let factor = contributors
+        .iter()
+        .take_while(|(_, commits)| {
+            let below = (covered as f64) < total as f64 * BUS_FACTOR_SHARE;
+            covered += commits;
+            below
+        })
+        .count();
+
+    let locale = &options.locale;
+    let mut rows = String::new();
+    for (login, commits) in contributors.iter().take(TOP_CONTRIBUTORS) {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}%</td></tr>\n",
+            escape(&options.login(login)),
+            locale.format_number(*commits),
+            locale.format_decimal(*commits as f64 * 100.0 / total as f64, 1)
+        ));
+    }
+
+    let html = format!(
+        "<p>{} of {} active contributors authored at least {}% of the {} commits in the last \
+        year (among the top 100 contributors GitHub reports statistics for).</p>\n\
+        <table>\n<tr><th>Contributor</th><th>Commits</th><th>Share</th></tr>\n{}</table>",
+        locale.format_number(factor as i64),
+        locale.format_number(contributors.len() as i64),
+        locale.format_decimal(BUS_FACTOR_SHARE * 100.0, 0),
+        locale.format_number(total),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,137 @@
+/// Caveats added to sections computed from partially collected data
+///
+/// Events and reviews are collected pull request by pull request, so a report
+/// generated while those collectors are still catching up would quietly count
+/// only some pull requests. Sections that depend on them get a note saying how
+/// many pull requests their numbers are based on instead.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{ReportOptions, Section};
+
+/// Data collected for each pull request that sections depend on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    Events,
+    Reviews,
+}
+
+impl Source {
+    fn table(self) -> &'static str {
+        match self {
+            Source::Events => "issue_pull_events",
+            Source::Reviews => "issue_pull_reviews",
+        }
+    }
+
+    /// Collector that fills the table, which is also the name of its cursor
+    fn collector(self) -> &'static str {
+        match self {
+            Source::Events => "events",
+            Source::Reviews => "reviews",
+        }
+    }
+}
+
+/// How many of a repository's pull requests each source has been collected for
+#[derive(Debug, Default)]
+pub struct DataCoverage {
+    total: i64,
+    events: i64,
+    reviews: i64,
+}
+
+impl DataCoverage {
+    /// Counts the pull requests whose events and reviews have been collected
+    ///
+    /// A pull request counts as collected when rows for it are stored, or when
+    /// it hasn't changed since the last completed run of the collector, since
+    /// plenty of pull requests never get a review.
+    pub async fn measure(pool: &Pool<Sqlite>, repo: &Repository) -> Result<Self, sqlx::Error> {
+        let total: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM pulls WHERE repo_id = $1 AND NOT is_issue")
+            .bind(repo.id)
+            .fetch_one(pool)
+            .await?;
+
+        let mut coverage = DataCoverage { total, ..Default::default() };
+        for source in [Source::Events, Source::Reviews] {
+            let query = format!(
+                "SELECT COUNT(*) FROM pulls p
+                WHERE p.repo_id = $1 AND NOT p.is_issue
+                AND (
+                    EXISTS (SELECT 1 FROM {} t WHERE t.issue_pull_id = p.id)
+                    OR p.updated_at <= (
+                        SELECT updated_at FROM collection_cursors WHERE repo_id = $1 AND collector = $2
+                    )
+                )",
+                source.table()
+            );
+            let covered: i64 = sqlx::query_scalar(&query)
+                .bind(repo.id)
+                .bind(source.collector())
+                .fetch_one(pool)
+                .await?;
+            match source {
+                Source::Events => coverage.events = covered,
+                Source::Reviews => coverage.reviews = covered,
+            }
+        }
+
+        Ok(coverage)
+    }
+
+    fn covered(&self, source: Source) -> i64 {
+        match source {
+            Source::Events => self.events,
+            Source::Reviews => self.reviews,
+        }
+    }
+
+    /// Share of pull requests a source has been collected for, rounded down so
+    /// that anything short of all of them never reads as 100%
+    fn percent(&self, source: Source) -> i64 {
+        self.covered(source) * 100 / self.total
+    }
+
+    /// Prepends a caveat to a section computed from `sources` if any of them is incomplete
+    pub fn annotate(&self, mut section: Section, sources: &[Source], options: &ReportOptions) -> Section {
+        let incomplete: Vec<Source> = sources
+            .iter()
+            .copied()
+            .filter(|source| self.total > 0 && self.covered(*source) < self.total)
+            .collect();
+        if incomplete.is_empty() {
+            return section;
+        }
+
+        let locale = &options.locale;
+        let shares: Vec<String> = incomplete
+            .iter()
+            .enumerate()
+            .map(|(index, source)| {
+                let percent = locale.format_number(self.percent(*source));
+                match (index, incomplete.len()) {
+                    (_, 1) => format!("{}% of pull requests", percent),
+                    (0, _) => format!("{}% of pull requests for {}", percent, source.collector()),
+                    _ => format!("{}% for {}", percent, source.collector()),
+                }
+            })
+            .collect();
+        let commands: Vec<String> = incomplete
+            .iter()
+            .map(|source| format!("<code>gdfm collect {}</code>", source.collector()))
+            .collect();
+
+        section.html = format!(
+            "<p class=\"caveat\">Based on {}: {} haven't been collected for the rest yet. Run {} to fill them \
+            in.</p>\n{}",
+            shares.join(" and "),
+            incomplete.iter().map(|source| source.collector()).collect::<Vec<_>>().join(" and "),
+            commands.join(" and "),
+            section.html
+        );
+
+        section
+    }
+}
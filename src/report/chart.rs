@@ -0,0 +1,73 @@
+/// Small helpers for drawing charts as inline SVG
+///
+/// Reports are single HTML files, so charts are rendered server-side instead
+/// of pulling in a JavaScript charting library.
+use chrono::NaiveDate;
+
+use crate::report::escape;
+use crate::report::locale::Locale;
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 240.0;
+const PADDING: f64 = 40.0;
+const COLORS: [&str; 4] = ["#1f77b4", "#ff7f0e", "#2ca02c", "#d62728"];
+
+/// A named daily time series
+pub struct Series<'a> {
+    pub name: &'a str,
+    pub points: &'a [(NaiveDate, i64)],
+}
+
+/// Draws one or more daily series sharing the same axes
+pub fn line_chart(series: &[Series], locale: &Locale) -> String {
+    let all_points = series.iter().flat_map(|s| s.points.iter());
+    let (Some(start), Some(end)) = (
+        all_points.clone().map(|(day, _)| *day).min(),
+        all_points.clone().map(|(day, _)| *day).max(),
+    ) else {
+        return String::new();
+    };
+    let max_value = all_points.map(|(_, value)| *value).max().unwrap_or(0).max(1);
+    let total_days = (end - start).num_days().max(1) as f64;
+
+    let x = |day: NaiveDate| PADDING + (day - start).num_days() as f64 / total_days * (WIDTH - 2.0 * PADDING);
+    let y = |value: i64| HEIGHT - PADDING - value as f64 / max_value as f64 * (HEIGHT - 2.0 * PADDING);
+
+    let mut svg = format!(
+        "<svg viewBox=\"0 0 {WIDTH} {HEIGHT}\" width=\"100%\" role=\"img\">\n\
+        <line x1=\"{PADDING}\" y1=\"{bottom}\" x2=\"{right}\" y2=\"{bottom}\" stroke=\"#999\"/>\n\
+        <line x1=\"{PADDING}\" y1=\"{PADDING}\" x2=\"{PADDING}\" y2=\"{bottom}\" stroke=\"#999\"/>\n\
+        <text x=\"{PADDING}\" y=\"{label_y}\" font-size=\"11\">{start_label}</text>\n\
+        <text x=\"{right}\" y=\"{label_y}\" font-size=\"11\" text-anchor=\"end\">{end_label}</text>\n\
+        <text x=\"{max_x}\" y=\"{PADDING}\" font-size=\"11\" text-anchor=\"end\">{max_label}</text>\n",
+        bottom = HEIGHT - PADDING,
+        right = WIDTH - PADDING,
+        label_y = HEIGHT - PADDING + 15.0,
+        max_x = PADDING - 4.0,
+        start_label = locale.format_date(start),
+        end_label = locale.format_date(end),
+        max_label = locale.format_number(max_value),
+    );
+
+    for (index, s) in series.iter().enumerate() {
+        let color = COLORS[index % COLORS.len()];
+        let points: Vec<String> = s
+            .points
+            .iter()
+            .map(|(day, value)| format!("{:.1},{:.1}", x(*day), y(*value)))
+            .collect();
+        svg.push_str(&format!(
+            "<polyline fill=\"none\" stroke=\"{}\" stroke-width=\"1.5\" points=\"{}\"/>\n\
+            <text x=\"{}\" y=\"{}\" font-size=\"11\" fill=\"{}\">{}</text>\n",
+            color,
+            points.join(" "),
+            PADDING + 10.0 + index as f64 * 200.0,
+            PADDING - 15.0,
+            color,
+            escape(s.name)
+        ));
+    }
+    svg.push_str("</svg>");
+
+    svg
+}
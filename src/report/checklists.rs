@@ -0,0 +1,89 @@
+/// Report section on whether pull request checklists are filled out
+///
+/// Pull request templates often carry a task list ("- [ ] Tests added").
+/// `gdfm collect pulls` counts the ticked and unticked boxes in each
+/// description, which shows how many pull requests keep the template and how
+/// many of those actually work through it.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{ReportOptions, Section};
+
+#[derive(Debug, sqlx::FromRow)]
+struct ChecklistCounts {
+    pulls: i64,
+    with_checklist: i64,
+    complete: i64,
+    partial: i64,
+    untouched: i64,
+    merged_incomplete: i64,
+    items: i64,
+    ticked: i64,
+}
+
+pub async fn checklist_completion(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let counts: ChecklistCounts = sqlx::query_as("
+        SELECT
+            COUNT(*) AS pulls,
+            COUNT(c.id) AS with_checklist,
+            COUNT(CASE WHEN c.completed = c.total THEN 1 END) AS complete,
+            COUNT(CASE WHEN c.completed > 0 AND c.completed < c.total THEN 1 END) AS partial,
+            COUNT(CASE WHEN c.completed = 0 THEN 1 END) AS untouched,
+            COUNT(CASE WHEN c.completed < c.total AND p.merged_at IS NOT NULL THEN 1 END) AS merged_incomplete,
+            COALESCE(SUM(c.total), 0) AS items,
+            COALESCE(SUM(c.completed), 0) AS ticked
+        FROM pulls p
+        LEFT JOIN pull_checklists c ON c.issue_pull_id = p.id
+        WHERE p.repo_id = $1 AND p.is_issue = 0
+    ")
+    .bind(repo.id)
+    .fetch_one(pool)
+    .await?;
+
+    let title = "Pull request checklists".to_string();
+
+    if counts.with_checklist == 0 {
+        return Ok(Section {
+            title,
+            html: "<p>No pull request descriptions with a checklist. Run <code>gdfm collect pulls</code> \
+                first, and make sure <code>collect.checklists</code> is not turned off in the config.</p>"
+                .to_string(),
+        });
+    }
+
+    let locale = &options.locale;
+    let share = |count: i64, of: i64| {
+        if of == 0 {
+            "-".to_string()
+        } else {
+            format!("{}%", locale.format_decimal(count as f64 * 100.0 / of as f64, 1))
+        }
+    };
+    let row = |label: &str, count: i64, of: i64| {
+        format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            label,
+            locale.format_number(count),
+            share(count, of)
+        )
+    };
+
+    let html = format!(
+        "<p>{} of {} items across all checklists were ticked.</p>\n\
+        <table>\n<tr><th></th><th>Pull requests</th><th>Share</th></tr>\n{}{}{}{}{}</table>",
+        locale.format_number(counts.ticked),
+        locale.format_number(counts.items),
+        row("With a checklist in the description", counts.with_checklist, counts.pulls),
+        row("Every item ticked", counts.complete, counts.with_checklist),
+        row("Some items ticked", counts.partial, counts.with_checklist),
+        row("No items ticked", counts.untouched, counts.with_checklist),
+        row("Merged with items left unticked", counts.merged_incomplete, counts.with_checklist),
+    );
+
+    Ok(Section { title, html })
+}
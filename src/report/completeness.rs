@@ -0,0 +1,104 @@
+/// Report section counting stored rows with fields GitHub didn't provide
+///
+/// Without `--strict`, collectors store a missing author as an empty string and
+/// a missing timestamp as NULL, most often for accounts that were deleted since. Such rows
+/// drop out of the sections that need those fields, so this lists how many
+/// there are and on which pull requests and issues.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+/// (table, column, what the rows are) checked for empty values
+const CHECKS: &[(&str, &str, &str)] = &[
+    ("pulls", "created_at", "Pull requests and issues without a creation time"),
+    ("pulls", "updated_at", "Pull requests and issues without an update time"),
+    ("pulls", "author", "Pull requests and issues without an author"),
+    ("issue_pull_reviews", "reviewer", "Reviews without a reviewer"),
+    ("issue_pull_reviews", "submitted_at", "Reviews without a submission time"),
+    ("issue_pull_events", "actor", "Events without an actor"),
+    ("issue_pull_events", "created_at", "Events without a time"),
+    ("issue_pull_comments", "author", "Comments without an author"),
+    ("issue_pull_review_comments", "author", "Review comments without an author"),
+];
+
+/// Pull request numbers listed per check, lowest first
+const EXAMPLES: usize = 5;
+
+pub async fn missing_data(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let locale = &options.locale;
+    let mut rows = String::new();
+    let mut total = 0;
+
+    for (table, column, description) in CHECKS {
+        // Numbers of the pull requests or issues the incomplete rows belong to
+        let query = if *table == "pulls" {
+            format!("SELECT number FROM pulls WHERE repo_id = $1 AND COALESCE({}, '') = ''", column)
+        } else {
+            format!(
+                "SELECT p.number FROM {} t JOIN pulls p ON p.id = t.issue_pull_id
+                WHERE p.repo_id = $1 AND COALESCE(t.{}, '') = ''",
+                table, column
+            )
+        };
+        let mut numbers: Vec<u32> = sqlx::query_scalar(&query).bind(repo.id).fetch_all(pool).await?;
+        if numbers.is_empty() {
+            continue;
+        }
+        total += numbers.len();
+
+        let count = numbers.len();
+        numbers.sort_unstable();
+        numbers.dedup();
+        let examples: Vec<String> = numbers
+            .iter()
+            .take(EXAMPLES)
+            .map(|number| {
+                format!(
+                    "<a href=\"https://github.com/{}/{}/issues/{}\">#{}</a>",
+                    escape(&repo.owner),
+                    escape(&repo.name),
+                    number,
+                    number
+                )
+            })
+            .collect();
+        let more = if numbers.len() > EXAMPLES { ", &hellip;" } else { "" };
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td><code>{}.{}</code></td><td class=\"num\">{}</td><td>{}{}</td></tr>\n",
+            description,
+            table,
+            column,
+            locale.format_number(count as i64),
+            examples.join(", "),
+            more
+        ));
+    }
+
+    let title = "Missing data".to_string();
+
+    if total == 0 {
+        return Ok(Section {
+            title,
+            html: "<p>No stored pull requests, issues, reviews, events or comments are missing an author or \
+                timestamp.</p>"
+                .to_string(),
+        });
+    }
+
+    let html = format!(
+        "<p>{} stored rows are missing a field GitHub didn't provide, usually because the account was \
+        deleted, and are left out wherever that field is needed. Collect with <code>--strict</code> to \
+        stop on such items instead of storing them.</p>\n\
+        <table>\n<tr><th>Rows</th><th>Column</th><th>Count</th><th>On</th></tr>\n{}</table>",
+        locale.format_number(total as i64),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,93 @@
+/// Report section on how often issues get fixed by a pull request
+///
+/// Links come from closing keywords ("Fixes #123") in pull request bodies.
+/// The conversion rate and time to fix need issues from `gdfm collect issues`.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{median, ReportOptions, Section};
+
+#[derive(Debug, sqlx::FromRow)]
+struct LinkCounts {
+    linked_issues: i64,
+    fixed_issues: i64,
+    linking_pulls: i64,
+    issues: i64,
+    issues_with_pull: i64,
+}
+
+pub async fn issue_conversion(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let counts: LinkCounts = sqlx::query_as("
+        SELECT
+            COUNT(DISTINCT l.issue_number) AS linked_issues,
+            COUNT(DISTINCT CASE WHEN p.merged_at IS NOT NULL THEN l.issue_number END) AS fixed_issues,
+            COUNT(DISTINCT l.issue_pull_id) AS linking_pulls,
+            (SELECT COUNT(*) FROM pulls WHERE repo_id = $1 AND is_issue = 1) AS issues,
+            COUNT(DISTINCT issue.number) AS issues_with_pull
+        FROM linked_issues l
+        JOIN pulls p ON p.id = l.issue_pull_id
+        LEFT JOIN pulls issue ON issue.repo_id = p.repo_id AND issue.number = l.issue_number AND issue.is_issue = 1
+        WHERE p.repo_id = $1
+    ")
+    .bind(repo.id)
+    .fetch_one(pool)
+    .await?;
+
+    // Days from the issue being opened to the first merged pull request fixing it
+    let mut days_to_fix: Vec<f64> = sqlx::query_scalar("
+        SELECT MIN(julianday(p.merged_at)) - julianday(issue.created_at)
+        FROM linked_issues l
+        JOIN pulls p ON p.id = l.issue_pull_id
+        JOIN pulls issue ON issue.repo_id = p.repo_id AND issue.number = l.issue_number AND issue.is_issue = 1
+        WHERE p.repo_id = $1 AND p.merged_at IS NOT NULL
+        GROUP BY l.issue_number
+    ")
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let locale = &options.locale;
+    let median_days = match median(&mut days_to_fix) {
+        Some(days) => format!(
+            "{} days (over {} issues)",
+            locale.format_decimal(days, 1),
+            locale.format_number(days_to_fix.len() as i64)
+        ),
+        None => "n/a (no issue records collected)".to_string(),
+    };
+
+    let conversion_rate = if counts.issues > 0 {
+        format!(
+            "{}% of {} issues",
+            locale.format_decimal(counts.issues_with_pull as f64 / counts.issues as f64 * 100.0, 1),
+            locale.format_number(counts.issues)
+        )
+    } else {
+        "n/a (no issue records collected)".to_string()
+    };
+
+    let html = format!(
+        "<table>\n\
+        <tr><td>Pull requests that close an issue</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Issues referenced by a closing keyword</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Issues fixed by a merged pull request</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Issues with a linked pull request</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Median time from issue opened to fix merged</td><td class=\"num\">{}</td></tr>\n\
+        </table>",
+        locale.format_number(counts.linking_pulls),
+        locale.format_number(counts.linked_issues),
+        locale.format_number(counts.fixed_issues),
+        conversion_rate,
+        median_days
+    );
+
+    Ok(Section {
+        title: "Issue to pull request conversion".to_string(),
+        html,
+    })
+}
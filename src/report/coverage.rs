@@ -0,0 +1,118 @@
+/// Report section showing when maintainers respond, by weekday and month
+///
+/// A day counts as covered when at least one maintainer review, comment or
+/// event was recorded on it. Weekdays and months covered far less often than
+/// average are flagged as gaps a triage rotation should fill.
+use chrono::{Datelike, Month};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::locale::Locale;
+use crate::report::{maintainer_responses_per_day, ReportOptions, Section};
+
+/// A period is a gap when its coverage is below this fraction of the overall coverage
+const GAP_RATIO: f64 = 0.5;
+
+/// Responses and covered days for one weekday or month
+#[derive(Debug, Default, Clone, Copy)]
+struct Coverage {
+    responses: i64,
+    covered_days: i64,
+    days: i64,
+}
+
+impl Coverage {
+    fn add(&mut self, responses: i64) {
+        self.responses += responses;
+        self.days += 1;
+        if responses > 0 {
+            self.covered_days += 1;
+        }
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.days == 0 {
+            0.0
+        } else {
+            self.covered_days as f64 / self.days as f64
+        }
+    }
+}
+
+pub async fn response_coverage(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let daily = maintainer_responses_per_day(pool, repo).await?;
+
+    let title = "Maintainer response coverage".to_string();
+    let (Some(first), Some(last)) = (daily.keys().next().copied(), daily.keys().last().copied()) else {
+        return Ok(Section {
+            title,
+            html: "<p>No maintainer responses collected.</p>".to_string(),
+        });
+    };
+
+    let mut overall = Coverage::default();
+    let mut weekdays = [Coverage::default(); 7];
+    let mut months = [Coverage::default(); 12];
+    for day in first.iter_days().take_while(|day| *day <= last) {
+        let responses = daily.get(&day).copied().unwrap_or(0);
+        overall.add(responses);
+        weekdays[day.weekday().num_days_from_monday() as usize].add(responses);
+        months[day.month0() as usize].add(responses);
+    }
+
+    let locale = &options.locale;
+    let weekday_rows: Vec<(String, Coverage)> = locale
+        .weekdays()
+        .map(|weekday| (weekday.to_string(), weekdays[weekday.num_days_from_monday() as usize]))
+        .collect();
+    let month_rows: Vec<(String, Coverage)> = months
+        .iter()
+        .enumerate()
+        .filter(|(_, coverage)| coverage.days > 0)
+        .map(|(index, coverage)| {
+            let name = Month::try_from(index as u8 + 1).map(|month| month.name()).unwrap_or("");
+            (name.to_string(), *coverage)
+        })
+        .collect();
+
+    let html = format!(
+        "<p>Share of days between {} and {} with at least one maintainer review, comment or \
+        event. Overall {}% of days are covered; periods below {}% of that are marked as gaps.</p>\n\
+        <h3>By weekday</h3>\n{}\n<h3>By month</h3>\n{}",
+        locale.format_date(first),
+        locale.format_date(last),
+        locale.format_decimal(overall.ratio() * 100.0, 1),
+        locale.format_decimal(GAP_RATIO * 100.0, 0),
+        coverage_table("Weekday", &weekday_rows, overall.ratio(), locale),
+        coverage_table("Month", &month_rows, overall.ratio(), locale)
+    );
+
+    Ok(Section { title, html })
+}
+
+fn coverage_table(label: &str, rows: &[(String, Coverage)], overall: f64, locale: &Locale) -> String {
+    let mut body = String::new();
+    for (name, coverage) in rows {
+        let is_gap = coverage.days > 0 && coverage.ratio() < overall * GAP_RATIO;
+        let gap = if is_gap { "Gap" } else { "" };
+        body.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}%</td><td>{}</td></tr>\n",
+            name,
+            locale.format_number(coverage.responses),
+            locale.format_number(coverage.covered_days),
+            locale.format_decimal(coverage.ratio() * 100.0, 1),
+            gap
+        ));
+    }
+    format!(
+        "<table>\n<tr><th>{}</th><th>Responses</th><th>Days covered</th><th>Coverage</th>\
+        <th></th></tr>\n{}</table>",
+        label, body
+    )
+}
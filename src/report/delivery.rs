@@ -0,0 +1,196 @@
+/// Report section on delivery performance measured from deployments
+///
+/// Follows the DORA metrics: how often changes are deployed, how long a merged
+/// pull request waits until it is deployed, how many deployments fail and how
+/// long it takes to deploy successfully again afterwards. Lead time is measured
+/// to the first successful deployment to the same environment after the merge,
+/// as gdfm doesn't know which commits each deployment contains.
+use std::collections::BTreeMap;
+
+use chrono::{Duration, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, median, parse_timestamp, ReportOptions, Section};
+
+/// Only deployments and merges in this many days are measured
+const DELIVERY_DAYS: i64 = 90;
+
+/// Environment names measured in preference to the most deployed one
+const PRODUCTION_ENVIRONMENTS: &[&str] = &["production", "prod"];
+
+/// Deployment statuses that end a deployment as failed
+const FAILED_STATES: &[&str] = &["failure", "error"];
+
+#[derive(Debug, sqlx::FromRow)]
+struct DeploymentOutcome {
+    environment: String,
+    created_at: String,
+    /// Last status that settled the deployment, empty while none did
+    outcome: String,
+    /// When the deployment first succeeded
+    deployed_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct EnvironmentCounts {
+    deployments: i64,
+    succeeded: i64,
+    failed: i64,
+}
+
+pub async fn delivery_performance(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(DELIVERY_DAYS)).naive_utc();
+    let deployments: Vec<DeploymentOutcome> = sqlx::query_as(
+        "SELECT d.environment, d.created_at,
+            COALESCE((
+                SELECT s.state FROM deployment_statuses s
+                WHERE s.deployment_id = d.id AND s.state IN ('success', 'failure', 'error')
+                ORDER BY s.created_at DESC LIMIT 1
+            ), '') AS outcome,
+            (
+                SELECT MIN(s.created_at) FROM deployment_statuses s
+                WHERE s.deployment_id = d.id AND s.state = 'success'
+            ) AS deployed_at
+        FROM deployments d
+        WHERE d.repo_id = $1 AND d.created_at >= $2
+        ORDER BY d.created_at",
+    )
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Delivery performance".to_string();
+
+    if deployments.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>No deployments in the last {} days. Run <code>gdfm collect deployments</code> first.</p>",
+                DELIVERY_DAYS
+            ),
+        });
+    }
+
+    let mut environments: BTreeMap<&str, EnvironmentCounts> = BTreeMap::new();
+    for deployment in &deployments {
+        let counts = environments.entry(&deployment.environment).or_default();
+        counts.deployments += 1;
+        if deployment.deployed_at.is_some() {
+            counts.succeeded += 1;
+        }
+        if FAILED_STATES.contains(&deployment.outcome.as_str()) {
+            counts.failed += 1;
+        }
+    }
+    let environment = environments
+        .keys()
+        .copied()
+        .find(|name| PRODUCTION_ENVIRONMENTS.contains(&name.to_lowercase().as_str()))
+        .or_else(|| {
+            environments
+                .iter()
+                .max_by(|(a_name, a), (b_name, b)| a.succeeded.cmp(&b.succeeded).then(b_name.cmp(a_name)))
+                .map(|(name, _)| *name)
+        })
+        .unwrap_or_default();
+
+    let measured: Vec<&DeploymentOutcome> =
+        deployments.iter().filter(|deployment| deployment.environment == environment).collect();
+    let mut deployed_at: Vec<&str> =
+        measured.iter().filter_map(|deployment| deployment.deployed_at.as_deref()).collect();
+    deployed_at.sort_unstable();
+
+    // Hours from each failed deployment to the next successful one
+    let mut hours_to_restore = Vec::new();
+    for deployment in measured.iter().filter(|deployment| FAILED_STATES.contains(&deployment.outcome.as_str())) {
+        let restored = deployed_at.iter().find(|at| **at > deployment.created_at.as_str());
+        if let (Some(failed), Some(restored)) =
+            (parse_timestamp(&deployment.created_at), restored.and_then(|at| parse_timestamp(at)))
+        {
+            hours_to_restore.push((restored - failed).num_minutes() as f64 / 60.0);
+        }
+    }
+
+    // (created_at, merged_at) of pull requests merged in the period
+    let merged: Vec<(String, String)> = sqlx::query_as(
+        "SELECT created_at, merged_at FROM pulls
+        WHERE repo_id = $1 AND is_issue = 0 AND merged_at >= $2 AND created_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    let mut hours_from_merge = Vec::new();
+    let mut hours_from_opening = Vec::new();
+    for (created_at, merged_at) in &merged {
+        let deployed = deployed_at.iter().find(|at| **at >= merged_at.as_str());
+        let Some(deployed) = deployed.and_then(|at| parse_timestamp(at)) else {
+            continue;
+        };
+        if let Some(merged_at) = parse_timestamp(merged_at) {
+            hours_from_merge.push((deployed - merged_at).num_minutes() as f64 / 60.0);
+        }
+        if let Some(created_at) = parse_timestamp(created_at) {
+            hours_from_opening.push((deployed - created_at).num_minutes() as f64 / 60.0);
+        }
+    }
+
+    let locale = &options.locale;
+    let hours = |value: Option<f64>| {
+        value
+            .map(|hours| locale.format_decimal(hours, 1))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let counts = &environments[environment];
+    let finished = counts.succeeded + counts.failed;
+    let failure_rate = if finished == 0 {
+        "-".to_string()
+    } else {
+        format!("{}%", locale.format_decimal(counts.failed as f64 / finished as f64 * 100.0, 1))
+    };
+    let per_week = counts.succeeded as f64 / (DELIVERY_DAYS as f64 / 7.0);
+
+    let mut rows = String::new();
+    for (name, counts) in &environments {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(name),
+            locale.format_number(counts.deployments),
+            locale.format_number(counts.succeeded),
+            locale.format_number(counts.failed)
+        ));
+    }
+
+    let html = format!(
+        "<p>Measured on <code>{}</code> over the last {} days.</p>\n\
+        <table>\n\
+        <tr><th>Deployment frequency</th><td class=\"num\">{} successful deployments a week</td></tr>\n\
+        <tr><th>Lead time for changes</th><td class=\"num\">{} median hours from merge, {} from opening \
+        ({} of {} merged pull requests deployed)</td></tr>\n\
+        <tr><th>Change failure rate</th><td class=\"num\">{} of {} finished deployments</td></tr>\n\
+        <tr><th>Time to restore</th><td class=\"num\">{} median hours after a failed deployment</td></tr>\n\
+        </table>\n\
+        <table>\n<tr><th>Environment</th><th>Deployments</th><th>Successful</th><th>Failed</th></tr>\n{}</table>",
+        escape(environment),
+        DELIVERY_DAYS,
+        locale.format_decimal(per_week, 1),
+        hours(median(&mut hours_from_merge)),
+        hours(median(&mut hours_from_opening)),
+        locale.format_number(hours_from_merge.len() as i64),
+        locale.format_number(merged.len() as i64),
+        failure_rate,
+        locale.format_number(finished),
+        hours(median(&mut hours_to_restore)),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
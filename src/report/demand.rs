@@ -0,0 +1,57 @@
+/// Report section ranking the "most demanded" open issues
+///
+/// Demand is a weighted sum of comments and positive reactions taken from the
+/// last `gdfm collect demand` run. Thumbs up reactions count double because
+/// they are the explicit "I want this too" signal on GitHub.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::{IssueDemand, Repository};
+use crate::report::{escape, ReportOptions, Section};
+
+/// Number of issues listed in the section
+const TOP_ISSUES: u32 = 20;
+
+pub async fn most_demanded(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let issues = IssueDemand::fetch_top(pool, repo.id, TOP_ISSUES).await?;
+
+    let html = if issues.is_empty() {
+        "<p>No open issues collected. Run <code>gdfm collect demand</code> first.</p>".to_string()
+    } else {
+        let mut rows = String::new();
+        let locale = &options.locale;
+        for issue in &issues {
+            rows.push_str(&format!(
+                "<tr><td><a href=\"https://github.com/{}/{}/issues/{}\">#{}</a></td><td>{}</td>\
+                <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(&repo.owner),
+                escape(&repo.name),
+                issue.number,
+                issue.number,
+                escape(&issue.title),
+                locale.format_number(issue.plus_one.into()),
+                locale.format_number(issue.positive_reactions.into()),
+                locale.format_number(issue.comments.into()),
+                locale.format_number(issue.demand_score.into())
+            ));
+        }
+        format!(
+            "<p>Open issues ranked by comments plus positive reactions (👍 counted {}×). \
+            Collected at {} UTC.</p>\n\
+            <table>\n<tr><th>Issue</th><th>Title</th><th>👍</th><th>Positive reactions</th>\
+            <th>Comments</th><th>Demand</th></tr>\n{}</table>",
+            IssueDemand::PLUS_ONE_WEIGHT,
+            escape(&locale.format_datetime(issues[0].collected_at)),
+            rows
+        )
+    };
+
+    Ok(Section {
+        title: "Most demanded open issues".to_string(),
+        html,
+    })
+}
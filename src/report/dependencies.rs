@@ -0,0 +1,110 @@
+/// Report section on dependencies shared with other tracked repositories
+///
+/// Uses the snapshots stored by `gdfm collect dependencies`. A tracked
+/// repository counts as a dependency when a package with the same name as the
+/// repository shows up in the other repository's dependency graph.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+/// Number of shared packages listed in the section
+const TOP_PACKAGES: u32 = 25;
+
+#[derive(Debug, sqlx::FromRow)]
+struct SharedPackage {
+    ecosystem: String,
+    name: String,
+    repositories: String,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RepoLink {
+    owner: String,
+    name: String,
+    ecosystem: String,
+}
+
+pub async fn dependency_overlap(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    _options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let shared: Vec<SharedPackage> = sqlx::query_as("
+        SELECT d.ecosystem, d.name, GROUP_CONCAT(r.owner || '/' || r.name, ', ') AS repositories
+        FROM dependencies here
+        JOIN dependencies d ON d.ecosystem = here.ecosystem AND d.name = here.name AND d.repo_id != here.repo_id
+        JOIN repositories r ON r.id = d.repo_id AND r.archived = 0
+        WHERE here.repo_id = $1
+        GROUP BY d.ecosystem, d.name
+        ORDER BY COUNT(*) DESC, d.name
+        LIMIT $2
+    ")
+    .bind(repo.id)
+    .bind(TOP_PACKAGES)
+    .fetch_all(pool)
+    .await?;
+
+    // Tracked repositories whose name shows up in this repository's dependencies
+    let depends_on: Vec<RepoLink> = sqlx::query_as("
+        SELECT r.owner, r.name, d.ecosystem
+        FROM dependencies d
+        JOIN repositories r ON lower(r.name) = lower(d.name) AND r.id != d.repo_id AND r.archived = 0
+        WHERE d.repo_id = $1
+        ORDER BY r.owner, r.name
+    ")
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    // Tracked repositories that have this repository in their dependencies
+    let depended_on_by: Vec<RepoLink> = sqlx::query_as("
+        SELECT r.owner, r.name, d.ecosystem
+        FROM dependencies d
+        JOIN repositories r ON r.id = d.repo_id AND r.archived = 0
+        WHERE lower(d.name) = lower($1) AND d.repo_id != $2
+        ORDER BY r.owner, r.name
+    ")
+    .bind(&repo.name)
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut html = String::new();
+
+    if shared.is_empty() {
+        html.push_str("<p>No dependencies are shared with other tracked repositories. \
+            Run <code>gdfm collect dependencies</code> for each repository first.</p>\n");
+    } else {
+        let mut rows = String::new();
+        for package in &shared {
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&package.ecosystem),
+                escape(&package.name),
+                escape(&package.repositories)
+            ));
+        }
+        html.push_str(&format!(
+            "<table>\n<tr><th>Ecosystem</th><th>Package</th><th>Also used by</th></tr>\n{}</table>\n",
+            rows
+        ));
+    }
+
+    for (heading, links) in [("Depends on", &depends_on), ("Depended on by", &depended_on_by)] {
+        if links.is_empty() {
+            continue;
+        }
+        let items: Vec<String> = links
+            .iter()
+            .map(|link| format!("<li>{}/{} ({})</li>", escape(&link.owner), escape(&link.name), escape(&link.ecosystem)))
+            .collect();
+        html.push_str(&format!("<h3>{} tracked repositories</h3>\n<ul>\n{}\n</ul>\n", heading, items.join("\n")));
+    }
+
+    Ok(Section {
+        title: "Shared dependencies".to_string(),
+        html,
+    })
+}
@@ -0,0 +1,109 @@
+/// Report section on maintainer load in GitHub Discussions
+///
+/// Discussions come from `gdfm collect discussions`. Answer latency is the time
+/// from a question being asked to an answer being marked.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{is_maintainer, median, ReportOptions, Section};
+
+#[derive(Debug, sqlx::FromRow)]
+struct DiscussionCounts {
+    discussions: i64,
+    questions: i64,
+    answered: i64,
+    open_unanswered: i64,
+    maintainer_comments: i64,
+    comments: i64,
+}
+
+pub async fn discussion_load(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let commenter_is_maintainer = is_maintainer("c.author_association", "c.author");
+    let counts: DiscussionCounts = sqlx::query_as(&format!(
+        "SELECT
+            COUNT(*) AS discussions,
+            COUNT(CASE WHEN answerable = 1 THEN 1 END) AS questions,
+            COUNT(CASE WHEN answerable = 1 AND answer_chosen_at IS NOT NULL THEN 1 END) AS answered,
+            COUNT(CASE WHEN answerable = 1 AND answer_chosen_at IS NULL AND closed = 0 THEN 1 END) AS open_unanswered,
+            (SELECT COUNT(*) FROM discussion_comments c JOIN discussions d ON d.id = c.discussion_id
+             WHERE d.repo_id = $1 AND {commenter_is_maintainer}) AS maintainer_comments,
+            (SELECT COUNT(*) FROM discussion_comments c JOIN discussions d ON d.id = c.discussion_id
+             WHERE d.repo_id = $1) AS comments
+        FROM discussions WHERE repo_id = $1"
+    ))
+    .bind(repo.id)
+    .fetch_one(pool)
+    .await?;
+
+    let title = "Discussions".to_string();
+
+    if counts.discussions == 0 {
+        return Ok(Section {
+            title,
+            html: "<p>No discussions. Run <code>gdfm collect discussions</code> first.</p>".to_string(),
+        });
+    }
+
+    let mut hours_to_answer: Vec<f64> = sqlx::query_scalar(
+        "SELECT (julianday(answer_chosen_at) - julianday(created_at)) * 24
+        FROM discussions
+        WHERE repo_id = $1 AND answerable = 1 AND answer_chosen_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut hours_to_maintainer: Vec<f64> = sqlx::query_scalar(&format!(
+        "SELECT (MIN(julianday(c.created_at)) - julianday(d.created_at)) * 24
+        FROM discussions d
+        JOIN discussion_comments c ON c.discussion_id = d.id
+        WHERE d.repo_id = $1 AND c.author != d.author
+            AND {commenter_is_maintainer}
+        GROUP BY d.id"
+    ))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let locale = &options.locale;
+    let format_median = |values: &mut Vec<f64>| match median(values) {
+        Some(hours) => format!("{} hours", locale.format_decimal(hours, 1)),
+        None => "n/a".to_string(),
+    };
+    let answered = if counts.questions > 0 {
+        format!(
+            "{} ({}%)",
+            locale.format_number(counts.answered),
+            locale.format_decimal(counts.answered as f64 / counts.questions as f64 * 100.0, 1)
+        )
+    } else {
+        "n/a".to_string()
+    };
+
+    let html = format!(
+        "<table>\n\
+        <tr><td>Discussions</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Questions (answerable categories)</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Questions with a marked answer</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Open questions without an answer</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Median time to a marked answer</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Median time to the first maintainer comment</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Comments written by maintainers</td><td class=\"num\">{} of {}</td></tr>\n\
+        </table>",
+        locale.format_number(counts.discussions),
+        locale.format_number(counts.questions),
+        answered,
+        locale.format_number(counts.open_unanswered),
+        format_median(&mut hours_to_answer),
+        format_median(&mut hours_to_maintainer),
+        locale.format_number(counts.maintainer_comments),
+        locale.format_number(counts.comments)
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,131 @@
+/// Report section on how much contribution goes to documentation
+///
+/// Merged pull requests are split by the files they change (from
+/// `gdfm collect files`), and wiki edits come from `gdfm collect wiki`. Paths
+/// matching `report.docs_pattern` in the config count as documentation.
+use std::collections::{HashMap, HashSet};
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{ReportOptions, Section};
+
+/// Docs directories plus common documentation file types
+pub const DOCS_PATTERN: &str = r"(?i)(^|/)docs?/|\.(md|rst|adoc|txt)$";
+
+#[derive(Debug, sqlx::FromRow)]
+struct ChangedFile {
+    issue_pull_id: i64,
+    author: String,
+    path: String,
+    lines: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct WikiCounts {
+    edits: i64,
+    editors: i64,
+    pages: i64,
+}
+
+/// Lines of documentation and code changed by one pull request
+#[derive(Debug, Default)]
+struct PullShare {
+    docs: i64,
+    code: i64,
+    touches_docs: bool,
+    touches_code: bool,
+}
+
+pub async fn documentation_share(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let files: Vec<ChangedFile> = sqlx::query_as(
+        "SELECT f.issue_pull_id, p.author, f.path, f.additions + f.deletions AS lines
+        FROM issue_pull_files f
+        JOIN pulls p ON p.id = f.issue_pull_id
+        WHERE p.repo_id = $1 AND p.merged_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let wiki: WikiCounts = sqlx::query_as(
+        "SELECT COUNT(*) AS edits, COUNT(DISTINCT actor) AS editors, COUNT(DISTINCT page_name) AS pages
+        FROM wiki_edits WHERE repo_id = $1",
+    )
+    .bind(repo.id)
+    .fetch_one(pool)
+    .await?;
+
+    let title = "Documentation".to_string();
+
+    if files.is_empty() && wiki.edits == 0 {
+        return Ok(Section {
+            title,
+            html: "<p>No changed files or wiki edits. Run <code>gdfm collect files</code> and \
+                <code>gdfm collect wiki</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    let mut pulls: HashMap<i64, PullShare> = HashMap::new();
+    let mut docs_authors = HashSet::new();
+    let mut code_authors = HashSet::new();
+    for file in &files {
+        let share = pulls.entry(file.issue_pull_id).or_default();
+        if options.docs_pattern.is_match(&file.path) {
+            share.docs += file.lines;
+            share.touches_docs = true;
+            docs_authors.insert(file.author.as_str());
+        } else {
+            share.code += file.lines;
+            share.touches_code = true;
+            code_authors.insert(file.author.as_str());
+        }
+    }
+
+    let docs_only = pulls.values().filter(|pull| pull.touches_docs && !pull.touches_code).count();
+    let mixed = pulls.values().filter(|pull| pull.touches_docs && pull.touches_code).count();
+    let code_only = pulls.values().filter(|pull| !pull.touches_docs).count();
+    let docs_lines: i64 = pulls.values().map(|pull| pull.docs).sum();
+    let code_lines: i64 = pulls.values().map(|pull| pull.code).sum();
+    let docs_only_authors = docs_authors.difference(&code_authors).count();
+
+    let locale = &options.locale;
+    let percent = |part: f64, whole: f64| {
+        if whole > 0.0 {
+            format!("{}%", locale.format_decimal(part / whole * 100.0, 1))
+        } else {
+            "n/a".to_string()
+        }
+    };
+    let total = pulls.len() as f64;
+
+    let html = format!(
+        "<table>\n\
+        <tr><td>Merged pull requests with changed files collected</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Documentation only</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Documentation and code</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Code only</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>Documentation share of changed lines</td><td class=\"num\">{}</td></tr>\n\
+        <tr><td>People changing documentation (of which never code)</td><td class=\"num\">{} ({})</td></tr>\n\
+        <tr><td>Wiki edits (people, pages)</td><td class=\"num\">{} ({}, {})</td></tr>\n\
+        </table>",
+        locale.format_number(pulls.len() as i64),
+        percent(docs_only as f64, total),
+        percent(mixed as f64, total),
+        percent(code_only as f64, total),
+        percent(docs_lines as f64, (docs_lines + code_lines) as f64),
+        locale.format_number(docs_authors.len() as i64),
+        locale.format_number(docs_only_authors as i64),
+        locale.format_number(wiki.edits),
+        locale.format_number(wiki.editors),
+        locale.format_number(wiki.pages)
+    );
+
+    Ok(Section { title, html })
+}
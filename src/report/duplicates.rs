@@ -0,0 +1,219 @@
+/// Report section flagging open pull requests that likely duplicate others
+///
+/// Two pull requests look alike when their titles share most character
+/// trigrams, or when they change mostly the same files. Each open pull request
+/// is compared with the other open ones and with those merged recently; an open
+/// pull request resembling a merged one has probably been superseded and can
+/// be closed.
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{Duration, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+/// Open pull requests are also compared with those merged in this many days
+const MERGED_DAYS: i64 = 90;
+
+/// Share of title trigrams two pull requests must have in common to be flagged
+const TITLE_SIMILARITY: f64 = 0.6;
+
+/// Share of changed files two pull requests must have in common to be flagged
+const FILE_OVERLAP: f64 = 0.8;
+
+/// Title similarity still required of pull requests flagged for their files,
+/// so two unrelated changes to the same lock file aren't paired
+const TITLE_SIMILARITY_WITH_FILES: f64 = 0.3;
+
+/// Number of pairs listed, most alike first
+const TOP_PAIRS: usize = 30;
+
+#[derive(Debug, sqlx::FromRow)]
+struct Candidate {
+    id: i64,
+    number: u32,
+    title: String,
+    author: String,
+    merged: bool,
+}
+
+#[derive(Debug)]
+struct Pair<'a> {
+    open: &'a Candidate,
+    other: &'a Candidate,
+    title: f64,
+    files: Option<f64>,
+}
+
+/// Character trigrams of a title, ignoring case, punctuation and a conventional commit prefix
+fn trigrams(title: &str) -> BTreeSet<String> {
+    let title = match title.split_once(": ") {
+        Some((prefix, rest)) if !prefix.contains(' ') => rest,
+        _ => title,
+    };
+    let words: Vec<String> = title
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    let padded: Vec<char> = format!("  {} ", words.join(" ")).chars().collect();
+
+    padded.windows(3).map(|window| window.iter().collect()).collect()
+}
+
+/// Share of the union two sets have in common (Jaccard index)
+fn similarity<T: Ord>(a: &BTreeSet<T>, b: &BTreeSet<T>) -> f64 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+
+    a.intersection(b).count() as f64 / union as f64
+}
+
+pub async fn duplicate_pulls(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(MERGED_DAYS)).naive_utc();
+    let candidates: Vec<Candidate> = sqlx::query_as(
+        "SELECT id, number, title, author, merged_at IS NOT NULL AS merged FROM pulls
+        WHERE repo_id = $1 AND is_issue = 0
+            AND (closed_at IS NULL OR merged_at >= $2)
+        ORDER BY number",
+    )
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Likely duplicate pull requests".to_string();
+
+    let open: Vec<&Candidate> = candidates.iter().filter(|pull| !pull.merged).collect();
+    if open.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No open pull requests. Run <code>gdfm collect pulls</code> first.</p>".to_string(),
+        });
+    }
+
+    // (pull request id, path)
+    let changed: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT f.issue_pull_id, f.path FROM issue_pull_files f
+        JOIN pulls p ON p.id = f.issue_pull_id
+        WHERE p.repo_id = $1 AND p.is_issue = 0
+            AND (p.closed_at IS NULL OR p.merged_at >= $2)",
+    )
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+    let mut files: HashMap<i64, BTreeSet<String>> = HashMap::new();
+    for (pull_id, path) in changed {
+        files.entry(pull_id).or_default().insert(path);
+    }
+
+    let titles: HashMap<i64, BTreeSet<String>> =
+        candidates.iter().map(|pull| (pull.id, trigrams(&pull.title))).collect();
+
+    let mut pairs: Vec<Pair> = Vec::new();
+    for (i, pull) in open.iter().enumerate() {
+        // Open pull requests before this one were paired with it already
+        let merged = candidates.iter().filter(|other| other.merged);
+        for other in open.iter().skip(i + 1).copied().chain(merged) {
+            let title = similarity(&titles[&pull.id], &titles[&other.id]);
+            let files = match (files.get(&pull.id), files.get(&other.id)) {
+                (Some(a), Some(b)) => Some(similarity(a, b)),
+                _ => None,
+            };
+            let by_files =
+                files.is_some_and(|files| files >= FILE_OVERLAP) && title >= TITLE_SIMILARITY_WITH_FILES;
+            if title >= TITLE_SIMILARITY || by_files {
+                pairs.push(Pair { open: pull, other, title, files });
+            }
+        }
+    }
+
+    if pairs.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>None of the {} open pull requests look like another open one or one merged in the \
+                last {} days.</p>",
+                options.locale.format_number(open.len() as i64),
+                MERGED_DAYS
+            ),
+        });
+    }
+
+    let score = |pair: &Pair| pair.title.max(pair.files.unwrap_or(0.0));
+    pairs.sort_by(|a, b| score(b).total_cmp(&score(a)).then(a.open.number.cmp(&b.open.number)));
+    let superseded = pairs.iter().filter(|pair| pair.other.merged).count();
+
+    let locale = &options.locale;
+    let link = |pull: &Candidate| {
+        format!(
+            "<a href=\"https://github.com/{}/{}/pull/{}\">#{}</a> {} ({})",
+            escape(&repo.owner),
+            escape(&repo.name),
+            pull.number,
+            pull.number,
+            escape(&pull.title),
+            escape(&options.login(&pull.author))
+        )
+    };
+
+    let mut rows = String::new();
+    for pair in pairs.iter().take(TOP_PAIRS) {
+        let kind = if pair.other.merged { "superseded by merged" } else { "duplicate of open" };
+        let files = match pair.files {
+            Some(files) => format!("{}%", locale.format_decimal(files * 100.0, 1)),
+            None => "&ndash;".to_string(),
+        };
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}%</td><td class=\"num\">{}</td></tr>\n",
+            link(pair.open),
+            kind,
+            link(pair.other),
+            locale.format_decimal(pair.title * 100.0, 1),
+            files
+        ));
+    }
+
+    let html = format!(
+        "<p>{} pairs of pull requests look alike, {} of them an open pull request and one merged in the \
+        last {} days. Titles are compared by shared character trigrams, changed files by the share both \
+        touch; a dash means the files of one side weren't collected with <code>gdfm collect files</code>.</p>\n\
+        <table>\n<tr><th>Open pull request</th><th>Looks like a</th><th>Pull request</th>\
+        <th>Title similarity</th><th>Shared files</th></tr>\n{}</table>",
+        locale.format_number(pairs.len() as i64),
+        locale.format_number(superseded as i64),
+        MERGED_DAYS,
+        rows
+    );
+
+    Ok(Section { title, html })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn titles_differing_in_prefix_and_case_are_identical() {
+        let a = trigrams("fix: Crash when the config is empty");
+        let b = trigrams("Crash when the config is EMPTY!");
+        assert_eq!(similarity(&a, &b), 1.0);
+    }
+
+    #[test]
+    fn unrelated_titles_share_little() {
+        let a = trigrams("Add dark mode to the settings page");
+        let b = trigrams("Bump serde from 1.0.1 to 1.0.2");
+        assert!(similarity(&a, &b) < 0.1);
+        assert_eq!(similarity::<String>(&BTreeSet::new(), &BTreeSet::new()), 0.0);
+    }
+}
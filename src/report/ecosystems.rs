@@ -0,0 +1,218 @@
+/// Ecosystem tagging of pull requests and the report section grouping by it
+///
+/// Every file a pull request changes (from `gdfm collect files`) is put in one
+/// ecosystem, such as CI, Rust or Python, and the pull request is tagged with
+/// the ecosystems of all its files. Paths matching `report.docs_pattern` count
+/// as documentation, so this agrees with the documentation section.
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::config::EcosystemRule;
+use crate::database::Repository;
+use crate::report::{docs, escape, median, parse_timestamp, ReportOptions, Section};
+
+/// Name of the ecosystem paths matching `report.docs_pattern` fall in
+pub const DOCUMENTATION: &str = "Documentation";
+
+/// Name of the ecosystem for paths no rule matches
+pub const OTHER: &str = "Other";
+
+/// Built-in rules, checked in order after those from the config; an ecosystem
+/// may have several
+///
+/// Languages come before documentation, so `docs/conf.py` is Python and
+/// `requirements.txt` isn't mistaken for a text document.
+const RULES: &[(&str, &str)] = &[
+    ("CI", r"(?i)^\.github/(workflows|actions)/|^\.circleci/|^\.buildkite/|(^|/)Jenkinsfile$"),
+    ("CI", r"(?i)^(\.gitlab-ci|\.travis|azure-pipelines[^/]*)\.ya?ml$"),
+    ("Rust", r"\.rs$|(^|/)Cargo\.(toml|lock)$"),
+    ("Python", r"\.(py|pyi|pyx|pxd|ipynb)$"),
+    ("Python", r"(^|/)(pyproject\.toml|setup\.cfg|requirements[^/]*\.txt|Pipfile(\.lock)?|(poetry|uv)\.lock)$"),
+    ("JavaScript/TypeScript", r"\.(js|jsx|mjs|cjs|ts|tsx|vue|svelte)$"),
+    ("JavaScript/TypeScript", r"(^|/)(package(-lock)?\.json|yarn\.lock|pnpm-lock\.yaml|tsconfig[^/]*\.json)$"),
+    ("Go", r"\.go$|(^|/)go\.(mod|sum)$"),
+    ("JVM", r"\.(java|kt|kts|scala|groovy|gradle)$|(^|/)pom\.xml$"),
+    ("C/C++", r"\.(c|h|cc|cpp|cxx|hh|hpp|hxx)$|(^|/)(CMakeLists\.txt|meson\.build)$"),
+    ("Ruby", r"\.(rb|gemspec)$|(^|/)(Gemfile(\.lock)?|Rakefile)$"),
+    ("Shell", r"\.(sh|bash|zsh|fish|ps1|bat)$"),
+    ("Web", r"\.(html?|css|scss|sass|less)$"),
+];
+
+/// Rules checked after documentation, so only files nothing else claims count
+const CONFIGURATION_RULES: &[(&str, &str)] = &[
+    ("Configuration", r"\.(ya?ml|toml|json|ini|cfg|conf)$|(^|/)\.[^/]+$"),
+];
+
+/// Rules mapping file paths to ecosystems, checked in order
+#[derive(Debug, Clone)]
+pub struct Ecosystems {
+    rules: Vec<(String, Regex)>,
+}
+
+impl Default for Ecosystems {
+    fn default() -> Self {
+        let docs_pattern = Regex::new(docs::DOCS_PATTERN).expect("docs regex should be valid");
+        Self::new(&[], &docs_pattern).expect("built-in ecosystem patterns should be valid")
+    }
+}
+
+impl Ecosystems {
+    /// Builds the rules, with those from the config checked before the built-in ones
+    pub fn new(custom: &[EcosystemRule], docs_pattern: &Regex) -> Result<Self, String> {
+        let mut rules = Vec::new();
+        for rule in custom {
+            let pattern = Regex::new(&rule.pattern)
+                .map_err(|err| format!("invalid pattern for ecosystem \"{}\": {}", rule.name, err))?;
+            rules.push((rule.name.clone(), pattern));
+        }
+        let built_in = |rules: &mut Vec<(String, Regex)>, built_in: &[(&str, &str)]| -> Result<(), String> {
+            for (name, pattern) in built_in {
+                rules.push((name.to_string(), Regex::new(pattern).map_err(|err| err.to_string())?));
+            }
+            Ok(())
+        };
+        built_in(&mut rules, RULES)?;
+        rules.push((DOCUMENTATION.to_string(), docs_pattern.clone()));
+        built_in(&mut rules, CONFIGURATION_RULES)?;
+
+        Ok(Self { rules })
+    }
+
+    /// Ecosystem a changed file belongs to
+    pub fn classify(&self, path: &str) -> &str {
+        self.rules
+            .iter()
+            .find(|(_, pattern)| pattern.is_match(path))
+            .map_or(OTHER, |(name, _)| name.as_str())
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct ChangedFile {
+    issue_pull_id: i64,
+    path: String,
+    lines: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct Pull {
+    id: i64,
+    author: String,
+    created_at: String,
+    merged_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct EcosystemStats {
+    pulls: i64,
+    merged: i64,
+    days_to_merge: Vec<f64>,
+    authors: HashSet<String>,
+    lines: i64,
+}
+
+pub async fn ecosystem_breakdown(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let files: Vec<ChangedFile> = sqlx::query_as(
+        "SELECT f.issue_pull_id, f.path, f.additions + f.deletions AS lines
+        FROM issue_pull_files f
+        JOIN pulls p ON p.id = f.issue_pull_id
+        WHERE p.repo_id = $1",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+    let pulls: Vec<Pull> = sqlx::query_as(
+        "SELECT id, author, created_at, merged_at FROM pulls
+        WHERE repo_id = $1 AND created_at IS NOT NULL AND id IN (SELECT issue_pull_id FROM issue_pull_files)",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Ecosystems".to_string();
+
+    if files.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No changed files. Run <code>gdfm collect files</code> first.</p>".to_string(),
+        });
+    }
+
+    // Ecosystems each pull request touches, with the lines it changed in each
+    let mut tags: HashMap<i64, HashMap<&str, i64>> = HashMap::new();
+    for file in &files {
+        let ecosystem = options.ecosystems.classify(&file.path);
+        *tags.entry(file.issue_pull_id).or_default().entry(ecosystem).or_default() += file.lines;
+    }
+
+    let mut stats: HashMap<&str, EcosystemStats> = HashMap::new();
+    for pull in &pulls {
+        let Some(ecosystems) = tags.get(&pull.id) else {
+            continue;
+        };
+        let days = pull
+            .merged_at
+            .as_deref()
+            .and_then(parse_timestamp)
+            .zip(parse_timestamp(&pull.created_at))
+            .map(|(merged, created)| (merged - created).num_seconds() as f64 / 86_400.0);
+        for (ecosystem, lines) in ecosystems {
+            let entry = stats.entry(ecosystem).or_default();
+            entry.pulls += 1;
+            entry.lines += lines;
+            if let Some(days) = days {
+                entry.merged += 1;
+                entry.days_to_merge.push(days);
+            }
+            if !pull.author.is_empty() {
+                entry.authors.insert(options.login(&pull.author).into_owned());
+            }
+        }
+    }
+
+    let mut stats: Vec<(&str, EcosystemStats)> = stats.into_iter().collect();
+    stats.sort_by(|(a_name, a), (b_name, b)| b.pulls.cmp(&a.pulls).then(a_name.cmp(b_name)));
+    let total_lines: i64 = stats.iter().map(|(_, stats)| stats.lines).sum();
+
+    let locale = &options.locale;
+    let mut rows = String::new();
+    for (name, stats) in stats.iter_mut() {
+        let days = match median(&mut stats.days_to_merge) {
+            Some(days) => locale.format_decimal(days, 1),
+            None => "n/a".to_string(),
+        };
+        let share = if total_lines > 0 {
+            format!("{}%", locale.format_decimal(stats.lines as f64 / total_lines as f64 * 100.0, 1))
+        } else {
+            "n/a".to_string()
+        };
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(name),
+            locale.format_number(stats.pulls),
+            locale.format_number(stats.merged),
+            days,
+            locale.format_number(stats.authors.len() as i64),
+            share
+        ));
+    }
+
+    let html = format!(
+        "<p>The {} pull requests with changed files collected, tagged by the kinds of files they change. A pull \
+        request touching several ecosystems counts under each. Days to merge are medians from opening.</p>\n\
+        <table>\n<tr><th>Ecosystem</th><th>Pull requests</th><th>Merged</th><th>Days to merge</th>\
+        <th>Authors</th><th>Share of changed lines</th></tr>\n{}</table>",
+        locale.format_number(tags.len() as i64),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,117 @@
+/// Report section on how license and governance files changed over time
+///
+/// `gdfm collect governance` stores every commit touching files such as
+/// `LICENSE`, `GOVERNANCE.md` or `MAINTAINERS`. The section lists each file
+/// with when it first appeared and last changed, followed by a timeline of the
+/// most recent changes. A commit touching several of the files is one entry.
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::{GovernanceChange, Repository};
+use crate::report::{escape, ReportOptions, Section};
+
+/// Number of most recent changes listed in the timeline
+const RECENT_CHANGES: usize = 50;
+
+/// When a governance file first appeared and last changed, and in how many commits
+#[derive(Debug)]
+struct FileHistory {
+    added: Option<DateTime<Utc>>,
+    changed: Option<DateTime<Utc>>,
+    commits: i64,
+}
+
+pub async fn governance_changes(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let changes = GovernanceChange::fetch_all(pool, repo.id).await?;
+
+    let title = "Governance changes".to_string();
+
+    if changes.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No license or governance file history. Run <code>gdfm collect governance</code> \
+                first.</p>"
+                .to_string(),
+        });
+    }
+
+    let locale = &options.locale;
+
+    // Changes are ordered oldest first, so the first one seen for a file added it
+    let mut files: BTreeMap<&str, FileHistory> = BTreeMap::new();
+    for change in &changes {
+        let file = files.entry(&change.path).or_insert(FileHistory {
+            added: change.committed_at,
+            changed: None,
+            commits: 0,
+        });
+        file.changed = change.committed_at;
+        file.commits += 1;
+    }
+    let mut file_rows = String::new();
+    for (path, file) in &files {
+        file_rows.push_str(&format!(
+            "<tr><td><code>{}</code></td><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(path),
+            escape(&file.added.map(|at| locale.format_datetime(at)).unwrap_or_default()),
+            escape(&file.changed.map(|at| locale.format_datetime(at)).unwrap_or_default()),
+            locale.format_number(file.commits)
+        ));
+    }
+
+    // One entry per commit, newest first, with every governance file it touched
+    let mut commits: Vec<(&GovernanceChange, Vec<&str>)> = Vec::new();
+    for change in changes.iter().rev() {
+        match commits.iter_mut().find(|(commit, _)| commit.sha == change.sha) {
+            Some((_, paths)) => paths.push(&change.path),
+            None => commits.push((change, vec![&change.path])),
+        }
+    }
+    let mut timeline_rows = String::new();
+    for (commit, paths) in commits.iter().take(RECENT_CHANGES) {
+        let added: Vec<&str> = paths
+            .iter()
+            .copied()
+            .filter(|path| files.get(path).is_some_and(|file| file.added == commit.committed_at))
+            .collect();
+        let paths: Vec<String> = paths
+            .iter()
+            .map(|path| {
+                let marker = if added.contains(path) { " (added)" } else { "" };
+                format!("<code>{}</code>{}", escape(path), marker)
+            })
+            .collect();
+        timeline_rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td>\
+            <td><a href=\"https://github.com/{}/{}/commit/{}\">{}</a></td></tr>\n",
+            escape(&commit.committed_at.map(|at| locale.format_datetime(at)).unwrap_or_default()),
+            paths.join(", "),
+            escape(&options.login(&commit.author)),
+            escape(&repo.owner),
+            escape(&repo.name),
+            escape(&commit.sha),
+            escape(&commit.summary)
+        ));
+    }
+
+    let html = format!(
+        "<p>{} commits changed {} license and governance files. The {} most recent are listed \
+        below.</p>\n\
+        <table>\n<tr><th>File</th><th>Added</th><th>Last changed</th><th>Changes</th></tr>\n{}</table>\n\
+        <table>\n<tr><th>Date</th><th>Files</th><th>Author</th><th>Commit</th></tr>\n{}</table>",
+        locale.format_number(commits.len() as i64),
+        locale.format_number(files.len() as i64),
+        locale.format_number(commits.len().min(RECENT_CHANGES) as i64),
+        file_rows,
+        timeline_rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,111 @@
+/// Report section charting repository growth next to maintainer activity
+///
+/// Star counts are rebuilt from the `starred_at` time of everyone currently
+/// starring the repository, so people who unstarred don't show up at all.
+/// Fork counts come from the creation time of each collected fork.
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::chart::{line_chart, Series};
+use crate::report::{maintainer_responses_per_day, parse_day, ReportOptions, Section};
+
+pub async fn repository_growth(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let starred: Vec<(String,)> = sqlx::query_as(
+        "SELECT starred_at FROM stargazers WHERE repo_id = $1",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+    let forked: Vec<(String,)> = sqlx::query_as(
+        "SELECT created_at FROM forks WHERE repo_id = $1 AND created_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Repository growth".to_string();
+
+    let new_stars = per_day(&starred);
+    let new_forks = per_day(&forked);
+    let first = match (new_stars.keys().next(), new_forks.keys().next()) {
+        (Some(stars), Some(forks)) => *stars.min(forks),
+        (Some(first), None) | (None, Some(first)) => *first,
+        (None, None) => {
+            return Ok(Section {
+                title,
+                html: "<p>No stargazers or forks collected. Run <code>gdfm collect stars</code> \
+                    or <code>gdfm collect forks</code> first.</p>".to_string(),
+            });
+        }
+    };
+
+    let stars = running_total(&new_stars, first);
+    let forks = running_total(&new_forks, first);
+
+    // Weekly totals read better than daily ones, which are mostly zero
+    let week_start = options.locale.week_start;
+    let mut weekly: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    for (day, responses) in maintainer_responses_per_day(pool, repo).await? {
+        if day < first {
+            continue;
+        }
+        let offset = day.weekday().days_since(week_start);
+        *weekly.entry(day - Duration::days(offset.into())).or_default() += responses;
+    }
+    let responses: Vec<(NaiveDate, i64)> = weekly.into_iter().collect();
+
+    let total = |points: &[(NaiveDate, i64)]| points.last().map_or(0, |(_, total)| *total);
+    let mut html = format!(
+        "<p>Stars ({} today) and forks ({} today) over time, next to the number of maintainer \
+        reviews, comments and events per week.</p>\n{}",
+        options.locale.format_number(total(&stars)),
+        options.locale.format_number(total(&forks)),
+        line_chart(
+            &[
+                Series { name: "Stars", points: &stars },
+                Series { name: "Forks", points: &forks },
+            ],
+            &options.locale
+        )
+    );
+    if !responses.is_empty() {
+        html.push_str(&line_chart(
+            &[Series { name: "Maintainer responses per week", points: &responses }],
+            &options.locale,
+        ));
+    }
+
+    Ok(Section { title, html })
+}
+
+fn per_day(timestamps: &[(String,)]) -> BTreeMap<NaiveDate, i64> {
+    let mut counts: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    for (timestamp,) in timestamps {
+        if let Some(day) = parse_day(timestamp) {
+            *counts.entry(day).or_default() += 1;
+        }
+    }
+    counts
+}
+
+/// Running total of daily counts for every day from `first` up to today
+fn running_total(counts: &BTreeMap<NaiveDate, i64>, first: NaiveDate) -> Vec<(NaiveDate, i64)> {
+    let today = Utc::now().date_naive();
+    let mut total = 0;
+    first
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(|day| {
+            total += counts.get(&day).copied().unwrap_or(0);
+            (day, total)
+        })
+        .collect()
+}
@@ -0,0 +1,131 @@
+/// Report section slicing pull requests and issues by label
+///
+/// Labels come from `gdfm collect pulls`, `gdfm collect issues` or
+/// `gdfm collect labels`.
+use std::collections::HashMap;
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, median, parse_timestamp, ReportOptions, Section};
+
+/// Number of labels listed in the section, most used first
+const TOP_LABELS: usize = 20;
+
+#[derive(Debug, sqlx::FromRow)]
+struct LabelledRecord {
+    name: String,
+    color: String,
+    is_issue: bool,
+    created_at: String,
+    closed_at: Option<String>,
+    merged_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct LabelStats {
+    color: String,
+    pulls: i64,
+    merged: i64,
+    issues: i64,
+    closed_issues: i64,
+    days_to_merge: Vec<f64>,
+    days_to_close: Vec<f64>,
+}
+
+impl LabelStats {
+    fn total(&self) -> i64 {
+        self.pulls + self.issues
+    }
+}
+
+/// Days between two stored timestamps, if both are set
+fn days_between(start: &str, end: Option<&String>) -> Option<f64> {
+    let start = parse_timestamp(start)?;
+    let end = parse_timestamp(end?)?;
+    Some((end - start).num_seconds() as f64 / 86_400.0)
+}
+
+pub async fn label_breakdown(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let records: Vec<LabelledRecord> = sqlx::query_as(
+        "SELECT l.name, l.color, p.is_issue, p.created_at, p.closed_at, p.merged_at
+        FROM issue_pull_labels pl
+        JOIN labels l ON l.id = pl.label_id
+        JOIN pulls p ON p.id = pl.issue_pull_id
+        WHERE p.repo_id = $1 AND p.created_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Labels".to_string();
+
+    if records.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No labelled pull requests or issues. Run <code>gdfm collect labels</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    let mut labels: HashMap<String, LabelStats> = HashMap::new();
+    for record in &records {
+        let stats = labels.entry(record.name.clone()).or_default();
+        stats.color = record.color.clone();
+        if record.is_issue {
+            stats.issues += 1;
+            if let Some(days) = days_between(&record.created_at, record.closed_at.as_ref()) {
+                stats.closed_issues += 1;
+                stats.days_to_close.push(days);
+            }
+        } else {
+            stats.pulls += 1;
+            if let Some(days) = days_between(&record.created_at, record.merged_at.as_ref()) {
+                stats.merged += 1;
+                stats.days_to_merge.push(days);
+            }
+        }
+    }
+
+    let mut labels: Vec<(String, LabelStats)> = labels.into_iter().collect();
+    labels.sort_by(|(a_name, a), (b_name, b)| b.total().cmp(&a.total()).then(a_name.cmp(b_name)));
+
+    let locale = &options.locale;
+    let format_median = |values: &mut Vec<f64>| match median(values) {
+        Some(days) => locale.format_decimal(days, 1),
+        None => "n/a".to_string(),
+    };
+
+    let mut rows = String::new();
+    for (name, stats) in labels.iter_mut().take(TOP_LABELS) {
+        rows.push_str(&format!(
+            "<tr><td><span style=\"color: #{}\">&#9679;</span> {}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&stats.color),
+            escape(name),
+            locale.format_number(stats.pulls),
+            locale.format_number(stats.merged),
+            format_median(&mut stats.days_to_merge),
+            locale.format_number(stats.issues),
+            locale.format_number(stats.closed_issues),
+            format_median(&mut stats.days_to_close)
+        ));
+    }
+
+    let html = format!(
+        "<p>The {} most used of {} labels. Times are medians in days from opening.</p>\n\
+        <table>\n<tr><th>Label</th><th>Pull requests</th><th>Merged</th><th>Days to merge</th>\
+        <th>Issues</th><th>Closed</th><th>Days to close</th></tr>\n{}</table>",
+        locale.format_number(labels.len().min(TOP_LABELS) as i64),
+        locale.format_number(labels.len() as i64),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
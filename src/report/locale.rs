@@ -0,0 +1,90 @@
+/// Locale-aware formatting of dates and numbers in reports
+///
+/// Only a handful of locales are supported; we don't pull in ICU for what
+/// amounts to separators, date ordering and the first day of the week.
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Locale {
+    pub tag: &'static str,
+    date_format: &'static str,
+    thousands_separator: &'static str,
+    decimal_separator: &'static str,
+    pub week_start: Weekday,
+}
+
+pub const LOCALES: &[Locale] = &[
+    Locale { tag: "en-US", date_format: "%m/%d/%Y", thousands_separator: ",", decimal_separator: ".", week_start: Weekday::Sun },
+    Locale { tag: "en-GB", date_format: "%d/%m/%Y", thousands_separator: ",", decimal_separator: ".", week_start: Weekday::Mon },
+    Locale { tag: "de-DE", date_format: "%d.%m.%Y", thousands_separator: ".", decimal_separator: ",", week_start: Weekday::Mon },
+    Locale { tag: "fr-FR", date_format: "%d/%m/%Y", thousands_separator: "\u{202f}", decimal_separator: ",", week_start: Weekday::Mon },
+    Locale { tag: "es-ES", date_format: "%d/%m/%Y", thousands_separator: ".", decimal_separator: ",", week_start: Weekday::Mon },
+    Locale { tag: "pt-BR", date_format: "%d/%m/%Y", thousands_separator: ".", decimal_separator: ",", week_start: Weekday::Sun },
+    Locale { tag: "ja-JP", date_format: "%Y/%m/%d", thousands_separator: ",", decimal_separator: ".", week_start: Weekday::Sun },
+];
+
+impl Default for Locale {
+    fn default() -> Self {
+        LOCALES[0]
+    }
+}
+
+impl Locale {
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        LOCALES.iter().find(|locale| locale.tag.eq_ignore_ascii_case(tag)).copied()
+    }
+
+    pub fn format_date(&self, date: NaiveDate) -> String {
+        date.format(self.date_format).to_string()
+    }
+
+    /// Formats a stored timestamp like `2025-03-29 14:01:02 UTC`, keeping the time of day
+    pub fn format_timestamp(&self, timestamp: &str) -> String {
+        match crate::report::parse_day(timestamp) {
+            Some(date) => format!("{}{}", self.format_date(date), timestamp.get(10..).unwrap_or("")),
+            None => timestamp.to_string(),
+        }
+    }
+
+    /// Formats a stored time as [`Self::format_timestamp`] does its text
+    pub fn format_datetime(&self, at: DateTime<Utc>) -> String {
+        format!("{}{}", self.format_date(at.date_naive()), at.format(" %H:%M:%S"))
+    }
+
+    /// The seven weekdays in display order, starting from `week_start`
+    pub fn weekdays(&self) -> impl Iterator<Item = Weekday> {
+        std::iter::successors(Some(self.week_start), |day| Some(day.succ())).take(7)
+    }
+
+    pub fn format_number(&self, value: i64) -> String {
+        let digits = value.unsigned_abs().to_string();
+        let mut grouped = String::new();
+
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                grouped.push_str(self.thousands_separator);
+            }
+            grouped.push(digit);
+        }
+
+        if value < 0 {
+            format!("-{}", grouped)
+        } else {
+            grouped
+        }
+    }
+
+    pub fn format_decimal(&self, value: f64, places: usize) -> String {
+        let formatted = format!("{:.*}", places, value.abs());
+        let (whole, fraction) = formatted.split_once('.').unwrap_or((&formatted, ""));
+        let whole = self.format_number(whole.parse().unwrap_or(0));
+        let is_zero = formatted.trim_start_matches(['0', '.']).is_empty();
+        let sign = if value < 0.0 && !is_zero { "-" } else { "" };
+
+        if fraction.is_empty() {
+            format!("{}{}", sign, whole)
+        } else {
+            format!("{}{}{}{}", sign, whole, self.decimal_separator, fraction)
+        }
+    }
+}
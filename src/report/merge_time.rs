@@ -0,0 +1,215 @@
+/// Report section estimating when open pull requests will be merged
+///
+/// Each open pull request is compared with pull requests merged in the last
+/// year that had the same size bucket and were opened by the same kind of
+/// author (maintainer or outside contributor). The median time those took is
+/// the estimate; pull requests already older than 90% of them are overdue.
+/// Segments with too few merged pull requests fall back to the author role
+/// alone, and then to every merged pull request.
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::size::pull_sizes;
+use crate::report::{escape, is_maintainer, median, parse_timestamp, ReportOptions, Section};
+
+/// Merged pull requests from this many days back are used for the estimates
+const HISTORY_DAYS: i64 = 365;
+
+/// Merged pull requests a segment needs before its own estimate is used
+const MIN_SAMPLES: usize = 5;
+
+/// Share of comparable pull requests merged faster before an open one counts as overdue
+const OVERDUE_PERCENTILE: f64 = 0.9;
+
+/// Which merged pull requests an estimate was based on
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum Segment {
+    RoleAndSize { maintainer: bool, size: String },
+    Role { maintainer: bool },
+    All,
+}
+
+impl Segment {
+    fn describe(&self) -> String {
+        let role = |maintainer: &bool| if *maintainer { "maintainers" } else { "contributors" };
+        match self {
+            Self::RoleAndSize { maintainer, size } => format!("{} by {}", escape(size), role(maintainer)),
+            Self::Role { maintainer } => format!("any size by {}", role(maintainer)),
+            Self::All => "all merged".to_string(),
+        }
+    }
+}
+
+/// Median and overdue threshold, in hours, of the merged pull requests in a segment
+struct Estimate {
+    median: f64,
+    overdue_after: f64,
+    samples: usize,
+}
+
+/// Value below which the given share of the values fall, sorting them in place
+fn percentile(values: &mut [f64], share: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+    let rank = ((values.len() as f64 * share).ceil() as usize).clamp(1, values.len());
+
+    Some(values[rank - 1])
+}
+
+pub async fn merge_time_estimates(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(HISTORY_DAYS)).naive_utc();
+    let author_is_maintainer = is_maintainer("author_association", "author");
+    // (id, created_at, merged_at, by maintainer)
+    let merged: Vec<(i64, String, String, bool)> = sqlx::query_as(&format!("
+        SELECT id, created_at, merged_at, {author_is_maintainer}
+        FROM pulls
+        WHERE repo_id = $1 AND is_issue = 0 AND merged_at IS NOT NULL AND created_at IS NOT NULL
+            AND merged_at >= $2
+    "))
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    // (id, number, title, author, created_at, by maintainer)
+    let open: Vec<(i64, u32, String, String, String, bool)> = sqlx::query_as(&format!("
+        SELECT id, number, title, author, created_at, {author_is_maintainer}
+        FROM pulls
+        WHERE repo_id = $1 AND is_issue = 0 AND closed_at IS NULL AND created_at IS NOT NULL
+        ORDER BY created_at
+    "))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Expected merge time".to_string();
+
+    if merged.is_empty() || open.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>No open pull requests, or none merged in the last {} days to compare them with. \
+                Run <code>gdfm collect pulls --state all</code> and <code>gdfm collect pull-details</code> first.</p>",
+                HISTORY_DAYS
+            ),
+        });
+    }
+
+    let sizes = pull_sizes(pool, repo.id).await?;
+    let buckets = &options.size_buckets;
+    let segments_of = |id: &i64, maintainer: bool| {
+        let mut segments = Vec::new();
+        if let Some(size) = sizes.get(id) {
+            segments.push(Segment::RoleAndSize { maintainer, size: buckets.bucket(*size).to_string() });
+        }
+        segments.push(Segment::Role { maintainer });
+        segments.push(Segment::All);
+        segments
+    };
+
+    let mut hours_by_segment: HashMap<Segment, Vec<f64>> = HashMap::new();
+    for (id, created_at, merged_at, maintainer) in &merged {
+        let (Some(created), Some(merged)) = (parse_timestamp(created_at), parse_timestamp(merged_at)) else {
+            continue;
+        };
+        let hours = (merged - created).num_minutes() as f64 / 60.0;
+        for segment in segments_of(id, *maintainer) {
+            hours_by_segment.entry(segment).or_default().push(hours);
+        }
+    }
+    let estimates: HashMap<Segment, Estimate> = hours_by_segment
+        .into_iter()
+        .filter_map(|(segment, mut hours)| {
+            let estimate = Estimate {
+                median: median(&mut hours)?,
+                overdue_after: percentile(&mut hours, OVERDUE_PERCENTILE)?,
+                samples: hours.len(),
+            };
+            Some((segment, estimate))
+        })
+        .collect();
+
+    let locale = &options.locale;
+    let now = Utc::now().naive_utc();
+    let mut overdue = 0;
+    let mut rows = String::new();
+    for (id, number, pull_title, author, created_at, maintainer) in &open {
+        let Some(created) = parse_timestamp(created_at) else {
+            continue;
+        };
+        let segments = segments_of(id, *maintainer);
+        let Some((segment, estimate)) = segments
+            .iter()
+            .filter_map(|segment| estimates.get(segment).map(|estimate| (segment, estimate)))
+            .find(|(segment, estimate)| estimate.samples >= MIN_SAMPLES || **segment == Segment::All)
+        else {
+            continue;
+        };
+
+        let age_hours = (now - created).num_minutes() as f64 / 60.0;
+        let expected = created + Duration::minutes((estimate.median * 60.0) as i64);
+        let status = if age_hours > estimate.overdue_after {
+            overdue += 1;
+            "<strong>overdue</strong>"
+        } else if age_hours > estimate.median {
+            "past median"
+        } else {
+            "on track"
+        };
+        let size = sizes.get(id).map(|size| buckets.bucket(*size)).unwrap_or("?");
+
+        rows.push_str(&format!(
+            "<tr><td><a href=\"https://github.com/{}/{}/pull/{number}\">#{number}</a></td><td>{}</td>\
+            <td>{}</td><td>{}</td><td class=\"num\">{}</td><td>{}</td><td>{}</td><td>{} ({})</td></tr>\n",
+            escape(&repo.owner),
+            escape(&repo.name),
+            escape(pull_title),
+            escape(&options.login(author)),
+            escape(size),
+            locale.format_decimal(age_hours / 24.0, 1),
+            locale.format_date(expected.date()),
+            status,
+            segment.describe(),
+            locale.format_number(estimate.samples as i64)
+        ));
+    }
+
+    let html = format!(
+        "<p>{} of {} open pull requests are overdue: older than {}% of comparable pull requests \
+        merged in the last {} days. The expected merge date is the opening date plus the median \
+        time comparable pull requests took; the last column says which ones they were and how many.</p>\n\
+        <table>\n<tr><th>Pull request</th><th>Title</th><th>Author</th><th>Size</th><th>Age (days)</th>\
+        <th>Expected merge</th><th>Status</th><th>Compared with</th></tr>\n{}</table>",
+        locale.format_number(overdue),
+        locale.format_number(open.len() as i64),
+        (OVERDUE_PERCENTILE * 100.0) as i64,
+        HISTORY_DAYS,
+        rows
+    );
+
+    Ok(Section { title, html })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentile_takes_the_value_at_its_rank() {
+        let mut values = [5.0, 1.0, 4.0, 2.0, 3.0];
+        assert_eq!(percentile(&mut values, 0.5), Some(3.0));
+        assert_eq!(percentile(&mut values, 0.9), Some(5.0));
+        assert_eq!(percentile(&mut values, 0.0), Some(1.0));
+        assert_eq!(percentile(&mut [], 0.5), None);
+    }
+}
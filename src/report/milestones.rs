@@ -0,0 +1,102 @@
+/// Report section on throughput and time to merge per milestone
+///
+/// Milestones come from `gdfm collect milestones`; pull requests and issues
+/// collected afterwards keep theirs up to date.
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, median, ReportOptions, Section};
+
+/// Number of most recent milestones listed in the section
+const RECENT_MILESTONES: i64 = 20;
+
+#[derive(Debug, sqlx::FromRow)]
+struct MilestoneCounts {
+    id: i64,
+    title: String,
+    state: String,
+    due_on: Option<DateTime<Utc>>,
+    pulls: i64,
+    merged: i64,
+    issues: i64,
+    closed_issues: i64,
+}
+
+pub async fn milestone_throughput(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let milestones: Vec<MilestoneCounts> = sqlx::query_as(
+        "SELECT m.id, m.title, m.state, m.due_on,
+            COUNT(CASE WHEN p.is_issue = 0 THEN 1 END) AS pulls,
+            COUNT(CASE WHEN p.is_issue = 0 AND p.merged_at IS NOT NULL THEN 1 END) AS merged,
+            COUNT(CASE WHEN p.is_issue = 1 THEN 1 END) AS issues,
+            COUNT(CASE WHEN p.is_issue = 1 AND p.closed_at IS NOT NULL THEN 1 END) AS closed_issues
+        FROM milestones m
+        LEFT JOIN issue_pull_milestones pm ON pm.milestone_id = m.id
+        LEFT JOIN pulls p ON p.id = pm.issue_pull_id
+        WHERE m.repo_id = $1
+        GROUP BY m.id
+        ORDER BY m.created_at DESC
+        LIMIT $2",
+    )
+    .bind(repo.id)
+    .bind(RECENT_MILESTONES)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Milestones".to_string();
+
+    if milestones.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No milestones. Run <code>gdfm collect milestones</code> first.</p>".to_string(),
+        });
+    }
+
+    let locale = &options.locale;
+    let mut rows = String::new();
+    for milestone in &milestones {
+        let mut days_to_merge: Vec<f64> = sqlx::query_scalar(
+            "SELECT julianday(p.merged_at) - julianday(p.created_at)
+            FROM issue_pull_milestones pm
+            JOIN pulls p ON p.id = pm.issue_pull_id
+            WHERE pm.milestone_id = $1 AND p.is_issue = 0 AND p.merged_at IS NOT NULL",
+        )
+        .bind(milestone.id)
+        .fetch_all(pool)
+        .await?;
+
+        let due_on = milestone
+            .due_on
+            .map(|due_on| locale.format_date(due_on.date_naive()))
+            .unwrap_or_default();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{} / {}</td>\
+            <td class=\"num\">{} / {}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&milestone.title),
+            escape(&milestone.state),
+            escape(&due_on),
+            locale.format_number(milestone.merged),
+            locale.format_number(milestone.pulls),
+            locale.format_number(milestone.closed_issues),
+            locale.format_number(milestone.issues),
+            median(&mut days_to_merge)
+                .map(|days| locale.format_decimal(days, 1))
+                .unwrap_or_else(|| "n/a".to_string())
+        ));
+    }
+
+    let html = format!(
+        "<p>The {} most recently created milestones.</p>\n\
+        <table>\n<tr><th>Milestone</th><th>State</th><th>Due</th><th>Pull requests merged</th>\
+        <th>Issues closed</th><th>Median days to merge</th></tr>\n{}</table>",
+        locale.format_number(milestones.len() as i64),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
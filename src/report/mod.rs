@@ -0,0 +1,294 @@
+/// Holds the sections that make up a generated report
+///
+/// Each submodule queries the database and renders a single HTML section.
+/// This module stitches those sections together into a standalone HTML page.
+pub mod absence;
+pub mod anonymize;
+pub mod appendix;
+pub mod branches;
+pub mod bus_factor;
+pub mod caveats;
+pub mod chart;
+pub mod checklists;
+pub mod completeness;
+pub mod conversion;
+pub mod coverage;
+pub mod delivery;
+pub mod demand;
+pub mod dependencies;
+pub mod duplicates;
+pub mod ecosystems;
+pub mod discussions;
+pub mod docs;
+pub mod governance;
+pub mod growth;
+pub mod labels;
+pub mod locale;
+pub mod merge_time;
+pub mod milestones;
+pub mod notes;
+pub mod overlap;
+pub mod periods;
+pub mod progression;
+pub mod queue;
+pub mod reciprocity;
+pub mod releases;
+pub mod review_churn;
+pub mod review_depth;
+pub mod review_wait;
+pub mod size;
+pub mod slo;
+pub mod stale_bots;
+pub mod taxonomy;
+pub mod time_investment;
+pub mod titles;
+pub mod unanswered;
+
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, NaiveDateTime};
+use regex::Regex;
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::locale::Locale;
+
+/// Author associations (upper-cased) that we treat as maintainers in SQL queries
+pub const MAINTAINER_ASSOCIATIONS: &str = "('OWNER', 'MEMBER', 'COLLABORATOR')";
+
+/// SQL condition that holds when `login` is a maintainer of the repository bound to `$1`
+///
+/// Once `gdfm collect maintainers` has run, maintainers are those with push
+/// access. Before that the author association has to do, although it calls
+/// every organization member a maintainer and misses those granted access
+/// through a team.
+pub fn is_maintainer(association: &str, login: &str) -> String {
+    format!(
+        "(CASE WHEN EXISTS (SELECT 1 FROM repository_maintainers WHERE repo_id = $1)
+            THEN {login} IN (SELECT m.login FROM maintainers m
+                JOIN repository_maintainers rm ON rm.maintainer_id = m.id WHERE rm.repo_id = $1)
+            ELSE upper({association}) IN {MAINTAINER_ASSOCIATIONS} END)"
+    )
+}
+
+/// Bot accounts end in `[bot]`; stale bots running under a user account usually say so in their name
+pub fn is_bot(login: &str) -> bool {
+    login.ends_with("[bot]") || login.to_lowercase().contains("stale")
+}
+
+/// Options from the command line that affect how every section is rendered
+#[derive(Debug)]
+pub struct ReportOptions {
+    pub locale: Locale,
+    /// Pattern pull request titles are expected to follow
+    pub title_pattern: Regex,
+    /// Pattern matching the paths of documentation files
+    pub docs_pattern: Regex,
+    /// Rules tagging changed files with the ecosystem they belong to
+    pub ecosystems: ecosystems::Ecosystems,
+    /// Thresholds shared by every size-segmented metric
+    pub size_buckets: size::SizeBuckets,
+    /// Contributions before a quiet contributor is listed
+    pub absence_min_contributions: u32,
+    /// Months without contributions before a contributor counts as quiet
+    pub absence_months: u32,
+    /// First response targets, by label
+    pub slo_policies: slo::SloPolicies,
+    /// Reporting periods activity is counted in
+    pub bucket: periods::Bucket,
+    /// Minutes per maintainer action in the time investment estimate
+    pub time_weights: time_investment::TimeWeights,
+    /// Replaces usernames with pseudonyms when set
+    pub anonymizer: Option<anonymize::Anonymizer>,
+    /// Maintainer commentary merged into the report
+    pub notes: Option<notes::Notes>,
+}
+
+impl ReportOptions {
+    /// How a login is shown: as is, or as its pseudonym in anonymized reports
+    pub fn login<'a>(&self, login: &'a str) -> Cow<'a, str> {
+        match &self.anonymizer {
+            Some(anonymizer) => anonymizer.pseudonym(login),
+            None => Cow::Borrowed(login),
+        }
+    }
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            locale: Locale::default(),
+            title_pattern: Regex::new(titles::CONVENTIONAL_COMMIT_PATTERN)
+                .expect("conventional commit regex should be valid"),
+            docs_pattern: Regex::new(docs::DOCS_PATTERN).expect("docs regex should be valid"),
+            ecosystems: ecosystems::Ecosystems::default(),
+            size_buckets: size::SizeBuckets::default(),
+            absence_min_contributions: absence::MIN_CONTRIBUTIONS,
+            absence_months: absence::ABSENT_MONTHS,
+            slo_policies: slo::SloPolicies::default(),
+            bucket: periods::Bucket::default(),
+            time_weights: time_investment::TimeWeights::default(),
+            anonymizer: None,
+            notes: None,
+        }
+    }
+}
+
+/// A rendered piece of the report with its own heading
+pub struct Section {
+    pub title: String,
+    pub html: String,
+}
+
+/// Renders all sections into a complete HTML document
+pub fn render(repo: &Repository, sections: &[Section], options: &ReportOptions) -> String {
+    let title = format!("{}/{}", escape(&repo.owner), escape(&repo.name));
+    let mut body = String::new();
+    let notes = options.notes.as_ref();
+
+    if let Some(introduction) = notes.and_then(|notes| notes.introduction.as_ref()) {
+        body.push_str(&format!("<section class=\"notes\">\n{}\n</section>\n", introduction));
+    }
+    for section in sections {
+        let commentary = notes
+            .and_then(|notes| notes.for_section(&section.title))
+            .map(|html| format!("<div class=\"notes\">\n{}\n</div>\n", html))
+            .unwrap_or_default();
+        body.push_str(&format!(
+            "<section>\n<h2>{}</h2>\n{}{}\n</section>\n",
+            escape(&section.title),
+            commentary,
+            section.html
+        ));
+    }
+    if let Some(notes) = notes {
+        let titles: Vec<&str> = sections.iter().map(|section| section.title.as_str()).collect();
+        for (heading, html) in notes.unmatched(&titles) {
+            body.push_str(&format!(
+                "<section class=\"notes\">\n<h2>{}</h2>\n{}\n</section>\n",
+                escape(heading),
+                html
+            ));
+        }
+    }
+
+    format!(
+        "<!DOCTYPE html>
+<html lang=\"{lang}\">
+<head>
+<meta charset=\"utf-8\">
+<title>gdfm report: {title}</title>
+<style>
+body {{ font-family: sans-serif; margin: 2em auto; max-width: 60em; }}
+table {{ border-collapse: collapse; width: 100%; }}
+th, td {{ border-bottom: 1px solid #ddd; padding: 0.3em 0.6em; text-align: left; }}
+td.num {{ text-align: right; }}
+.notes {{ border-left: 3px solid #888; padding-left: 1em; }}
+.caveat {{ background: #fff4d6; padding: 0.4em 0.8em; }}
+</style>
+</head>
+<body>
+<h1>{title}</h1>
+{body}</body>
+</html>
+",
+        lang = options.locale.tag
+    )
+}
+
+/// Counts maintainer reviews, events and comments on other people's pull
+/// requests and issues for every day they happened
+pub async fn maintainer_responses_per_day(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+) -> Result<BTreeMap<NaiveDate, i64>, sqlx::Error> {
+    let reviewer_is_maintainer = is_maintainer("r.author_association", "r.reviewer");
+    let actor_is_maintainer = is_maintainer("e.author_association", "e.actor");
+    let commenter_is_maintainer = is_maintainer("c.author_association", "c.author");
+    let timestamps: Vec<(String,)> = sqlx::query_as(&format!("
+        SELECT r.submitted_at FROM issue_pull_reviews r
+        JOIN pulls p ON p.id = r.issue_pull_id
+        WHERE p.repo_id = $1 AND r.reviewer != p.author AND r.submitted_at IS NOT NULL
+            AND {reviewer_is_maintainer}
+        UNION ALL
+        SELECT e.created_at FROM issue_pull_events e
+        JOIN pulls p ON p.id = e.issue_pull_id
+        WHERE p.repo_id = $1 AND e.actor != p.author AND e.created_at IS NOT NULL
+            AND {actor_is_maintainer}
+        UNION ALL
+        SELECT c.created_at FROM issue_pull_comments c
+        JOIN pulls p ON p.id = c.issue_pull_id
+        WHERE p.repo_id = $1 AND c.author != p.author
+            AND {commenter_is_maintainer}
+    "))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut daily: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    for (timestamp,) in &timestamps {
+        if let Some(day) = parse_day(timestamp) {
+            *daily.entry(day).or_default() += 1;
+        }
+    }
+
+    Ok(daily)
+}
+
+/// Escapes text so it can be safely embedded in HTML
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Returns the median of the values, sorting them in place
+pub fn median(values: &mut [f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort_by(|a, b| a.total_cmp(b));
+
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) / 2.0)
+    } else {
+        Some(values[mid])
+    }
+}
+
+/// Parses the day out of a stored timestamp such as `2025-03-29 14:01:02` or `2025-03-29 14:01:02 UTC`
+pub fn parse_day(timestamp: &str) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(timestamp.get(..10)?, "%Y-%m-%d").ok()
+}
+
+/// Parses a stored timestamp such as `2025-03-29 14:01:02`, or `2025-03-29 14:01:02 UTC` as databases
+/// written by older versions hold, ignoring fractional seconds
+pub fn parse_timestamp(timestamp: &str) -> Option<NaiveDateTime> {
+    NaiveDateTime::parse_from_str(timestamp.get(..19)?, "%Y-%m-%d %H:%M:%S").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn median_of_odd_and_even_counts() {
+        assert_eq!(median(&mut []), None);
+        assert_eq!(median(&mut [3.0, 1.0, 2.0]), Some(2.0));
+        assert_eq!(median(&mut [4.0, 1.0, 3.0, 2.0]), Some(2.5));
+    }
+
+    #[test]
+    fn timestamps_of_every_stored_format_parse() {
+        let expected = NaiveDate::from_ymd_opt(2025, 3, 29).unwrap().and_hms_opt(14, 1, 2).unwrap();
+        assert_eq!(parse_timestamp("2025-03-29 14:01:02"), Some(expected));
+        assert_eq!(parse_timestamp("2025-03-29 14:01:02 UTC"), Some(expected));
+        assert_eq!(parse_timestamp("2025-03-29 14:01:02.250"), Some(expected));
+        assert_eq!(parse_timestamp(""), None);
+        assert_eq!(parse_day("2025-03-29 14:01:02 UTC"), Some(expected.date()));
+    }
+}
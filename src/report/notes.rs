@@ -0,0 +1,198 @@
+/// Maintainer commentary merged into a generated report
+///
+/// A notes file is Markdown split into sections by `##` headings. A section
+/// whose heading matches the title of a report section, ignoring case, is shown
+/// at the top of that section; any other section is added after the computed
+/// ones. Text before the first heading opens the report. Only the Markdown
+/// commentary needs is supported: paragraphs, `###` headings, lists, quotes,
+/// fenced code, and inline code, emphasis and links.
+use std::sync::LazyLock;
+
+use regex::{Captures, Regex};
+
+use crate::report::escape;
+
+static LINK: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\[([^\]]+)\]\(([^)\s]+)\)").expect("link regex should be valid"));
+
+static STRONG: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\*\*([^*]+)\*\*").expect("strong regex should be valid"));
+
+static EMPHASIS: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"\*([^*]+)\*|\b_([^_]+)_\b").expect("emphasis regex should be valid"));
+
+/// Notes to merge into a report, each rendered to HTML
+#[derive(Debug, Default)]
+pub struct Notes {
+    /// Text before the first heading
+    pub introduction: Option<String>,
+    /// Heading and body of every `##` section, in file order
+    pub sections: Vec<(String, String)>,
+}
+
+impl Notes {
+    pub fn parse(markdown: &str) -> Self {
+        let mut notes = Self::default();
+        let mut heading: Option<String> = None;
+        let mut body: Vec<&str> = Vec::new();
+        let mut in_code = false;
+
+        for line in markdown.lines() {
+            if line.trim_start().starts_with("```") {
+                in_code = !in_code;
+            }
+            match line.strip_prefix("## ") {
+                Some(next) if !in_code => {
+                    notes.push(heading.take(), &body);
+                    heading = Some(next.trim().trim_end_matches('#').trim().to_string());
+                    body.clear();
+                }
+                _ => body.push(line),
+            }
+        }
+        notes.push(heading, &body);
+
+        notes
+    }
+
+    fn push(&mut self, heading: Option<String>, body: &[&str]) {
+        let html = to_html(body);
+        match heading {
+            Some(heading) => self.sections.push((heading, html)),
+            None if !html.is_empty() => self.introduction = Some(html),
+            None => {}
+        }
+    }
+
+    /// Commentary for the report section with this title, if any
+    pub fn for_section(&self, title: &str) -> Option<&str> {
+        self.sections
+            .iter()
+            .find(|(heading, _)| heading.eq_ignore_ascii_case(title))
+            .map(|(_, html)| html.as_str())
+    }
+
+    /// Sections whose heading matches none of the report section titles
+    pub fn unmatched<'a>(&'a self, titles: &'a [&str]) -> impl Iterator<Item = &'a (String, String)> {
+        self.sections
+            .iter()
+            .filter(|(heading, _)| !titles.iter().any(|title| heading.eq_ignore_ascii_case(title)))
+    }
+}
+
+/// Renders lines of Markdown to HTML
+fn to_html(lines: &[&str]) -> String {
+    let mut html = String::new();
+    let mut paragraph: Vec<&str> = Vec::new();
+    // Tag of the list being written, `ul` or `ol`
+    let mut list: Option<&str> = None;
+    let mut code: Option<Vec<&str>> = None;
+
+    let close_paragraph = |html: &mut String, paragraph: &mut Vec<&str>| {
+        if !paragraph.is_empty() {
+            html.push_str(&format!("<p>{}</p>\n", inline(&paragraph.join(" "))));
+            paragraph.clear();
+        }
+    };
+    let close_list = |html: &mut String, list: &mut Option<&str>| {
+        if let Some(tag) = list.take() {
+            html.push_str(&format!("</{}>\n", tag));
+        }
+    };
+
+    for line in lines {
+        if let Some(block) = &mut code {
+            if line.trim_start().starts_with("```") {
+                html.push_str(&format!("<pre><code>{}</code></pre>\n", escape(&block.join("\n"))));
+                code = None;
+            } else {
+                block.push(line);
+            }
+            continue;
+        }
+
+        let trimmed = line.trim();
+        let item = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+            .map(|item| ("ul", item))
+            .or_else(|| {
+                let (number, item) = trimmed.split_once(". ")?;
+                number.chars().all(|c| c.is_ascii_digit()).then_some(("ol", item))
+            });
+
+        if trimmed.starts_with("```") {
+            close_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            code = Some(Vec::new());
+        } else if trimmed.is_empty() {
+            close_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+        } else if let Some((level, heading)) = heading(trimmed) {
+            close_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            html.push_str(&format!("<h{level}>{}</h{level}>\n", inline(heading)));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            close_paragraph(&mut html, &mut paragraph);
+            close_list(&mut html, &mut list);
+            html.push_str(&format!("<blockquote>{}</blockquote>\n", inline(quote.trim())));
+        } else if let Some((tag, item)) = item {
+            close_paragraph(&mut html, &mut paragraph);
+            if list != Some(tag) {
+                close_list(&mut html, &mut list);
+                html.push_str(&format!("<{}>\n", tag));
+                list = Some(tag);
+            }
+            html.push_str(&format!("<li>{}</li>\n", inline(item)));
+        } else {
+            close_list(&mut html, &mut list);
+            paragraph.push(trimmed);
+        }
+    }
+    if let Some(block) = code {
+        html.push_str(&format!("<pre><code>{}</code></pre>\n", escape(&block.join("\n"))));
+    }
+    close_paragraph(&mut html, &mut paragraph);
+    close_list(&mut html, &mut list);
+
+    html.trim_end().to_string()
+}
+
+/// Level and text of a heading within a notes section; `#` renders as `###`
+/// since the report and its sections already use the two top levels
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let level = line.chars().take_while(|c| *c == '#').count();
+    let text = line[level..].strip_prefix(' ')?;
+
+    (level > 0).then_some((level.clamp(3, 6), text.trim()))
+}
+
+/// Escapes text and renders its inline code, links, strong and emphasized text
+fn inline(text: &str) -> String {
+    // Odd pieces are inside backticks and are kept as they are
+    text.split('`')
+        .enumerate()
+        .map(|(i, piece)| {
+            let piece = escape(piece);
+            if i % 2 == 1 {
+                return format!("<code>{}</code>", piece);
+            }
+            let piece = LINK.replace_all(&piece, |captures: &Captures| {
+                let url = &captures[2];
+                let safe = url.starts_with("https://") || url.starts_with("http://") || url.starts_with('#');
+                if safe {
+                    format!("<a href=\"{}\">{}</a>", url, &captures[1])
+                } else {
+                    captures[1].to_string()
+                }
+            });
+            let piece = STRONG.replace_all(&piece, "<strong>$1</strong>");
+            EMPHASIS
+                .replace_all(&piece, |captures: &Captures| {
+                    let text = captures.get(1).or_else(|| captures.get(2)).map(|m| m.as_str()).unwrap_or_default();
+                    format!("<em>{}</em>", text)
+                })
+                .to_string()
+        })
+        .collect()
+}
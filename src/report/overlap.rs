@@ -0,0 +1,120 @@
+/// Report section on contributors shared with other tracked repositories
+///
+/// A contributor is "active" in a repository when they authored or reviewed a
+/// pull request there. Comparing the first activity in each repository tells us
+/// which way contributors flow between projects.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+/// First activity of every contributor in every repository
+const FIRST_ACTIVITY: &str = "
+    SELECT login, repo_id, MIN(at) AS first_at FROM (
+        SELECT author AS login, repo_id, created_at AS at FROM pulls WHERE author != ''
+        UNION ALL
+        SELECT r.reviewer AS login, p.repo_id, r.submitted_at AS at
+        FROM issue_pull_reviews r JOIN pulls p ON p.id = r.issue_pull_id
+        WHERE r.reviewer != '' AND r.submitted_at IS NOT NULL
+    ) GROUP BY login, repo_id
+";
+
+#[derive(Debug, sqlx::FromRow)]
+struct SharedContributors {
+    owner: String,
+    name: String,
+    shared: i64,
+    arrived_from: i64,
+    went_to: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct YearlyArrivals {
+    year: String,
+    newcomers: i64,
+    from_ecosystem: i64,
+}
+
+pub async fn contributor_overlap(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let shared: Vec<SharedContributors> = sqlx::query_as(&format!("
+        WITH first_activity AS ({FIRST_ACTIVITY})
+        SELECT o.owner, o.name,
+            COUNT(*) AS shared,
+            SUM(other.first_at < here.first_at) AS arrived_from,
+            SUM(other.first_at > here.first_at) AS went_to
+        FROM first_activity here
+        JOIN first_activity other ON other.login = here.login AND other.repo_id != here.repo_id
+        JOIN repositories o ON o.id = other.repo_id AND o.archived = 0
+        WHERE here.repo_id = $1
+        GROUP BY o.id
+        ORDER BY shared DESC
+    "))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let arrivals: Vec<YearlyArrivals> = sqlx::query_as(&format!("
+        WITH first_activity AS ({FIRST_ACTIVITY})
+        SELECT substr(here.first_at, 1, 4) AS year,
+            COUNT(*) AS newcomers,
+            SUM(EXISTS (
+                SELECT 1 FROM first_activity other
+                WHERE other.login = here.login
+                    AND other.repo_id != here.repo_id
+                    AND other.first_at < here.first_at
+            )) AS from_ecosystem
+        FROM first_activity here
+        WHERE here.repo_id = $1
+        GROUP BY year
+        ORDER BY year
+    "))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let html = if shared.is_empty() {
+        "<p>No contributors are shared with other tracked repositories.</p>".to_string()
+    } else {
+        let locale = &options.locale;
+        let mut rows = String::new();
+        for other in &shared {
+            rows.push_str(&format!(
+                "<tr><td>{}/{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(&other.owner),
+                escape(&other.name),
+                locale.format_number(other.shared),
+                locale.format_number(other.arrived_from),
+                locale.format_number(other.went_to)
+            ));
+        }
+
+        let mut yearly = String::new();
+        for year in &arrivals {
+            yearly.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(&year.year),
+                locale.format_number(year.newcomers),
+                locale.format_number(year.from_ecosystem)
+            ));
+        }
+
+        format!(
+            "<p>Contributors who authored or reviewed pull requests in this and other tracked repositories. \
+            \"Arrived from\" counts contributors active in the other repository first.</p>\n\
+            <table>\n<tr><th>Repository</th><th>Shared</th><th>Arrived from</th><th>Went on to</th></tr>\n{}</table>\n\
+            <h3>First-time contributors by year</h3>\n\
+            <table>\n<tr><th>Year</th><th>New contributors</th><th>Already active elsewhere</th></tr>\n{}</table>",
+            rows, yearly
+        )
+    };
+
+    Ok(Section {
+        title: "Contributor overlap with other repositories".to_string(),
+        html,
+    })
+}
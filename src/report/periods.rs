@@ -0,0 +1,163 @@
+/// Report section counting activity per reporting period
+///
+/// Periods are ISO weeks, calendar months, quarters or fiscal years, chosen
+/// with `gdfm report --bucket`. The table is in long format, one row per
+/// period and metric, so it can be pasted into a spreadsheet and pivoted to
+/// line up with an organization's own reporting periods.
+use std::collections::BTreeMap;
+
+use chrono::{Datelike, Duration, Month, NaiveDate};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, maintainer_responses_per_day, parse_day, ReportOptions, Section};
+
+/// Month fiscal years start in when `fiscal-year` is given without one
+const DEFAULT_FISCAL_YEAR_START: Month = Month::October;
+
+/// How days are grouped into reporting periods
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Bucket {
+    /// ISO 8601 weeks, starting on Monday
+    Week,
+    #[default]
+    Month,
+    Quarter,
+    /// Years starting on the first day of the given month, named after the
+    /// calendar year they end in
+    FiscalYear(Month),
+}
+
+impl Bucket {
+    /// Parses `week`, `month`, `quarter`, `fiscal-year` or `fiscal-year:<month>`, e.g. `fiscal-year:apr`
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.split_once(':') {
+            None => match s {
+                "week" => Ok(Self::Week),
+                "month" => Ok(Self::Month),
+                "quarter" => Ok(Self::Quarter),
+                "fiscal-year" => Ok(Self::FiscalYear(DEFAULT_FISCAL_YEAR_START)),
+                _ => Err("Must be week, month, quarter or fiscal-year[:month]".to_string()),
+            },
+            Some(("fiscal-year", month)) => month
+                .parse::<Month>()
+                .map(Self::FiscalYear)
+                .map_err(|_| format!("\"{}\" is not a month such as oct or april", month)),
+            Some(_) => Err("Only fiscal-year takes a start month, e.g. fiscal-year:oct".to_string()),
+        }
+    }
+
+    /// The first day of the period a day falls in, and the period's label
+    pub fn period(&self, day: NaiveDate) -> (NaiveDate, String) {
+        match self {
+            Self::Week => {
+                let week = day.iso_week();
+                let start = day - Duration::days(day.weekday().num_days_from_monday().into());
+                (start, format!("{}-W{:02}", week.year(), week.week()))
+            }
+            Self::Month => (day.with_day(1).unwrap_or(day), day.format("%Y-%m").to_string()),
+            Self::Quarter => {
+                let quarter = day.month0() / 3;
+                let start = NaiveDate::from_ymd_opt(day.year(), quarter * 3 + 1, 1).unwrap_or(day);
+                (start, format!("{}-Q{}", day.year(), quarter + 1))
+            }
+            Self::FiscalYear(start_month) => {
+                let start_month = start_month.number_from_month();
+                let start_year = if day.month() >= start_month { day.year() } else { day.year() - 1 };
+                let start = NaiveDate::from_ymd_opt(start_year, start_month, 1).unwrap_or(day);
+                let end_year = if start_month == 1 { start_year } else { start_year + 1 };
+                (start, format!("FY{}", end_year))
+            }
+        }
+    }
+
+    /// Name of the period for prose, e.g. "quarter"
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Week => "ISO week",
+            Self::Month => "month",
+            Self::Quarter => "quarter",
+            Self::FiscalYear(_) => "fiscal year",
+        }
+    }
+}
+
+pub async fn activity_by_period(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    // (is_issue, created_at, closed_at, merged_at)
+    let items: Vec<(bool, String, Option<String>, Option<String>)> = sqlx::query_as(
+        "SELECT is_issue, created_at, closed_at, merged_at FROM pulls WHERE repo_id = $1 AND created_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Activity by period".to_string();
+
+    if items.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No pull requests or issues collected. Run <code>gdfm collect pulls</code> \
+                and <code>gdfm collect issues</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    const METRICS: [&str; 5] = [
+        "Pull requests opened",
+        "Pull requests merged",
+        "Issues opened",
+        "Issues closed",
+        "Maintainer responses",
+    ];
+
+    // Period start -> (label, count per metric)
+    let mut periods: BTreeMap<NaiveDate, (String, [i64; METRICS.len()])> = BTreeMap::new();
+    let bucket = options.bucket;
+    let mut count = |timestamp: Option<&str>, metric: usize, amount: i64| {
+        if let Some(day) = timestamp.and_then(parse_day) {
+            let (start, label) = bucket.period(day);
+            periods.entry(start).or_insert_with(|| (label, [0; METRICS.len()])).1[metric] += amount;
+        }
+    };
+
+    for (is_issue, created_at, closed_at, merged_at) in &items {
+        if *is_issue {
+            count(Some(created_at), 2, 1);
+            count(closed_at.as_deref(), 3, 1);
+        } else {
+            count(Some(created_at), 0, 1);
+            count(merged_at.as_deref(), 1, 1);
+        }
+    }
+    for (day, responses) in maintainer_responses_per_day(pool, repo).await? {
+        count(Some(&day.to_string()), 4, responses);
+    }
+
+    let locale = &options.locale;
+    let mut rows = String::new();
+    for (label, counts) in periods.values().rev() {
+        for (metric, value) in METRICS.iter().zip(counts) {
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(label),
+                metric,
+                locale.format_number(*value)
+            ));
+        }
+    }
+
+    let html = format!(
+        "<p>Pull requests, issues and maintainer reviews, comments and events per {}, most \
+        recent first, with one row per period and metric.</p>\n\
+        <table>\n<tr><th>Period</th><th>Metric</th><th>Count</th></tr>\n{}</table>",
+        bucket.name(),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
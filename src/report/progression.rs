@@ -0,0 +1,122 @@
+/// Report section following contributors as their author association changes
+///
+/// GitHub only reports a contributor's association as it is when something is
+/// fetched, so `gdfm collect` snapshots the associations seen on pull requests,
+/// issues, reviews, comments and events with the earliest time each appeared.
+/// Contributors seen with more than one association are listed with the dates
+/// they moved from one to the next, e.g. from `NONE` to `CONTRIBUTOR` to `MEMBER`.
+use std::collections::BTreeMap;
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, parse_day, ReportOptions, Section};
+
+/// Associations from least to most involved, to order those first seen at the same time
+const ASSOCIATION_RANKS: [&str; 7] = [
+    "NONE",
+    "FIRST_TIME_CONTRIBUTOR",
+    "FIRST_TIMER",
+    "CONTRIBUTOR",
+    "COLLABORATOR",
+    "MEMBER",
+    "OWNER",
+];
+
+/// Associations held by maintainers, as in `MAINTAINER_ASSOCIATIONS`
+const MAINTAINER_RANK: usize = 4;
+
+fn rank(association: &str) -> usize {
+    ASSOCIATION_RANKS
+        .iter()
+        .position(|known| *known == association)
+        .unwrap_or(0)
+}
+
+pub async fn contributor_progression(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    // (login, association, first seen)
+    let snapshots: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT login, association, first_seen_at FROM association_history WHERE repo_id = $1",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Contributor progression".to_string();
+
+    if snapshots.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No author associations recorded. Run <code>gdfm collect pulls</code>, \
+                <code>gdfm collect reviews</code> and <code>gdfm collect comments</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    let mut by_login: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+    for (login, association, first_seen_at) in snapshots {
+        by_login.entry(login).or_default().push((association, first_seen_at));
+    }
+
+    let mut progressions: Vec<(String, Vec<(String, String)>)> = by_login
+        .into_iter()
+        .filter(|(_, steps)| steps.len() > 1)
+        .map(|(login, mut steps)| {
+            steps.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| rank(&a.0).cmp(&rank(&b.0))));
+            (login, steps)
+        })
+        .collect();
+    // Most recent change first
+    progressions.sort_by(|a, b| b.1.last().map(|step| &step.1).cmp(&a.1.last().map(|step| &step.1)));
+
+    let became_maintainers = progressions
+        .iter()
+        .filter(|(_, steps)| {
+            rank(&steps[0].0) < MAINTAINER_RANK
+                && steps.iter().any(|(association, _)| rank(association) >= MAINTAINER_RANK)
+        })
+        .count();
+
+    let locale = &options.locale;
+    let summary = format!(
+        "{} contributors have been seen with more than one author association; {} of them \
+        started out as outside contributors and became collaborators, members or owners. Dates \
+        are when gdfm first saw each association on a pull request, issue, review, comment or \
+        event, so changes before the first collection show up late.",
+        locale.format_number(progressions.len() as i64),
+        locale.format_number(became_maintainers as i64)
+    );
+
+    if progressions.is_empty() {
+        return Ok(Section { title, html: format!("<p>{}</p>", summary) });
+    }
+
+    let mut rows = String::new();
+    for (login, steps) in &progressions {
+        let chain: Vec<String> = steps
+            .iter()
+            .map(|(association, first_seen_at)| {
+                let day = parse_day(first_seen_at).map(|day| locale.format_date(day)).unwrap_or_default();
+                format!("{} ({})", escape(association), escape(&day))
+            })
+            .collect();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            escape(&options.login(login)),
+            chain.join(" &rarr; ")
+        ));
+    }
+
+    let html = format!(
+        "<p>{}</p>\n\
+        <table>\n<tr><th>Contributor</th><th>Associations</th></tr>\n{}</table>",
+        summary, rows
+    );
+
+    Ok(Section { title, html })
+}
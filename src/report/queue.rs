@@ -0,0 +1,121 @@
+/// Report section charting the review queue over time
+///
+/// Rather than only showing how many pull requests are open today, the daily
+/// queue length is reconstructed from when each pull request was opened, first
+/// responded to by a maintainer, and closed.
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDate, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::chart::{line_chart, Series};
+use crate::report::{is_maintainer, parse_day, ReportOptions, Section};
+
+#[derive(Debug, sqlx::FromRow)]
+struct PullTimeline {
+    created_at: String,
+    closed_at: Option<String>,
+    first_response_at: Option<String>,
+}
+
+pub async fn queue_history(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let reviewer_is_maintainer = is_maintainer("r.author_association", "r.reviewer");
+    let actor_is_maintainer = is_maintainer("e.author_association", "e.actor");
+    let commenter_is_maintainer = is_maintainer("c.author_association", "c.author");
+    let timelines: Vec<PullTimeline> = sqlx::query_as(&format!("
+        SELECT p.created_at, p.closed_at, (
+            SELECT MIN(at) FROM (
+                SELECT r.submitted_at AS at FROM issue_pull_reviews r
+                WHERE r.issue_pull_id = p.id AND r.reviewer != p.author
+                    AND {reviewer_is_maintainer}
+                UNION ALL
+                SELECT e.created_at AS at FROM issue_pull_events e
+                WHERE e.issue_pull_id = p.id AND e.actor != p.author
+                    AND {actor_is_maintainer}
+                UNION ALL
+                SELECT c.created_at AS at FROM issue_pull_comments c
+                WHERE c.issue_pull_id = p.id AND c.author != p.author
+                    AND {commenter_is_maintainer}
+            ) WHERE at IS NOT NULL
+        ) AS first_response_at
+        FROM pulls p
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.created_at IS NOT NULL
+    "))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    // +1 on the day something enters the queue, -1 on the day it leaves
+    let mut open_deltas: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    let mut waiting_deltas: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+
+    for timeline in &timelines {
+        let Some(created) = parse_day(&timeline.created_at) else {
+            continue;
+        };
+        let closed = timeline.closed_at.as_deref().and_then(parse_day);
+        let responded = timeline.first_response_at.as_deref().and_then(parse_day);
+
+        *open_deltas.entry(created).or_default() += 1;
+        if let Some(closed) = closed {
+            *open_deltas.entry(closed).or_default() -= 1;
+        }
+
+        *waiting_deltas.entry(created).or_default() += 1;
+        let stopped_waiting = match (responded, closed) {
+            (Some(responded), Some(closed)) => Some(responded.min(closed)),
+            (responded, closed) => responded.or(closed),
+        };
+        if let Some(stopped) = stopped_waiting {
+            *waiting_deltas.entry(stopped).or_default() -= 1;
+        }
+    }
+
+    let open = accumulate(&open_deltas);
+    let waiting = accumulate(&waiting_deltas);
+
+    let html = if open.is_empty() {
+        "<p>No pull requests collected.</p>".to_string()
+    } else {
+        format!(
+            "<p>Daily number of open pull requests, and of those still waiting for a first \
+            maintainer review, comment or event, reconstructed from collected history.</p>\n{}",
+            line_chart(
+                &[
+                    Series { name: "Open pull requests", points: &open },
+                    Series { name: "Awaiting maintainer response", points: &waiting },
+                ],
+                &options.locale
+            )
+        )
+    };
+
+    Ok(Section {
+        title: "Review queue history".to_string(),
+        html,
+    })
+}
+
+/// Turns daily deltas into a running total for every day up to today
+fn accumulate(deltas: &BTreeMap<NaiveDate, i64>) -> Vec<(NaiveDate, i64)> {
+    let Some(first) = deltas.keys().next().copied() else {
+        return vec![];
+    };
+    let today = Utc::now().date_naive();
+
+    let mut total = 0;
+    first
+        .iter_days()
+        .take_while(|day| *day <= today)
+        .map(|day| {
+            total += deltas.get(&day).copied().unwrap_or(0);
+            (day, total)
+        })
+        .collect()
+}
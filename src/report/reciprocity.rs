@@ -0,0 +1,166 @@
+/// Report section on whether maintainers review each other's pull requests in turn
+///
+/// Counts the pull requests each maintainer reviewed for every other maintainer
+/// and shows them as a matrix. A pair is reciprocal when both review each other
+/// about as often; the imbalance score is the share of all reviews between
+/// maintainers that isn't matched by reviews going the other way, from 0% when
+/// every pair evens out to 100% when review only ever flows one way.
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{Duration, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, is_maintainer, ReportOptions, Section};
+
+/// Only reviews submitted in this many days are counted
+const RECIPROCITY_DAYS: i64 = 180;
+
+/// Maintainers shown in the matrix, most reviews given and received first
+const TOP_MAINTAINERS: usize = 10;
+
+/// Pairs listed as the most one-sided, and the reviews a pair needs to be listed
+const TOP_PAIRS: usize = 5;
+const MIN_PAIR_REVIEWS: i64 = 3;
+
+pub async fn review_reciprocity(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(RECIPROCITY_DAYS)).naive_utc();
+    let reviewer_is_maintainer = is_maintainer("r.author_association", "r.reviewer");
+    let author_is_maintainer = is_maintainer("p.author_association", "p.author");
+    // (reviewer, author, pull requests reviewed)
+    let pairs: Vec<(String, String, i64)> = sqlx::query_as(&format!(
+        "SELECT r.reviewer, p.author, COUNT(DISTINCT r.issue_pull_id)
+        FROM issue_pull_reviews r
+        JOIN pulls p ON p.id = r.issue_pull_id
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND r.submitted_at >= $2
+            AND r.reviewer != '' AND p.author != '' AND r.reviewer != p.author
+            AND {reviewer_is_maintainer} AND {author_is_maintainer}
+        GROUP BY r.reviewer, p.author"
+    ))
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Review reciprocity".to_string();
+
+    if pairs.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>No maintainer reviewed another maintainer's pull request in the last {} days. Run \
+                <code>gdfm collect reviews</code> first.</p>",
+                RECIPROCITY_DAYS
+            ),
+        });
+    }
+
+    let reviews: HashMap<(&str, &str), i64> =
+        pairs.iter().map(|(reviewer, author, count)| ((reviewer.as_str(), author.as_str()), *count)).collect();
+    let count = |reviewer: &str, author: &str| reviews.get(&(reviewer, author)).copied().unwrap_or(0);
+
+    // (given, received) per maintainer
+    let mut totals: HashMap<&str, (i64, i64)> = HashMap::new();
+    for (reviewer, author, count) in &pairs {
+        totals.entry(reviewer).or_default().0 += count;
+        totals.entry(author).or_default().1 += count;
+    }
+
+    // Each unordered pair once, as (a, b, a reviewed b, b reviewed a)
+    let mut unordered: BTreeSet<(&str, &str)> = BTreeSet::new();
+    for (reviewer, author, _) in &pairs {
+        let (reviewer, author) = (reviewer.as_str(), author.as_str());
+        unordered.insert(if reviewer < author { (reviewer, author) } else { (author, reviewer) });
+    }
+    let mut balances: Vec<(&str, &str, i64, i64)> =
+        unordered.into_iter().map(|(a, b)| (a, b, count(a, b), count(b, a))).collect();
+    let total: i64 = balances.iter().map(|(_, _, ab, ba)| ab + ba).sum();
+    let unmatched: i64 = balances.iter().map(|(_, _, ab, ba)| (ab - ba).abs()).sum();
+    let mutual = balances.iter().filter(|(_, _, ab, ba)| *ab > 0 && *ba > 0).count();
+
+    let locale = &options.locale;
+    let percent = |value: f64| format!("{}%", locale.format_decimal(value * 100.0, 1));
+
+    let mut team: Vec<(&str, (i64, i64))> = totals.into_iter().collect();
+    team.sort_by(|(a_name, a), (b_name, b)| (b.0 + b.1).cmp(&(a.0 + a.1)).then(a_name.cmp(b_name)));
+    team.truncate(TOP_MAINTAINERS);
+
+    let header: String = team
+        .iter()
+        .map(|(login, _)| format!("<th>{}</th>", escape(&options.login(login))))
+        .collect();
+    let mut matrix = String::new();
+    for (reviewer, (given, received)) in &team {
+        let cells: String = team
+            .iter()
+            .map(|(author, _)| {
+                if author == reviewer {
+                    "<td class=\"num\">&ndash;</td>".to_string()
+                } else {
+                    format!("<td class=\"num\">{}</td>", locale.format_number(count(reviewer, author)))
+                }
+            })
+            .collect();
+        let balance = (given - received) as f64 / (given + received) as f64;
+        matrix.push_str(&format!(
+            "<tr><th>{}</th>{}<td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}{}</td></tr>\n",
+            escape(&options.login(reviewer)),
+            cells,
+            locale.format_number(*given),
+            locale.format_number(*received),
+            if balance > 0.0 { "+" } else { "" },
+            percent(balance)
+        ));
+    }
+
+    let mut html = format!(
+        "<p>Maintainers reviewed each other's pull requests {} times in the last {} days. {} of the {} \
+        pairs who reviewed each other at all did so both ways, and the imbalance score is {}: that share \
+        of the reviews isn't matched by a review going the other way.</p>\n\
+        <p>Rows are reviewers and columns authors. Balance is the reviews a maintainer gave minus those \
+        they received, as a share of both, so it is positive for those who give more than they get.</p>\n\
+        <table>\n<tr><th>Reviewer \\ Author</th>{}<th>Given</th><th>Received</th><th>Balance</th></tr>\n\
+        {}</table>",
+        locale.format_number(total),
+        RECIPROCITY_DAYS,
+        locale.format_number(mutual as i64),
+        locale.format_number(balances.len() as i64),
+        percent(unmatched as f64 / total as f64),
+        header,
+        matrix
+    );
+
+    balances.retain(|(_, _, ab, ba)| ab + ba >= MIN_PAIR_REVIEWS && ab != ba);
+    balances.sort_by(|a, b| {
+        let skew = |(_, _, ab, ba): &(&str, &str, i64, i64)| (ab - ba).abs() as f64 / (ab + ba) as f64;
+        skew(b).total_cmp(&skew(a)).then((b.2 + b.3).cmp(&(a.2 + a.3)))
+    });
+    if !balances.is_empty() {
+        let mut rows = String::new();
+        for (a, b, ab, ba) in balances.iter().take(TOP_PAIRS) {
+            // The busier direction first
+            let (giver, taker, given, returned) = if ab > ba { (a, b, ab, ba) } else { (b, a, ba, ab) };
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(&options.login(giver)),
+                escape(&options.login(taker)),
+                locale.format_number(*given),
+                locale.format_number(*returned)
+            ));
+        }
+        html.push_str(&format!(
+            "\n<p>Most one-sided pairs with at least {} reviews between them:</p>\n\
+            <table>\n<tr><th>Reviewer</th><th>Of</th><th>Reviews given</th><th>Reviews returned</th></tr>\n\
+            {}</table>",
+            MIN_PAIR_REVIEWS,
+            rows
+        ));
+    }
+
+    Ok(Section { title, html })
+}
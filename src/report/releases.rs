@@ -0,0 +1,89 @@
+/// Report section relating pull request throughput to release cadence
+///
+/// Each release is credited with the pull requests merged since the previous
+/// published release. Pre-releases count as releases of their own.
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::{Release, Repository};
+use crate::report::{escape, median, ReportOptions, Section};
+
+/// Number of most recent releases listed in the section
+const RECENT_RELEASES: usize = 20;
+
+pub async fn release_cadence(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let releases = Release::fetch_published(pool, repo.id).await?;
+    let merged: Vec<(DateTime<Utc>,)> = sqlx::query_as(
+        "SELECT merged_at FROM pulls
+        WHERE repo_id = $1 AND is_issue = 0 AND merged_at IS NOT NULL
+        ORDER BY merged_at",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Release cadence".to_string();
+
+    if releases.len() < 2 {
+        return Ok(Section {
+            title,
+            html: "<p>At least two published releases are needed. Run \
+                <code>gdfm collect releases</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    // (release, days since previous release, pull requests merged in between)
+    let mut windows = Vec::new();
+    for pair in releases.windows(2) {
+        let (previous, release) = (&pair[0], &pair[1]);
+        let merged_in_window = merged
+            .iter()
+            .filter(|(at,)| Some(*at) > previous.published_at && Some(*at) <= release.published_at)
+            .count();
+        let days = match (previous.published_at, release.published_at) {
+            (Some(start), Some(end)) => (end.date_naive() - start.date_naive()).num_days(),
+            _ => 0,
+        };
+        windows.push((release, days, merged_in_window));
+    }
+
+    let mut gaps: Vec<f64> = windows.iter().map(|(_, days, _)| *days as f64).collect();
+    let mut per_release: Vec<f64> = windows.iter().map(|(_, _, count)| *count as f64).collect();
+
+    let locale = &options.locale;
+    let mut rows = String::new();
+    for (release, days, count) in windows.iter().rev().take(RECENT_RELEASES) {
+        let name = if release.name.is_empty() { &release.tag_name } else { &release.name };
+        rows.push_str(&format!(
+            "<tr><td><a href=\"https://github.com/{}/{}/releases/tag/{}\">{}</a>{}</td><td>{}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&repo.owner),
+            escape(&repo.name),
+            escape(&release.tag_name),
+            escape(name),
+            if release.prerelease { " (pre-release)" } else { "" },
+            escape(&release.published_at.map(|at| locale.format_datetime(at)).unwrap_or_default()),
+            locale.format_number(*days),
+            locale.format_number(*count as i64)
+        ));
+    }
+
+    let html = format!(
+        "<p>{} published releases. A release ships every {} days (median), with {} merged pull \
+        requests per release (median).</p>\n\
+        <table>\n<tr><th>Release</th><th>Published</th><th>Days since previous</th>\
+        <th>Pull requests merged</th></tr>\n{}</table>",
+        locale.format_number(releases.len() as i64),
+        locale.format_decimal(median(&mut gaps).unwrap_or(0.0), 1),
+        locale.format_decimal(median(&mut per_release).unwrap_or(0.0), 1),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,188 @@
+/// Report section measuring review requests that change hands before anyone reviews
+///
+/// A pull request churns when a review request is removed before the first
+/// review, usually because it was passed on to someone else. Churn adds to
+/// the wait for a first review, and reviewers whose requests are removed over
+/// and over tend to point at auto-assignment rules (CODEOWNERS, team
+/// round-robin) that pick the wrong people.
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, median, parse_timestamp, ReportOptions, Section};
+
+/// Only pull requests opened in this many days are measured
+const CHURN_DAYS: i64 = 180;
+
+/// Reviewers listed in the table of removed requests
+const TOP_REVIEWERS: usize = 10;
+
+/// Review requests and removals on one pull request, up to its first review
+#[derive(Debug, Default)]
+struct Requests {
+    first_requested: Option<String>,
+    removed: usize,
+    /// Requests made after one was removed
+    re_requested: usize,
+}
+
+pub async fn review_request_churn(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(CHURN_DAYS)).naive_utc();
+    // (pull request id, removed, requested reviewer, created_at, first review), in time order
+    let events: Vec<(i64, bool, String, String, Option<String>)> = sqlx::query_as(
+        "SELECT e.issue_pull_id, lower(replace(e.event_type, '_', '')) = 'reviewrequestremoved',
+            e.requested_reviewer, e.created_at, f.first_review
+        FROM issue_pull_events e
+        JOIN pulls p ON p.id = e.issue_pull_id
+        LEFT JOIN (
+            SELECT r.issue_pull_id, MIN(r.submitted_at) AS first_review FROM issue_pull_reviews r
+            JOIN pulls p ON p.id = r.issue_pull_id
+            WHERE p.repo_id = $1 AND r.reviewer != p.author AND r.submitted_at IS NOT NULL
+            GROUP BY r.issue_pull_id
+        ) f ON f.issue_pull_id = p.id
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.created_at >= $2 AND e.created_at IS NOT NULL
+            AND lower(replace(e.event_type, '_', '')) IN ('reviewrequested', 'reviewrequestremoved')
+        ORDER BY e.created_at",
+    )
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Review request churn".to_string();
+
+    if events.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>No review requests on pull requests opened in the last {} days. Run \
+                <code>gdfm collect pulls</code> and <code>gdfm collect events</code> first.</p>",
+                CHURN_DAYS
+            ),
+        });
+    }
+
+    let mut pulls: HashMap<i64, Requests> = HashMap::new();
+    let mut first_reviews: HashMap<i64, String> = HashMap::new();
+    // Reviewer -> (requested, removed before the first review)
+    let mut reviewers: HashMap<String, (i64, i64)> = HashMap::new();
+    for (pull_id, removed, reviewer, created_at, first_review) in &events {
+        if let Some(first_review) = first_review {
+            if created_at >= first_review {
+                continue;
+            }
+            first_reviews.insert(*pull_id, first_review.clone());
+        }
+        let requests = pulls.entry(*pull_id).or_default();
+        let counts = (!reviewer.is_empty()).then(|| reviewers.entry(reviewer.clone()).or_default());
+        if *removed {
+            requests.removed += 1;
+            if let Some(counts) = counts {
+                counts.1 += 1;
+            }
+        } else {
+            if requests.first_requested.is_none() {
+                requests.first_requested = Some(created_at.clone());
+            }
+            if requests.removed > 0 {
+                requests.re_requested += 1;
+            }
+            if let Some(counts) = counts {
+                counts.0 += 1;
+            }
+        }
+    }
+
+    let mut churned = 0;
+    let mut re_requested = 0;
+    let mut hours_with_churn = Vec::new();
+    let mut hours_without_churn = Vec::new();
+    for (pull_id, requests) in &pulls {
+        if requests.removed > 0 {
+            churned += 1;
+        }
+        if requests.re_requested > 0 {
+            re_requested += 1;
+        }
+        let requested = requests.first_requested.as_deref().and_then(parse_timestamp);
+        let reviewed = first_reviews.get(pull_id).and_then(|at| parse_timestamp(at));
+        if let (Some(requested), Some(reviewed)) = (requested, reviewed) {
+            let hours = (reviewed - requested).num_minutes() as f64 / 60.0;
+            if requests.removed > 0 {
+                hours_with_churn.push(hours);
+            } else {
+                hours_without_churn.push(hours);
+            }
+        }
+    }
+
+    let locale = &options.locale;
+    let median_with = median(&mut hours_with_churn);
+    let median_without = median(&mut hours_without_churn);
+    let hours = |value: Option<f64>| {
+        value
+            .map(|hours| locale.format_decimal(hours, 1))
+            .unwrap_or_else(|| "-".to_string())
+    };
+    let added = match (median_with, median_without) {
+        (Some(with), Some(without)) => format!(", {} hours more", locale.format_decimal(with - without, 1)),
+        _ => String::new(),
+    };
+    let summary = format!(
+        "{} of {} pull requests with review requests opened in the last {} days had a request \
+        removed before anyone reviewed; {} of them were then requested from someone again. \
+        Median hours from the first request to the first review: {} with churn and {} without{}.",
+        locale.format_number(churned),
+        locale.format_number(pulls.len() as i64),
+        CHURN_DAYS,
+        locale.format_number(re_requested),
+        hours(median_with),
+        hours(median_without),
+        added
+    );
+
+    let mut removed_reviewers: Vec<(String, (i64, i64))> = reviewers
+        .into_iter()
+        .filter(|(_, (_, removed))| *removed > 0)
+        .collect();
+    removed_reviewers.sort_by(|a, b| b.1 .1.cmp(&a.1 .1).then_with(|| a.0.cmp(&b.0)));
+
+    if removed_reviewers.is_empty() {
+        return Ok(Section { title, html: format!("<p>{}</p>", summary) });
+    }
+
+    let mut rows = String::new();
+    for (reviewer, (requested, removed)) in removed_reviewers.iter().take(TOP_REVIEWERS) {
+        let share = if *requested == 0 {
+            String::new()
+        } else {
+            format!("{}%", locale.format_decimal(*removed as f64 * 100.0 / *requested as f64, 1))
+        };
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&options.login(reviewer)),
+            locale.format_number(*requested),
+            locale.format_number(*removed),
+            share
+        ));
+    }
+
+    let html = format!(
+        "<p>{}</p>\n\
+        <p>Reviewers whose requests were removed most often before the first review. A reviewer \
+        removed from most of their requests is a sign an auto-assignment rule picks the wrong \
+        person.</p>\n\
+        <table>\n<tr><th>Reviewer</th><th>Requested</th><th>Removed before review</th>\
+        <th>Share removed</th></tr>\n{}</table>",
+        summary, rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,105 @@
+/// Report section measuring how many inline comments each review leaves
+///
+/// Only reviews collected with their GitHub id can be matched to inline
+/// comments, so reviews stored by older versions of gdfm are skipped.
+use sqlx::sqlite::Sqlite;
+use sqlx::{Pool, Row};
+
+use crate::database::Repository;
+use crate::report::{escape, median, ReportOptions, Section};
+
+pub async fn review_comment_depth(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let rows = sqlx::query(
+        "SELECT r.state, COUNT(c.id) AS comments
+        FROM issue_pull_reviews r
+        JOIN pulls p ON p.id = r.issue_pull_id
+        LEFT JOIN issue_pull_review_comments c ON c.review_id = r.github_id
+        WHERE p.repo_id = $1 AND r.github_id IS NOT NULL
+        GROUP BY r.id
+        ORDER BY r.state",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Review comment depth".to_string();
+
+    if rows.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No reviews collected. Run <code>gdfm collect reviews</code> and \
+                <code>gdfm collect review-comments</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    // (state, comment counts per review), rows are already ordered by state
+    let mut states: Vec<(String, Vec<f64>)> = Vec::new();
+    for row in &rows {
+        let state: String = row.get("state");
+        let comments: i64 = row.get("comments");
+        match states.last_mut() {
+            Some((last, counts)) if *last == state => counts.push(comments as f64),
+            _ => states.push((state, vec![comments as f64])),
+        }
+    }
+
+    let locale = &options.locale;
+    let mut table_rows = String::new();
+    for (state, counts) in states.iter_mut() {
+        let reviews = counts.len();
+        let total: f64 = counts.iter().sum();
+        let without = counts.iter().filter(|count| **count == 0.0).count();
+        let median_comments = median(counts).unwrap_or(0.0);
+
+        table_rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}%</td></tr>\n",
+            escape(state),
+            locale.format_number(reviews as i64),
+            locale.format_number(total as i64),
+            locale.format_decimal(total / reviews as f64, 1),
+            locale.format_decimal(median_comments, 1),
+            locale.format_decimal(without as f64 * 100.0 / reviews as f64, 1)
+        ));
+    }
+
+    let threads = sqlx::query(
+        "SELECT COUNT(*) AS threads,
+            (SELECT COUNT(*) FROM issue_pull_review_comments c
+                JOIN pulls p ON p.id = c.issue_pull_id
+                WHERE p.repo_id = $1 AND c.in_reply_to_id IS NOT NULL) AS replies
+        FROM issue_pull_review_comments c
+        JOIN pulls p ON p.id = c.issue_pull_id
+        WHERE p.repo_id = $1 AND c.in_reply_to_id IS NULL",
+    )
+    .bind(repo.id)
+    .fetch_one(pool)
+    .await?;
+    let thread_count: i64 = threads.get("threads");
+    let replies: i64 = threads.get("replies");
+
+    let thread_summary = if thread_count > 0 {
+        format!(
+            "<p>{} inline threads received {} replies, {} replies per thread on average.</p>\n",
+            locale.format_number(thread_count),
+            locale.format_number(replies),
+            locale.format_decimal(replies as f64 / thread_count as f64, 2)
+        )
+    } else {
+        String::new()
+    };
+
+    let html = format!(
+        "<p>Inline (line-level) comments left per review, grouped by review state.</p>\n\
+        <table>\n<tr><th>State</th><th>Reviews</th><th>Inline comments</th><th>Mean</th>\
+        <th>Median</th><th>Without comments</th></tr>\n{}</table>\n{}",
+        table_rows, thread_summary
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,162 @@
+/// Report section measuring how long requested reviewers take to review
+///
+/// Each `review_requested` event naming a user is matched with the first review
+/// that user submitted on the pull request at or after the request. Requests
+/// still pending come from the requested reviewers stored by `gdfm collect
+/// pulls`, which include teams.
+use std::collections::HashMap;
+
+use chrono::{Duration, NaiveDateTime, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, median, parse_timestamp, ReportOptions, Section};
+
+/// Only review requests made in this many days are measured
+const WAIT_DAYS: i64 = 180;
+
+/// Reviewers listed, most requests first
+const TOP_REVIEWERS: usize = 10;
+
+/// Pending requests older than this many days are counted as overdue
+const OVERDUE_DAYS: i64 = 7;
+
+#[derive(Debug, sqlx::FromRow)]
+struct Request {
+    reviewer: String,
+    requested_at: String,
+    reviewed_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct ReviewerWait {
+    requested: i64,
+    hours: Vec<f64>,
+}
+
+pub async fn review_request_response(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(WAIT_DAYS)).naive_utc();
+    let requests: Vec<Request> = sqlx::query_as(
+        "SELECT e.requested_reviewer AS reviewer, e.created_at AS requested_at, (
+            SELECT MIN(r.submitted_at) FROM issue_pull_reviews r
+            WHERE r.issue_pull_id = e.issue_pull_id AND r.reviewer = e.requested_reviewer
+                AND r.submitted_at >= e.created_at
+        ) AS reviewed_at
+        FROM issue_pull_events e
+        JOIN pulls p ON p.id = e.issue_pull_id
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND e.created_at >= $2 AND e.requested_reviewer != ''
+            AND lower(replace(e.event_type, '_', '')) = 'reviewrequested'",
+    )
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    // (reviewer, is team, requested at), requested at is NULL when no event was collected
+    let pending: Vec<(String, bool, Option<NaiveDateTime>)> = sqlx::query_as(
+        "SELECT q.reviewer, q.is_team, (
+            SELECT MAX(e.created_at) FROM issue_pull_events e
+            WHERE e.issue_pull_id = q.issue_pull_id AND e.requested_reviewer = q.reviewer
+                AND lower(replace(e.event_type, '_', '')) = 'reviewrequested'
+        )
+        FROM issue_pull_requested_reviewers q
+        JOIN pulls p ON p.id = q.issue_pull_id
+        WHERE p.repo_id = $1 AND p.closed_at IS NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Time to review after a request".to_string();
+
+    if requests.is_empty() && pending.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>No review requests in the last {} days. Run <code>gdfm collect pulls</code> and \
+                <code>gdfm collect events</code> first.</p>",
+                WAIT_DAYS
+            ),
+        });
+    }
+
+    let mut all_hours = Vec::new();
+    let mut reviewers: HashMap<&str, ReviewerWait> = HashMap::new();
+    for request in &requests {
+        let wait = reviewers.entry(&request.reviewer).or_default();
+        wait.requested += 1;
+        let requested_at = parse_timestamp(&request.requested_at);
+        let reviewed_at = request.reviewed_at.as_deref().and_then(parse_timestamp);
+        if let (Some(requested_at), Some(reviewed_at)) = (requested_at, reviewed_at) {
+            let hours = (reviewed_at - requested_at).num_minutes() as f64 / 60.0;
+            wait.hours.push(hours);
+            all_hours.push(hours);
+        }
+    }
+    let answered = all_hours.len();
+
+    let locale = &options.locale;
+    let hours = |value: Option<f64>| {
+        value
+            .map(|hours| locale.format_decimal(hours, 1))
+            .unwrap_or_else(|| "-".to_string())
+    };
+
+    let overdue_before = (Utc::now() - Duration::days(OVERDUE_DAYS)).naive_utc();
+    let teams = pending.iter().filter(|(_, is_team, _)| *is_team).count();
+    let overdue = pending
+        .iter()
+        .filter(|(_, _, requested_at)| requested_at.is_some_and(|at| at < overdue_before))
+        .count();
+
+    let mut html = String::new();
+    if !requests.is_empty() {
+        let share = answered as f64 / requests.len() as f64 * 100.0;
+        html.push_str(&format!(
+            "<p>{} reviews were requested from people in the last {} days and {}% of them were given. \
+            Median hours from the request to the review: {}.</p>\n",
+            locale.format_number(requests.len() as i64),
+            WAIT_DAYS,
+            locale.format_decimal(share, 1),
+            hours(median(&mut all_hours))
+        ));
+    }
+    html.push_str(&format!(
+        "<p>{} review requests are pending on open pull requests, {} of them for teams. \
+        {} were made more than {} days ago.</p>",
+        locale.format_number(pending.len() as i64),
+        locale.format_number(teams as i64),
+        locale.format_number(overdue as i64),
+        OVERDUE_DAYS
+    ));
+
+    if reviewers.is_empty() {
+        return Ok(Section { title, html });
+    }
+
+    let mut reviewers: Vec<(&str, ReviewerWait)> = reviewers.into_iter().collect();
+    reviewers.sort_by(|(a_name, a), (b_name, b)| b.requested.cmp(&a.requested).then(a_name.cmp(b_name)));
+
+    let mut rows = String::new();
+    for (reviewer, wait) in reviewers.iter_mut().take(TOP_REVIEWERS) {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&options.login(reviewer)),
+            locale.format_number(wait.requested),
+            locale.format_number(wait.hours.len() as i64),
+            hours(median(&mut wait.hours))
+        ));
+    }
+    html.push_str(&format!(
+        "\n<table>\n<tr><th>Reviewer</th><th>Requested</th><th>Reviewed</th>\
+        <th>Median hours to review</th></tr>\n{}</table>",
+        rows
+    ));
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,205 @@
+/// Pull request size buckets and the report section segmenting by them
+///
+/// Sizes come from `gdfm collect pull-details`, or else from the files
+/// collected by `gdfm collect files`. Every
+/// size-segmented metric should go through [`SizeBuckets`] so "small" means the
+/// same thing across the whole report.
+use std::collections::HashMap;
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::config::{SizeBucket, SizeMeasure};
+use crate::database::Repository;
+use crate::report::{escape, median, parse_timestamp, ReportOptions, Section};
+
+/// Size of a pull request from its changed files
+#[derive(Debug, Default, Clone, Copy)]
+pub struct PullSize {
+    /// Lines added plus lines deleted
+    pub lines: u32,
+    pub files: u32,
+}
+
+/// Named size thresholds, from smallest to largest
+#[derive(Debug, Clone)]
+pub struct SizeBuckets {
+    pub measure: SizeMeasure,
+    buckets: Vec<SizeBucket>,
+}
+
+impl Default for SizeBuckets {
+    fn default() -> Self {
+        let bucket = |name: &str, max: Option<u32>| SizeBucket { name: name.to_string(), max };
+        Self {
+            measure: SizeMeasure::Lines,
+            buckets: vec![
+                bucket("XS", Some(10)),
+                bucket("S", Some(100)),
+                bucket("M", Some(500)),
+                bucket("L", Some(1000)),
+                bucket("XL", None),
+            ],
+        }
+    }
+}
+
+impl SizeBuckets {
+    /// Builds buckets from the config, checking thresholds only ever increase
+    ///
+    /// A bucket for everything larger is added when the last one has a `max`.
+    pub fn new(measure: SizeMeasure, mut buckets: Vec<SizeBucket>) -> Result<Self, String> {
+        if buckets.is_empty() {
+            return Err("at least one size bucket is needed".to_string());
+        }
+        let mut previous = None;
+        for (index, bucket) in buckets.iter().enumerate() {
+            match bucket.max {
+                None if index != buckets.len() - 1 => {
+                    return Err(format!("only the last size bucket can leave out max, not \"{}\"", bucket.name));
+                }
+                Some(max) if previous.is_some_and(|previous| max <= previous) => {
+                    return Err(format!("size bucket \"{}\" must have a larger max than the one before", bucket.name));
+                }
+                _ => previous = bucket.max,
+            }
+        }
+        if let Some(max) = buckets.last().and_then(|bucket| bucket.max) {
+            buckets.push(SizeBucket { name: format!("> {}", max), max: None });
+        }
+
+        Ok(Self { measure, buckets })
+    }
+
+    /// Bucket names from smallest to largest
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.buckets.iter().map(|bucket| bucket.name.as_str())
+    }
+
+    /// Name of the bucket a pull request of the given size falls in
+    pub fn bucket(&self, size: PullSize) -> &str {
+        let value = match self.measure {
+            SizeMeasure::Lines => size.lines,
+            SizeMeasure::Files => size.files,
+        };
+        self.buckets
+            .iter()
+            .find(|bucket| bucket.max.is_none_or(|max| value <= max))
+            .map(|bucket| bucket.name.as_str())
+            .expect("the last size bucket has no max")
+    }
+}
+
+/// Sizes of every pull request in a repository with collected details or files, keyed by pull request id
+///
+/// Sizes from `gdfm collect pull-details` win; those added up from changed files
+/// fill in for pull requests without them.
+pub async fn pull_sizes(pool: &Pool<Sqlite>, repo_id: u32) -> Result<HashMap<i64, PullSize>, sqlx::Error> {
+    let sizes: Vec<(i64, u32, u32)> = sqlx::query_as(
+        "SELECT id, additions + deletions, changed_files
+        FROM pulls
+        WHERE repo_id = $1 AND additions IS NOT NULL AND changed_files IS NOT NULL
+        UNION ALL
+        SELECT f.issue_pull_id, SUM(f.additions + f.deletions), COUNT(*)
+        FROM issue_pull_files f
+        JOIN pulls p ON p.id = f.issue_pull_id
+        WHERE p.repo_id = $1 AND (p.additions IS NULL OR p.changed_files IS NULL)
+        GROUP BY f.issue_pull_id",
+    )
+    .bind(repo_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(sizes
+        .into_iter()
+        .map(|(id, lines, files)| (id, PullSize { lines, files }))
+        .collect())
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct MergedPull {
+    id: i64,
+    created_at: String,
+    merged_at: String,
+    first_review_at: Option<String>,
+}
+
+/// Hours between two stored timestamps, if both are set
+fn hours_between(start: &str, end: &str) -> Option<f64> {
+    Some((parse_timestamp(end)? - parse_timestamp(start)?).num_minutes() as f64 / 60.0)
+}
+
+pub async fn size_breakdown(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let sizes = pull_sizes(pool, repo.id).await?;
+    let merged: Vec<MergedPull> = sqlx::query_as(
+        "SELECT p.id, p.created_at, p.merged_at,
+            (SELECT MIN(r.submitted_at) FROM issue_pull_reviews r
+             WHERE r.issue_pull_id = p.id AND r.reviewer != p.author AND r.submitted_at IS NOT NULL) AS first_review_at
+        FROM pulls p
+        WHERE p.repo_id = $1 AND p.is_issue = 0 AND p.merged_at IS NOT NULL AND p.created_at IS NOT NULL",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Pull request size".to_string();
+
+    if sizes.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No pull request sizes. Run <code>gdfm collect pull-details</code> or \
+                <code>gdfm collect files</code> first.</p>".to_string(),
+        });
+    }
+
+    let buckets = &options.size_buckets;
+    // Hours to first review and to merge per bucket
+    let mut by_bucket: HashMap<&str, (Vec<f64>, Vec<f64>)> = HashMap::new();
+    for pull in &merged {
+        let Some(size) = sizes.get(&pull.id) else {
+            continue;
+        };
+        let (to_review, to_merge) = by_bucket.entry(buckets.bucket(*size)).or_default();
+        if let Some(hours) = pull.first_review_at.as_deref().and_then(|at| hours_between(&pull.created_at, at)) {
+            to_review.push(hours);
+        }
+        if let Some(hours) = hours_between(&pull.created_at, &pull.merged_at) {
+            to_merge.push(hours);
+        }
+    }
+
+    let locale = &options.locale;
+    let format_median = |values: &mut Vec<f64>| match median(values) {
+        Some(hours) => locale.format_decimal(hours, 1),
+        None => "n/a".to_string(),
+    };
+
+    let mut rows = String::new();
+    for name in buckets.names() {
+        let (mut to_review, mut to_merge) = by_bucket.remove(name).unwrap_or_default();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(name),
+            locale.format_number(to_merge.len() as i64),
+            format_median(&mut to_review),
+            format_median(&mut to_merge)
+        ));
+    }
+
+    let measure = match buckets.measure {
+        SizeMeasure::Lines => "lines changed",
+        SizeMeasure::Files => "files changed",
+    };
+    let html = format!(
+        "<p>Merged pull requests bucketed by {}. Times are medians in hours from opening.</p>\n\
+        <table>\n<tr><th>Size</th><th>Merged</th><th>Hours to first review</th><th>Hours to merge</th></tr>\n\
+        {}</table>",
+        measure, rows
+    );
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,271 @@
+/// First response targets (SLOs) and the report section checking them
+///
+/// Every pull request and issue opened by a non-maintainer is expected to get a
+/// maintainer review, comment or event within a target time. Labels can have
+/// their own targets; items without such a label fall under the default one.
+use std::collections::HashMap;
+
+use chrono::{Duration, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::config::SloConfig;
+use crate::database::Repository;
+use crate::report::{escape, is_maintainer, median, parse_timestamp, ReportOptions, Section};
+
+/// First response target for items without a label of their own
+pub const DEFAULT_RESPONSE_TARGET: &str = "3d";
+
+/// Only items opened in this many days are checked
+const SLO_DAYS: i64 = 90;
+
+/// A response target for the items with a label, or for everything else
+#[derive(Debug, Clone)]
+pub struct SloPolicy {
+    /// `None` for the default policy
+    pub label: Option<String>,
+    /// The target as written in the config, e.g. `24h`
+    pub target_text: String,
+    pub target: Duration,
+}
+
+/// The default policy and one policy per label, sorted by label
+#[derive(Debug, Clone)]
+pub struct SloPolicies {
+    pub default: SloPolicy,
+    pub labels: Vec<SloPolicy>,
+}
+
+impl Default for SloPolicies {
+    fn default() -> Self {
+        Self::new(&SloConfig::default()).expect("default response target should be valid")
+    }
+}
+
+impl SloPolicies {
+    /// Builds the policies from the config, checking every target parses
+    pub fn new(config: &SloConfig) -> Result<Self, String> {
+        let policy = |label: Option<&str>, text: &str| {
+            parse_target(text)
+                .map(|target| SloPolicy {
+                    label: label.map(str::to_string),
+                    target_text: text.to_string(),
+                    target,
+                })
+                .ok_or_else(|| {
+                    format!("\"{}\" is not a target such as 90m, 24h, 14d or 2w", text)
+                })
+        };
+
+        let default = policy(None, config.response.as_deref().unwrap_or(DEFAULT_RESPONSE_TARGET))?;
+        let mut labels = config
+            .labels
+            .iter()
+            .map(|(label, text)| policy(Some(label), text))
+            .collect::<Result<Vec<_>, _>>()?;
+        labels.sort_by(|a, b| a.label.cmp(&b.label));
+
+        Ok(Self { default, labels })
+    }
+
+    /// The policies an item with these labels is held to
+    fn policies_for<'a>(&'a self, item_labels: &[String]) -> Vec<&'a SloPolicy> {
+        let matching: Vec<&SloPolicy> = self
+            .labels
+            .iter()
+            .filter(|policy| {
+                item_labels
+                    .iter()
+                    .any(|label| policy.label.as_deref().is_some_and(|name| name.eq_ignore_ascii_case(label)))
+            })
+            .collect();
+
+        if matching.is_empty() {
+            vec![&self.default]
+        } else {
+            matching
+        }
+    }
+}
+
+/// Parses a target such as `90m`, `24h`, `14d` or `2w`
+fn parse_target(text: &str) -> Option<Duration> {
+    let text = text.trim();
+    let unit = text.chars().last()?;
+    let amount: i64 = text[..text.len() - unit.len_utf8()].trim().parse().ok()?;
+    if amount <= 0 {
+        return None;
+    }
+
+    match unit {
+        'm' => Some(Duration::minutes(amount)),
+        'h' => Some(Duration::hours(amount)),
+        'd' => Some(Duration::days(amount)),
+        'w' => Some(Duration::weeks(amount)),
+        _ => None,
+    }
+}
+
+/// How the items under one policy did
+#[derive(Debug, Default)]
+struct Outcome {
+    met: i64,
+    breached: i64,
+    /// No response yet, but still within the target
+    waiting: i64,
+    /// Hours to first response of every item that got one
+    response_hours: Vec<f64>,
+}
+
+pub async fn response_slos(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let since = (Utc::now() - Duration::days(SLO_DAYS)).naive_utc();
+    let reviewer_is_maintainer = is_maintainer("r.author_association", "r.reviewer");
+    let commenter_is_maintainer = is_maintainer("c.author_association", "c.author");
+    let actor_is_maintainer = is_maintainer("e.author_association", "e.actor");
+    let author_is_maintainer = is_maintainer("p.author_association", "p.author");
+    // (id, created_at, first maintainer response)
+    let items: Vec<(i64, String, Option<String>)> = sqlx::query_as(&format!("
+        SELECT p.id, p.created_at, f.first_response
+        FROM pulls p
+        LEFT JOIN (
+            SELECT issue_pull_id, MIN(at) AS first_response FROM (
+                SELECT r.issue_pull_id, r.submitted_at AS at FROM issue_pull_reviews r
+                JOIN pulls p ON p.id = r.issue_pull_id
+                WHERE p.repo_id = $1 AND r.reviewer != p.author
+                    AND {reviewer_is_maintainer}
+                UNION ALL
+                SELECT c.issue_pull_id, c.created_at FROM issue_pull_comments c
+                JOIN pulls p ON p.id = c.issue_pull_id
+                WHERE p.repo_id = $1 AND c.author != p.author
+                    AND {commenter_is_maintainer}
+                UNION ALL
+                SELECT e.issue_pull_id, e.created_at FROM issue_pull_events e
+                JOIN pulls p ON p.id = e.issue_pull_id
+                WHERE p.repo_id = $1 AND e.actor != p.author
+                    AND {actor_is_maintainer}
+            )
+            WHERE at IS NOT NULL
+            GROUP BY issue_pull_id
+        ) f ON f.issue_pull_id = p.id
+        WHERE p.repo_id = $1 AND p.created_at >= $2
+            AND NOT {author_is_maintainer}
+    "))
+    .bind(repo.id)
+    .bind(since)
+    .fetch_all(pool)
+    .await?;
+
+    let item_labels: Vec<(i64, String)> = sqlx::query_as(
+        "SELECT il.issue_pull_id, l.name
+        FROM issue_pull_labels il
+        JOIN labels l ON l.id = il.label_id
+        JOIN pulls p ON p.id = il.issue_pull_id
+        WHERE p.repo_id = $1",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "First response targets".to_string();
+
+    if items.is_empty() {
+        return Ok(Section {
+            title,
+            html: format!(
+                "<p>No pull requests or issues from outside contributors in the last {} days. Run \
+                <code>gdfm collect pulls</code> and <code>gdfm collect issues</code> first.</p>",
+                SLO_DAYS
+            ),
+        });
+    }
+
+    let mut labels_by_item: HashMap<i64, Vec<String>> = HashMap::new();
+    for (id, name) in item_labels {
+        labels_by_item.entry(id).or_default().push(name);
+    }
+
+    let policies = &options.slo_policies;
+    let now = Utc::now().naive_utc();
+    let mut outcomes: HashMap<Option<&str>, Outcome> = HashMap::new();
+    for (id, created_at, first_response) in &items {
+        let Some(created) = parse_timestamp(created_at) else {
+            continue;
+        };
+        let response = first_response.as_deref().and_then(parse_timestamp);
+        let labels = labels_by_item.get(id).map(Vec::as_slice).unwrap_or_default();
+
+        for policy in policies.policies_for(labels) {
+            let outcome = outcomes.entry(policy.label.as_deref()).or_default();
+            match response {
+                Some(response) => {
+                    let waited = response - created;
+                    outcome.response_hours.push(waited.num_minutes() as f64 / 60.0);
+                    if waited <= policy.target {
+                        outcome.met += 1;
+                    } else {
+                        outcome.breached += 1;
+                    }
+                }
+                None if now - created > policy.target => outcome.breached += 1,
+                None => outcome.waiting += 1,
+            }
+        }
+    }
+
+    let locale = &options.locale;
+    let mut rows = String::new();
+    for policy in policies.labels.iter().chain(std::iter::once(&policies.default)) {
+        let name = match &policy.label {
+            Some(label) => escape(label),
+            None if policies.labels.is_empty() => "All".to_string(),
+            None => "Everything else".to_string(),
+        };
+        let Some(outcome) = outcomes.get_mut(&policy.label.as_deref()) else {
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">0</td><td></td><td></td><td></td>\
+                <td></td><td></td></tr>\n",
+                name,
+                escape(&policy.target_text)
+            ));
+            continue;
+        };
+        let decided = outcome.met + outcome.breached;
+        let met_share = if decided == 0 {
+            String::new()
+        } else {
+            format!("{}%", locale.format_decimal(outcome.met as f64 * 100.0 / decided as f64, 1))
+        };
+        let median_hours = median(&mut outcome.response_hours)
+            .map(|hours| locale.format_decimal(hours, 1))
+            .unwrap_or_default();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}</td></tr>\n",
+            name,
+            escape(&policy.target_text),
+            locale.format_number(decided + outcome.waiting),
+            locale.format_number(outcome.met),
+            locale.format_number(outcome.breached),
+            locale.format_number(outcome.waiting),
+            met_share,
+            median_hours
+        ));
+    }
+
+    let html = format!(
+        "<p>Pull requests and issues opened by non-maintainers in the last {} days, checked \
+        against the time to a first maintainer review, comment or event. Items without a \
+        response count as breached once they are older than the target; an item with several \
+        labels that have targets counts under each of them.</p>\n\
+        <table>\n<tr><th>Label</th><th>Target</th><th>Items</th><th>Met</th><th>Breached</th>\
+        <th>Waiting</th><th>Met on time</th><th>Median hours to response</th></tr>\n{}</table>",
+        SLO_DAYS, rows
+    );
+
+    Ok(Section { title, html })
+}
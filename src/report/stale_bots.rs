@@ -0,0 +1,204 @@
+/// Report section auditing stale bots: who closes issues and pull requests,
+/// and how often what a bot closed is reopened
+///
+/// A close counts as a stale bot's when a bot account closes something it
+/// labeled earlier, which is what stale bots do (label as stale, then close
+/// when nothing happens), or when the account's name says it is a stale bot.
+/// Reopens after a close are the clearest sign it shouldn't have happened.
+use std::collections::{BTreeMap, HashSet};
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, is_bot, ReportOptions, Section};
+
+#[derive(Debug, sqlx::FromRow)]
+struct Event {
+    issue_pull_id: i64,
+    is_issue: bool,
+    event_type: String,
+    actor: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Closer {
+    Person,
+    StaleBot,
+    OtherBot,
+}
+
+impl Closer {
+    const ALL: [Closer; 3] = [Closer::Person, Closer::StaleBot, Closer::OtherBot];
+
+    fn label(self) -> &'static str {
+        match self {
+            Closer::Person => "People",
+            Closer::StaleBot => "Stale bots",
+            Closer::OtherBot => "Other bots",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Close {
+    is_issue: bool,
+    closer: Closer,
+    actor: String,
+    reopened: bool,
+}
+
+pub async fn stale_bot_audit(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    // Closing events of merges are left out, as the merge is what closed those
+    let events: Vec<Event> = sqlx::query_as(
+        "SELECT e.issue_pull_id, p.is_issue, lower(e.event_type) AS event_type, e.actor
+        FROM issue_pull_events e
+        JOIN pulls p ON p.id = e.issue_pull_id
+        WHERE p.repo_id = $1 AND e.created_at IS NOT NULL AND lower(e.event_type) IN ('labeled', 'closed', 'reopened')
+            AND NOT (lower(e.event_type) = 'closed' AND p.merged_at IS NOT NULL
+                AND e.created_at >= p.merged_at)
+        ORDER BY e.issue_pull_id, e.created_at, e.id",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Stale bot audit".to_string();
+
+    let mut closes: Vec<Close> = Vec::new();
+    let mut labelers: HashSet<&str> = HashSet::new();
+    // Index into `closes` of the item's last close, until something reopens it
+    let mut open_close: Option<usize> = None;
+    let mut current = None;
+    for event in &events {
+        if current != Some(event.issue_pull_id) {
+            current = Some(event.issue_pull_id);
+            labelers.clear();
+            open_close = None;
+        }
+        match event.event_type.as_str() {
+            "labeled" => {
+                labelers.insert(&event.actor);
+            }
+            "closed" => {
+                let closer = if !is_bot(&event.actor) {
+                    Closer::Person
+                } else if labelers.contains(event.actor.as_str()) || event.actor.to_lowercase().contains("stale") {
+                    Closer::StaleBot
+                } else {
+                    Closer::OtherBot
+                };
+                closes.push(Close {
+                    is_issue: event.is_issue,
+                    closer,
+                    actor: event.actor.clone(),
+                    reopened: false,
+                });
+                open_close = Some(closes.len() - 1);
+            }
+            _ => {
+                if let Some(index) = open_close.take() {
+                    closes[index].reopened = true;
+                }
+            }
+        }
+    }
+
+    if closes.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No closed issues or pull requests with events stored. Run <code>gdfm collect issues</code> \
+                and <code>gdfm collect events</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    let locale = &options.locale;
+    let percent = |part: usize, whole: usize| {
+        if whole == 0 {
+            "n/a".to_string()
+        } else {
+            format!("{}%", locale.format_decimal(part as f64 / whole as f64 * 100.0, 1))
+        }
+    };
+
+    let mut rows = String::new();
+    for closer in Closer::ALL {
+        let by: Vec<&Close> = closes.iter().filter(|close| close.closer == closer).collect();
+        let issues = by.iter().filter(|close| close.is_issue).count();
+        let reopened = by.iter().filter(|close| close.reopened).count();
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            closer.label(),
+            locale.format_number(issues as i64),
+            locale.format_number((by.len() - issues) as i64),
+            percent(by.len(), closes.len()),
+            locale.format_number(reopened as i64),
+            percent(reopened, by.len())
+        ));
+    }
+
+    // (closer, closes, reopened afterwards) of every bot account that closed something
+    let mut bots: BTreeMap<&str, (Closer, i64, i64)> = BTreeMap::new();
+    for close in closes.iter().filter(|close| close.closer != Closer::Person) {
+        let entry = bots.entry(&close.actor).or_insert((close.closer, 0, 0));
+        // An account that closed anything as a stale bot is listed as one
+        entry.0 = entry.0.min(close.closer);
+        entry.1 += 1;
+        entry.2 += close.reopened as i64;
+    }
+    let mut bots: Vec<(&str, (Closer, i64, i64))> = bots.into_iter().collect();
+    bots.sort_by(|(a_login, a), (b_login, b)| b.1.cmp(&a.1).then(a_login.cmp(b_login)));
+    let mut bot_rows = String::new();
+    for (login, (closer, closed, reopened)) in &bots {
+        bot_rows.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            escape(&options.login(login)),
+            if *closer == Closer::StaleBot { "Stale bot" } else { "Other bot" },
+            locale.format_number(*closed),
+            locale.format_number(*reopened)
+        ));
+    }
+
+    let stale: Vec<&Close> = closes.iter().filter(|close| close.closer == Closer::StaleBot).collect();
+    let by_people: Vec<&Close> = closes.iter().filter(|close| close.closer == Closer::Person).collect();
+    let summary = if stale.is_empty() {
+        format!(
+            "<p>No stale bot activity found among the {} closes of issues and pull requests stored: no bot \
+            closed anything it had labeled before.</p>",
+            locale.format_number(closes.len() as i64)
+        )
+    } else {
+        format!(
+            "<p>Stale bots made {} of the {} closes of issues and pull requests stored ({}). {} of what they \
+            closed was reopened afterwards, against {} of what people closed.</p>",
+            locale.format_number(stale.len() as i64),
+            locale.format_number(closes.len() as i64),
+            percent(stale.len(), closes.len()),
+            percent(stale.iter().filter(|close| close.reopened).count(), stale.len()),
+            percent(by_people.iter().filter(|close| close.reopened).count(), by_people.len())
+        )
+    };
+
+    let mut html = format!(
+        "{}\n<p>A bot counts as a stale bot when it closes something it labeled earlier, or when its name \
+        says so. Pull requests closed by being merged aren't counted.</p>\n\
+        <table>\n<tr><th>Closed by</th><th>Issues</th><th>Pull requests</th><th>Share of closes</th>\
+        <th>Reopened afterwards</th><th>Reopen rate</th></tr>\n{}</table>",
+        summary, rows
+    );
+    if !bots.is_empty() {
+        html.push_str(&format!(
+            "\n<h3>Bot accounts</h3>\n<table>\n<tr><th>Account</th><th>Kind</th><th>Closes</th>\
+            <th>Reopened afterwards</th></tr>\n{}</table>",
+            bot_rows
+        ));
+    }
+
+    Ok(Section { title, html })
+}
@@ -0,0 +1,196 @@
+/// Report section comparing label taxonomies across tracked repositories
+///
+/// Labels are grouped by name ignoring case and punctuation, so `Type: Bug` and
+/// `type-bug` count as one label spelled two ways. The section lists the labels
+/// most repositories share, the labels most often applied together and the
+/// labels of this repository nothing carries, to help an organization settle on
+/// one set of triage labels.
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+/// Number of labels listed, shared by most repositories first
+const TOP_LABELS: usize = 30;
+
+/// Number of label pairs listed, most often applied together first
+const TOP_PAIRS: usize = 15;
+
+#[derive(Debug, sqlx::FromRow)]
+struct DefinedLabel {
+    repo_id: u32,
+    name: String,
+    uses: i64,
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct AppliedLabel {
+    issue_pull_id: i64,
+    name: String,
+}
+
+#[derive(Debug, Default)]
+struct LabelUsage {
+    repositories: BTreeSet<u32>,
+    uses: i64,
+    /// Uses in the reported repository, `None` when it has no such label
+    here: Option<i64>,
+    spellings: BTreeSet<String>,
+}
+
+/// Key labels are grouped by: lower case letters and digits only
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+pub async fn label_taxonomy(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let defined: Vec<DefinedLabel> = sqlx::query_as(
+        "SELECT l.repo_id, l.name,
+            (SELECT COUNT(*) FROM issue_pull_labels pl WHERE pl.label_id = l.id) AS uses
+        FROM labels l
+        JOIN repositories r ON r.id = l.repo_id
+        WHERE r.archived = 0 OR r.id = $1",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Label taxonomy".to_string();
+
+    if !defined.iter().any(|label| label.repo_id == repo.id) {
+        return Ok(Section {
+            title,
+            html: "<p>No labels. Run <code>gdfm collect labels</code> first.</p>".to_string(),
+        });
+    }
+
+    let applied: Vec<AppliedLabel> = sqlx::query_as(
+        "SELECT pl.issue_pull_id, l.name
+        FROM issue_pull_labels pl
+        JOIN labels l ON l.id = pl.label_id
+        JOIN repositories r ON r.id = l.repo_id
+        WHERE r.archived = 0 OR r.id = $1",
+    )
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut labels: HashMap<String, LabelUsage> = HashMap::new();
+    for label in &defined {
+        let usage = labels.entry(normalize(&label.name)).or_default();
+        usage.repositories.insert(label.repo_id);
+        usage.uses += label.uses;
+        usage.spellings.insert(label.name.clone());
+        if label.repo_id == repo.id {
+            *usage.here.get_or_insert(0) += label.uses;
+        }
+    }
+    let repositories: BTreeSet<u32> = defined.iter().map(|label| label.repo_id).collect();
+
+    let mut labels: Vec<(String, LabelUsage)> = labels.into_iter().collect();
+    labels.sort_by(|(a_name, a), (b_name, b)| {
+        b.repositories
+            .len()
+            .cmp(&a.repositories.len())
+            .then(b.uses.cmp(&a.uses))
+            .then(a_name.cmp(b_name))
+    });
+
+    // Pairs of grouped labels found on the same pull request or issue
+    let mut on_item: BTreeMap<i64, BTreeSet<String>> = BTreeMap::new();
+    for label in &applied {
+        on_item.entry(label.issue_pull_id).or_default().insert(normalize(&label.name));
+    }
+    let mut pairs: HashMap<(&str, &str), i64> = HashMap::new();
+    for names in on_item.values() {
+        for (i, first) in names.iter().enumerate() {
+            for second in names.iter().skip(i + 1) {
+                *pairs.entry((first, second)).or_default() += 1;
+            }
+        }
+    }
+    let mut pairs: Vec<((&str, &str), i64)> = pairs.into_iter().collect();
+    pairs.sort_by(|(a_pair, a), (b_pair, b)| b.cmp(a).then(a_pair.cmp(b_pair)));
+
+    let spelling = |key: &str| -> String {
+        labels
+            .iter()
+            .find(|(name, _)| name == key)
+            .and_then(|(_, usage)| usage.spellings.first().cloned())
+            .unwrap_or_else(|| key.to_string())
+    };
+
+    let mut unused: Vec<&str> = defined
+        .iter()
+        .filter(|label| label.repo_id == repo.id && label.uses == 0)
+        .map(|label| label.name.as_str())
+        .collect();
+    unused.sort_unstable();
+    let unused_everywhere = labels.iter().filter(|(_, usage)| usage.uses == 0).count();
+
+    let locale = &options.locale;
+
+    let mut label_rows = String::new();
+    for (_, usage) in labels.iter().take(TOP_LABELS) {
+        let spellings: Vec<String> = usage.spellings.iter().map(|name| escape(name)).collect();
+        label_rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            spellings.join(", "),
+            locale.format_number(usage.repositories.len() as i64),
+            locale.format_number(usage.uses),
+            usage.here.map(|uses| locale.format_number(uses)).unwrap_or_else(|| "&ndash;".to_string())
+        ));
+    }
+
+    let mut html = format!(
+        "<p>{} tracked repositories define {} labels, {} after ignoring case and punctuation. \
+        The {} shared by most repositories are listed with every spelling in use; \
+        a dash means this repository has no such label.</p>\n\
+        <table>\n<tr><th>Label</th><th>Repositories</th><th>Uses</th><th>Uses here</th></tr>\n{}</table>\n",
+        locale.format_number(repositories.len() as i64),
+        locale.format_number(defined.len() as i64),
+        locale.format_number(labels.len() as i64),
+        locale.format_number(labels.len().min(TOP_LABELS) as i64),
+        label_rows
+    );
+
+    if !pairs.is_empty() {
+        let mut pair_rows = String::new();
+        for ((first, second), count) in pairs.iter().take(TOP_PAIRS) {
+            pair_rows.push_str(&format!(
+                "<tr><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(&spelling(first)),
+                escape(&spelling(second)),
+                locale.format_number(*count)
+            ));
+        }
+        html.push_str(&format!(
+            "<p>Labels most often applied to the same pull request or issue. Pairs that always \
+            come together may be one label too many.</p>\n\
+            <table>\n<tr><th>Label</th><th>Label</th><th>Applied together</th></tr>\n{}</table>\n",
+            pair_rows
+        ));
+    }
+
+    if unused.is_empty() {
+        html.push_str("<p>Every label of this repository is in use.</p>");
+    } else {
+        let names: Vec<String> = unused.iter().map(|name| format!("<code>{}</code>", escape(name))).collect();
+        html.push_str(&format!(
+            "<p>{} labels of this repository are on no collected pull request or issue: {}. \
+            Across tracked repositories, {} grouped labels are never used.</p>",
+            locale.format_number(unused.len() as i64),
+            names.join(", "),
+            locale.format_number(unused_everywhere as i64)
+        ));
+    }
+
+    Ok(Section { title, html })
+}
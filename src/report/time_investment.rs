@@ -0,0 +1,190 @@
+/// Report section estimating the hours maintainers put in each month
+///
+/// Maintainer reviews, inline review comments and triage events are counted
+/// per month along with merges, and each is multiplied by the minutes it is
+/// assumed to take. The result is rough, as none of it says how long anyone
+/// actually spent, but it gives funding conversations a number to start from.
+use std::collections::BTreeMap;
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{is_maintainer, ReportOptions, Section};
+
+/// Event types that count as triage: sorting, assigning, closing and reopening
+///
+/// Written lower-case without underscores, as event types are compared that way
+/// so both the REST and GraphQL spellings match.
+pub const TRIAGE_EVENTS: &[&str] = &[
+    "assigned",
+    "closed",
+    "demilestoned",
+    "labeled",
+    "locked",
+    "markedasduplicate",
+    "milestoned",
+    "pinned",
+    "reopened",
+    "transferred",
+    "unassigned",
+    "unlabeled",
+    "unlocked",
+    "unpinned",
+];
+
+/// Months shown, most recent first
+const MONTHS: usize = 12;
+
+/// Minutes a maintainer is assumed to spend on each kind of action
+#[derive(Debug, Clone, Copy)]
+pub struct TimeWeights {
+    pub review: f64,
+    pub review_comment: f64,
+    pub triage: f64,
+    pub merge: f64,
+}
+
+impl Default for TimeWeights {
+    fn default() -> Self {
+        Self { review: 20.0, review_comment: 5.0, triage: 2.0, merge: 10.0 }
+    }
+}
+
+impl TimeWeights {
+    /// Rejects weights that would make the estimate meaningless
+    pub fn validate(self) -> Result<Self, String> {
+        let weights = [
+            ("review", self.review),
+            ("review_comment", self.review_comment),
+            ("triage", self.triage),
+            ("merge", self.merge),
+        ];
+        for (name, minutes) in weights {
+            if !minutes.is_finite() || minutes < 0.0 {
+                return Err(format!("{} must be zero or more minutes, not {}", name, minutes));
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+/// Maintainer actions in one month
+#[derive(Debug, Default)]
+struct Month {
+    reviews: i64,
+    review_comments: i64,
+    triage: i64,
+    merges: i64,
+}
+
+impl Month {
+    fn hours(&self, weights: &TimeWeights) -> f64 {
+        (self.reviews as f64 * weights.review
+            + self.review_comments as f64 * weights.review_comment
+            + self.triage as f64 * weights.triage
+            + self.merges as f64 * weights.merge)
+            / 60.0
+    }
+}
+
+pub async fn time_investment(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let triage_events = TRIAGE_EVENTS
+        .iter()
+        .map(|event| format!("'{}'", event))
+        .collect::<Vec<_>>()
+        .join(", ");
+    // (month, action, count); a merge also closes the pull request, which isn't counted again
+    let counts: Vec<(String, String, i64)> = sqlx::query_as(&format!(
+        "SELECT substr(at, 1, 7) AS month, action, COUNT(*) FROM (
+            SELECT r.submitted_at AS at, 'review' AS action FROM issue_pull_reviews r
+            JOIN pulls p ON p.id = r.issue_pull_id
+            WHERE p.repo_id = $1 AND {}
+            UNION ALL
+            SELECT c.created_at, 'review_comment' FROM issue_pull_review_comments c
+            JOIN pulls p ON p.id = c.issue_pull_id
+            WHERE p.repo_id = $1 AND {}
+            UNION ALL
+            SELECT e.created_at, 'triage' FROM issue_pull_events e
+            JOIN pulls p ON p.id = e.issue_pull_id
+            WHERE p.repo_id = $1 AND lower(replace(e.event_type, '_', '')) IN ({}) AND {}
+                AND NOT (lower(e.event_type) = 'closed' AND p.merged_at IS NOT NULL)
+            UNION ALL
+            SELECT merged_at, 'merge' FROM pulls WHERE repo_id = $1 AND merged_at IS NOT NULL
+        )
+        WHERE at IS NOT NULL
+        GROUP BY month, action",
+        is_maintainer("r.author_association", "r.reviewer"),
+        is_maintainer("c.author_association", "c.author"),
+        triage_events,
+        is_maintainer("e.author_association", "e.actor")
+    ))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let title = "Maintainer time investment".to_string();
+
+    let mut months: BTreeMap<String, Month> = BTreeMap::new();
+    for (month, action, count) in counts {
+        let entry = months.entry(month).or_default();
+        match action.as_str() {
+            "review" => entry.reviews = count,
+            "review_comment" => entry.review_comments = count,
+            "triage" => entry.triage = count,
+            _ => entry.merges = count,
+        }
+    }
+    if months.is_empty() {
+        return Ok(Section {
+            title,
+            html: "<p>No maintainer reviews, triage or merges stored. Run <code>gdfm collect pulls</code>, \
+                <code>gdfm collect reviews</code> and <code>gdfm collect events</code> first.</p>"
+                .to_string(),
+        });
+    }
+
+    let locale = &options.locale;
+    let weights = &options.time_weights;
+    let recent: Vec<(&String, &Month)> = months.iter().rev().take(MONTHS).collect();
+    let total: f64 = recent.iter().map(|(_, month)| month.hours(weights)).sum();
+
+    let mut rows = String::new();
+    for (name, month) in &recent {
+        rows.push_str(&format!(
+            "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+            <td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            name,
+            locale.format_number(month.reviews),
+            locale.format_number(month.review_comments),
+            locale.format_number(month.triage),
+            locale.format_number(month.merges),
+            locale.format_decimal(month.hours(weights), 1)
+        ));
+    }
+
+    let minutes = |value: f64| locale.format_decimal(value, if value.fract() == 0.0 { 0 } else { 1 });
+    let html = format!(
+        "<p>Roughly {} maintainer hours over the last {} months with activity, {} a month on average. \
+        This assumes {} minutes per review, {} per inline review comment, {} per triage event (labeling, \
+        assigning, closing and the like) and {} per merge; set <code>[report.time_weights]</code> in the \
+        config to match how your project works.</p>\n\
+        <table>\n<tr><th>Month</th><th>Reviews</th><th>Review comments</th><th>Triage events</th>\
+        <th>Merges</th><th>Estimated hours</th></tr>\n{}</table>",
+        locale.format_decimal(total, 0),
+        locale.format_number(recent.len() as i64),
+        locale.format_decimal(total / recent.len() as f64, 1),
+        minutes(weights.review),
+        minutes(weights.review_comment),
+        minutes(weights.triage),
+        minutes(weights.merge),
+        rows
+    );
+
+    Ok(Section { title, html })
+}
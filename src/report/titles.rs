@@ -0,0 +1,107 @@
+/// Report section on pull request title conventions
+///
+/// Teams that generate changelogs from pull request titles need those titles
+/// to follow a convention. By default we check for conventional commits
+/// (`feat(scope)!: message`); `report.title_pattern` in the config overrides it.
+use std::collections::BTreeMap;
+
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, ReportOptions, Section};
+
+pub const CONVENTIONAL_COMMIT_PATTERN: &str = r"^(?P<type>[a-zA-Z]+)(?:\([^)]*\))?!?: \S";
+
+/// Number of non-compliant titles listed as examples
+const EXAMPLES: usize = 10;
+
+#[derive(Debug, sqlx::FromRow)]
+struct PullTitle {
+    number: u32,
+    title: String,
+    merged_at: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct TypeCounts {
+    total: i64,
+    merged: i64,
+}
+
+pub async fn title_compliance(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let pulls: Vec<PullTitle> = sqlx::query_as("
+        SELECT number, title, merged_at FROM pulls WHERE repo_id = $1 AND is_issue = 0 ORDER BY number DESC
+    ")
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let pattern = &options.title_pattern;
+    let mut by_type: BTreeMap<String, TypeCounts> = BTreeMap::new();
+    let mut non_compliant: Vec<&PullTitle> = vec![];
+
+    for pull in &pulls {
+        match pattern.captures(&pull.title) {
+            Some(captures) => {
+                let pull_type = captures
+                    .name("type")
+                    .map(|m| m.as_str().to_lowercase())
+                    .unwrap_or_else(|| "(matched)".to_string());
+                let counts = by_type.entry(pull_type).or_default();
+                counts.total += 1;
+                if pull.merged_at.is_some() {
+                    counts.merged += 1;
+                }
+            }
+            None => non_compliant.push(pull),
+        }
+    }
+
+    let locale = &options.locale;
+    let html = if pulls.is_empty() {
+        "<p>No pull requests collected.</p>".to_string()
+    } else {
+        let compliant = pulls.len() - non_compliant.len();
+        let rate = compliant as f64 / pulls.len() as f64 * 100.0;
+
+        let mut rows = String::new();
+        let mut types: Vec<(&String, &TypeCounts)> = by_type.iter().collect();
+        types.sort_by_key(|(_, counts)| std::cmp::Reverse(counts.total));
+        for (pull_type, counts) in types {
+            rows.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(pull_type),
+                locale.format_number(counts.total),
+                locale.format_number(counts.merged)
+            ));
+        }
+
+        let examples: Vec<String> = non_compliant
+            .iter()
+            .take(EXAMPLES)
+            .map(|pull| format!("<li>#{} {}</li>", pull.number, escape(&pull.title)))
+            .collect();
+
+        format!(
+            "<p>{} of {} pull request titles ({}%) match <code>{}</code>.</p>\n\
+            <table>\n<tr><th>Type</th><th>Pull requests</th><th>Merged</th></tr>\n{}</table>\n\
+            <h3>Recent titles that don't match</h3>\n<ul>\n{}\n</ul>",
+            locale.format_number(compliant as i64),
+            locale.format_number(pulls.len() as i64),
+            locale.format_decimal(rate, 1),
+            escape(pattern.as_str()),
+            rows,
+            examples.join("\n")
+        )
+    };
+
+    Ok(Section {
+        title: "Pull request title conventions".to_string(),
+        html,
+    })
+}
@@ -0,0 +1,115 @@
+/// Report section on pull requests closed without any maintainer response
+///
+/// A pull request that is closed (by a stale bot or a person) without a single
+/// maintainer comment or review is the outcome most damaging to community
+/// trust, so these are counted per month and the most recent are listed.
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::Repository;
+use crate::report::{escape, is_bot, is_maintainer, ReportOptions, Section};
+
+/// Number of recent pull requests listed in the section
+const RECENT: usize = 20;
+
+#[derive(Debug, sqlx::FromRow)]
+struct UnansweredPull {
+    number: u32,
+    title: String,
+    author: String,
+    closed_at: String,
+    closed_by: Option<String>,
+}
+
+pub async fn closed_without_response(
+    pool: &Pool<Sqlite>,
+    repo: &Repository,
+    options: &ReportOptions,
+) -> Result<Section, sqlx::Error> {
+    let reviewer_is_maintainer = is_maintainer("r.author_association", "r.reviewer");
+    let commenter_is_maintainer = is_maintainer("c.author_association", "c.author");
+    let pulls: Vec<UnansweredPull> = sqlx::query_as(&format!("
+        SELECT p.number, p.title, p.author, p.closed_at, (
+            SELECT e.actor FROM issue_pull_events e
+            WHERE e.issue_pull_id = p.id AND lower(e.event_type) = 'closed'
+            ORDER BY e.created_at DESC LIMIT 1
+        ) AS closed_by
+        FROM pulls p
+        WHERE p.repo_id = $1 AND p.is_issue = 0
+            AND p.closed_at IS NOT NULL AND p.merged_at IS NULL
+            AND NOT EXISTS (
+                SELECT 1 FROM issue_pull_reviews r
+                WHERE r.issue_pull_id = p.id AND r.reviewer != p.author
+                    AND {reviewer_is_maintainer}
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM issue_pull_comments c
+                WHERE c.issue_pull_id = p.id AND c.author != p.author
+                    AND {commenter_is_maintainer}
+            )
+        ORDER BY p.closed_at DESC
+    "))
+    .bind(repo.id)
+    .fetch_all(pool)
+    .await?;
+
+    let locale = &options.locale;
+    let html = if pulls.is_empty() {
+        "<p>Every closed pull request received at least one maintainer comment or review.</p>".to_string()
+    } else {
+        // (month, by bot, by someone else); pulls are already sorted newest first
+        let mut months: Vec<(String, i64, i64)> = vec![];
+        for pull in &pulls {
+            let month = pull.closed_at.get(..7).unwrap_or("").to_string();
+            let by_bot = pull.closed_by.as_deref().is_some_and(is_bot);
+            match months.last_mut() {
+                Some(last) if last.0 == month => {
+                    if by_bot { last.1 += 1 } else { last.2 += 1 }
+                }
+                _ => months.push((month, by_bot as i64, !by_bot as i64)),
+            }
+        }
+
+        let mut month_rows = String::new();
+        for (month, by_bot, by_human) in &months {
+            month_rows.push_str(&format!(
+                "<tr><td>{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+                escape(month),
+                locale.format_number(by_bot + by_human),
+                locale.format_number(*by_bot),
+                locale.format_number(*by_human)
+            ));
+        }
+
+        let mut recent_rows = String::new();
+        for pull in pulls.iter().take(RECENT) {
+            recent_rows.push_str(&format!(
+                "<tr><td><a href=\"https://github.com/{}/{}/pull/{}\">#{}</a></td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                escape(&repo.owner),
+                escape(&repo.name),
+                pull.number,
+                pull.number,
+                escape(&pull.title),
+                escape(&options.login(&pull.author)),
+                escape(&pull.closed_by.clone().unwrap_or_default()),
+                escape(&locale.format_timestamp(&pull.closed_at))
+            ));
+        }
+
+        format!(
+            "<p>{} pull requests were closed without being merged and without a single maintainer \
+            comment or review.</p>\n\
+            <table>\n<tr><th>Month</th><th>Closed without response</th><th>By a bot</th><th>By a person</th></tr>\n{}</table>\n\
+            <h3>Most recent</h3>\n\
+            <table>\n<tr><th>Pull request</th><th>Title</th><th>Author</th><th>Closed by</th><th>Closed at</th></tr>\n{}</table>",
+            locale.format_number(pulls.len() as i64),
+            month_rows,
+            recent_rows
+        )
+    };
+
+    Ok(Section {
+        title: "Closed without a maintainer response".to_string(),
+        html,
+    })
+}
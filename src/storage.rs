@@ -0,0 +1,591 @@
+/// Holds the `Storage` trait which collectors use to persist what they fetch
+///
+/// Collectors only talk to a `dyn Storage`, which keeps their SQL in one
+/// place. The trait takes octocrab's and gdfm's GitHub types as they come
+/// from the API, so it is not a boundary for other backends. The SQLite
+/// implementation simply delegates to the models in `database.rs`.
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+
+use crate::database::{
+    AssociationChange,
+    CollectionCursor,
+    HttpCacheEntry,
+    HostFeature,
+    CollectionRun,
+    setup_db,
+    Commit,
+    Contributor,
+    ContributorWeek,
+    Dependency,
+    Deployment,
+    DeploymentStatus,
+    Discussion,
+    Fork,
+    GovernanceChange,
+    IssueDemand,
+    Label,
+    LinkedIssue,
+    MetricSnapshot,
+    PullRequestChecklist,
+    Milestone,
+    RepositoryMaintainer,
+    PullRequest,
+    PullRequestComment,
+    PullRequestEvent,
+    PullRequestFile,
+    PullRequestAssignee,
+    PullRequestLabel,
+    PullRequestRequestedReviewer,
+    PullRequestMilestone,
+    PullRequestReview,
+    PullRequestReviewComment,
+    RawPayload,
+    Release,
+    Repository,
+    RepositoryEvent,
+    Stargazer,
+    TrafficDay,
+    WikiEdit,
+};
+use crate::github::{
+    ActivityEvent,
+    Discussion as GitHubDiscussion,
+    Contributor as GitHubContributor,
+    ContributorStats,
+    Deployment as GitHubDeployment,
+    DeploymentStatus as GitHubDeploymentStatus,
+    IssueComment,
+    IssueWithReactions,
+    RepositoryCommit,
+    RepositoryIssueEvent,
+    ReviewComment,
+    Stargazer as GitHubStargazer,
+    TrafficDay as GitHubTrafficDay,
+    WikiPage,
+};
+use crate::forge::{Etags, ForgeComment, ForgeEvent, ForgePull, ForgeReview};
+
+#[async_trait]
+pub trait Storage: Send + Sync {
+    /// Looks up a tracked repository from its `owner/name` path
+    async fn get_repository(&self, path: &str) -> Result<Repository, sqlx::Error>;
+
+    async fn create_repository(&self, path: &str) -> Result<Repository, sqlx::Error>;
+
+    /// Latest `updated_at` handled by the last completed run of a collector
+    async fn get_cursor(&self, repo_id: u32, collector: &str) -> Result<Option<DateTime<Utc>>, sqlx::Error>;
+
+    async fn set_cursor(&self, repo_id: u32, collector: &str, updated_at: DateTime<Utc>) -> Result<(), sqlx::Error>;
+
+    /// Records the author associations seen on everything stored for a repository
+    async fn snapshot_associations(&self, repo_id: u32) -> Result<(), sqlx::Error>;
+
+    /// Records headline metrics for every complete week not recorded yet
+    async fn record_weekly_metrics(&self, repo_id: u32) -> Result<(), sqlx::Error>;
+
+    /// ETags of the last responses stored for a repository, keyed by page route
+    async fn get_etags(&self, repo_id: u32) -> Result<Etags, sqlx::Error>;
+
+    /// Remembers an ETag once the response it came with has been stored
+    async fn set_etag(&self, repo_id: u32, url: &str, etag: &str) -> Result<(), sqlx::Error>;
+
+    /// Where the previous run of a collector covering `scope` stopped, if it didn't finish
+    async fn get_unfinished_checkpoint(
+        &self,
+        repo_id: u32,
+        collector: &str,
+        scope: &str,
+        before_run: Option<u32>,
+    ) -> Result<Option<String>, sqlx::Error>;
+
+    async fn set_run_checkpoint(&self, run_id: u32, scope: &str, checkpoint: &str) -> Result<(), sqlx::Error>;
+
+    /// What was last found out about the APIs an API host offers
+    async fn get_host_features(&self, host: &str) -> Result<Vec<HostFeature>, sqlx::Error>;
+
+    async fn set_host_feature(&self, host: &str, feature: &str, available: bool, reason: &str) -> Result<(), sqlx::Error>;
+
+    async fn set_repository_archived(&self, repo_id: u32, archived: bool) -> Result<(), sqlx::Error>;
+
+    /// Records where a repository is hosted, e.g. `gitlab`
+    async fn set_repository_forge(&self, repo_id: u32, forge: &str) -> Result<(), sqlx::Error>;
+
+    /// Stores a page of pull requests at once, updating those stored before
+    async fn create_pull_requests(
+        &self,
+        pulls: &[&octocrab::models::pulls::PullRequest],
+        repo_id: u32,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Issues are stored alongside pull requests with `is_issue` set, under
+    /// the local ids returned in the same order
+    async fn create_issues(
+        &self,
+        issues: &[&octocrab::models::issues::Issue],
+        repo_id: u32,
+    ) -> Result<Vec<i64>, sqlx::Error>;
+
+    /// Returns the given pull requests, or all of them when `numbers` is empty
+    async fn get_pull_requests(&self, repo_id: u32, numbers: &[u32]) -> Result<Vec<PullRequest>, sqlx::Error>;
+
+    async fn get_pull_requests_updated_since(
+        &self,
+        repo_id: u32,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<PullRequest>, sqlx::Error>;
+
+    /// Pull requests and issues whose events were never collected, whether or
+    /// not webhooks stored some of them
+    async fn get_pull_requests_without_events(&self, repo_id: u32) -> Result<Vec<PullRequest>, sqlx::Error>;
+
+    async fn get_pull_requests_without_reviews(&self, repo_id: u32) -> Result<Vec<PullRequest>, sqlx::Error>;
+
+    /// Stores the events of a pull request fetched again and removes the stored
+    /// ones whose ids aren't in `timeline`, in one transaction
+    async fn replace_pull_request_events(
+        &self,
+        issue_pull_id: i64,
+        events: &[&ForgeEvent],
+        timeline: &[i64],
+    ) -> Result<(), sqlx::Error>;
+
+    /// Records that the events of a pull request were collected, changed or not
+    async fn set_events_collected(&self, issue_pull_id: i64) -> Result<(), sqlx::Error>;
+
+    /// Replaces the reviews of a pull request with those fetched again, in one transaction
+    async fn replace_pull_request_reviews(&self, issue_pull_id: i64, reviews: &[ForgeReview]) -> Result<(), sqlx::Error>;
+
+    /// Stores the JSON GitHub sent for pull requests, events or reviews (`kind`),
+    /// given with their GitHub id and the pull request they belong to
+    async fn create_raw_payloads(
+        &self,
+        repo_id: u32,
+        kind: &str,
+        payloads: &[(i64, i64, &serde_json::Value)],
+    ) -> Result<(), sqlx::Error>;
+
+    /// Stores pull requests read from a forge other than GitHub
+    async fn create_forge_pulls(&self, repo_id: u32, pulls: &[ForgePull]) -> Result<(), sqlx::Error>;
+
+    async fn create_forge_comment(&self, issue_pull_id: i64, comment: &ForgeComment) -> Result<(), sqlx::Error>;
+
+    async fn create_pull_request_comment(&self, issue_pull_id: i64, comment: &IssueComment) -> Result<(), sqlx::Error>;
+
+    async fn create_review_comment(&self, issue_pull_id: i64, comment: &ReviewComment) -> Result<(), sqlx::Error>;
+
+    async fn create_commit(&self, repo_id: u32, commit: &RepositoryCommit, on_default_branch: bool) -> Result<(), sqlx::Error>;
+
+    /// Records a commit that changed one of the `collect.governance_files`
+    async fn create_governance_change(&self, repo_id: u32, path: &str, commit: &RepositoryCommit) -> Result<(), sqlx::Error>;
+
+    async fn create_release(&self, repo_id: u32, release: &octocrab::models::repos::Release) -> Result<(), sqlx::Error>;
+
+    async fn create_deployment(&self, repo_id: u32, deployment: &GitHubDeployment) -> Result<(), sqlx::Error>;
+
+    async fn create_deployment_status(&self, deployment_id: i64, status: &GitHubDeploymentStatus) -> Result<(), sqlx::Error>;
+
+    async fn create_fork(&self, repo_id: u32, fork: &octocrab::models::Repository) -> Result<(), sqlx::Error>;
+
+    /// Removes the maintainers recorded for a repository before they are fetched again
+    async fn clear_maintainers(&self, repo_id: u32) -> Result<(), sqlx::Error>;
+
+    /// Records someone with push access, directly or through `teams`
+    async fn create_maintainer(&self, repo_id: u32, login: &str, permission: &str, teams: &[String]) -> Result<(), sqlx::Error>;
+
+    async fn clear_contributors(&self, repo_id: u32) -> Result<(), sqlx::Error>;
+
+    async fn create_contributor(&self, repo_id: u32, contributor: &GitHubContributor) -> Result<(), sqlx::Error>;
+
+    async fn create_contributor_weeks(&self, repo_id: u32, stats: &ContributorStats) -> Result<(), sqlx::Error>;
+
+    async fn create_traffic_day(&self, repo_id: u32, kind: &str, day: &GitHubTrafficDay) -> Result<(), sqlx::Error>;
+
+    async fn clear_stargazers(&self, repo_id: u32) -> Result<(), sqlx::Error>;
+
+    async fn create_stargazer(&self, repo_id: u32, stargazer: &GitHubStargazer) -> Result<(), sqlx::Error>;
+
+    async fn create_linked_issue(&self, issue_pull_id: i64, issue_number: u32) -> Result<(), sqlx::Error>;
+
+    /// Records the `(total, completed)` checklist counts of a pull request, or
+    /// removes them when it is `None`
+    async fn set_pull_request_checklist(&self, issue_pull_id: i64, counts: Option<(u32, u32)>) -> Result<(), sqlx::Error>;
+
+    async fn create_label(&self, repo_id: u32, label: &octocrab::models::Label) -> Result<(), sqlx::Error>;
+
+    /// Replaces the files of a pull request with those fetched again, in one transaction
+    async fn replace_pull_request_files(
+        &self,
+        issue_pull_id: i64,
+        files: &[octocrab::models::repos::DiffEntry],
+    ) -> Result<(), sqlx::Error>;
+
+    async fn create_milestone(&self, repo_id: u32, milestone: &octocrab::models::Milestone) -> Result<(), sqlx::Error>;
+
+    /// Stores a discussion with its comments
+    async fn create_discussion(&self, repo_id: u32, discussion: &GitHubDiscussion) -> Result<(), sqlx::Error>;
+
+    /// Stores `milestone` and makes it the milestone of a pull request or issue,
+    /// or clears the milestone when it is `None`
+    async fn set_pull_request_milestone(
+        &self,
+        repo_id: u32,
+        issue_pull_id: i64,
+        milestone: Option<&octocrab::models::Milestone>,
+    ) -> Result<(), sqlx::Error>;
+
+    /// Replaces who a pull request or issue is assigned to
+    async fn set_pull_request_assignees(&self, issue_pull_id: i64, logins: &[String]) -> Result<(), sqlx::Error>;
+
+    /// Replaces the users and teams whose review is still requested on a pull request
+    async fn set_requested_reviewers(
+        &self,
+        issue_pull_id: i64,
+        users: &[String],
+        teams: &[String],
+    ) -> Result<(), sqlx::Error>;
+
+    async fn create_wiki_edit(&self, repo_id: u32, event: &ActivityEvent, page: &WikiPage) -> Result<(), sqlx::Error>;
+
+    /// Stores `label` and applies it to a pull request or issue
+    async fn create_pull_request_label(
+        &self,
+        repo_id: u32,
+        issue_pull_id: i64,
+        label: &octocrab::models::Label,
+    ) -> Result<(), sqlx::Error>;
+
+    async fn clear_issue_demand(&self, repo_id: u32) -> Result<(), sqlx::Error>;
+
+    async fn create_issue_demand(
+        &self,
+        repo_id: u32,
+        issue: &IssueWithReactions,
+    ) -> Result<IssueDemand, sqlx::Error>;
+
+    async fn create_repository_event(&self, repo_id: u32, event: &RepositoryIssueEvent) -> Result<(), sqlx::Error>;
+
+    async fn clear_dependencies(&self, repo_id: u32) -> Result<(), sqlx::Error>;
+
+    async fn create_dependency(
+        &self,
+        repo_id: u32,
+        ecosystem: &str,
+        name: &str,
+        version: &str,
+    ) -> Result<(), sqlx::Error>;
+}
+
+/// Storage backed by the local SQLite database file
+pub struct SqliteStorage {
+    pool: Pool<Sqlite>,
+}
+
+impl SqliteStorage {
+    pub async fn connect() -> Result<Self, sqlx::Error> {
+        Ok(Self { pool: setup_db().await? })
+    }
+}
+
+/// Opens the database collectors should write to
+pub async fn open_storage() -> Result<Box<dyn Storage>, sqlx::Error> {
+    Ok(Box::new(SqliteStorage::connect().await?))
+}
+
+#[async_trait]
+impl Storage for SqliteStorage {
+    async fn get_repository(&self, path: &str) -> Result<Repository, sqlx::Error> {
+        Repository::from(&self.pool, path).await
+    }
+
+    async fn create_repository(&self, path: &str) -> Result<Repository, sqlx::Error> {
+        Repository::create(&self.pool, path).await
+    }
+
+    async fn get_cursor(&self, repo_id: u32, collector: &str) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+        CollectionCursor::fetch(&self.pool, repo_id, collector).await
+    }
+
+    async fn set_cursor(&self, repo_id: u32, collector: &str, updated_at: DateTime<Utc>) -> Result<(), sqlx::Error> {
+        CollectionCursor::set(&self.pool, repo_id, collector, updated_at).await
+    }
+
+    async fn snapshot_associations(&self, repo_id: u32) -> Result<(), sqlx::Error> {
+        AssociationChange::snapshot(&self.pool, repo_id).await
+    }
+
+    async fn record_weekly_metrics(&self, repo_id: u32) -> Result<(), sqlx::Error> {
+        MetricSnapshot::record_weeks(&self.pool, repo_id).await
+    }
+
+    async fn get_etags(&self, repo_id: u32) -> Result<Etags, sqlx::Error> {
+        Ok(HttpCacheEntry::fetch_all(&self.pool, repo_id).await?.into_iter().collect())
+    }
+
+    async fn set_etag(&self, repo_id: u32, url: &str, etag: &str) -> Result<(), sqlx::Error> {
+        HttpCacheEntry::set(&self.pool, repo_id, url, etag).await
+    }
+
+    async fn get_unfinished_checkpoint(
+        &self,
+        repo_id: u32,
+        collector: &str,
+        scope: &str,
+        before_run: Option<u32>,
+    ) -> Result<Option<String>, sqlx::Error> {
+        CollectionRun::unfinished_checkpoint(&self.pool, repo_id, collector, scope, before_run).await
+    }
+
+    async fn set_run_checkpoint(&self, run_id: u32, scope: &str, checkpoint: &str) -> Result<(), sqlx::Error> {
+        CollectionRun::set_checkpoint(&self.pool, run_id, scope, checkpoint).await
+    }
+
+    async fn get_host_features(&self, host: &str) -> Result<Vec<HostFeature>, sqlx::Error> {
+        HostFeature::fetch(&self.pool, host).await
+    }
+
+    async fn set_host_feature(&self, host: &str, feature: &str, available: bool, reason: &str) -> Result<(), sqlx::Error> {
+        HostFeature::set(&self.pool, host, feature, available, reason).await
+    }
+
+    async fn set_repository_archived(&self, repo_id: u32, archived: bool) -> Result<(), sqlx::Error> {
+        Repository::set_archived(&self.pool, repo_id, archived).await
+    }
+
+    async fn set_repository_forge(&self, repo_id: u32, forge: &str) -> Result<(), sqlx::Error> {
+        Repository::set_forge(&self.pool, repo_id, forge).await
+    }
+
+    async fn create_pull_requests(
+        &self,
+        pulls: &[&octocrab::models::pulls::PullRequest],
+        repo_id: u32,
+    ) -> Result<(), sqlx::Error> {
+        PullRequest::create_many(&self.pool, pulls, repo_id).await
+    }
+
+    async fn create_issues(
+        &self,
+        issues: &[&octocrab::models::issues::Issue],
+        repo_id: u32,
+    ) -> Result<Vec<i64>, sqlx::Error> {
+        PullRequest::create_many_from_issues(&self.pool, issues, repo_id).await
+    }
+
+    async fn get_pull_requests(&self, repo_id: u32, numbers: &[u32]) -> Result<Vec<PullRequest>, sqlx::Error> {
+        PullRequest::fetch_many(&self.pool, repo_id, numbers).await
+    }
+
+    async fn get_pull_requests_updated_since(
+        &self,
+        repo_id: u32,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<PullRequest>, sqlx::Error> {
+        PullRequest::fetch_updated_since(&self.pool, repo_id, since).await
+    }
+
+    async fn get_pull_requests_without_events(&self, repo_id: u32) -> Result<Vec<PullRequest>, sqlx::Error> {
+        PullRequest::fetch_events_not_collected(&self.pool, repo_id).await
+    }
+
+    async fn get_pull_requests_without_reviews(&self, repo_id: u32) -> Result<Vec<PullRequest>, sqlx::Error> {
+        PullRequest::fetch_without(&self.pool, repo_id, "issue_pull_reviews").await
+    }
+
+    async fn replace_pull_request_events(
+        &self,
+        issue_pull_id: i64,
+        events: &[&ForgeEvent],
+        timeline: &[i64],
+    ) -> Result<(), sqlx::Error> {
+        PullRequestEvent::replace(&self.pool, issue_pull_id, events, timeline).await
+    }
+
+    async fn set_events_collected(&self, issue_pull_id: i64) -> Result<(), sqlx::Error> {
+        PullRequest::set_events_collected(&self.pool, issue_pull_id).await
+    }
+
+    async fn replace_pull_request_reviews(&self, issue_pull_id: i64, reviews: &[ForgeReview]) -> Result<(), sqlx::Error> {
+        PullRequestReview::replace(&self.pool, issue_pull_id, reviews).await
+    }
+
+    async fn create_raw_payloads(
+        &self,
+        repo_id: u32,
+        kind: &str,
+        payloads: &[(i64, i64, &serde_json::Value)],
+    ) -> Result<(), sqlx::Error> {
+        RawPayload::create_many(&self.pool, repo_id, kind, payloads).await
+    }
+
+    async fn create_forge_pulls(&self, repo_id: u32, pulls: &[ForgePull]) -> Result<(), sqlx::Error> {
+        PullRequest::create_many_from_forge(&self.pool, pulls, repo_id).await
+    }
+
+    async fn create_forge_comment(&self, issue_pull_id: i64, comment: &ForgeComment) -> Result<(), sqlx::Error> {
+        PullRequestComment::create_from_forge(&self.pool, issue_pull_id, comment).await
+    }
+
+    async fn create_pull_request_comment(&self, issue_pull_id: i64, comment: &IssueComment) -> Result<(), sqlx::Error> {
+        PullRequestComment::create(&self.pool, issue_pull_id, comment).await
+    }
+
+    async fn create_review_comment(&self, issue_pull_id: i64, comment: &ReviewComment) -> Result<(), sqlx::Error> {
+        PullRequestReviewComment::create(&self.pool, issue_pull_id, comment).await
+    }
+
+    async fn create_commit(&self, repo_id: u32, commit: &RepositoryCommit, on_default_branch: bool) -> Result<(), sqlx::Error> {
+        Commit::create(&self.pool, repo_id, commit, on_default_branch).await
+    }
+
+    async fn create_governance_change(&self, repo_id: u32, path: &str, commit: &RepositoryCommit) -> Result<(), sqlx::Error> {
+        GovernanceChange::create(&self.pool, repo_id, path, commit).await
+    }
+
+    async fn create_release(&self, repo_id: u32, release: &octocrab::models::repos::Release) -> Result<(), sqlx::Error> {
+        Release::create(&self.pool, repo_id, release).await
+    }
+
+    async fn create_deployment(&self, repo_id: u32, deployment: &GitHubDeployment) -> Result<(), sqlx::Error> {
+        Deployment::create(&self.pool, repo_id, deployment).await
+    }
+
+    async fn create_deployment_status(&self, deployment_id: i64, status: &GitHubDeploymentStatus) -> Result<(), sqlx::Error> {
+        DeploymentStatus::create(&self.pool, deployment_id, status).await
+    }
+
+    async fn create_fork(&self, repo_id: u32, fork: &octocrab::models::Repository) -> Result<(), sqlx::Error> {
+        Fork::create(&self.pool, repo_id, fork).await
+    }
+
+    async fn clear_maintainers(&self, repo_id: u32) -> Result<(), sqlx::Error> {
+        RepositoryMaintainer::clear(&self.pool, repo_id).await
+    }
+
+    async fn create_maintainer(&self, repo_id: u32, login: &str, permission: &str, teams: &[String]) -> Result<(), sqlx::Error> {
+        RepositoryMaintainer::create(&self.pool, repo_id, login, permission, teams).await
+    }
+
+    async fn clear_contributors(&self, repo_id: u32) -> Result<(), sqlx::Error> {
+        Contributor::clear(&self.pool, repo_id).await
+    }
+
+    async fn create_contributor(&self, repo_id: u32, contributor: &GitHubContributor) -> Result<(), sqlx::Error> {
+        Contributor::create(&self.pool, repo_id, contributor).await
+    }
+
+    async fn create_contributor_weeks(&self, repo_id: u32, stats: &ContributorStats) -> Result<(), sqlx::Error> {
+        ContributorWeek::create_many(&self.pool, repo_id, stats).await
+    }
+
+    async fn create_traffic_day(&self, repo_id: u32, kind: &str, day: &GitHubTrafficDay) -> Result<(), sqlx::Error> {
+        TrafficDay::create(&self.pool, repo_id, kind, day).await
+    }
+
+    async fn clear_stargazers(&self, repo_id: u32) -> Result<(), sqlx::Error> {
+        Stargazer::clear(&self.pool, repo_id).await
+    }
+
+    async fn create_stargazer(&self, repo_id: u32, stargazer: &GitHubStargazer) -> Result<(), sqlx::Error> {
+        Stargazer::create(&self.pool, repo_id, stargazer).await
+    }
+
+    async fn create_linked_issue(&self, issue_pull_id: i64, issue_number: u32) -> Result<(), sqlx::Error> {
+        LinkedIssue::create(&self.pool, issue_pull_id, issue_number).await
+    }
+
+    async fn set_pull_request_checklist(&self, issue_pull_id: i64, counts: Option<(u32, u32)>) -> Result<(), sqlx::Error> {
+        PullRequestChecklist::set(&self.pool, issue_pull_id, counts).await
+    }
+
+    async fn create_label(&self, repo_id: u32, label: &octocrab::models::Label) -> Result<(), sqlx::Error> {
+        Label::create(&self.pool, repo_id, label).await
+    }
+
+    async fn replace_pull_request_files(
+        &self,
+        issue_pull_id: i64,
+        files: &[octocrab::models::repos::DiffEntry],
+    ) -> Result<(), sqlx::Error> {
+        PullRequestFile::replace(&self.pool, issue_pull_id, files).await
+    }
+
+    async fn create_milestone(&self, repo_id: u32, milestone: &octocrab::models::Milestone) -> Result<(), sqlx::Error> {
+        Milestone::create(&self.pool, repo_id, milestone).await
+    }
+
+    async fn create_discussion(&self, repo_id: u32, discussion: &GitHubDiscussion) -> Result<(), sqlx::Error> {
+        Discussion::create(&self.pool, repo_id, discussion).await
+    }
+
+    async fn set_pull_request_milestone(
+        &self,
+        repo_id: u32,
+        issue_pull_id: i64,
+        milestone: Option<&octocrab::models::Milestone>,
+    ) -> Result<(), sqlx::Error> {
+        if let Some(milestone) = milestone {
+            Milestone::create(&self.pool, repo_id, milestone).await?;
+        }
+        PullRequestMilestone::set(&self.pool, issue_pull_id, milestone.map(|milestone| milestone.id.into_inner() as i64)).await
+    }
+
+    async fn set_pull_request_assignees(&self, issue_pull_id: i64, logins: &[String]) -> Result<(), sqlx::Error> {
+        PullRequestAssignee::set(&self.pool, issue_pull_id, logins).await
+    }
+
+    async fn set_requested_reviewers(
+        &self,
+        issue_pull_id: i64,
+        users: &[String],
+        teams: &[String],
+    ) -> Result<(), sqlx::Error> {
+        PullRequestRequestedReviewer::set(&self.pool, issue_pull_id, users, teams).await
+    }
+
+    async fn create_wiki_edit(&self, repo_id: u32, event: &ActivityEvent, page: &WikiPage) -> Result<(), sqlx::Error> {
+        WikiEdit::create(&self.pool, repo_id, event, page).await
+    }
+
+    async fn create_pull_request_label(
+        &self,
+        repo_id: u32,
+        issue_pull_id: i64,
+        label: &octocrab::models::Label,
+    ) -> Result<(), sqlx::Error> {
+        Label::create(&self.pool, repo_id, label).await?;
+        PullRequestLabel::create(&self.pool, issue_pull_id, label.id.into_inner() as i64).await
+    }
+
+    async fn clear_issue_demand(&self, repo_id: u32) -> Result<(), sqlx::Error> {
+        IssueDemand::clear(&self.pool, repo_id).await
+    }
+
+    async fn create_issue_demand(
+        &self,
+        repo_id: u32,
+        issue: &IssueWithReactions,
+    ) -> Result<IssueDemand, sqlx::Error> {
+        IssueDemand::create(&self.pool, repo_id, issue).await
+    }
+
+    async fn create_repository_event(&self, repo_id: u32, event: &RepositoryIssueEvent) -> Result<(), sqlx::Error> {
+        RepositoryEvent::create(&self.pool, repo_id, event).await
+    }
+
+    async fn clear_dependencies(&self, repo_id: u32) -> Result<(), sqlx::Error> {
+        Dependency::clear(&self.pool, repo_id).await
+    }
+
+    async fn create_dependency(
+        &self,
+        repo_id: u32,
+        ecosystem: &str,
+        name: &str,
+        version: &str,
+    ) -> Result<(), sqlx::Error> {
+        Dependency::create(&self.pool, repo_id, ecosystem, name, version).await
+    }
+}
@@ -0,0 +1,184 @@
+/// Renders tables for commands that print to the terminal
+///
+/// Commands fill a [`Table`] and print it, so columns line up the same way
+/// everywhere: a bold header over a rule, numbers right-aligned, and values cut
+/// short when the table is wider than the terminal. Output that isn't going to
+/// a terminal keeps values whole, and colors are left out with `--no-color`,
+/// `NO_COLOR` or when stdout isn't a terminal.
+use console::{measure_text_width, pad_str, style, truncate_str, Alignment, Term};
+
+/// Space between columns
+const GAP: &str = "  ";
+
+/// Columns aren't narrowed below this many characters to fit the terminal
+const MIN_WIDTH: usize = 8;
+
+/// Turns colors off for everything printed, including progress bars and errors
+pub fn disable_colors() {
+    console::set_colors_enabled(false);
+    console::set_colors_enabled_stderr(false);
+    let _ = miette::set_hook(Box::new(|_| Box::new(miette::MietteHandlerOpts::new().color(false).build())));
+}
+
+/// Width of the terminal stdout is, or `None` when output goes to a file or pipe
+pub fn terminal_width() -> Option<usize> {
+    let term = Term::stdout();
+    if !term.is_term() {
+        return None;
+    }
+    term.size_checked().map(|(_, columns)| columns as usize)
+}
+
+/// Rows printed in aligned columns; cells may be styled with [`console::style`]
+#[derive(Debug, Clone)]
+pub struct Table {
+    headers: Vec<String>,
+    right: Vec<bool>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Self {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            right: vec![false; headers.len()],
+            rows: Vec::new(),
+        }
+    }
+
+    /// Right-aligns a column, as is done for numbers
+    pub fn align_right(mut self, column: usize) -> Self {
+        self.right[column] = true;
+        self
+    }
+
+    /// Adds a row, keeping every cell on one line
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row.into_iter().map(|cell| cell.replace(['\r', '\n', '\t'], " ")).collect());
+    }
+
+    /// Whether no row was added
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn print(&self) {
+        println!("{}", self.render());
+    }
+
+    /// The table as [`Table::print`] prints it, for output put together before printing
+    pub fn render(&self) -> String {
+        self.lines(terminal_width()).join("\n")
+    }
+
+    /// The table as lines of text, narrowed to `width` characters where it can be
+    fn lines(&self, width: Option<usize>) -> Vec<String> {
+        if self.headers.is_empty() {
+            return Vec::new();
+        }
+        let mut widths: Vec<usize> = self
+            .headers
+            .iter()
+            .enumerate()
+            .map(|(index, header)| {
+                self.rows
+                    .iter()
+                    .filter_map(|row| row.get(index))
+                    .map(|cell| measure_text_width(cell))
+                    .chain([measure_text_width(header)])
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        if let Some(width) = width {
+            // The widest column gives way first, until everything fits or nothing can shrink further
+            let available = width.saturating_sub(GAP.len() * (widths.len() - 1));
+            while widths.iter().sum::<usize>() > available {
+                let (index, widest) = widths
+                    .iter()
+                    .enumerate()
+                    .max_by_key(|(_, width)| **width)
+                    .expect("tables have a column");
+                if *widest <= MIN_WIDTH {
+                    break;
+                }
+                widths[index] -= 1;
+            }
+        }
+
+        let line = |cells: &[String]| -> String {
+            let cells: Vec<String> = widths
+                .iter()
+                .enumerate()
+                .map(|(index, width)| {
+                    let cell = cells.get(index).map(String::as_str).unwrap_or_default();
+                    let align = if self.right[index] { Alignment::Right } else { Alignment::Left };
+                    // `truncate_str` also shortens values that fit exactly, so only long ones are passed to it
+                    let cell = if measure_text_width(cell) > *width {
+                        truncate_str(cell, *width, "…")
+                    } else {
+                        cell.into()
+                    };
+                    pad_str(&cell, *width, align, None).into_owned()
+                })
+                .collect();
+            cells.join(GAP).trim_end().to_string()
+        };
+
+        let mut lines = vec![
+            style(line(&self.headers)).bold().to_string(),
+            widths.iter().map(|width| "-".repeat(*width)).collect::<Vec<_>>().join(GAP),
+        ];
+        lines.extend(self.rows.iter().map(|row| line(row)));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table() -> Table {
+        let mut table = Table::new(&["Repository", "Rows", "Description"]).align_right(1);
+        table.push(vec!["conda/conda".to_string(), "12".to_string(), "A package manager for any language".to_string()]);
+        table.push(vec!["conda/conda-build".to_string(), "3456".to_string(), "Builds packages".to_string()]);
+        table
+    }
+
+    fn plain(lines: Vec<String>) -> Vec<String> {
+        lines.into_iter().map(|line| console::strip_ansi_codes(&line).into_owned()).collect()
+    }
+
+    #[test]
+    fn tables_keep_values_whole_without_a_width() {
+        assert_eq!(plain(table().lines(None)), [
+            "Repository         Rows  Description",
+            "-----------------  ----  ----------------------------------",
+            "conda/conda          12  A package manager for any language",
+            "conda/conda-build  3456  Builds packages",
+        ]);
+    }
+
+    #[test]
+    fn the_widest_column_gives_way_first() {
+        let lines = plain(table().lines(Some(45)));
+        assert_eq!(lines, [
+            "Repository         Rows  Description",
+            "-----------------  ----  --------------------",
+            "conda/conda          12  A package manager f…",
+            "conda/conda-build  3456  Builds packages",
+        ]);
+        assert!(lines.iter().all(|line| measure_text_width(line) <= 45));
+    }
+
+    #[test]
+    fn narrow_terminals_cut_every_column_down_to_the_minimum() {
+        let lines = plain(table().lines(Some(20)));
+        assert_eq!(lines, [
+            "Reposit…  Rows  Descrip…",
+            "--------  ----  --------",
+            "conda/c…    12  A packa…",
+            "conda/c…  3456  Builds …",
+        ]);
+    }
+}
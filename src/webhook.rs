@@ -0,0 +1,258 @@
+/// Verification and storage of GitHub webhook deliveries
+///
+/// Every delivery is checked against the HMAC-SHA256 signature GitHub sends in
+/// `X-Hub-Signature-256`. Those that pass are recorded by their
+/// `X-GitHub-Delivery` id so a replayed delivery is only ever accepted once. `pull_request`,
+/// `pull_request_review` and `issue_comment` deliveries about a tracked
+/// repository are also written into the tables the collectors fill.
+use hmac::{Hmac, Mac};
+use http::HeaderMap;
+use serde::Deserialize;
+use sha2::Sha256;
+use sqlx::sqlite::Sqlite;
+use sqlx::Pool;
+use thiserror::Error;
+
+use crate::config::WebhookConfig;
+use crate::database::{
+    LinkedIssue,
+    PullRequest,
+    PullRequestAssignee,
+    PullRequestComment,
+    PullRequestRequestedReviewer,
+    PullRequestReview,
+    Repository,
+    WebhookDelivery,
+};
+use crate::github::{assignee_logins, closing_issue_numbers, requested_reviewers, IssueComment};
+
+pub const SIGNATURE_HEADER: &str = "x-hub-signature-256";
+pub const DELIVERY_HEADER: &str = "x-github-delivery";
+pub const EVENT_HEADER: &str = "x-github-event";
+pub const HOOK_ID_HEADER: &str = "x-github-hook-id";
+
+#[derive(Debug, Error)]
+pub enum WebhookError {
+    #[error("Missing {0} header")]
+    MissingHeader(&'static str),
+    #[error("Invalid JSON payload: {0}")]
+    InvalidPayload(#[from] serde_json::Error),
+    #[error("No webhook secret configured for {0}")]
+    NoSecret(String),
+    #[error("Signature does not match the configured secret for {0}")]
+    InvalidSignature(String),
+    #[error("Unable to store delivery: {0}")]
+    Database(#[from] sqlx::Error),
+}
+
+/// What happened to a delivery that passed verification
+#[derive(Debug, PartialEq, Eq)]
+pub enum Outcome {
+    Accepted,
+    /// A delivery with the same id was already stored
+    Duplicate,
+    /// Stored, but what it says could not be written into the collected data
+    NotIngested(String),
+}
+
+/// The parts of a webhook payload needed before knowing its event type
+#[derive(Debug, Deserialize)]
+struct PayloadHeader {
+    action: Option<String>,
+    repository: Option<PayloadRepository>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PayloadRepository {
+    full_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PullRequestPayload {
+    pull_request: octocrab::models::pulls::PullRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReviewPayload {
+    pull_request: octocrab::models::pulls::PullRequest,
+    review: octocrab::models::pulls::Review,
+}
+
+#[derive(Debug, Deserialize)]
+struct IssueCommentPayload {
+    issue: octocrab::models::issues::Issue,
+    comment: IssueComment,
+}
+
+/// Checks a `sha256=<hex>` signature against the body in constant time
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(Ok(expected)) = signature.strip_prefix("sha256=").map(hex::decode) else {
+        return false;
+    };
+    let Ok(mut mac) = Hmac::<Sha256>::new_from_slice(secret.as_bytes()) else {
+        return false;
+    };
+    mac.update(body);
+
+    mac.verify_slice(&expected).is_ok()
+}
+
+/// Verifies a delivery and records it
+///
+/// Deliveries with a bad signature are rejected without being stored, as
+/// anyone can send one under a delivery id of their choosing.
+pub async fn receive(
+    pool: &Pool<Sqlite>,
+    config: &WebhookConfig,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> Result<Outcome, WebhookError> {
+    let header = |name: &'static str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .ok_or(WebhookError::MissingHeader(name))
+    };
+    let delivery_id = header(DELIVERY_HEADER)?;
+    let event = header(EVENT_HEADER)?;
+    let signature = header(SIGNATURE_HEADER)?;
+    let hook_id = header(HOOK_ID_HEADER).ok().and_then(|id| id.parse::<i64>().ok());
+
+    let payload: PayloadHeader = serde_json::from_slice(body)?;
+    let repository = payload.repository.map(|repo| repo.full_name).unwrap_or_default();
+    let action = payload.action.unwrap_or_default();
+
+    let secret = config
+        .secret_for(&repository)
+        .ok_or_else(|| WebhookError::NoSecret(repository.clone()))?;
+
+    if !verify_signature(&secret, body, signature) {
+        return Err(WebhookError::InvalidSignature(repository));
+    }
+
+    let payload = String::from_utf8_lossy(body);
+    let is_new =
+        WebhookDelivery::create(pool, delivery_id, hook_id, &repository, event, &action, &payload).await?;
+    if !is_new {
+        return Ok(Outcome::Duplicate);
+    }
+
+    match ingest(pool, &repository, event, body).await {
+        Ok(()) => Ok(Outcome::Accepted),
+        // The delivery is kept either way, so a failure here must not make GitHub retry it
+        Err(err) => Ok(Outcome::NotIngested(err.to_string())),
+    }
+}
+
+/// Writes a verified delivery into the collected data
+///
+/// Events other than `pull_request`, `pull_request_review` and `issue_comment`,
+/// and deliveries about repositories gdfm doesn't track, are only kept as deliveries.
+async fn ingest(pool: &Pool<Sqlite>, repository: &str, event: &str, body: &[u8]) -> Result<(), WebhookError> {
+    if !matches!(event, "pull_request" | "pull_request_review" | "issue_comment") {
+        return Ok(());
+    }
+    let repo = match Repository::from(pool, repository).await {
+        Ok(repo) => repo,
+        Err(sqlx::Error::RowNotFound) => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+
+    match event {
+        "pull_request" => {
+            let payload: PullRequestPayload = serde_json::from_slice(body)?;
+            store_pull_request(pool, &payload.pull_request, repo.id).await?;
+        }
+        "pull_request_review" => {
+            let payload: ReviewPayload = serde_json::from_slice(body)?;
+            let pull = store_pull_request(pool, &payload.pull_request, repo.id).await?;
+            // Edited and dismissed reviews arrive again under the same id and are updated in place
+            PullRequestReview::create_many(pool, pull.id, &[&payload.review]).await?;
+        }
+        _ => {
+            let payload: IssueCommentPayload = serde_json::from_slice(body)?;
+            let pull = match PullRequest::fetch(pool, repo.id, payload.issue.number as u32).await {
+                Ok(pull) => pull,
+                // Pull requests only arrive here as issues, which lack what `pulls` stores for them
+                Err(sqlx::Error::RowNotFound) if payload.issue.pull_request.is_some() => return Ok(()),
+                Err(sqlx::Error::RowNotFound) => {
+                    PullRequest::create_many_from_issues(pool, &[&payload.issue], repo.id).await?;
+                    PullRequest::fetch(pool, repo.id, payload.issue.number as u32).await?
+                }
+                Err(err) => return Err(err.into()),
+            };
+            PullRequestComment::create(pool, pull.id, &payload.comment).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Stores a pull request, its assignees and requested reviewers, and the issues its body says it closes
+async fn store_pull_request(
+    pool: &Pool<Sqlite>,
+    pull: &octocrab::models::pulls::PullRequest,
+    repo_id: u32,
+) -> Result<PullRequest, WebhookError> {
+    PullRequest::create_many(pool, &[pull], repo_id).await?;
+    let pull_db = PullRequest::fetch(pool, repo_id, pull.number as u32).await?;
+    PullRequestAssignee::set(pool, pull_db.id, &assignee_logins(pull)).await?;
+    let (users, teams) = requested_reviewers(pull);
+    PullRequestRequestedReviewer::set(pool, pull_db.id, &users, &teams).await?;
+    if let Some(body) = &pull.body {
+        for issue_number in closing_issue_numbers(body) {
+            LinkedIssue::create(pool, pull_db.id, issue_number).await?;
+        }
+    }
+
+    Ok(pull_db)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The example from GitHub's documentation on validating deliveries
+    const SECRET: &str = "It's a Secret to Everybody";
+    const SIGNATURE: &str = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+
+    #[test]
+    fn signatures_match_the_body_and_secret() {
+        assert!(verify_signature(SECRET, b"Hello, World!", SIGNATURE));
+        assert!(!verify_signature(SECRET, b"Hello, World?", SIGNATURE));
+        assert!(!verify_signature("another secret", b"Hello, World!", SIGNATURE));
+    }
+
+    #[test]
+    fn malformed_signatures_are_rejected() {
+        let sha1 = SIGNATURE.replacen("sha256", "sha1", 1);
+        assert!(!verify_signature(SECRET, b"Hello, World!", &sha1));
+        assert!(!verify_signature(SECRET, b"Hello, World!", "sha256=not hex"));
+        assert!(!verify_signature(SECRET, b"Hello, World!", ""));
+    }
+
+    #[tokio::test]
+    async fn deliveries_with_a_bad_signature_are_not_stored() {
+        let path = std::env::temp_dir().join(format!("gdfm-webhook-{}.db", std::process::id()));
+        let pool = crate::database::open_db(&path).await.unwrap();
+        let config = WebhookConfig { secret: Some(SECRET.to_string()), ..Default::default() };
+        let mut headers = HeaderMap::new();
+        headers.insert(DELIVERY_HEADER, "chosen-by-the-sender".parse().unwrap());
+        headers.insert(EVENT_HEADER, "ping".parse().unwrap());
+        headers.insert(SIGNATURE_HEADER, SIGNATURE.parse().unwrap());
+        let body = br#"{"repository":{"full_name":"octo/repo"}}"#;
+
+        let outcome = receive(&pool, &config, &headers, body).await;
+        let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webhook_deliveries")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        pool.close().await;
+        for suffix in ["", "-wal", "-shm"] {
+            let _ = std::fs::remove_file(format!("{}{}", path.display(), suffix));
+        }
+
+        assert!(matches!(outcome, Err(WebhookError::InvalidSignature(repository)) if repository == "octo/repo"));
+        assert_eq!(stored, 0);
+    }
+}